@@ -0,0 +1,44 @@
+//! Benchmarks of loading the synthetic files of `ffreader::bench_support`, sequentially and
+//! with several threads, and of parsing rows into a reused buffer. Run with `cargo bench`;
+//! save a baseline with `cargo bench -- --save-baseline main` and compare a change against it
+//! with `cargo bench -- --baseline main`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ffreader::{bench_support, DataFile, LoadOptions, RowParser};
+use std::hint::black_box;
+
+const ROWS: usize = 10_000;
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load");
+    for workload in bench_support::workloads(ROWS) {
+        group.throughput(Throughput::Bytes(workload.len() as u64));
+        for threads in [1, 4] {
+            let options = LoadOptions { threads: Some(threads), ..Default::default() };
+            group.bench_with_input(BenchmarkId::new(workload.name, threads), &workload.data, |b, data| {
+                b.iter(|| DataFile::try_from_str(black_box(data), &workload.defs, &options).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn parse_into(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_into");
+    for workload in bench_support::workloads(ROWS) {
+        group.throughput(Throughput::Bytes(workload.len() as u64));
+        let parser = RowParser::new(&workload.defs, LoadOptions::default()).unwrap();
+        group.bench_function(workload.name, |b| {
+            let mut buf = parser.buffer();
+            b.iter(|| {
+                for line in workload.data.lines() {
+                    parser.parse_into(&mut buf, black_box(line)).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, load, parse_into);
+criterion_main!(benches);
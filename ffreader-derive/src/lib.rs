@@ -0,0 +1,109 @@
+#![warn(missing_docs)]
+//! # ffreader-derive
+//! The `FixedWidthRecord` derive macro for `ffreader`, enabled there with the `derive`
+//! feature. See `ffreader::FixedWidthRecord`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, LitInt, LitStr, PathArguments, Type};
+
+/// The layout of one struct field, from its `#[field(...)]` attribute.
+struct FieldLayout {
+    name: String,
+    start: usize,
+    end: usize
+}
+
+/// Derive `ffreader::FixedWidthRecord` for a struct with named fields, each annotated with
+/// `#[field(start = 0, end = 11)]` and optionally `name = "AccountNo1"` (the struct field's
+/// name is used otherwise).
+#[proc_macro_derive(FixedWidthRecord, attributes(field))]
+pub fn derive_fixed_width_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "FixedWidthRecord needs a struct with named fields"))
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "FixedWidthRecord can only be derived for structs"))
+    };
+
+    let mut defs = vec![];
+    let mut values = vec![];
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let layout = field_layout(field)?;
+        let (name, start, end) = (&layout.name, layout.start, layout.end);
+        defs.push(quote! {
+            ::ffreader::DataFieldDef::new_owned(#name, #start, #end, |s| Ok(s))
+        });
+        values.push(match option_inner(&field.ty) {
+            Some(inner) => quote! { #ident: ::ffreader::__derive::parse_optional::<#inner>(row, #name)? },
+            None => {
+                let ty = &field.ty;
+                quote! { #ident: ::ffreader::__derive::parse::<#ty>(row, #name)? }
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ffreader::FixedWidthRecord for #ident #ty_generics #where_clause {
+            fn field_defs() -> ::std::vec::Vec<::ffreader::DataFieldDef<'static>> {
+                ::std::vec![#(#defs),*]
+            }
+
+            fn from_row(row: &::ffreader::DataRow) -> ::ffreader::DataRowResult<Self> {
+                Ok(#ident { #(#values),* })
+            }
+        }
+    })
+}
+
+/// Read the `#[field(...)]` attribute of a struct field.
+fn field_layout(field: &syn::Field) -> syn::Result<FieldLayout> {
+    let ident = field.ident.as_ref().expect("named field");
+    let attr = field.attrs.iter().find(|a| a.path().is_ident("field"))
+        .ok_or_else(|| syn::Error::new_spanned(ident, "missing #[field(start = .., end = ..)] attribute"))?;
+
+    let (mut name, mut start, mut end) = (ident.to_string(), None, None);
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("start") {
+            start = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("end") {
+            end = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("name") {
+            name = meta.value()?.parse::<LitStr>()?.value();
+        } else {
+            return Err(meta.error("expected `start`, `end` or `name`"));
+        }
+        Ok(())
+    })?;
+
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => Ok(FieldLayout { name, start, end }),
+        (Some(_), Some(_)) => Err(syn::Error::new_spanned(attr, "`start` must not be after `end`")),
+        _ => Err(syn::Error::new_spanned(attr, "both `start` and `end` are required"))
+    }
+}
+
+/// Get `T` if the type is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last().filter(|s| s.ident == "Option")?;
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(inner)) => Some(inner),
+            _ => None
+        },
+        _ => None
+    }
+}
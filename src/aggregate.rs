@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use crate::{DataField, DataFile, DataFileResult, DataRowError};
+
+/// The result of a numeric aggregate over a column, along with the values that could not be
+/// included in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnAggregate {
+    /// The aggregate value, or None if the column has no numeric values.
+    pub value: Option<f64>,
+    /// The number of numeric values aggregated.
+    pub count: usize,
+    /// The line index (if known) and value of each non-blank value that was not numeric.
+    pub non_numeric: Vec<(Option<usize>, String)>
+}
+
+/// Parse a numeric field value, applying implied decimal places if it has no decimal point.
+///
+/// Surrounding spaces are ignored, and a sign may lead or trail the digits (`123-`), as is
+/// common in mainframe extracts.
+pub(crate) fn parse_number(value: &str, implied_decimals: usize) -> Option<f64> {
+    let value = value.trim();
+    let (negative, digits) = match value.strip_suffix('-').or_else(|| value.strip_prefix('-')) {
+        Some(d) => (true, d),
+        None => (false, value.strip_suffix('+').or_else(|| value.strip_prefix('+')).unwrap_or(value))
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    let mut number: f64 = digits.parse().ok()?;
+    if !digits.contains('.') {
+        number /= 10f64.powi(implied_decimals as i32);
+    }
+    Some(if negative { -number } else { number })
+}
+
+/// Aggregate a column's values (with the line index of each, if known) as numbers, honoring the
+/// field's implied decimal places. Blank values are ignored; other non-numeric values are
+/// reported in the result.
+pub(crate) fn aggregate_values<'a, F: Fn(&[f64]) -> f64>(column: impl Iterator<Item = (Option<usize>, &'a str)>,
+                                                         implied_decimals: usize, f: F) -> ColumnAggregate {
+    let mut values = vec![];
+    let mut non_numeric = vec![];
+    for (line_index, data) in column {
+        if data.trim().is_empty() {
+            continue;
+        }
+        match parse_number(data, implied_decimals) {
+            Some(n) => values.push(n),
+            None => non_numeric.push((line_index, data.to_string()))
+        }
+    }
+    ColumnAggregate {
+        value: (!values.is_empty()).then(|| f(&values)),
+        count: values.len(),
+        non_numeric
+    }
+}
+
+impl DataFile {
+    /// Get the processed data of a field in every row, in row order, without cloning it, for
+    /// analyses that need only a column or two. Fails if a row doesn't have the field.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let accounts = df.column("AccountNo1").unwrap();
+    /// println!("{} accounts, first {:?}", accounts.len(), accounts.first());
+    /// ```
+    pub fn column(&self, field_name: &str) -> DataFileResult<Vec<&str>> {
+        self.column_fields(field_name).map(|fields| fields.into_iter().map(DataField::data_ref).collect())
+    }
+
+    /// Get the typed value of a field in every row, in row order, as converted by its parser
+    /// (see `DataFieldDef::with_parser()`); None where a row's field has no value of type `T`.
+    /// Fails if a row doesn't have the field.
+    pub fn column_values<T: std::any::Any>(&self, field_name: &str) -> DataFileResult<Vec<Option<&T>>> {
+        self.column_fields(field_name).map(|fields| fields.into_iter().map(|f| f.value()).collect())
+    }
+
+    /// Get the named field of every row, failing if a row doesn't have it.
+    fn column_fields(&self, field_name: &str) -> DataFileResult<Vec<&DataField>> {
+        self.rows().iter()
+            .map(|row| row.get(field_name).ok_or_else(|| DataRowError::FieldNameNotFound(field_name.to_string()).into()))
+            .collect()
+    }
+
+    /// Count the rows having each distinct value of a field, in value order, e.g. to check that
+    /// a code field holds only expected codes. Rows without the field are ignored.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Status", 16, 23, &post_function)];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// for (status, count) in df.distinct("Status") {
+    ///     println!("{:<8}{}", status, count);
+    /// }
+    /// ```
+    pub fn distinct(&self, field_name: &str) -> BTreeMap<String, usize> {
+        self.distinct_up_to(field_name, usize::MAX).0
+    }
+
+    /// Count distinct values as with `distinct()`, stopping at the first row with a value beyond
+    /// the first `max` found, e.g. to explore a field that may hold a value per row. The counts
+    /// are then of the rows before that one, and true is returned with them.
+    pub fn distinct_up_to(&self, field_name: &str, max: usize) -> (BTreeMap<String, usize>, bool) {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for field in self.rows().iter().filter_map(|row| row.get(field_name)) {
+            if let Some(count) = counts.get_mut(field.data_ref()) {
+                *count += 1;
+            } else if counts.len() == max {
+                return (counts, true);
+            } else {
+                counts.insert(field.data(), 1);
+            }
+        }
+        (counts, false)
+    }
+
+    /// Aggregate a column; rows without the field are ignored.
+    fn aggregate<F: Fn(&[f64]) -> f64>(&self, field_name: &str, f: F) -> ColumnAggregate {
+        let column = self.rows().iter()
+            .filter_map(|row| row.get(field_name).map(|field| (row.line_index(), field.data_ref())));
+        aggregate_values(column, self.implied_decimals(field_name), f)
+    }
+
+    /// Sum a numeric column, e.g. to check a control total against a trailer record.
+    ///
+    /// Values are divided by 10 to the power of the field's implied decimal places (see
+    /// `DataFieldDef::with_implied_decimals()`) unless they contain a decimal point. Blank
+    /// values are ignored and non-numeric values are skipped and reported in the result.
+    /// Sums are floating point, so round before comparing with an exact total.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Amount", 0, 11, &post_function).with_implied_decimals(2)];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let total = df.column_sum("Amount");
+    /// println!("{:.2} over {} rows, {} bad", total.value.unwrap_or(0.0), total.count, total.non_numeric.len());
+    /// ```
+    pub fn column_sum(&self, field_name: &str) -> ColumnAggregate {
+        self.aggregate(field_name, |v| v.iter().sum())
+    }
+
+    /// Average a numeric column; see `column_sum()`.
+    pub fn column_mean(&self, field_name: &str) -> ColumnAggregate {
+        self.aggregate(field_name, |v| v.iter().sum::<f64>() / v.len() as f64)
+    }
+
+    /// Find the smallest value in a numeric column; see `column_sum()`.
+    pub fn column_min(&self, field_name: &str) -> ColumnAggregate {
+        self.aggregate(field_name, |v| v.iter().copied().fold(f64::INFINITY, f64::min))
+    }
+
+    /// Find the largest value in a numeric column; see `column_sum()`.
+    pub fn column_max(&self, field_name: &str) -> ColumnAggregate {
+        self.aggregate(field_name, |v| v.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldDef, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn numbers_parsed() {
+        assert_eq!(parse_number(" 0012345", 2), Some(123.45));
+        assert_eq!(parse_number("12.5", 2), Some(12.5));
+        assert_eq!(parse_number("500-", 1), Some(-50.0));
+        assert_eq!(parse_number("+7", 0), Some(7.0));
+        assert_eq!(parse_number("1,000", 0), None);
+        assert_eq!(parse_number("-", 0), None);
+    }
+
+    #[test]
+    fn column_aggregates() {
+        let path = std::env::temp_dir().join(format!("ffreader_aggregate_{}.txt", std::process::id()));
+        let contents: String = ["0001050", "0000250-", "       ", "N/A", "0010000"].iter()
+            .map(|v| format!("{:<200}\n", v))
+            .collect();
+        std::fs::write(&path, contents).unwrap();
+        let defs = vec![DataFieldDef::new("Amount", 0, 8, &echo_ok).with_implied_decimals(2)];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        let sum = df.column_sum("Amount");
+        assert!((sum.value.unwrap() - 108.0).abs() < 1e-9);
+        assert_eq!(sum.count, 3);
+        assert_eq!(sum.non_numeric, vec![(Some(3), "N/A".to_string())]);
+        assert!((df.column_mean("Amount").value.unwrap() - 36.0).abs() < 1e-9);
+        assert_eq!(df.column_min("Amount").value, Some(-2.5));
+        assert_eq!(df.column_max("Amount").value, Some(100.0));
+        assert_eq!(df.column_sum("Missing").value, None);
+        assert_eq!(df.column("Amount").unwrap(), ["0001050", "0000250-", "", "N/A", "0010000"]);
+        assert!(df.column("Missing").is_err());
+        let expected = [("", 1), ("0000250-", 1), ("0001050", 1), ("0010000", 1), ("N/A", 1)];
+        assert!(df.distinct("Amount").into_iter().eq(expected.map(|(v, c)| (v.to_string(), c))));
+        let (capped, more) = df.distinct_up_to("Amount", 2);
+        assert_eq!((capped.len(), more), (2, true));
+        assert!(!df.distinct_up_to("Amount", 5).1);
+        assert!(df.distinct("Missing").is_empty());
+
+        let defs = vec![DataFieldDef::new("AmountDue", 0, 8, &echo_ok).with_implied_decimals(2).with_alias("AMT")];
+        let options = crate::LoadOptions { name_matching: crate::NameMatching::Normalized, ..Default::default() };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        for field_name in ["AmountDue", "AMT", "amount due"] {
+            assert!((df.column_sum(field_name).value.unwrap() - 108.0).abs() < 1e-9);
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use crate::DataRow;
+use crate::format::json_string;
+
+/// A change made to a field's value by its post-processing while loading, recorded when
+/// `LoadOptions::audit` is set (see `DataFile::audit_log()`) or written by an AuditWriter.
+/// Values replaced by a FieldErrorPolicy fallback are changes too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The 0-based index of the source line of the row.
+    pub line_index: usize,
+    /// The name of the field.
+    pub field: String,
+    /// The trimmed raw text, as passed to the post-processing.
+    pub before: String,
+    /// The processed data.
+    pub after: String
+}
+
+impl AuditEntry {
+    /// List the changes post-processing made to the fields of a row.
+    pub(crate) fn changes(line_index: usize, row: &DataRow) -> impl Iterator<Item = AuditEntry> + '_ {
+        row.fields().iter()
+            .filter(|f| f.altered())
+            .map(move |f| AuditEntry {
+                line_index,
+                field: f.name().to_string(),
+                before: f.raw().trim().to_string(),
+                after: f.data()
+            })
+    }
+
+    /// Format the entry as a JSON object with `line` (1-based), `field`, `before` and `after`
+    /// members.
+    pub fn to_json(&self) -> String {
+        format!("{{\"line\": {}, \"field\": {}, \"before\": {}, \"after\": {}}}", self.line_index + 1,
+                json_string(&self.field), json_string(&self.before), json_string(&self.after))
+    }
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, {}: {:?} -> {:?}", self.line_index + 1, self.field, self.before, self.after)
+    }
+}
+
+/// Streams the changes made by post-processing to a writer as they happen, one JSON object
+/// per line (see `AuditEntry::to_json()`), so that a log of every modification is kept even
+/// for files streamed with DataFileReader. Set one with `LoadOptions::audit_writer`; clones
+/// write to the same writer.
+///
+/// Values are written as they were read, whatever redactions are set for exports. Entries of
+/// a file loaded on several threads are written in file order once every thread has finished.
+/// Writing stops at the first error, which `finish()` returns.
+/// ```
+/// use std::fs::{self, File};
+/// use std::io::BufWriter;
+/// use ffreader::{AuditWriter, DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+/// fn upper(value: String) -> DataFieldResult<String> {
+///     Ok(value.to_uppercase())
+/// }
+/// let defs = vec![DataFieldDef::new("Name", 0, 10, &upper)];
+/// let path = std::env::temp_dir().join("ffreader_audit_doc.jsonl");
+/// let writer = AuditWriter::new(BufWriter::new(File::create(&path).unwrap()));
+/// let options = LoadOptions { audit_writer: Some(writer.clone()), ..Default::default() };
+/// DataFile::try_from_str(&format!("{:<200}\n{:<200}\n", "ann", "BOB"), &defs, &options).unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(fs::read_to_string(&path).unwrap(),
+///            "{\"line\": 1, \"field\": \"Name\", \"before\": \"ann\", \"after\": \"ANN\"}\n");
+/// ```
+#[derive(Clone)]
+pub struct AuditWriter {
+    sink: Arc<Mutex<Sink>>
+}
+
+/// The writer of an AuditWriter, and the first error writing to it.
+struct Sink {
+    writer: Box<dyn Write + Send>,
+    error: Option<io::Error>
+}
+
+impl AuditWriter {
+    /// Stream entries to the writer. Wrap it in a BufWriter if it's a file.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        AuditWriter { sink: Arc::new(Mutex::new(Sink { writer: Box::new(writer), error: None })) }
+    }
+
+    /// Write the changes made to a row.
+    pub(crate) fn write_row(&self, line_index: usize, row: &DataRow) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        if sink.error.is_some() {
+            return;
+        }
+        for entry in AuditEntry::changes(line_index, row) {
+            if let Err(e) = writeln!(sink.writer, "{}", entry.to_json()) {
+                sink.error = Some(e);
+                return;
+            }
+        }
+    }
+
+    /// Flush the writer, or return the first error writing to it since the last call, after
+    /// which writing resumes.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        match sink.error.take() {
+            Some(e) => Err(e),
+            None => sink.writer.flush()
+        }
+    }
+}
+
+impl Debug for AuditWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AuditWriter")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFile, DataFileReader, DataFieldDef, DataFieldError, DataFieldResult, FieldErrorPolicy, LoadOptions};
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.lock().unwrap().write(buf) }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    fn upper(s: String) -> DataFieldResult<String> { Ok(s.to_uppercase()) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn changes_logged_and_streamed() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &numeric).with_on_error(FieldErrorPolicy::WarnAndUseDefault("0000".to_string())),
+            DataFieldDef::new("Name", 4, 10, &upper),
+        ];
+        let data = format!("{:<200}\n{:<200}\n{:<200}\n", "0001BOB", "00X2ann", "0003\"q\"");
+        let options = LoadOptions { audit: true, ..Default::default() };
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+        let changes: Vec<(usize, &str, &str, &str)> = df.audit_log().iter()
+            .map(|e| (e.line_index, e.field.as_str(), e.before.as_str(), e.after.as_str()))
+            .collect();
+        assert_eq!(changes, [(1, "Id", "00X2", "0000"), (1, "Name", "ann", "ANN")]);
+        assert_eq!(df.select(|_| true, &["Name"]).unwrap().audit_log().len(), 1);
+        assert!(DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap().audit_log().is_empty());
+
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = AuditWriter::new(Shared(Arc::clone(&buffer)));
+        let options = LoadOptions { audit_writer: Some(writer.clone()), ..Default::default() };
+        assert_eq!(DataFileReader::new(Cursor::new(&data), &defs, options).filter(|r| r.is_ok()).count(), 2);
+        writer.finish().unwrap();
+        let log = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        assert_eq!(log.lines().collect::<Vec<&str>>(), df.audit_log().iter().map(AuditEntry::to_json).collect::<Vec<String>>());
+    }
+}
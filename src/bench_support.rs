@@ -0,0 +1,93 @@
+//! Synthetic files for benchmarking, shared by the crate's `benches/` suite so that changes
+//! made for performance can be measured against the same data, and usable for benchmarking
+//! your own pipelines. Files are generated with TestGen, so the same seed and row count give
+//! the same file.
+//! ```
+//! use ffreader::{bench_support, DataFile, LoadOptions};
+//! for workload in bench_support::workloads(100) {
+//!     let df = DataFile::try_from_str(&workload.data, &workload.defs, &LoadOptions::default()).unwrap();
+//!     assert_eq!(df.rows().len(), 100);
+//! }
+//! ```
+
+use crate::{processors, DataFieldDef, TestGen, TestValue};
+
+const SEED: u64 = 0x5EED;
+
+/// A layout and a file of rows to load with it.
+pub struct Workload {
+    /// A short name for the workload, e.g. for naming a benchmark.
+    pub name: &'static str,
+    /// The field definitions of the layout.
+    pub defs: Vec<DataFieldDef<'static>>,
+    /// The rows of the file, one per line.
+    pub data: String
+}
+
+impl Workload {
+    /// Generate `rows` rows for the layout.
+    fn new(name: &'static str, defs: Vec<DataFieldDef<'static>>, rows: usize,
+           values: &[(&str, TestValue)]) -> Self {
+        let mut gen = TestGen::new(&defs, SEED);
+        for (field, value) in values {
+            gen = gen.with_value(field, value.clone());
+        }
+        let data = gen.file(rows);
+        Workload { name, defs, data }
+    }
+
+    /// The size of the file, in bytes.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Determine whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Rows of 200 fields of 10 columns each, whose post-processing does nothing: the cost of
+/// splitting wide rows into many fields.
+pub fn wide_rows(rows: usize) -> Workload {
+    let defs = (0..200).map(|i| DataFieldDef::new_owned(format!("Field{}", i), i * 10, i * 10 + 10, Ok)).collect();
+    Workload::new("wide_rows", defs, rows, &[])
+}
+
+/// Rows of the minimum length, with a few short fields whose post-processing does nothing:
+/// the cost of reading and splitting many records.
+pub fn many_rows(rows: usize) -> Workload {
+    let defs = vec![
+        DataFieldDef::new_owned("AccountNo", 0, 10, Ok),
+        DataFieldDef::new_owned("Status", 10, 12, Ok),
+        DataFieldDef::new_owned("Name", 12, 42, Ok),
+        DataFieldDef::new_owned("Balance", 42, 54, Ok),
+    ];
+    Workload::new("many_rows", defs, rows, &[("Balance", TestValue::Digits)])
+}
+
+/// Rows whose fields are normalized by processors: the cost of the callbacks, rather than
+/// of parsing.
+pub fn heavy_callbacks(rows: usize) -> Workload {
+    let defs = vec![
+        DataFieldDef::new_owned("Phone", 0, 10, processors::phone(true)),
+        DataFieldDef::new_owned("Zip", 10, 19, processors::zip()),
+        DataFieldDef::new_owned("Street", 19, 59, processors::collapse_whitespace()),
+        DataFieldDef::new_owned("Direction", 59, 64, processors::street_direction()),
+        DataFieldDef::new_owned("Balance", 64, 76, processors::scale(0.01, 2)),
+        DataFieldDef::new_owned("Name", 76, 106, |s: String| Ok(s.to_lowercase())),
+    ];
+    let values = [
+        ("Phone", TestValue::Digits),
+        ("Zip", TestValue::Digits),
+        ("Direction", TestValue::OneOf(vec!["N".to_string(), "SOUTH".to_string(), "n.w.".to_string()])),
+        ("Balance", TestValue::Digits),
+        ("Name", TestValue::Letters),
+    ];
+    Workload::new("heavy_callbacks", defs, rows, &values)
+}
+
+/// All the workloads, each of `rows` rows.
+pub fn workloads(rows: usize) -> Vec<Workload> {
+    vec![wide_rows(rows), many_rows(rows), heavy_callbacks(rows)]
+}
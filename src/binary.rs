@@ -0,0 +1,150 @@
+//! Compact binary exports: each is an array of maps from field name to value, like the JSON
+//! export.
+use crate::{DataFile, DataRowResult, ExportOptions};
+
+/// Append a MessagePack header for a container or string of the given length: `fix` is the
+/// first byte of the short form (holding lengths below `fix_limit`), `wide` that of the 16 bit
+/// form, followed by the 32 bit form. Strings also have an 8 bit form, given by `narrow`.
+#[cfg(feature = "msgpack")]
+fn msgpack_header(out: &mut Vec<u8>, len: usize, fix: u8, fix_limit: usize, narrow: Option<u8>, wide: u8) {
+    match (len, narrow) {
+        (l, _) if l < fix_limit => out.push(fix | l as u8),
+        (l, Some(n)) if l <= u8::MAX as usize => out.extend([n, l as u8]),
+        (l, _) if l <= u16::MAX as usize => {
+            out.push(wide);
+            out.extend((l as u16).to_be_bytes());
+        }
+        (l, _) => {
+            out.push(wide + 1);
+            out.extend((l as u32).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "msgpack")]
+fn msgpack_str(out: &mut Vec<u8>, s: &str) {
+    msgpack_header(out, s.len(), 0xa0, 32, Some(0xd9), 0xda);
+    out.extend(s.as_bytes());
+}
+
+/// Append a CBOR head with the given major type and argument.
+#[cfg(feature = "cbor")]
+fn cbor_head(out: &mut Vec<u8>, major: u8, n: usize) {
+    let major = major << 5;
+    match n {
+        n if n < 24 => out.push(major | n as u8),
+        n if n <= u8::MAX as usize => out.extend([major | 24, n as u8]),
+        n if n <= u16::MAX as usize => {
+            out.push(major | 25);
+            out.extend((n as u16).to_be_bytes());
+        }
+        n if n <= u32::MAX as usize => {
+            out.push(major | 26);
+            out.extend((n as u32).to_be_bytes());
+        }
+        n => {
+            out.push(major | 27);
+            out.extend((n as u64).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+fn cbor_str(out: &mut Vec<u8>, s: &str) {
+    cbor_head(out, 3, s.len());
+    out.extend(s.as_bytes());
+}
+
+impl DataFile {
+    /// Generate a MessagePack version of the data. Only available with the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        self.to_msgpack_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate a MessagePack version of the data using the provided ExportOptions: an array
+    /// with a map of field names to values for each row.
+    /// Fails if a selected field name is not found in a row.
+    /// Only available with the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack_with_options(&self, options: &ExportOptions) -> DataRowResult<Vec<u8>> {
+        let records = options.records(self)?;
+        let mut out = vec![];
+        msgpack_header(&mut out, records.len(), 0x90, 16, None, 0xdc);
+        for record in records {
+            msgpack_header(&mut out, record.len(), 0x80, 16, None, 0xde);
+            for (name, value) in record {
+                msgpack_str(&mut out, &name);
+                match value {
+                    Some(v) => msgpack_str(&mut out, &v),
+                    None => out.push(0xc0)
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Generate a CBOR version of the data. Only available with the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        self.to_cbor_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate a CBOR version of the data using the provided ExportOptions: an array with a
+    /// map of field names to values for each row.
+    /// Fails if a selected field name is not found in a row.
+    /// Only available with the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor_with_options(&self, options: &ExportOptions) -> DataRowResult<Vec<u8>> {
+        let records = options.records(self)?;
+        let mut out = vec![];
+        cbor_head(&mut out, 4, records.len());
+        for record in records {
+            cbor_head(&mut out, 5, record.len());
+            for (name, value) in record {
+                cbor_str(&mut out, &name);
+                match value {
+                    Some(v) => cbor_str(&mut out, &v),
+                    None => out.push(0xf6)
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_encoded() {
+        let mut out = vec![];
+        msgpack_str(&mut out, "ab");
+        assert_eq!(out, b"\xa2ab");
+        let mut out = vec![];
+        msgpack_str(&mut out, &"x".repeat(40));
+        assert_eq!(&out[..2], b"\xd9\x28");
+        let mut out = vec![];
+        msgpack_header(&mut out, 20, 0x90, 16, None, 0xdc);
+        msgpack_header(&mut out, 70000, 0x80, 16, None, 0xde);
+        assert_eq!(out, b"\xdc\x00\x14\xdf\x00\x01\x11\x70");
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_encoded() {
+        let mut out = vec![];
+        cbor_head(&mut out, 4, 1);
+        cbor_head(&mut out, 5, 1);
+        cbor_str(&mut out, "a");
+        cbor_str(&mut out, &"x".repeat(30));
+        assert_eq!(&out[..6], b"\x81\xa1\x61a\x78\x1e");
+        let mut out = vec![];
+        cbor_head(&mut out, 3, 500);
+        assert_eq!(out, b"\x79\x01\xf4");
+    }
+}
@@ -0,0 +1,276 @@
+use std::path::Path;
+use std::sync::Arc;
+use crate::{ColumnAggregate, DataField, DataFieldDef, DataFile, DataFileReader, DataFileResult, DataRow, LoadOptions,
+            LoadSummary, LoadWarning, NameMatching};
+use crate::aggregate::aggregate_values;
+#[cfg(feature = "csv")]
+use crate::format::csv_field;
+
+/// The values of one field across every row of a ColumnarFile.
+#[derive(Clone)]
+struct Column {
+    name: Arc<str>,
+    aliases: Arc<[String]>,
+    raw: Vec<String>,
+    data: Vec<Option<String>>,
+    implied_decimals: usize
+}
+
+/// Holds loaded rows column by column, one vector per field, rather than as a DataRow of
+/// DataFields each. Storing a field's values together takes far fewer allocations than a
+/// DataFile of the same rows, and reading a whole column (for exports and aggregates) doesn't
+/// have to visit every row.
+///
+/// Load one with `ColumnarFile::try_load_with_options()`, which streams the file so that the rows
+/// are never all held as DataRows, or convert a DataFile with `ColumnarFile::from()`. DataRows
+/// are made on demand by `row()`.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{ColumnarFile, DataFieldDef, DataFieldResult, LoadOptions};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Amount", 0, 11, &post_function).with_implied_decimals(2)];
+/// let columns = ColumnarFile::try_load_with_options(Path::new("extract.txt"), &defs, LoadOptions::default()).unwrap();
+/// println!("{} rows, total {:?}", columns.len(), columns.column_sum("Amount").value);
+/// ```
+#[derive(Clone)]
+pub struct ColumnarFile {
+    columns: Vec<Column>,
+    line_indexes: Vec<Option<usize>>,
+    load_warnings: Vec<LoadWarning>,
+    summary: LoadSummary,
+    name_matching: NameMatching
+}
+
+impl ColumnarFile {
+    /// Load a file as with `DataFile::try_load_with_options()`, storing the rows by column.
+    pub fn try_load_with_options<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions)
+        -> DataFileResult<ColumnarFile> {
+        let name_matching = options.name_matching;
+        let mut columnar = ColumnarFile::empty(row_defs, name_matching);
+        let mut reader = DataFileReader::open(path, row_defs, options)?;
+        for row in &mut reader {
+            columnar.push(row?);
+        }
+        let (summary, warnings) = reader.finish();
+        columnar.summary = summary;
+        columnar.load_warnings = warnings;
+        Ok(columnar)
+    }
+
+    /// Make a file with no rows and a column for each field of the layout: one for each
+    /// definition, followed by one for each part it is split into.
+    fn empty(row_defs: &[DataFieldDef], name_matching: NameMatching) -> ColumnarFile {
+        let columns = row_defs.iter().flat_map(|d| {
+            let (name, aliases) = d.shared_names();
+            let field = Column { name, aliases, raw: vec![], data: vec![], implied_decimals: d.implied_decimals };
+            let parts = d.splitter.iter().flat_map(|s| s.names()).map(|part| {
+                Column { name: Arc::from(part), aliases: Arc::from([]), raw: vec![], data: vec![], implied_decimals: 0 }
+            });
+            std::iter::once(field).chain(parts).collect::<Vec<Column>>()
+        }).collect();
+        ColumnarFile {
+            columns,
+            line_indexes: vec![],
+            load_warnings: vec![],
+            summary: LoadSummary::default(),
+            name_matching
+        }
+    }
+
+    /// Move a row's fields to the end of each column. Rows have a field for each column, in order,
+    /// as a row of the layout has a field for each definition followed by any split parts.
+    fn push(&mut self, row: DataRow) {
+        self.line_indexes.push(row.line_index());
+        for (column, field) in self.columns.iter_mut().zip(row.into_fields()) {
+            let (raw, data) = field.into_parts();
+            column.raw.push(raw);
+            column.data.push(data);
+        }
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize {
+        self.line_indexes.len()
+    }
+
+    /// Determine whether there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.line_indexes.is_empty()
+    }
+
+    /// Get the names of the columns, in order.
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(|c| &*c.name).collect()
+    }
+
+    fn find(&self, name: &str) -> Option<&Column> {
+        let matches = |n: &str| self.name_matching.matches(name, n);
+        self.columns.iter().find(|c| matches(&c.name))
+            .or_else(|| self.columns.iter().find(|c| c.aliases.iter().any(|a| matches(a))))
+    }
+
+    /// Get the data of the named field (or alias) in every row, None where it is blank. Names are
+    /// matched as set by `LoadOptions::name_matching`.
+    pub fn column(&self, name: &str) -> Option<&[Option<String>]> {
+        self.find(name).map(|c| c.data.as_slice())
+    }
+
+    /// Make a DataRow of the row at the given index. Typed values (see
+    /// `DataFieldDef::with_parser()`) are not stored, so the fields have none.
+    pub fn row(&self, index: usize) -> Option<DataRow> {
+        let line_index = *self.line_indexes.get(index)?;
+        let fields = self.columns.iter()
+            .map(|c| DataField::from_parts(c.name.clone(), c.aliases.clone(), c.raw[index].clone(), c.data[index].clone()))
+            .collect();
+        Some(DataRow::from_fields(fields, line_index).with_name_matching(self.name_matching).with_row_id(Some(index)))
+    }
+
+    /// Iterate over the rows, making a DataRow of each.
+    pub fn rows(&self) -> impl Iterator<Item = DataRow> + '_ {
+        (0..self.len()).filter_map(|i| self.row(i))
+    }
+
+    /// Get a reference to the list of warnings generated during loading.
+    pub fn warnings(&self) -> &Vec<LoadWarning> {
+        &self.load_warnings
+    }
+
+    /// Get the summary of the load.
+    pub fn summary(&self) -> &LoadSummary {
+        &self.summary
+    }
+
+    fn aggregate<F: Fn(&[f64]) -> f64>(&self, name: &str, f: F) -> ColumnAggregate {
+        let Some(column) = self.find(name) else { return aggregate_values(std::iter::empty(), 0, f) };
+        let values = self.line_indexes.iter().zip(&column.data)
+            .map(|(line_index, data)| (*line_index, data.as_deref().unwrap_or("")));
+        aggregate_values(values, column.implied_decimals, f)
+    }
+
+    /// Sum a numeric column, as with `DataFile::column_sum()`.
+    pub fn column_sum(&self, name: &str) -> ColumnAggregate {
+        self.aggregate(name, |v| v.iter().sum())
+    }
+
+    /// Average a numeric column, as with `DataFile::column_mean()`.
+    pub fn column_mean(&self, name: &str) -> ColumnAggregate {
+        self.aggregate(name, |v| v.iter().sum::<f64>() / v.len() as f64)
+    }
+
+    /// Find the smallest value in a numeric column, as with `DataFile::column_min()`.
+    pub fn column_min(&self, name: &str) -> ColumnAggregate {
+        self.aggregate(name, |v| v.iter().copied().fold(f64::INFINITY, f64::min))
+    }
+
+    /// Find the largest value in a numeric column, as with `DataFile::column_max()`.
+    pub fn column_max(&self, name: &str) -> ColumnAggregate {
+        self.aggregate(name, |v| v.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+    }
+
+    /// Generate a CSV version of the data, with a header of the column names, as
+    /// `DataFile::to_csv()` would for the same rows.
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self) -> String {
+        let mut csv = self.columns.iter().map(|c| csv_field(&c.name)).collect::<Vec<String>>().join(",");
+        csv.push('\n');
+        for i in 0..self.len() {
+            let values: Vec<String> = self.columns.iter()
+                .map(|c| csv_field(c.data[i].as_deref().unwrap_or("")))
+                .collect();
+            csv.push_str(&values.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl From<DataFile> for ColumnarFile {
+    /// Convert a DataFile by moving its rows into columns, those of the first row.
+    fn from(data_file: DataFile) -> Self {
+        let first = data_file.rows().first();
+        let name_matching = first.map_or(NameMatching::default(), |r| r.name_matching());
+        let columns = first.map(|r| r.fields().iter().map(|f| {
+            let (name, aliases) = f.shared_names();
+            Column {
+                implied_decimals: data_file.implied_decimals(&name),
+                name,
+                aliases,
+                raw: Vec::with_capacity(data_file.rows().len()),
+                data: Vec::with_capacity(data_file.rows().len())
+            }
+        }).collect()).unwrap_or_default();
+
+        let mut columnar = ColumnarFile {
+            columns,
+            line_indexes: Vec::with_capacity(data_file.rows().len()),
+            load_warnings: data_file.warnings().clone(),
+            summary: data_file.summary().clone(),
+            name_matching
+        };
+        for row in data_file {
+            columnar.push(row);
+        }
+        columnar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldResult, Splitter};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn columns_match_rows() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 3, &echo_ok),
+            DataFieldDef::new("Amount", 3, 8, &echo_ok).with_implied_decimals(2).with_alias("Amt"),
+        ];
+        let data = format!("{:<200}\nshort\n{:<200}\n", "00100150", "002");
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        #[cfg(feature = "csv")]
+        let expected_csv = df.to_csv();
+        let expected_rows: Vec<Vec<String>> = df.iter().map(|r| r.fields().iter().map(|f| f.data()).collect()).collect();
+
+        let path = std::env::temp_dir().join(format!("ffreader_columnar_{}.txt", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let loaded = ColumnarFile::try_load_with_options(&path, &defs, LoadOptions::default()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        for columnar in [loaded, ColumnarFile::from(df)] {
+            assert_eq!(columnar.len(), 2);
+            assert_eq!(columnar.column_names(), ["Id", "Amount"]);
+            assert_eq!(columnar.column("Amt").unwrap(), [Some("00150".to_string()), None]);
+            assert_eq!(columnar.column_sum("Amount").value, Some(1.5));
+            assert_eq!(columnar.warnings().len(), 1);
+            let rows: Vec<Vec<String>> = columnar.rows().map(|r| r.fields().iter().map(|f| f.data()).collect()).collect();
+            assert_eq!(rows, expected_rows);
+            assert_eq!(columnar.row(1).unwrap().line_index(), Some(2));
+            #[cfg(feature = "csv")]
+            assert_eq!(columnar.to_csv(), expected_csv);
+        }
+    }
+
+    #[test]
+    fn split_parts_columns() {
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 9, &echo_ok).with_splitter(Splitter::delimiter(' ', &["First", "Last"])),
+            DataFieldDef::new("Status", 9, 10, &echo_ok),
+        ];
+        let data = format!("{:<200}\n", "JOHN DOE A");
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        let path = std::env::temp_dir().join(format!("ffreader_columnar_split_{}.txt", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let loaded = ColumnarFile::try_load_with_options(&path, &defs, LoadOptions::default()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        for columnar in [loaded, ColumnarFile::from(df)] {
+            assert_eq!(columnar.column_names(), ["Name", "First", "Last", "Status"]);
+            assert_eq!(columnar.column("Status").unwrap(), [Some("A".to_string())]);
+            assert_eq!(columnar.column("Last").unwrap(), [Some("DOE".to_string())]);
+        }
+    }
+}
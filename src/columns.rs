@@ -0,0 +1,104 @@
+use std::fmt::{Display, Formatter};
+use unicode_width::UnicodeWidthChar;
+
+/// The unit field indexes are measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnUnit {
+    /// Bytes, for ASCII files (the default). Non-ASCII data is rejected.
+    #[default]
+    Bytes,
+    /// Characters of UTF-8 text, for producers that count each character as one column
+    /// whatever its encoded length.
+    Chars,
+    /// Display columns, for UTF-8 reports aligned for a terminal or printer, where East Asian
+    /// wide characters take two columns. A wide character straddling a field boundary belongs
+    /// to the field it starts in.
+    DisplayWidth
+}
+
+impl ColumnUnit {
+    /// The name of the unit in schema documents.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ColumnUnit::Bytes => "bytes",
+            ColumnUnit::Chars => "chars",
+            ColumnUnit::DisplayWidth => "display_width"
+        }
+    }
+
+    /// Find the unit with the given name in schema documents.
+    pub(crate) fn from_name(name: &str) -> Option<ColumnUnit> {
+        [ColumnUnit::Bytes, ColumnUnit::Chars, ColumnUnit::DisplayWidth].into_iter().find(|u| u.name() == name)
+    }
+
+    /// Determine whether the unit requires ASCII data.
+    pub(crate) fn ascii_only(&self) -> bool {
+        *self == ColumnUnit::Bytes
+    }
+
+    /// Get the length of a row in this unit.
+    pub(crate) fn len(&self, row: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => row.len(),
+            ColumnUnit::Chars => row.chars().count(),
+            ColumnUnit::DisplayWidth => row.chars().map(width).sum()
+        }
+    }
+
+    /// Get the byte offset of the column in a row, or the length of the row if the column is
+    /// past its end. In bytes, a column inside a multi-byte character (only found in rows
+    /// that will be rejected as non-ASCII) is taken as the start of the character.
+    pub(crate) fn byte_offset(&self, row: &str, column: usize) -> usize {
+        match self {
+            ColumnUnit::Bytes => row.floor_char_boundary(column.min(row.len())),
+            ColumnUnit::Chars => row.char_indices().nth(column).map(|(i, _)| i).unwrap_or(row.len()),
+            ColumnUnit::DisplayWidth => {
+                let mut position = 0;
+                for (i, c) in row.char_indices() {
+                    if position >= column {
+                        return i;
+                    }
+                    position += width(c);
+                }
+                row.len()
+            }
+        }
+    }
+
+    /// Get the text of a row between two columns.
+    pub(crate) fn slice<'a>(&self, row: &'a str, start: usize, end: usize) -> &'a str {
+        let start = self.byte_offset(row, start);
+        &row[start..self.byte_offset(row, end).max(start)]
+    }
+}
+
+impl Display for ColumnUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The display width of a character; control characters are given a width of 0.
+fn width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_columns_sliced() {
+        let row = "東京都 ABC";
+        let unit = ColumnUnit::DisplayWidth;
+        assert_eq!(unit.len(row), 10);
+        assert_eq!(unit.slice(row, 0, 6), "東京都");
+        assert_eq!(unit.slice(row, 7, 10), "ABC");
+        assert_eq!(unit.slice(row, 1, 4), "京");
+        assert_eq!(ColumnUnit::Bytes.slice("ABCDEF", 2, 4), "CD");
+        assert_eq!(ColumnUnit::Bytes.slice("AB\u{e9}CD", 3, 5), "\u{e9}C");
+        assert_eq!(ColumnUnit::Chars.len(row), 7);
+        assert_eq!(ColumnUnit::Chars.slice(row, 1, 5), "京都 A");
+        assert_eq!(ColumnUnit::from_name("chars"), Some(ColumnUnit::Chars));
+    }
+}
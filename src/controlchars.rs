@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use crate::ColumnUnit;
+
+/// What to do with control characters (tabs, NULs, escapes and the like) inside fields, which
+/// otherwise pass straight through to the processed data and on to exports such as CSV.
+/// Leading and trailing whitespace, including tabs, is trimmed from fields before this is
+/// applied, so only characters within a value count.
+/// ```
+/// use ffreader::{ControlCharPolicy, DataFile, DataFieldDef, DataFieldResult, LoadOptions, WarningKind};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Name", 0, 10, &post_function)];
+/// let options = LoadOptions { control_chars: ControlCharPolicy::Replace(' '), ..Default::default() };
+/// let df = DataFile::try_from_str(&format!("{:<200}\n", "ANN\tLEE"), &defs, &options).unwrap();
+/// assert_eq!(df.rows()[0].get("Name").unwrap().data(), "ANN LEE");
+/// assert_eq!(df.warnings()[0].kind(), WarningKind::ControlCharacters);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlCharPolicy {
+    /// Keep them (the default).
+    #[default]
+    Keep,
+    /// Remove them, and record a `WarningKind::ControlCharacters` warning giving their columns.
+    Strip,
+    /// Replace each with the character (e.g. a space), and record a warning as for `Strip`.
+    Replace(char),
+    /// Fail the field with `DataFieldError::ControlCharacters`, which is then handled by its
+    /// FieldErrorPolicy.
+    Error
+}
+
+impl ControlCharPolicy {
+    /// Find the control characters in the trimmed text of a field taken from `raw`, which
+    /// starts at `start_idx` in the row, returning their columns in the row and the text with
+    /// them stripped or replaced. Nothing is looked for when they are kept.
+    pub(crate) fn apply<'a>(self, raw: &str, text: &'a str, start_idx: usize, unit: ColumnUnit)
+        -> (Cow<'a, str>, Vec<usize>) {
+        if self == ControlCharPolicy::Keep || !text.contains(char::is_control) {
+            return (Cow::Borrowed(text), vec![]);
+        }
+
+        // leading whitespace can't contain the first character of the trimmed text
+        let lead = raw.find(text).unwrap_or_default();
+        let columns = text.char_indices()
+            .filter(|(_, c)| c.is_control())
+            .map(|(i, _)| start_idx + unit.len(&raw[..lead + i]))
+            .collect();
+        let cleaned = match self {
+            ControlCharPolicy::Replace(r) => text.chars().map(|c| if c.is_control() { r } else { c }).collect(),
+            _ => text.chars().filter(|c| !c.is_control()).collect()
+        };
+        (Cow::Owned(cleaned), columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFile, DataFieldDef, DataFieldError, DataFieldResult, DataRowError, LoadOptions, RowParser, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn control_characters_handled_by_policy() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok), DataFieldDef::new("Name", 4, 14, &echo_ok)];
+        let line = format!("{:<200}", "0001\tAN\0N\u{1b}LEE");
+        let load = |policy| {
+            let options = LoadOptions { control_chars: policy, ..Default::default() };
+            DataFile::try_from_str(&format!("{}\n", line), &defs, &options).unwrap()
+        };
+
+        let kept = load(ControlCharPolicy::Keep);
+        assert_eq!(kept.rows()[0].get("Name").unwrap().data(), "AN\0N\u{1b}LEE");
+        assert!(kept.warnings().is_empty());
+
+        let stripped = load(ControlCharPolicy::Strip);
+        assert_eq!(stripped.rows()[0].get("Name").unwrap().data(), "ANNLEE");
+        assert_eq!(stripped.warnings()[0].kind(), WarningKind::ControlCharacters);
+        assert_eq!(stripped.warnings()[0].field_name(), Some("Name"));
+        assert!(stripped.warnings()[0].message().contains("columns 7, 9"));
+        assert_eq!(load(ControlCharPolicy::Replace(' ')).rows()[0].get("Name").unwrap().data(), "AN N LEE");
+
+        let failed = load(ControlCharPolicy::Error);
+        assert!(failed.rows().is_empty());
+        assert_eq!(failed.warnings()[0].kind(), WarningKind::FieldError);
+
+        let options = LoadOptions { control_chars: ControlCharPolicy::Error, ..Default::default() };
+        let parser = RowParser::new(&defs, options).unwrap();
+        let mut buf = parser.buffer();
+        assert!(matches!(parser.parse_into(&mut buf, &line),
+                         Err(DataRowError::FieldError(DataFieldError::ControlCharacters(_, c))) if c == [7, 9]));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{DataFieldDef, DataFileError, DataFileResult, DataRow, LoadOptions, LoadWarning, NameMatching};
+use crate::aggregate::parse_number;
+
+/// What the detail rows are measured by for a ControlTotal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlMeasure {
+    /// The number of rows loaded.
+    Count,
+    /// The sum of the named numeric field over the rows loaded, honoring its implied decimals.
+    Sum(String)
+}
+
+/// A check balancing the detail rows of a file against a total given in its trailer record,
+/// evaluated at the end of the load. Set them with `LoadOptions::control_totals`.
+///
+/// The trailer is the line starting with `trailer_prefix`; it is skipped rather than loaded as
+/// a row. A mismatch (or a missing or non-numeric total) is reported as a
+/// `WarningKind::ControlTotalMismatch` warning, or fails the load with
+/// `DataFileError::ControlTotalMismatch` if `fail_on_mismatch()` is set. Checked when loading a
+/// DataFile or streaming with DataFileReader.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{ControlTotal, DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Amount", 10, 21, &post_function).with_implied_decimals(2)];
+/// let options = LoadOptions {
+///     control_totals: vec![
+///         ControlTotal::count("TRL", 3, 12),
+///         ControlTotal::sum("Amount", "TRL", 12, 25).with_implied_decimals(2).fail_on_mismatch(),
+///     ],
+///     ..Default::default()
+/// };
+/// let df = DataFile::try_load_with_options(Path::new("nightly.txt"), &defs, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlTotal {
+    /// What is totalled.
+    pub measure: ControlMeasure,
+    /// The start of the trailer line, identifying it.
+    pub trailer_prefix: String,
+    /// The start index of the total in the trailer line.
+    pub start_idx: usize,
+    /// The end index (exclusive) of the total in the trailer line.
+    pub end_idx: usize,
+    /// The number of implied decimal places of the total, as for
+    /// `DataFieldDef::with_implied_decimals()`.
+    pub implied_decimals: usize,
+    /// Whether a mismatch fails the load rather than being recorded as a warning.
+    pub fail_on_mismatch: bool
+}
+
+impl ControlTotal {
+    /// Check the number of rows loaded against the total at the given columns of the trailer.
+    pub fn count(trailer_prefix: impl ToString, start_idx: usize, end_idx: usize) -> Self {
+        ControlTotal {
+            measure: ControlMeasure::Count,
+            trailer_prefix: trailer_prefix.to_string(),
+            start_idx,
+            end_idx,
+            implied_decimals: 0,
+            fail_on_mismatch: false
+        }
+    }
+
+    /// Check the sum of a numeric field against the total at the given columns of the trailer.
+    pub fn sum(field_name: impl ToString, trailer_prefix: impl ToString, start_idx: usize, end_idx: usize) -> Self {
+        ControlTotal {
+            measure: ControlMeasure::Sum(field_name.to_string()),
+            ..Self::count(trailer_prefix, start_idx, end_idx)
+        }
+    }
+
+    /// Set the number of implied decimal places of the trailer's total.
+    pub fn with_implied_decimals(mut self, implied_decimals: usize) -> Self {
+        self.implied_decimals = implied_decimals;
+        self
+    }
+
+    /// Fail the load on a mismatch rather than recording a warning.
+    pub fn fail_on_mismatch(mut self) -> Self {
+        self.fail_on_mismatch = true;
+        self
+    }
+
+    /// Determine whether a line is this total's trailer.
+    pub(crate) fn is_trailer(&self, line: &str) -> bool {
+        !self.trailer_prefix.is_empty() && line.starts_with(&self.trailer_prefix)
+    }
+}
+
+/// Accumulates the detail rows and trailers of a load for its control totals.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ControlTally {
+    trailers: Vec<Option<(usize, String)>>,
+    rows: usize,
+    sums: Vec<f64>
+}
+
+impl ControlTally {
+    /// Note a record, keeping it if it is the trailer of a control total.
+    pub(crate) fn record(&mut self, options: &LoadOptions, line_index: usize, line: &str) {
+        self.trailers.resize(options.control_totals.len(), None);
+        for (trailer, total) in self.trailers.iter_mut().zip(&options.control_totals) {
+            if total.is_trailer(line) {
+                *trailer = Some((line_index, line.to_string()));
+            }
+        }
+    }
+
+    /// Add a loaded row to the totals.
+    pub(crate) fn row(&mut self, options: &LoadOptions, row_defs: &[DataFieldDef], row: &DataRow) {
+        self.rows += 1;
+        self.sums.resize(options.control_totals.len(), 0.0);
+        for (sum, total) in self.sums.iter_mut().zip(&options.control_totals) {
+            if let ControlMeasure::Sum(field_name) = &total.measure {
+                let value = row.get(field_name).and_then(|f| parse_number(&f.data(), implied_decimals(row_defs, field_name, options.name_matching)));
+                *sum += value.unwrap_or(0.0);
+            }
+        }
+    }
+
+    /// Check the totals at the end of the load, adding a warning for each mismatch or failing
+    /// on the first that is set to. `last_line_index` is that of the last line read, which
+    /// warnings about a missing trailer refer to.
+    pub(crate) fn finish(&self, options: &LoadOptions, row_defs: &[DataFieldDef], last_line_index: usize,
+                         warnings: &mut Vec<LoadWarning>) -> DataFileResult<()> {
+        for (i, total) in options.control_totals.iter().enumerate() {
+            let trailer = self.trailers.get(i).cloned().flatten();
+            let Some(message) = self.mismatch(total, row_defs, options.name_matching, trailer.as_ref().map(|(_, t)| t.as_str()), i) else {
+                continue
+            };
+            if total.fail_on_mismatch {
+                return Err(DataFileError::ControlTotalMismatch(message));
+            }
+            let (line_index, line) = trailer.unwrap_or((last_line_index, String::new()));
+            warnings.push(LoadWarning::control_total_mismatch(line_index, &line, message));
+        }
+        Ok(())
+    }
+
+    /// Describe how a control total doesn't balance, if it doesn't.
+    fn mismatch(&self, total: &ControlTotal, row_defs: &[DataFieldDef], name_matching: NameMatching,
+                trailer: Option<&str>, i: usize)
+        -> Option<String> {
+        let Some(trailer) = trailer else {
+            return Some(format!("No trailer line starting with \"{}\"", total.trailer_prefix));
+        };
+        let text = trailer.get(total.start_idx..total.end_idx.min(trailer.len())).unwrap_or("");
+        let Some(expected) = parse_number(text, total.implied_decimals) else {
+            return Some(format!("Trailer total \"{}\" is not numeric", text.trim()));
+        };
+
+        let (description, actual, decimals) = match &total.measure {
+            ControlMeasure::Count => ("Row count".to_string(), self.rows as f64, 0),
+            ControlMeasure::Sum(field_name) => (format!("Sum of {}", field_name), self.sums.get(i).copied().unwrap_or(0.0),
+                                                implied_decimals(row_defs, field_name, name_matching).max(total.implied_decimals))
+        };
+        let scale = 10f64.powi(decimals as i32);
+        if (actual * scale).round() == (expected * scale).round() {
+            return None;
+        }
+        Some(format!("{} is {:.*} but the trailer total is {:.*} (difference {:.*})", description,
+                     decimals, actual, decimals, expected, decimals, actual - expected))
+    }
+}
+
+/// Get the implied decimals of the named field, found by name and then by alias as
+/// `DataRow::get()` finds fields.
+fn implied_decimals(row_defs: &[DataFieldDef], field_name: &str, name_matching: NameMatching) -> usize {
+    let matches = |n: &str| name_matching.matches(field_name, n);
+    row_defs.iter().find(|d| matches(&d.name))
+        .or_else(|| row_defs.iter().find(|d| d.aliases.iter().any(|a| matches(a))))
+        .map_or(0, |d| d.implied_decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::{DataFile, DataFileReader, DataFieldResult, WarningKind};
+    use super::*;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn totals_balanced_against_trailer() {
+        let defs = vec![DataFieldDef::new("Amount", 0, 6, &echo_ok).with_implied_decimals(2)];
+        let data = format!("{:<200}\n{:<200}\nTRL000002000000175\n", "000150", "000025");
+        let options = |totals| LoadOptions { control_totals: totals, ..Default::default() };
+
+        let balanced = options(vec![ControlTotal::count("TRL", 3, 9), ControlTotal::sum("Amount", "TRL", 9, 18).with_implied_decimals(2)]);
+        let df = DataFile::try_from_str(&data, &defs, &balanced).unwrap();
+        assert!(df.warnings().is_empty());
+        assert_eq!(df.summary().rows_skipped, 1);
+
+        let unbalanced = options(vec![ControlTotal::count("TRL", 3, 8), ControlTotal::count("HDR", 3, 9)]);
+        let df = DataFile::try_from_str(&data, &defs, &unbalanced).unwrap();
+        let messages: Vec<String> = df.warnings().iter().map(|w| w.message()).collect();
+        assert_eq!(messages, ["Row count is 2 but the trailer total is 0 (difference 2)",
+                              "No trailer line starting with \"HDR\""]);
+        assert_eq!(df.warnings()[0].kind(), WarningKind::ControlTotalMismatch);
+        assert_eq!(df.warnings()[0].line_index(), 2);
+
+        let failing = options(vec![ControlTotal::sum("Amount", "TRL", 9, 18).fail_on_mismatch()]);
+        assert!(matches!(DataFile::try_from_str(&data, &defs, &failing),
+                         Err(DataFileError::ControlTotalMismatch(m)) if m == "Sum of Amount is 1.75 but the trailer total is 175.00 (difference -173.25)"));
+        let streamed: Vec<DataFileResult<DataRow>> = DataFileReader::new(Cursor::new(&data), &defs, failing).collect();
+        assert!(matches!(streamed.last(), Some(Err(DataFileError::ControlTotalMismatch(_)))));
+
+        let defs = vec![DataFieldDef::new("AmountDue", 0, 6, &echo_ok).with_implied_decimals(2).with_alias("AMT")];
+        for field_name in ["AMT", "amount due"] {
+            let by_alias = LoadOptions {
+                control_totals: vec![ControlTotal::sum(field_name, "TRL", 9, 18).with_implied_decimals(2).fail_on_mismatch()],
+                name_matching: NameMatching::Normalized,
+                ..Default::default()
+            };
+            assert!(DataFile::try_from_str(&data, &defs, &by_alias).unwrap().warnings().is_empty());
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use crate::{DataField, DataFieldDef, DataFileError, DataFileReader, DataFileResult, DataRow, FixedWidthWriter,
+            LoadOptions, LoadSummary};
+use crate::datafile::check_layout;
+
+/// Converts a feed from one layout to another: rows are read with the old layout and written
+/// as fixed-width lines of the new one, so that migrating a feed is a matter of configuration
+/// rather than code.
+///
+/// Each field of the new layout takes the processed data of the field of the old layout with
+/// the same name (or alias), or the one set with `rename()`. Fields new to the layout take the
+/// value set with `with_default()`, or are left blank, and fields of the old layout the new
+/// one doesn't have are dropped; `added()` and `dropped()` list them, to review a mapping.
+/// ```
+/// use std::io::Cursor;
+/// use ffreader::{DataFieldDef, DataFieldResult, LayoutConverter, LoadOptions};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let old = vec![
+///     DataFieldDef::new("AccountNo", 0, 10, &post_function),
+///     DataFieldDef::new("Branch", 10, 13, &post_function),
+///     DataFieldDef::new("Name", 13, 33, &post_function),
+/// ];
+/// let new = vec![
+///     DataFieldDef::new("Account", 0, 12, &post_function),
+///     DataFieldDef::new("Name", 12, 42, &post_function),
+///     DataFieldDef::new("Country", 42, 44, &post_function),
+/// ];
+/// let converter = LayoutConverter::new(&old, &new).unwrap()
+///     .rename("AccountNo", "Account")
+///     .with_default("Country", "US");
+/// assert_eq!(converter.dropped(), ["Branch"]);
+///
+/// let mut output = vec![];
+/// let input = format!("{:<200}\n", "5412345678001ANN LEE");
+/// let summary = converter.convert(Cursor::new(input), &mut output, LoadOptions::default()).unwrap();
+/// assert_eq!(summary.rows_loaded, 1);
+/// assert_eq!(String::from_utf8(output).unwrap(), format!("{:<12}{:<30}US\n", "5412345678", "ANN LEE"));
+/// ```
+pub struct LayoutConverter<'a> {
+    from: &'a Vec<DataFieldDef<'a>>,
+    to: &'a Vec<DataFieldDef<'a>>,
+    renames: HashMap<String, String>,
+    defaults: HashMap<String, String>
+}
+
+impl<'a> LayoutConverter<'a> {
+    /// Make a converter from rows of the `from` layout to lines of the `to` layout. Fails if
+    /// either layout is invalid; see `schema::validate()`.
+    pub fn new(from: &'a Vec<DataFieldDef<'a>>, to: &'a Vec<DataFieldDef<'a>>) -> DataFileResult<Self> {
+        check_layout(from)?;
+        check_layout(to)?;
+        Ok(LayoutConverter { from, to, renames: HashMap::new(), defaults: HashMap::new() })
+    }
+
+    /// Fill the field `to_name` of the new layout from the field `from_name` of the old one.
+    pub fn rename(mut self, from_name: impl ToString, to_name: impl ToString) -> Self {
+        self.renames.insert(to_name.to_string(), from_name.to_string());
+        self
+    }
+
+    /// Fill a field new to the layout with this value rather than leaving it blank.
+    pub fn with_default(mut self, field_name: impl ToString, value: impl ToString) -> Self {
+        self.defaults.insert(field_name.to_string(), value.to_string());
+        self
+    }
+
+    /// Get the name of the field of the old layout that fills a field of the new one, if any.
+    fn source(&self, def: &DataFieldDef) -> Option<&'a DataFieldDef<'a>> {
+        let name = self.renames.get(&def.name).unwrap_or(&def.name);
+        self.from.iter().find(|d| &d.name == name || d.aliases.contains(name))
+    }
+
+    /// Get the names of the fields of the new layout that aren't filled from the old one, and
+    /// so take their default or are left blank.
+    pub fn added(&self) -> Vec<&str> {
+        self.to.iter().filter(|d| self.source(d).is_none()).map(|d| d.name.as_str()).collect()
+    }
+
+    /// Get the names of the fields of the old layout that don't fill any of the new one.
+    pub fn dropped(&self) -> Vec<&str> {
+        let used: Vec<&str> = self.to.iter().filter_map(|d| self.source(d)).map(|d| d.name.as_str()).collect();
+        self.from.iter().map(|d| d.name.as_str()).filter(|n| !used.contains(n)).collect()
+    }
+
+    /// Make the row of the new layout for a row of the old one.
+    pub fn convert_row(&self, row: &DataRow) -> DataRow {
+        let fields = self.to.iter().map(|def| {
+            let data = match self.source(def) {
+                Some(source) => row.get(&source.name).map(|f| f.data()).unwrap_or_default(),
+                None => self.defaults.get(&def.name).cloned().unwrap_or_default()
+            };
+            DataField::derived(&def.name, data)
+        }).collect();
+        DataRow::from_fields(fields, row.line_index()).with_row_id(row.row_id())
+    }
+
+    /// Read rows of the old layout with the LoadOptions and write them as lines of the new
+    /// one, returning the summary of the rows read. Rows that fail to load are left out, as
+    /// when loading, and recorded in the summary. Fails if reading or writing fails.
+    pub fn convert<R: BufRead, W: Write>(&self, reader: R, writer: W, options: LoadOptions) -> DataFileResult<LoadSummary> {
+        let write_error = |e| DataFileError::FileError(PathBuf::new(), e);
+        let mut rows = DataFileReader::new(reader, self.from, options);
+        let mut writer = FixedWidthWriter::new(writer, self.to);
+        for row in rows.by_ref() {
+            writer.write_row(&self.convert_row(&row?)).map_err(write_error)?;
+        }
+        writer.finish().map_err(write_error)?;
+        Ok(rows.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldError, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn rows_converted_between_layouts() {
+        let old = vec![
+            DataFieldDef::new("Id", 0, 4, &numeric),
+            DataFieldDef::new("Status", 4, 5, &echo_ok).with_alias("Code"),
+            DataFieldDef::new("Legacy", 5, 8, &echo_ok),
+        ];
+        let new = vec![
+            DataFieldDef::new("Code", 0, 2, &echo_ok),
+            DataFieldDef::new("Number", 2, 8, &echo_ok).with_alignment(crate::Alignment::Right).with_pad_char('0'),
+            DataFieldDef::new("Region", 8, 10, &echo_ok),
+            DataFieldDef::new("Spare", 10, 12, &echo_ok),
+        ];
+        let converter = LayoutConverter::new(&old, &new).unwrap()
+            .rename("Id", "Number")
+            .with_default("Region", "EU");
+        assert_eq!(converter.added(), ["Region", "Spare"]);
+        assert_eq!(converter.dropped(), ["Legacy"]);
+
+        let input = format!("{:<200}\n{:<200}\n{:<200}\n", "0042AXYZ", "00X3B", "0007C");
+        let mut output = vec![];
+        let summary = converter.convert(Cursor::new(input), &mut output, LoadOptions::default()).unwrap();
+        assert_eq!((summary.rows_loaded, summary.rows_rejected), (2, 1));
+        assert_eq!(String::from_utf8(output).unwrap(), "A 000042EU  \nC 000007EU  \n");
+
+        let broken = vec![DataFieldDef::new("Id", 0, 4, &echo_ok), DataFieldDef::new("Id", 4, 8, &echo_ok)];
+        assert!(LayoutConverter::new(&old, &broken).is_err());
+    }
+}
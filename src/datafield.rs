@@ -1,33 +1,245 @@
+use std::any::Any;
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+use crate::{Alignment, BlankValue, ColumnUnit, ControlCharPolicy, LoadOptions, NameMatching, NumberFormat, Redaction};
+use crate::dictionary::{Dictionary, Text};
 
 /// Contains a datafield, including name, raw data, and processed data (if any).
+///
+/// Fields are equal, hashed and ordered by their name, processed data and raw text, in that
+/// order; aliases, typed values and provenance aren't compared.
 #[derive(Debug, Clone)]
 pub struct DataField {
-    name: String,
-    raw: String,
-    data: Option<String>
+    name: Arc<str>,
+    raw: Text,
+    data: Option<Text>,
+    value: Option<TypedValue>,
+    aliases: Arc<[String]>,
+    provenance: Option<Box<Provenance>>
+}
+
+/// Where a field's value came from, recorded when `LoadOptions::provenance` is set, so that
+/// lineage tooling can trace a value back to the bytes it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The 0-based index of the source line of the row, if it was loaded from a file.
+    pub line_index: Option<usize>,
+    /// The bytes of the row the raw text was taken from. For a record joined from
+    /// continuation lines, these are offsets in the joined record.
+    pub byte_range: Range<usize>,
+    /// Whether post-processing (or a FieldErrorPolicy fallback) changed the value from the
+    /// trimmed raw text.
+    pub altered: bool
+}
+
+/// A value converted from a field's data by a typed parser; see `DataFieldDef::with_parser()`.
+pub type TypedValue = Arc<dyn Any + Send + Sync>;
+
+/// A post-processing step turning a field's trimmed raw text into its data. Every function
+/// and closure taking and returning the value is one, and a struct can implement it to make a
+/// reusable processor configured by its fields, with a name shown in the errors it returns.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldError, DataFieldResult, FieldProcessor};
+/// struct MaxLength(usize);
+/// impl FieldProcessor for MaxLength {
+///     fn process(&self, value: String) -> DataFieldResult<String> {
+///         match value.len() <= self.0 {
+///             true => Ok(value),
+///             false => Err(DataFieldError::Problem(Box::new(format!("Longer than {}", self.0))))
+///         }
+///     }
+///     fn name(&self) -> Option<&str> {
+///         Some("MaxLength")
+///     }
+/// }
+/// let field_def = DataFieldDef::new_processor("Name", 0, 20, MaxLength(12));
+/// ```
+pub trait FieldProcessor: Sync {
+    /// Process a field's value.
+    fn process(&self, value: String) -> Result<String>;
+
+    /// Get the name of the processor, shown with the field in the errors it returns.
+    fn name(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<F: Fn(String) -> Result<String> + Sync + ?Sized> FieldProcessor for F {
+    fn process(&self, value: String) -> Result<String> {
+        self(value)
+    }
+}
+
+/// A post-processing function, as stored in a DataFieldDef: borrowed, as given to
+/// `DataFieldDef::new()`, owned by the definition, as given to `DataFieldDef::new_owned()`, or
+/// a FieldProcessor, as given to `DataFieldDef::new_processor()`.
+#[derive(Clone)]
+pub enum PostProcess<'a> {
+    /// A function borrowed for the lifetime of the definition.
+    Borrowed(&'a (dyn Fn(String) -> Result<String> + Sync)),
+    /// A function (e.g. a closure configured at runtime) shared by the definitions holding it.
+    Owned(Arc<dyn Fn(String) -> Result<String> + Send + Sync>),
+    /// A processor shared by the definitions holding it.
+    Processor(Arc<dyn FieldProcessor + Send>)
+}
+
+impl PostProcess<'_> {
+    /// Run the function on a field's value.
+    pub fn call(&self, value: String) -> Result<String> {
+        match self {
+            PostProcess::Borrowed(f) => f(value),
+            PostProcess::Owned(f) => f(value),
+            PostProcess::Processor(p) => p.process(value)
+        }
+    }
+
+    /// Get the name of the processor, if it is a FieldProcessor with one.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            PostProcess::Processor(p) => p.name(),
+            _ => None
+        }
+    }
+}
+
+/// A typed parser, as stored in a DataFieldDef; see `DataFieldDef::with_parser()`.
+pub type TypedParser<'a> = Box<dyn Fn(&str) -> Result<TypedValue> + Send + Sync + 'a>;
+
+/// Describes where a field error occurred: the field name, its column range, and the
+/// offending raw text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldErrorContext {
+    /// The name of the field.
+    pub name: String,
+    /// The start index of the field as defined.
+    pub start_idx: usize,
+    /// The (exclusive) end index of the field as defined.
+    pub end_idx: usize,
+    /// The raw text found at the field's location, if any could be extracted.
+    pub raw: String,
+    /// The name of the FieldProcessor that failed, if it has one.
+    pub processor: Option<String>
+}
+
+impl FieldErrorContext {
+    /// Longest raw snippet shown when displaying the context.
+    const SNIPPET_LENGTH: usize = 40;
+
+    /// Build the context for a field definition applied to a row.
+    pub fn new(row: &str, field_def: &DataFieldDef) -> FieldErrorContext {
+        Self::in_unit(row, field_def, ColumnUnit::Bytes)
+    }
+
+    /// Build the context for a field definition applied to a row, with indexes in the given unit.
+    pub(crate) fn in_unit(row: &str, field_def: &DataFieldDef, unit: ColumnUnit) -> FieldErrorContext {
+        let (start_idx, end_idx) = field_def.span(unit.len(row));
+        let raw = match unit {
+            ColumnUnit::Bytes => {
+                let end_idx = end_idx.min(row.len());
+                match row.get(start_idx..end_idx) {
+                    Some(r) => r.to_string(),
+                    None => String::from_utf8_lossy(row.as_bytes().get(start_idx..end_idx).unwrap_or(&[]))
+                        .to_string()
+                }
+            }
+            _ => unit.slice(row, start_idx, end_idx).to_string()
+        };
+
+        FieldErrorContext {
+            name: field_def.name.to_string(),
+            start_idx: field_def.start_idx,
+            end_idx: field_def.end_idx,
+            raw,
+            processor: None
+        }
+    }
+}
+
+impl Display for FieldErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let snippet: String = self.raw.chars().take(Self::SNIPPET_LENGTH).collect();
+        let ellipsis = if snippet.len() < self.raw.len() { "..." } else { "" };
+        write!(f, "{} [{}-{}] \"{}{}\"", self.name, self.start_idx, self.end_idx, snippet, ellipsis)?;
+        match &self.processor {
+            Some(p) => write!(f, " in {}", p),
+            None => Ok(())
+        }
+    }
 }
 
 /// Errors that DataFields may encounter.
 pub enum DataFieldError {
     /// The field specification has the starting index after the ending one.
-    StartAfterEnd(String),
+    StartAfterEnd(FieldErrorContext),
     /// The field contains non-ASCII characters.
-    NonASCII(String),
+    NonASCII(FieldErrorContext),
     /// Problem occurred: used for application-specific post-processing errors.
-    Problem(Box<dyn ToString>),
+    Problem(Box<dyn ToString + Send + Sync>),
+    /// An underlying parse error occurred: used by post-processing functions to preserve
+    /// the original error, which is available via `Error::source()`.
+    Parse(Box<dyn Error + Send + Sync>),
+    /// Not a failure: used by post-processing functions to accept a value (the first member)
+    /// while recording a warning message (the second member) about it.
+    Warning(String, String),
     /// The field contains a quotation mark (").
-    FieldContainsQuote(String)
+    FieldContainsQuote(FieldErrorContext),
+    /// Not a failure: the field's value fills its width with no padding, so the source system may
+    /// have cut off a longer value. Only reported for fields with `warn_on_truncation()`.
+    PossibleTruncation(FieldErrorContext),
+    /// The post-processing function failed; wraps the error it returned along with
+    /// the location of the field.
+    PostProcess(FieldErrorContext, Box<DataFieldError>),
+    /// The field contains control characters at these columns of the row: a failure with
+    /// `ControlCharPolicy::Error`, otherwise a warning that they were stripped or replaced.
+    ControlCharacters(FieldErrorContext, Vec<usize>),
+    /// The field isn't a number written in its NumberFormat.
+    NotANumber(FieldErrorContext)
+}
+
+impl DataFieldError {
+    /// Get the field location details for this error, if available.
+    /// Errors returned directly from post-processing functions carry no context until they
+    /// are wrapped by `DataField::try_from_row()`.
+    pub fn context(&self) -> Option<&FieldErrorContext> {
+        match self {
+            DataFieldError::StartAfterEnd(c) | DataFieldError::NonASCII(c) |
+            DataFieldError::FieldContainsQuote(c) | DataFieldError::PostProcess(c, _) |
+            DataFieldError::PossibleTruncation(c) | DataFieldError::ControlCharacters(c, _) |
+            DataFieldError::NotANumber(c) => Some(c),
+            DataFieldError::Problem(_) | DataFieldError::Parse(_) | DataFieldError::Warning(_, _) => None
+        }
+    }
+
+    /// If this is a `Warning` (possibly wrapped by `PostProcess`), get the value to be kept.
+    pub fn warning_value(&self) -> Option<&str> {
+        match self {
+            DataFieldError::Warning(v, _) => Some(v),
+            DataFieldError::PostProcess(_, e) => e.warning_value(),
+            _ => None
+        }
+    }
 }
 
 impl Display for DataFieldError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
-            DataFieldError::StartAfterEnd(i) => format!("Start index is after end ({})", i),
-            DataFieldError::NonASCII(f) => format!("Non ASCII ({})", f),
+            DataFieldError::StartAfterEnd(c) => format!("Start index is after end ({})", c),
+            DataFieldError::NonASCII(c) => format!("Non ASCII ({})", c),
             DataFieldError::Problem(p) =>  format!("Problem: {}", p.to_string()),
-            DataFieldError::FieldContainsQuote(f) => format!("Field contains quote ({})", f)
+            DataFieldError::Parse(e) => format!("Parse error: {}", e),
+            DataFieldError::Warning(_, m) => format!("Warning: {}", m),
+            DataFieldError::FieldContainsQuote(c) => format!("Field contains quote ({})", c),
+            DataFieldError::PossibleTruncation(c) => format!("Possible truncation ({})", c),
+            DataFieldError::PostProcess(c, e) => format!("{} ({})", e, c),
+            DataFieldError::ControlCharacters(c, columns) => {
+                let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+                format!("Control characters at columns {} ({})", columns.join(", "), c)
+            }
+            DataFieldError::NotANumber(c) => format!("Not a number ({})", c)
         };
         write!(f, "{}", s)
     }
@@ -35,15 +247,150 @@ impl Display for DataFieldError {
 
 impl Debug for DataFieldError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", self)
     }
 }
 
-impl Error for DataFieldError { }
+impl Error for DataFieldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataFieldError::Parse(e) => Some(e.as_ref()),
+            DataFieldError::PostProcess(_, e) => Some(e.as_ref()),
+            _ => None
+        }
+    }
+}
 
 /// Convenient Result shorthand for DataFieldError Results.
 pub type Result<T> = std::result::Result<T, DataFieldError>;
 
+/// What to do when a field fails to load (e.g., its post-processing function returns an error).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum FieldErrorPolicy {
+    /// The error fails the whole row (the default).
+    #[default]
+    Fail,
+    /// Record a warning and use the provided value as the field's data.
+    WarnAndUseDefault(String),
+    /// Record a warning and leave the field's data empty.
+    WarnAndBlank
+}
+
+/// A condition restricting a field to rows where another field has one of a set of values,
+/// e.g. unit columns that are only present when a flag column is 'U'.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldCondition {
+    /// The name of the controlling field. It must be defined before the conditional field.
+    pub field: String,
+    /// The (processed) values of the controlling field for which the conditional field is present.
+    pub values: Vec<String>
+}
+
+impl FieldCondition {
+    /// Check the condition against the fields parsed so far in a row. A missing controlling
+    /// field never satisfies the condition.
+    pub(crate) fn is_met(&self, fields: &[DataField], name_matching: NameMatching) -> bool {
+        self.find(fields.iter().map(|f| (f.name(), f.aliases())), name_matching)
+            .is_some_and(|i| self.values.contains(&fields[i].data()))
+    }
+
+    /// Find the index of the definition of the controlling field among those given.
+    pub(crate) fn controlling(&self, defs: &[DataFieldDef], name_matching: NameMatching) -> Option<usize> {
+        self.find(defs.iter().map(|d| (d.name.as_str(), d.aliases.as_slice())), name_matching)
+    }
+
+    /// Find the controlling field among the names and aliases of fields, by name and then by
+    /// alias, as `DataRow::get()` finds fields.
+    fn find<'n>(&self, mut fields: impl Iterator<Item = (&'n str, &'n [String])> + Clone, name_matching: NameMatching)
+        -> Option<usize> {
+        let matches = |n: &str| name_matching.matches(&self.field, n);
+        fields.clone().position(|(name, _)| matches(name))
+            .or_else(|| fields.position(|(_, aliases)| aliases.iter().any(|a| matches(a))))
+    }
+}
+
+/// Splits the processed value of a field into named parts, each added to the row as a field
+/// following it, e.g. the branch, account and suffix of a `0-0001-111.000` style key. Parts
+/// are trimmed, and blank when the field is, or when the value is too short or has too few
+/// delimiters; with `Delimiter`, anything after the last named part is left out. Set one with
+/// `DataFieldDef::with_splitter()`.
+///
+/// Parts are in rows made by DataRow, DataFile and ColumnarFile, and so in their exports, and
+/// can be got from `LazyRow::get()`. RowParser doesn't make them, and fails to be made for a
+/// layout with a splitter.
+/// ```
+/// use ffreader::{DataRow, DataFieldDef, DataFieldResult, Splitter};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("Key", 0, 14, &post_function)
+///         .with_splitter(Splitter::delimiter('-', &["Branch", "Account", "Suffix"])),
+///     DataFieldDef::new("Date", 14, 22, &post_function)
+///         .with_splitter(Splitter::ranges(&[("Year", 0, 4), ("Month", 4, 6)])),
+/// ];
+/// let row = DataRow::try_create(&format!("{:<200}", "0-0001-111.00020240307"), &defs).unwrap();
+/// assert_eq!(row.get("Account").unwrap().data(), "0001");
+/// assert_eq!(row.get("Suffix").unwrap().data(), "111.000");
+/// assert_eq!(row.get("Month").unwrap().data(), "03");
+/// assert_eq!(row.fields().len(), 7);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Splitter {
+    /// Parts at these character ranges (start and exclusive end) of the value.
+    Ranges(Vec<(String, usize, usize)>),
+    /// Parts separated by the delimiter, named in order.
+    Delimiter(char, Vec<String>)
+}
+
+impl Splitter {
+    /// Split values into parts at the named character ranges.
+    pub fn ranges(parts: &[(&str, usize, usize)]) -> Self {
+        Splitter::Ranges(parts.iter().map(|(name, start, end)| (name.to_string(), *start, *end)).collect())
+    }
+
+    /// Split values into parts separated by the delimiter, named in order.
+    pub fn delimiter(delimiter: char, names: &[&str]) -> Self {
+        Splitter::Delimiter(delimiter, names.iter().map(|n| n.to_string()).collect())
+    }
+
+    /// Get the names of the parts.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Splitter::Ranges(parts) => parts.iter().map(|(name, _, _)| name.as_str()).collect(),
+            Splitter::Delimiter(_, names) => names.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// Split a value into the fields of its parts.
+    pub(crate) fn fields(&self, value: &str) -> Vec<DataField> {
+        let part = |name: &str, text: &str| DataField::derived(name, text.trim().to_string());
+        match self {
+            Splitter::Ranges(parts) => parts.iter().map(|(name, start, end)| {
+                let text: String = value.chars().skip(*start).take(end.saturating_sub(*start)).collect();
+                part(name, &text)
+            }).collect(),
+            Splitter::Delimiter(delimiter, names) => {
+                let mut values = value.split(*delimiter);
+                names.iter().map(|name| part(name, values.next().unwrap_or_default())).collect()
+            }
+        }
+    }
+}
+
+/// What a field's start and end indexes are measured from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    /// Indexes are columns counted from the start of the row (the default).
+    #[default]
+    Start,
+    /// Indexes are counted back from the end of the row, so index 0 is the last column: start
+    /// index 0 and end index 1 is the last character, and start index 3 and end index 11 is the 8
+    /// characters ending 3 from the end. For layouts where a variable-width section shifts
+    /// the columns after it.
+    End
+}
+
 /// Holds details pertaining to the structure of a field and the desired post-processing function.
 ///
 /// A DataFieldDef is used to extract a DataField from a row of data, and performs post-processing
@@ -51,15 +398,57 @@ pub type Result<T> = std::result::Result<T, DataFieldError>;
 pub struct DataFieldDef<'a> {
     /// The name of the field.
     pub name: String,
+    /// Other names the field can be retrieved by, e.g. the name used in a vendor's documentation.
+    pub aliases: Vec<String>,
+    /// What the field holds, for documentation; see `schema::document()`.
+    pub description: Option<String>,
     /// The start index (0-based column) of the field
     pub start_idx: usize,
     /// The end index of the field (exclusive end)
     /// e.g., ABC123 with start index 0 and end index 3 yields "ABC"
     pub end_idx: usize,
+    /// What the indexes are measured from; the start of the row unless set.
+    pub anchor: Anchor,
+    /// The unit the indexes are measured in, if the layout states it. Loading fails if it
+    /// differs from `LoadOptions::columns`, so that a layout means the same thing wherever
+    /// it is used.
+    pub unit: Option<ColumnUnit>,
     /// Function to execute on the field after loading.
     /// This function's output will affect the data stored and can return a
-    /// DataFieldError to facilitate validation.
-    pub post_process: &'a dyn Fn(String) -> Result<String>
+    /// DataFieldError to facilitate validation. It must be `Sync`, so that definitions (and
+    /// files loaded with them) can be shared across threads.
+    pub post_process: PostProcess<'a>,
+    /// How errors in this field are handled; see FieldErrorPolicy.
+    pub on_error: FieldErrorPolicy,
+    /// If set, the field is only parsed when the condition is met; otherwise it is reported as
+    /// empty (no data) rather than as an error.
+    pub condition: Option<FieldCondition>,
+    /// If set, the field is sensitive and is masked this way in redacted exports.
+    pub redaction: Option<Redaction>,
+    /// If set, how blank values of the field are written in exports, overriding
+    /// `ExportOptions::blanks`.
+    pub blank: Option<BlankValue>,
+    /// How the value is aligned within the field when written.
+    pub alignment: Alignment,
+    /// The character used to pad the value to the field width when written.
+    pub pad_char: char,
+    /// The number of implied decimal places in a numeric field, e.g. 2 when `0012345` means
+    /// 123.45. Used by the numeric column aggregates.
+    pub implied_decimals: usize,
+    /// How numbers are written in the field, if it holds numbers in a form other than plain
+    /// digits; see NumberFormat.
+    pub number_format: Option<NumberFormat>,
+    /// Whether the field may overlap other fields, e.g. a key and its components; otherwise an
+    /// overlap is taken to be a mistake in the layout.
+    pub overlaps_allowed: bool,
+    /// Whether to warn when the value fills the field with no padding; see `warn_on_truncation()`.
+    pub truncation_warning: bool,
+    /// Converts the processed data to a typed value, if set; see `with_parser()`.
+    pub parser: Option<TypedParser<'a>>,
+    /// Splits the processed data into parts added to the row, if set; see Splitter.
+    pub splitter: Option<Splitter>,
+    shared_names: OnceLock<(Arc<str>, Arc<[String]>)>,
+    dictionary: Option<Dictionary>
 }
 
 impl Display for DataFieldDef<'_> {
@@ -68,7 +457,7 @@ impl Display for DataFieldDef<'_> {
     }
 }
 
-impl DataFieldDef<'_> {
+impl<'a> DataFieldDef<'a> {
     /// Convenience function to instantiate a new DataFieldDef with the provided data.
     /// Note the post-process function; this returns a DataFieldResult.
     /// ```
@@ -81,14 +470,309 @@ impl DataFieldDef<'_> {
     /// typically you would have several of these, once for each field, with different
     /// (non-overlapping) ranges.
     pub fn new(name: impl ToString, start_idx: usize, end_idx: usize,
-               post_process: &dyn Fn(String)-> Result<String>) -> DataFieldDef {
+               post_process: &(dyn Fn(String) -> Result<String> + Sync)) -> DataFieldDef<'_> {
+        DataFieldDef::with_post_process(name, start_idx, end_idx, PostProcess::Borrowed(post_process))
+    }
+
+    /// Instantiate a DataFieldDef that owns its post-processing function, so that it borrows
+    /// nothing. Layouts built at runtime, e.g. from configuration, can then be kept in structs
+    /// or caches as `Vec<DataFieldDef<'static>>`.
+    /// ```
+    /// use ffreader::DataFieldDef;
+    /// struct Layout {
+    ///     defs: Vec<DataFieldDef<'static>>
+    /// }
+    /// let prefix = String::from("ACCT-");
+    /// let layout = Layout {
+    ///     defs: vec![DataFieldDef::new_owned("Account", 0, 11, move |v| Ok(format!("{}{}", prefix, v)))]
+    /// };
+    /// ```
+    pub fn new_owned(name: impl ToString, start_idx: usize, end_idx: usize,
+                     post_process: impl Fn(String) -> Result<String> + Send + Sync + 'static) -> DataFieldDef<'a> {
+        DataFieldDef::with_post_process(name, start_idx, end_idx, PostProcess::Owned(Arc::new(post_process)))
+    }
+
+    /// Instantiate a DataFieldDef that owns a FieldProcessor, e.g. a configurable processor
+    /// struct; see `FieldProcessor`.
+    pub fn new_processor(name: impl ToString, start_idx: usize, end_idx: usize,
+                         processor: impl FieldProcessor + Send + 'static) -> DataFieldDef<'a> {
+        DataFieldDef::with_post_process(name, start_idx, end_idx, PostProcess::Processor(Arc::new(processor)))
+    }
+
+    fn with_post_process(name: impl ToString, start_idx: usize, end_idx: usize, post_process: PostProcess<'a>)
+        -> DataFieldDef<'a> {
         DataFieldDef {
             name: name.to_string(),
+            aliases: vec![],
+            description: None,
             start_idx,
             end_idx,
-            post_process
+            anchor: Anchor::Start,
+            unit: None,
+            post_process,
+            on_error: FieldErrorPolicy::Fail,
+            condition: None,
+            redaction: None,
+            blank: None,
+            alignment: Alignment::Left,
+            pad_char: ' ',
+            implied_decimals: 0,
+            number_format: None,
+            overlaps_allowed: false,
+            truncation_warning: false,
+            parser: None,
+            splitter: None,
+            shared_names: OnceLock::new(),
+            dictionary: None
+        }
+    }
+
+    /// Set the error policy for this field, e.g. to make an optional field fall back to a
+    /// default rather than failing the row.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef, FieldErrorPolicy};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let field_def = DataFieldDef::new("Field_1", 0, 11, &post_function)
+    ///     .with_on_error(FieldErrorPolicy::WarnAndUseDefault("0".to_string()));
+    /// ```
+    pub fn with_on_error(mut self, on_error: FieldErrorPolicy) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Mark this field as sensitive, to be masked with the given Redaction when exporting
+    /// with `ExportOptions::redact` set.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Add another name the field can be retrieved by with `DataRow::get()`, without defining
+    /// the field twice. Exports still use the field's name unless the alias is selected.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let field_def = DataFieldDef::new("AccountNo1", 0, 11, &post_function).with_alias("ACCT-NBR");
+    /// ```
+    pub fn with_alias(mut self, alias: impl ToString) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    /// Set what the field's indexes are measured from, e.g. to take the last characters of rows
+    /// whose length varies.
+    /// ```
+    /// use ffreader::{Anchor, DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let check_digit = DataFieldDef::new("CheckDigit", 0, 1, &post_function).with_anchor(Anchor::End);
+    /// ```
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// State the unit the field's indexes are measured in, recorded in schema documents and
+    /// checked against `LoadOptions::columns` when loading.
+    pub fn with_unit(mut self, unit: ColumnUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Get the start and (exclusive) end columns of the field in a row of the given length.
+    /// An end-anchored field that is longer than the row starts at column 0.
+    pub(crate) fn span(&self, row_len: usize) -> (usize, usize) {
+        match self.anchor {
+            Anchor::Start => (self.start_idx, self.end_idx),
+            Anchor::End => (row_len.saturating_sub(self.end_idx), row_len.saturating_sub(self.start_idx))
+        }
+    }
+
+    /// Allow this field to overlap others, for layouts that deliberately define several views
+    /// of the same columns, such as a full key alongside its components.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("Key", 0, 10, &post_function).allow_overlaps(),
+    ///     DataFieldDef::new("Branch", 0, 4, &post_function),
+    ///     DataFieldDef::new("Account", 4, 10, &post_function),
+    /// ];
+    /// ```
+    pub fn allow_overlaps(mut self) -> Self {
+        self.overlaps_allowed = true;
+        self
+    }
+
+    /// Warn (with `DataFieldError::PossibleTruncation`, kept as a LoadWarning) when the field's
+    /// value fills its whole width with no padding, which usually means the source system cut
+    /// off a longer value. Padding is expected after the value, or before it for fields aligned
+    /// right; the row is loaded either way.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let field_def = DataFieldDef::new("Name", 0, 30, &post_function).warn_on_truncation();
+    /// ```
+    pub fn warn_on_truncation(mut self) -> Self {
+        self.truncation_warning = true;
+        self
+    }
+
+    /// Keep one copy of each distinct value of the field, shared by every row loaded with this
+    /// definition, rather than a copy per row. For fields like a status or type code, with a
+    /// handful of values repeated over millions of rows, memory use then scales with the number
+    /// of distinct values. Fields are read as usual; the dictionary lasts as long as the
+    /// definition, and is shared by every file loaded with it.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let field_def = DataFieldDef::new("Status", 0, 2, &post_function).with_dictionary();
+    /// ```
+    pub fn with_dictionary(mut self) -> Self {
+        self.dictionary.get_or_insert_with(Dictionary::default);
+        self
+    }
+
+    /// Get the number of distinct values held for the field, if it has a dictionary; see
+    /// `with_dictionary()`.
+    pub fn dictionary_len(&self) -> Option<usize> {
+        self.dictionary.as_ref().map(|d| d.len())
+    }
+
+    /// Store text of the field, in the dictionary if it has one.
+    fn text(&self, text: String) -> Text {
+        match &self.dictionary {
+            Some(d) => Text::Shared(d.intern(&text)),
+            None => Text::Owned(text)
         }
     }
+
+    /// Get the name and aliases given to fields loaded with this definition, shared by all of
+    /// them rather than copied into each. They're made on first use, and again if the name or
+    /// aliases have since been changed.
+    pub(crate) fn shared_names(&self) -> (Arc<str>, Arc<[String]>) {
+        let make = || (Arc::from(self.name.as_str()), Arc::from(self.aliases.as_slice()));
+        let (name, aliases) = self.shared_names.get_or_init(make);
+        if **name == *self.name && **aliases == *self.aliases {
+            (name.clone(), aliases.clone())
+        } else {
+            make()
+        }
+    }
+
+    /// Determine whether a field's raw text, in the given unit, fills the field's width with no
+    /// padding where its alignment would put it.
+    pub(crate) fn fills_width(&self, raw: &str, unit: ColumnUnit) -> bool {
+        let padding = |c: Option<char>| c.is_none_or(|c| c == self.pad_char || c.is_whitespace());
+        let edge = match self.alignment {
+            Alignment::Left => raw.chars().next_back(),
+            Alignment::Right => raw.chars().next()
+        };
+        self.end_idx > self.start_idx && unit.len(raw) == self.end_idx - self.start_idx && !padding(edge)
+    }
+
+    /// Set how blank values of this field are written in exports, whatever
+    /// `ExportOptions::blanks` says, e.g. to write nulls for an optional date.
+    pub fn with_blank(mut self, blank: BlankValue) -> Self {
+        self.blank = Some(blank);
+        self
+    }
+
+    /// Set how the value is aligned within the field when written, e.g. right-aligned numbers.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Set the character used to pad the value to the field width when written.
+    pub fn with_pad_char(mut self, pad_char: char) -> Self {
+        self.pad_char = pad_char;
+        self
+    }
+
+    /// Describe what the field holds, for documentation; see `schema::document()`.
+    pub fn with_description(mut self, description: impl ToString) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set the number of implied decimal places in a numeric field without a decimal point.
+    pub fn with_implied_decimals(mut self, implied_decimals: usize) -> Self {
+        self.implied_decimals = implied_decimals;
+        self
+    }
+
+    /// Read the field's numbers as written in the given format, e.g. with a decimal comma, into
+    /// plain form before post-processing; see NumberFormat.
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = Some(number_format);
+        self
+    }
+
+    /// Convert the field's data to a typed value when the row is loaded, so that it is parsed
+    /// once rather than by every consumer. The parser is given the data after post-processing
+    /// and isn't run for blank fields; a failure is handled by the field's error policy, like a
+    /// post-processing failure. The value is available from `DataField::value()` and
+    /// `DataRow::value()`, while the data remains available as text for exports.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef, DataFieldError, DataRow};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// fn parse_count(value: &str) -> DataFieldResult<u32> {
+    ///     value.parse().map_err(|e| DataFieldError::Parse(Box::new(e)))
+    /// }
+    /// let defs = vec![DataFieldDef::new("Count", 0, 5, &post_function).with_parser(&parse_count)];
+    /// let row = DataRow::try_create(&format!("{:<200}", "00042"), &defs).unwrap();
+    /// assert_eq!(row.value::<u32>("Count"), Some(&42));
+    /// ```
+    pub fn with_parser<T: Any + Send + Sync>(mut self, parser: &'a (dyn Fn(&str) -> Result<T> + Sync)) -> Self {
+        self.parser = Some(Box::new(move |data| parser(data).map(|v| Arc::new(v) as TypedValue)));
+        self
+    }
+
+    /// Convert the field's data to a typed value as with `with_parser()`, with a parser owned by
+    /// the definition, e.g. a closure configured at runtime.
+    pub fn with_owned_parser<T: Any + Send + Sync>(mut self,
+                                                   parser: impl Fn(&str) -> Result<T> + Send + Sync + 'static) -> Self {
+        self.parser = Some(Box::new(move |data| parser(data).map(|v| Arc::new(v) as TypedValue)));
+        self
+    }
+
+    /// Only parse this field when the named (earlier) field has one of the given values.
+    /// ```
+    /// use ffreader::{DataFieldResult, DataFieldDef};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let flag = DataFieldDef::new("Flag", 0, 1, &post_function);
+    /// let unit = DataFieldDef::new("Unit", 1, 6, &post_function).only_when("Flag", &["U"]);
+    /// ```
+    pub fn only_when(mut self, field: impl ToString, values: &[&str]) -> Self {
+        self.condition = Some(FieldCondition {
+            field: field.to_string(),
+            values: values.iter().map(|v| v.to_string()).collect()
+        });
+        self
+    }
+
+    /// Split the processed value into named parts, each added to the row as a field; see
+    /// Splitter. The names of the parts must differ from those of the other fields.
+    pub fn with_splitter(mut self, splitter: Splitter) -> Self {
+        self.splitter = Some(splitter);
+        self
+    }
 }
 
 impl DataField {
@@ -98,78 +782,324 @@ impl DataField {
         assert!(data.is_ascii());
         assert!(!data.contains("\""));
         DataField {
-            name: name.to_string(),
-            raw: data.clone(),
-            data: if data.len() == 0 {
+            name: Arc::from(name),
+            raw: data.clone().into(),
+            data: if data.is_empty() {
                 None
             } else {
-                Some(data)
+                Some(data.into())
             },
+            value: None,
+            aliases: Arc::from([]),
+            provenance: None
         }
     }
 
+    /// Replace the processed data, keeping the raw text; any typed value is dropped.
+    pub(crate) fn set_data(&mut self, data: String) {
+        self.data = (!data.is_empty()).then(|| data.into());
+        self.value = None;
+    }
+
     /// Try to create a DataField from a row using the provided data field definition.
     /// This can fail, if the row doesn't meet certain criteria (e.g., if it's too short for the
     /// field definition, or isn't ASCII, or if the post_process function fails.
     pub fn try_from_row(row: &str, field_def: &DataFieldDef) -> Result<DataField>
     {
+        Self::try_from_row_in(row, field_def, ColumnUnit::Bytes, row.is_ascii(), ControlCharPolicy::Keep).map(|(f, _)| f)
+    }
+
+    /// Try to create a DataField as with `try_from_row()`, with the field's indexes measured in
+    /// the given unit, and control characters handled as set by the policy. `ascii` is whether
+    /// the whole row is ASCII, checked once per row by the caller rather than once per field.
+    /// Along with the field is a warning of any control characters stripped or replaced.
+    pub(crate) fn try_from_row_in(row: &str, field_def: &DataFieldDef, unit: ColumnUnit, ascii: bool,
+                                  control: ControlCharPolicy) -> Result<(DataField, Option<DataFieldError>)>
+    {
+        let row_len = unit.len(row);
+        let (start_idx, end_idx) = field_def.span(row_len);
+        let context = || FieldErrorContext::in_unit(row, field_def, unit);
+
         //fields can be optional and result in lines that are short
         //return nothing if the start is after the row (it's truncated)
-        if field_def.start_idx > row.len() {
-            return Ok(DataField {
-                name: field_def.name.to_string(),
-                raw: "".to_string(),
-                data: None
-            });
+        if start_idx > row_len {
+            return Ok((DataField::empty(field_def), None));
         }
 
-        let end_idx = if field_def.end_idx > row.len() {
-            row.len()
+        let end_idx = if end_idx > row_len {
+            row_len
         }
         else {
-            field_def.end_idx
+            end_idx
         };
 
-        if field_def.start_idx > end_idx {
-            return Err(DataFieldError::StartAfterEnd(field_def.name.to_string()));
+        if start_idx > end_idx {
+            return Err(DataFieldError::StartAfterEnd(context()));
         }
 
-        if !row.is_ascii() {
-            return Err(DataFieldError::NonASCII(field_def.name.to_string()));
+        if unit.ascii_only() && !ascii {
+            return Err(DataFieldError::NonASCII(context()));
         }
 
-        let raw = row[field_def.start_idx..end_idx].to_string();
-        let data = (field_def.post_process)(raw.trim().to_string())?;
+        let raw = unit.slice(row, start_idx, end_idx).to_string();
+        let (text, columns) = control.apply(&raw, trim(&raw, ascii), start_idx, unit);
+        let warning = match columns.is_empty() {
+            true => None,
+            false if control == ControlCharPolicy::Error => return Err(DataFieldError::ControlCharacters(context(), columns)),
+            false => Some(DataFieldError::ControlCharacters(context(), columns))
+        };
+        let text = match &field_def.number_format {
+            Some(format) => format.normalize(&text).ok_or_else(|| DataFieldError::NotANumber(context()))?,
+            None => text.into_owned()
+        };
+        let data = field_def.post_process.call(text).map_err(|e| {
+            let context = FieldErrorContext { processor: field_def.post_process.name().map(String::from), ..context() };
+            DataFieldError::PostProcess(context, Box::new(e))
+        })?;
 
         if data.contains("\"") {
-            return Err(DataFieldError::FieldContainsQuote(data));
+            return Err(DataFieldError::FieldContainsQuote(context()));
         }
 
-        Ok(DataField {
-            name: field_def.name.to_string(),
-            raw,
-            data: if data.len() == 0 {
+        let value = match &field_def.parser {
+            Some(parse) if !data.is_empty() => Some(parse(&data)
+                .map_err(|e| DataFieldError::PostProcess(context(), Box::new(e)))?),
+            _ => None
+        };
+
+        let (name, aliases) = field_def.shared_names();
+        Ok((DataField {
+            name,
+            raw: field_def.text(raw),
+            data: if data.is_empty() {
                 None
             } else {
-                Some(data)
+                Some(field_def.text(data))
             },
-        })
+            value,
+            aliases,
+            provenance: None
+        }, warning))
+    }
+
+    /// Extract a field from a row of the LoadOptions, applying the definition's
+    /// FieldErrorPolicy. Along with the field are the error of a field downgraded to a warning,
+    /// or warnings of a possible truncation or control characters; an error is only returned
+    /// for fields that fail. `ascii` is as for `try_from_row_in()`.
+    pub(crate) fn extract(row: &str, field_def: &DataFieldDef, options: &LoadOptions, ascii: bool)
+        -> Result<(DataField, Vec<DataFieldError>)> {
+        let unit = options.columns;
+        match (Self::try_from_row_in(row, field_def, unit, ascii, options.control_chars), &field_def.on_error) {
+            (Ok((f, warning)), _) => {
+                let truncated = field_def.truncation_warning && field_def.fills_width(f.raw(), unit);
+                let truncation = truncated
+                    .then(|| DataFieldError::PossibleTruncation(FieldErrorContext::in_unit(row, field_def, unit)));
+                Ok((f, warning.into_iter().chain(truncation).collect()))
+            }
+            (Err(e), _) if e.warning_value().is_some() => {
+                Ok((Self::fallback(row, field_def, e.warning_value().map(String::from), unit), vec![e]))
+            }
+            (Err(e), FieldErrorPolicy::WarnAndUseDefault(d)) => {
+                Ok((Self::fallback(row, field_def, Some(d.clone()), unit), vec![e]))
+            }
+            (Err(e), FieldErrorPolicy::WarnAndBlank) => Ok((Self::fallback(row, field_def, None, unit), vec![e])),
+            (Err(e), FieldErrorPolicy::Fail) => Err(e)
+        }
+    }
+
+    /// Create a DataField with no raw or processed data, for fields that are not present.
+    pub(crate) fn empty(field_def: &DataFieldDef) -> DataField {
+        let (name, aliases) = field_def.shared_names();
+        DataField {
+            name,
+            raw: String::new().into(),
+            data: None,
+            value: None,
+            aliases,
+            provenance: None
+        }
+    }
+
+    /// Create a DataField holding a substitute value for a field that failed to load.
+    /// The raw data is still taken from the row where possible.
+    pub(crate) fn fallback(row: &str, field_def: &DataFieldDef, data: Option<String>, unit: ColumnUnit) -> DataField {
+        let (start_idx, end_idx) = field_def.span(unit.len(row));
+        let raw = match unit {
+            ColumnUnit::Bytes => row.get(start_idx..end_idx.min(row.len())).unwrap_or(""),
+            _ => unit.slice(row, start_idx, end_idx)
+        };
+        let (name, aliases) = field_def.shared_names();
+        DataField {
+            name,
+            raw: field_def.text(raw.to_string()),
+            data: data.filter(|d| !d.is_empty()).map(|d| field_def.text(d)),
+            value: None,
+            aliases,
+            provenance: None
+        }
+    }
+
+    /// Create a DataField holding a value derived from other fields, such as a part of a split
+    /// field, whose raw text is the value.
+    pub(crate) fn derived(name: &str, data: String) -> DataField {
+        DataField::from_parts(Arc::from(name), Arc::from([]), data.clone(), Some(data).filter(|d| !d.is_empty()))
+    }
+
+    /// Create a DataField from its parts, e.g. to make a row from stored columns.
+    pub(crate) fn from_parts(name: Arc<str>, aliases: Arc<[String]>, raw: String, data: Option<String>) -> DataField {
+        DataField {
+            name,
+            raw: raw.into(),
+            data: data.map(Text::from),
+            value: None,
+            aliases,
+            provenance: None
+        }
+    }
+
+    /// Take the raw and processed data of the field, dropping any typed value.
+    pub(crate) fn into_parts(self) -> (String, Option<String>) {
+        (self.raw.into_string(), self.data.map(Text::into_string))
+    }
+
+    /// Get the shared name and aliases of the field.
+    pub(crate) fn shared_names(&self) -> (Arc<str>, Arc<[String]>) {
+        (self.name.clone(), self.aliases.clone())
+    }
+
+    /// Record where the field was taken from in the row; see Provenance.
+    pub(crate) fn with_provenance(mut self, row: &str, field_def: &DataFieldDef, unit: ColumnUnit) -> Self {
+        let row_len = unit.len(row);
+        let (start_idx, end_idx) = field_def.span(row_len);
+        let start = unit.byte_offset(row, start_idx.min(row_len));
+        let end = unit.byte_offset(row, end_idx.min(row_len)).max(start);
+        let altered = self.altered();
+        self.provenance = Some(Box::new(Provenance { line_index: None, byte_range: start..end, altered }));
+        self
+    }
+
+    /// Determine whether post-processing (or a fallback) changed the value from the trimmed raw
+    /// text.
+    pub(crate) fn altered(&self) -> bool {
+        self.data_ref() != self.raw().trim()
+    }
+
+    /// Record the index of the source line in the field's provenance, if it has any.
+    pub(crate) fn set_line_index(&mut self, line_index: usize) {
+        if let Some(p) = &mut self.provenance {
+            p.line_index = Some(line_index);
+        }
+    }
+
+    /// Get where the field's value came from, if `LoadOptions::provenance` was set when it was
+    /// loaded. Fields not read because their condition wasn't met have none.
+    /// ```
+    /// use ffreader::{DataRow, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn upper(value: String) -> DataFieldResult<String> {
+    ///     Ok(value.to_uppercase())
+    /// }
+    /// let defs = vec![DataFieldDef::new("Id", 0, 4, &upper), DataFieldDef::new("Name", 4, 10, &upper)];
+    /// let options = LoadOptions { provenance: true, ..Default::default() };
+    /// let row = DataRow::try_create_with_options(&format!("{:<200}", "0042ann"), &defs, &options).unwrap();
+    /// let name = row.get("Name").unwrap().provenance().unwrap();
+    /// assert_eq!((name.byte_range.clone(), name.altered), (4..10, true));
+    /// assert!(!row.get("Id").unwrap().provenance().unwrap().altered);
+    /// ```
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_deref()
+    }
+
+    /// Obtain a reference to the data, an empty string if None.
+    pub(crate) fn data_ref(&self) -> &str {
+        self.data.as_deref().unwrap_or("")
     }
 
-    /// Obtain a reference to the name.
-    pub fn name(&self) -> &String {
+    /// Obtain a reference to the name. Fields loaded with the same definition share one copy of
+    /// the name (and aliases), so a field costs a pointer for its name rather than a copy of it.
+    pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Obtain the other names the field can be retrieved by.
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// Estimate the memory held by the field, in bytes, excluding the shared name and aliases,
+    /// dictionary values (see `DataFieldDef::with_dictionary()`) and any typed value.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<DataField>() + self.raw.heap_size() + self.data.as_ref().map_or(0, |d| d.heap_size())
+            + self.provenance.as_ref().map_or(0, |_| size_of::<Provenance>())
+    }
+
     /// Obtain an unwrapped clone of the data. An empty string is returned if None.
     pub fn data(&self) -> String {
-        self.data.clone().unwrap_or("".to_string())
+        self.data.as_deref().unwrap_or("").to_string()
     }
 
     /// Obtain a reference to the raw data.
-    pub fn raw(&self) -> &String {
+    pub fn raw(&self) -> &str {
         &self.raw
     }
+
+    /// Determine whether the field is blank: its text was missing or all whitespace, or it was
+    /// left without data by an error policy. Blank fields have empty data unless post-processing
+    /// supplied a value.
+    pub fn is_blank(&self) -> bool {
+        self.data.is_none() || self.raw.trim().is_empty()
+    }
+
+    /// Obtain the typed value converted by the field definition's parser, if there is one of type
+    /// `T`. None for blank fields, fields without a parser, and fields given fallback values.
+    pub fn value<T: Any>(&self) -> Option<&T> {
+        self.value.as_ref().and_then(|v| v.downcast_ref())
+    }
+}
+
+impl DataField {
+    /// The values fields are compared by.
+    fn key(&self) -> (&str, &str, &str) {
+        (self.name(), self.data_ref(), self.raw())
+    }
+}
+
+impl PartialEq for DataField {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for DataField {}
+
+impl Hash for DataField {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl PartialOrd for DataField {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DataField {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Trim whitespace from both ends of a field's text, as `str::trim()` does. ASCII text is
+/// trimmed byte by byte, without decoding characters.
+pub(crate) fn trim(text: &str, ascii: bool) -> &str {
+    if !ascii {
+        return text.trim();
+    }
+    let is_space = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\x0B' | b'\x0C' | b'\r');
+    let bytes = text.as_bytes();
+    let start = bytes.iter().position(|b| !is_space(b)).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !is_space(b)).map_or(start, |i| i + 1);
+    &text[start..end]
 }
 
 #[cfg(test)]
@@ -221,7 +1151,7 @@ mod tests {
     #[test]
     fn fields_extracted() {
         let test_row = String::from("1234567890  test1 test2  x");
-        let defs = vec![
+        let defs = [
             DataFieldDef::new("field1", 0, 10, &echo_ok),
             DataFieldDef::new("field2", 11, 17, &echo_ok),
             DataFieldDef::new("field3", 18, 24, &echo_ok),
@@ -232,8 +1162,118 @@ mod tests {
         ];
 
         for (def, field) in defs.iter().zip(fields) {
-            let r = DataField::try_from_row(&test_row, &def).unwrap();
-            assert_eq!(r.data.unwrap(), field);
+            let r = DataField::try_from_row(&test_row, def).unwrap();
+            assert_eq!(&*r.data.unwrap(), field);
+        }
+    }
+
+    #[test]
+    fn errors_carry_context_and_source() {
+        fn parse_number(s: String) -> Result<String> {
+            s.parse::<u32>().map(|n| n.to_string()).map_err(|e| DataFieldError::Parse(Box::new(e)))
+        }
+        let test_row = String::from("12345 ABCDE");
+        let def = DataFieldDef::new("number", 6, 11, &parse_number);
+
+        let e = DataField::try_from_row(&test_row, &def).unwrap_err();
+        let context = e.context().unwrap();
+        assert_eq!(context.name, "number");
+        assert_eq!((context.start_idx, context.end_idx), (6, 11));
+        assert_eq!(context.raw, "ABCDE");
+        assert!(e.source().unwrap().source().unwrap().is::<std::num::ParseIntError>());
+        assert_eq!(e.to_string(), "Parse error: invalid digit found in string (number [6-11] \"ABCDE\")");
+    }
+
+    #[test]
+    fn typed_values_parsed_once() {
+        fn parse_amount(s: &str) -> Result<f64> {
+            s.parse().map_err(|e| DataFieldError::Parse(Box::new(e)))
+        }
+        let def = DataFieldDef::new("amount", 0, 6, &echo_ok).with_parser(&parse_amount);
+
+        let field = DataField::try_from_row("12.50 ", &def).unwrap();
+        assert_eq!(field.value::<f64>(), Some(&12.5));
+        assert_eq!(field.value::<u32>(), None);
+        assert_eq!(field.data(), "12.50");
+
+        assert!(DataField::try_from_row("      ", &def).unwrap().value::<f64>().is_none());
+        match DataField::try_from_row("ABC   ", &def) {
+            Err(DataFieldError::PostProcess(c, _)) => assert_eq!(c.name, "amount"),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn owned_definitions_built_at_runtime() {
+        fn layout(config: &[(&str, usize, usize)], suffix: &str) -> Vec<DataFieldDef<'static>> {
+            config.iter().map(|(name, start, end)| {
+                let suffix = suffix.to_string();
+                DataFieldDef::new_owned(name, *start, *end, move |v| Ok(v + &suffix))
+                    .with_owned_parser(|s| s.trim_end_matches('!').parse::<u32>().map_err(|e| DataFieldError::Parse(Box::new(e))))
+            }).collect()
         }
+        let defs = layout(&[("a", 0, 2), ("b", 2, 4)], "!");
+        let fields: Vec<DataField> = defs.iter().map(|d| DataField::try_from_row("1234", d).unwrap()).collect();
+        assert_eq!(fields[1].data(), "34!");
+        assert_eq!(fields[1].value::<u32>(), Some(&34));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn end_anchored_fields_follow_row_end() {
+        let last = DataFieldDef::new("last", 0, 1, &echo_ok).with_anchor(Anchor::End);
+        let tail = DataFieldDef::new("tail", 3, 11, &echo_ok).with_anchor(Anchor::End);
+        for row in ["HEAD 12345678XY7", "HEAD VARIABLE 12345678XY7"] {
+            assert_eq!(DataField::try_from_row(row, &last).unwrap().data(), "7");
+            assert_eq!(DataField::try_from_row(row, &tail).unwrap().data(), "12345678");
+        }
+        assert_eq!(DataField::try_from_row("345ABC7", &tail).unwrap().data(), "345A");
+
+        let reversed = DataFieldDef::new("reversed", 5, 2, &echo_ok).with_anchor(Anchor::End);
+        assert!(matches!(DataField::try_from_row("ABCDEFG", &reversed), Err(DataFieldError::StartAfterEnd(_))));
+    }
+
+    #[test]
+    fn dictionary_values_shared() {
+        let def = DataFieldDef::new("Status", 0, 2, &echo_ok).with_dictionary();
+        let fields: Vec<DataField> = ["OP", "CL", "OP", "OP"].iter()
+            .map(|row| DataField::try_from_row(row, &def).unwrap())
+            .collect();
+        assert_eq!(def.dictionary_len(), Some(2));
+        assert_eq!(fields[2].data(), "OP");
+        assert_eq!(fields[3].raw(), "OP");
+        assert_eq!(fields[0].memory_usage(), size_of::<DataField>());
+        assert_eq!(DataFieldDef::new("Status", 0, 2, &echo_ok).dictionary_len(), None);
+    }
+
+    #[test]
+    fn processor_named_in_errors() {
+        struct Digits;
+        impl FieldProcessor for Digits {
+            fn process(&self, value: String) -> Result<String> {
+                match value.chars().all(|c| c.is_ascii_digit()) {
+                    true => Ok(value),
+                    false => Err(DataFieldError::Problem(Box::new("Not digits")))
+                }
+            }
+            fn name(&self) -> Option<&str> {
+                Some("Digits")
+            }
+        }
+
+        let def = DataFieldDef::new_processor("Id", 0, 4, Digits);
+        assert_eq!(DataField::try_from_row("0042", &def).unwrap().data(), "0042");
+        let e = DataField::try_from_row("00X2", &def).unwrap_err();
+        assert_eq!(e.to_string(), "Problem: Not digits (Id [0-4] \"00X2\" in Digits)");
+        assert_eq!(echo_ok.process("A".to_string()).unwrap(), "A");
+        let lower = DataFieldDef::new_processor("Id", 0, 4, |v: String| Ok(v.to_lowercase()));
+        assert_eq!(DataField::try_from_row("00X2", &lower).unwrap().data(), "00x2");
+    }
+
+    #[test]
+    fn ascii_trim_matches_str_trim() {
+        for text in ["", "   ", " a b ", "\t\x0Bx\x0C\r\n", "\x1Fy\x1F", "z"] {
+            assert_eq!(trim(text, true), text.trim());
+        }
+        assert_eq!(trim("\u{3000}é\u{3000}", false), "é");
+    }
+}
@@ -1,16 +1,63 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::DataFieldDef;
-use crate::DataRow;
-use crate::LoadWarning;
+use sha2::{Digest, Sha256};
+use crate::{DataFieldDef, DataFieldError};
+use crate::{DataRow, DataRowError, DataRowResult};
+use crate::{LoadWarning, WarningAggregator, WarningGroup, WarningKind};
+use crate::LoadOptions;
+use crate::AuditEntry;
+use crate::LoadSummary;
+use crate::loadsummary::Stopwatch;
+use crate::duplicates::SeenKeys;
+use crate::{BlankValue, ColumnUnit, Redaction};
+#[cfg(feature = "json")]
+use crate::ExportOptions;
+use crate::writer::{render_row, roundtrip_difference};
+use crate::records::IndexedRecords;
+use crate::format::{hex, hex_dump};
+use crate::invalidbytes::decode;
+use crate::ExtraDataPolicy;
+use crate::extradata::extra_data;
+use crate::trace::{self, LoadSpan};
+use crate::controltotal::ControlTally;
+use crate::parallel::load_records_parallel;
+#[cfg(feature = "csv")]
+use crate::format::csv_field;
+#[cfg(feature = "json")]
+use crate::format::json_string;
 
 /// Holds a list of DataRows and a list of the LoadWarnings
 /// encountered during creation.
+///
+/// Displayed as its path with the number of rows and warnings. The Debug output is bounded,
+/// showing only the first few rows and warnings, so that a file of millions of rows can be
+/// logged.
 pub struct DataFile {
     rows: Vec<DataRow>,
-    load_warnings: Vec<LoadWarning>
+    load_warnings: Vec<LoadWarning>,
+    summary: LoadSummary,
+    redactions: HashMap<String, Redaction>,
+    blanks: HashMap<String, BlankValue>,
+    implied_decimals: HashMap<String, usize>,
+    source_hash: Option<SourceHash>,
+    path: PathBuf,
+    options: LoadOptions,
+    indexes: HashMap<String, HashMap<String, Vec<usize>>>,
+    audit_log: Vec<AuditEntry>
+}
+
+/// Identifies the exact input a DataFile was loaded from, for provenance and duplicate detection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceHash {
+    /// The path the file was loaded from.
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub byte_count: usize,
+    /// The SHA-256 digest of the file contents, as lowercase hex.
+    pub sha256: String
 }
 
 /// Errors that DataFiles may encounter.
@@ -18,25 +65,328 @@ pub struct DataFile {
 pub enum DataFileError {
     /// Non-ASCII characters were encountered.
     NonASCIIFile,
+    /// The file is not valid UTF-8 (when fields are measured in a unit allowing non-ASCII text).
+    InvalidUtf8File,
     /// A file I/O error.
-    FileError(PathBuf, std::io::Error)
+    FileError(PathBuf, std::io::Error),
+    /// A record descriptor word at the given byte offset is invalid or the record is truncated.
+    InvalidRecordDescriptor(usize),
+    /// None of the available layouts applies to the file.
+    NoMatchingLayout(PathBuf),
+    /// Seeking to a record requires a fixed-length record format.
+    SeekUnsupported,
+    /// The named field is split into parts (see `Splitter`), which a RowParser can't make.
+    SplitterUnsupported(String),
+    /// More than one field definition has this name or alias.
+    DuplicateFieldName(String),
+    /// The two named fields overlap, and neither allows overlaps.
+    OverlappingFields(String, String),
+    /// The field is measured in the given unit, which is not the one the file is loaded with
+    /// (the second unit).
+    ColumnUnitMismatch(String, ColumnUnit, ColumnUnit),
+    /// A row could not be exported (e.g., a selected field was not found).
+    ExportError(DataRowError),
+    /// The spreadsheet could not be written.
+    #[cfg(feature = "xlsx")]
+    XlsxError(rust_xlsxwriter::XlsxError),
+    /// More rows were rejected than `LoadOptions::max_errors` allows (the limit).
+    TooManyErrors(usize),
+    /// A control total set to fail on mismatch did not balance (described).
+    ControlTotalMismatch(String),
+    /// The file is not a DataFile saved with `DataFile::save()`, or is damaged (described).
+    InvalidSnapshot(PathBuf, String),
+    /// A record is longer than `LoadOptions::max_line_length` allows (the limit, in bytes).
+    LineTooLong(usize),
+    /// Loading the file (of a directory loaded on several threads) panicked.
+    LoadPanicked(PathBuf),
+    /// The error (the second member) was found at the given location in the file.
+    AtLocation(Box<ErrorLocation>, Box<DataFileError>)
+}
+
+/// Where in a file a load failed, so that the offending record can be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// The path of the file; empty when loading from memory.
+    pub path: PathBuf,
+    /// The 1-based line (record) number.
+    pub line_number: usize,
+    /// The offset of the offending byte from the start of the file.
+    pub byte_offset: usize,
+    /// The part of the record around the offending byte, with invalid bytes replaced.
+    pub snippet: String,
+    /// The bytes of the record the snippet was taken from.
+    pub region: Vec<u8>,
+    /// The position of the region in the record.
+    pub region_start: usize
+}
+
+impl ErrorLocation {
+    /// Number of bytes either side of the offending byte included in the snippet.
+    const SNIPPET_CONTEXT: usize = 20;
+
+    /// Get a hex and ASCII dump of the region around the offending byte, to see exactly what
+    /// the file contains there. Offsets are positions in the record.
+    pub fn hex_dump(&self) -> String {
+        hex_dump(&self.region, self.region_start)
+    }
 }
 
 impl Display for DataFileError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
+        write!(f, "Data File Error: {}", self.describe())
+    }
+}
+
+impl DataFileError {
+    /// Describe the error, without the "Data File Error" prefix.
+    fn describe(&self) -> String {
+        match self {
             DataFileError::NonASCIIFile => "Non ASCII file.".to_string(),
-            DataFileError::FileError(p, e) => format!("IO error on {} ({})", p.to_string_lossy(), e.to_string())
+            DataFileError::InvalidUtf8File => "Invalid UTF-8 file.".to_string(),
+            DataFileError::FileError(p, e) => format!("IO error on {} ({})", p.to_string_lossy(), e),
+            DataFileError::InvalidRecordDescriptor(o) => format!("Invalid record descriptor at byte {}", o),
+            DataFileError::NoMatchingLayout(p) => format!("No matching layout for {}", p.to_string_lossy()),
+            DataFileError::SeekUnsupported => "Seeking requires fixed-length records".to_string(),
+            DataFileError::SplitterUnsupported(n) => format!("Field {} is split into parts, which a RowParser can't make", n),
+            DataFileError::DuplicateFieldName(n) => format!("Duplicate field name {}", n),
+            DataFileError::OverlappingFields(a, b) => format!("Fields {} and {} overlap", a, b),
+            DataFileError::ColumnUnitMismatch(n, u, l) => format!("Field {} is measured in {}, not {}", n, u, l),
+            DataFileError::ExportError(e) => format!("Export failed ({})", e),
+            DataFileError::TooManyErrors(n) => format!("Too many rejected rows (more than {})", n),
+            DataFileError::ControlTotalMismatch(m) => format!("Control total mismatch ({})", m),
+            DataFileError::LineTooLong(n) => format!("Record longer than {} bytes (missing line breaks?)", n),
+            DataFileError::LoadPanicked(p) => format!("Loading {} panicked", p.to_string_lossy()),
+            DataFileError::InvalidSnapshot(p, m) => format!("Invalid saved file {} ({})", p.to_string_lossy(), m),
+            #[cfg(feature = "xlsx")]
+            DataFileError::XlsxError(e) => format!("Spreadsheet error ({})", e),
+            DataFileError::AtLocation(l, e) => {
+                let file = if l.path.as_os_str().is_empty() { String::new() } else { format!(" of {}", l.path.to_string_lossy()) };
+                format!("{} at line {}{}, byte {} ({:?})", e.describe(), l.line_number, file, l.byte_offset, l.snippet)
+            }
+        }
+    }
+}
+
+impl Error for DataFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataFileError::FileError(_, e) => Some(e),
+            DataFileError::ExportError(e) => Some(e),
+            #[cfg(feature = "xlsx")]
+            DataFileError::XlsxError(e) => Some(e),
+            DataFileError::AtLocation(_, e) => Some(e.as_ref()),
+            _ => None
+        }
+    }
+}
+
+impl From<DataRowError> for DataFileError {
+    fn from(value: DataRowError) -> Self {
+        DataFileError::ExportError(value)
+    }
+}
+
+impl DataFileError {
+    /// Get the kind of the underlying I/O error, if this is a `FileError`, e.g. to tell a
+    /// missing file (`std::io::ErrorKind::NotFound`) from other failures.
+    pub fn io_error_kind(&self) -> Option<std::io::ErrorKind> {
+        match self.root() {
+            DataFileError::FileError(_, e) => Some(e.kind()),
+            _ => None
+        }
+    }
+
+    /// Get the location in the file where the error was found, if known.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            DataFileError::AtLocation(l, _) => Some(l),
+            _ => None
+        }
+    }
+
+    /// Get the error without its location, for matching on the kind of error.
+    /// ```
+    /// use ffreader::{DataFile, DataFileError, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let e = DataFile::try_from_str("first\nsécond\n", &defs, &LoadOptions::default()).err().unwrap();
+    /// assert!(matches!(e.root(), DataFileError::NonASCIIFile));
+    /// assert_eq!(e.location().unwrap().line_number, 2);
+    /// assert_eq!(e.location().unwrap().byte_offset, 7);
+    /// ```
+    pub fn root(&self) -> &DataFileError {
+        match self {
+            DataFileError::AtLocation(_, e) => e.root(),
+            e => e
+        }
+    }
+
+    /// Attach the location of a record to an error found in it. The record is the one with the
+    /// given 0-based line index starting at the given offset in the file.
+    pub(crate) fn at(self, path: &Path, line_index: usize, record_offset: usize, record: &[u8]) -> DataFileError {
+        let position = match self {
+            DataFileError::NonASCIIFile => record.iter().position(|b| !b.is_ascii()),
+            DataFileError::InvalidUtf8File => std::str::from_utf8(record).err().map(|e| e.valid_up_to()),
+            _ => None
+        }.unwrap_or(0);
+        let start = position.saturating_sub(ErrorLocation::SNIPPET_CONTEXT);
+        let end = (position + ErrorLocation::SNIPPET_CONTEXT).min(record.len());
+        let location = ErrorLocation {
+            path: path.into(),
+            line_number: line_index + 1,
+            byte_offset: record_offset + position,
+            snippet: String::from_utf8_lossy(&record[start..end]).into_owned(),
+            region: record[start..end].to_vec(),
+            region_start: start
         };
-        write!(f, "Data File Error: {}", s)
+        DataFileError::AtLocation(Box::new(location), Box::new(self))
     }
 }
 
-impl Error for DataFileError { }
+/// Check that a record is no longer than `LoadOptions::max_line_length` allows.
+pub(crate) fn check_line_length(record: &[u8], options: &LoadOptions) -> Result<()> {
+    match record.len() > options.line_limit() {
+        true => Err(DataFileError::LineTooLong(options.line_limit())),
+        false => Ok(())
+    }
+}
 
 /// Convenient Result shorthand for DataFileError Results.
 pub type Result<T> = std::result::Result<T, DataFileError>;
 
+/// Check a layout before loading with it: no two field definitions may share a name or alias,
+/// so that every field can be retrieved, and fields may only overlap where one of them allows
+/// it. Fields with conditions may share a name and overlap, since they are alternative layouts
+/// of which only one is expected to apply to each row.
+pub(crate) fn check_layout(row_defs: &[DataFieldDef]) -> Result<()> {
+    let mut seen: HashMap<&str, bool> = HashMap::new();
+    for def in row_defs {
+        let parts = def.splitter.iter().flat_map(|s| s.names());
+        for name in std::iter::once(def.name.as_str()).chain(def.aliases.iter().map(String::as_str)).chain(parts) {
+            let conditional = def.condition.is_some();
+            if let Some(other_conditional) = seen.insert(name, conditional) {
+                if !(conditional && other_conditional) {
+                    return Err(DataFileError::DuplicateFieldName(name.to_string()));
+                }
+            }
+        }
+    }
+
+    for (i, a) in row_defs.iter().enumerate() {
+        for b in &row_defs[i + 1..] {
+            // fields anchored at opposite ends may or may not meet depending on the row length
+            let exempt = a.overlaps_allowed || b.overlaps_allowed || a.anchor != b.anchor
+                || (a.condition.is_some() && b.condition.is_some());
+            if !exempt && a.start_idx < b.end_idx && b.start_idx < a.end_idx {
+                return Err(DataFileError::OverlappingFields(a.name.clone(), b.name.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the units stated by the field definitions are the one the file is loaded with.
+pub(crate) fn check_units(row_defs: &[DataFieldDef], options: &LoadOptions) -> Result<()> {
+    match row_defs.iter().find(|d| d.unit.is_some_and(|u| u != options.columns)) {
+        Some(d) => Err(DataFileError::ColumnUnitMismatch(d.name.clone(), d.unit.unwrap_or_default(), options.columns)),
+        None => Ok(())
+    }
+}
+
+/// Check that the number of rows rejected so far is within `LoadOptions::max_errors`.
+pub(crate) fn check_error_limit(options: &LoadOptions, summary: &LoadSummary) -> Result<()> {
+    match options.max_errors {
+        Some(n) if summary.rows_rejected > n => Err(DataFileError::TooManyErrors(n)),
+        _ => Ok(())
+    }
+}
+
+/// Parse a single record into a row, or skip or reject it, recording the outcome in the
+/// warnings and summary. Shared by DataFile and DataFileReader so both load identically.
+pub(crate) fn load_record(line_index: usize, row: &str, row_defs: &Vec<DataFieldDef>, options: &LoadOptions,
+                          load_warnings: &mut Vec<LoadWarning>, summary: &mut LoadSummary) -> Option<DataRow> {
+    let row = load_record_with(line_index, row, row_defs, options, load_warnings, summary,
+                               |row| DataRow::try_create_with_warnings(row, row_defs, options))
+        .map(|r| r.with_line_index(line_index));
+    if let (Some(writer), Some(row)) = (&options.audit_writer, &row) {
+        writer.write_row(line_index, row);
+    }
+    row
+}
+
+/// Skip a record, or parse it with the given function, as described for `load_record()`.
+pub(crate) fn load_record_with<T>(line_index: usize, row: &str, row_defs: &[DataFieldDef], options: &LoadOptions,
+                                  load_warnings: &mut Vec<LoadWarning>, summary: &mut LoadSummary,
+                                  parse: impl FnOnce(&str) -> DataRowResult<(T, Vec<DataFieldError>)>) -> Option<T> {
+    if !options.is_sampled(line_index) {
+        summary.rows_skipped += 1;
+        return None;
+    }
+
+    let first_warning = load_warnings.len();
+    let row = load_row(line_index, row, row_defs, options, load_warnings, summary, parse);
+    load_warnings[first_warning..].iter().for_each(trace::warning);
+    row
+}
+
+/// Skip, parse or reject a sampled record, as described for `load_record()`.
+fn load_row<T>(line_index: usize, row: &str, row_defs: &[DataFieldDef], options: &LoadOptions,
+               load_warnings: &mut Vec<LoadWarning>, summary: &mut LoadSummary,
+               parse: impl FnOnce(&str) -> DataRowResult<(T, Vec<DataFieldError>)>)
+    -> Option<T> {
+    if let Some(reason) = options.skip_reason(row) {
+        trace::skipped_line(line_index, reason);
+        summary.rows_skipped += 1;
+        if options.warn_on_skipped_lines {
+            load_warnings.push(LoadWarning::skipped_line(line_index, row, reason));
+        }
+        return None;
+    }
+
+    let line = row;
+    let padded = options.pad_short_row(row, row_defs);
+    if let Some((padded_row, len)) = &padded {
+        load_warnings.push(LoadWarning::padded_row(line_index, row, *len, options.columns.len(padded_row)));
+    }
+    let row = padded.as_ref().map_or(row, |(r, _)| r.as_str());
+
+    match parse(row) {
+        Ok((r, field_warnings)) => {
+            for w in field_warnings {
+                load_warnings.push(LoadWarning::field_downgraded(line_index, w));
+            }
+            if options.extra_data == ExtraDataPolicy::Warn {
+                if let Some((column, text)) = extra_data(row, row_defs, options.columns) {
+                    load_warnings.push(LoadWarning::extra_data(line_index, column, text));
+                }
+            }
+            if let Some(check) = options.printable_ascii.as_ref().filter(|c| !c.reject) {
+                for u in check.find(row, row_defs, options.columns) {
+                    load_warnings.push(LoadWarning::unprintable(line_index, u.field_name, u.raw, u.start, &u.chars));
+                }
+            }
+            if let Some(metrics) = &options.metrics {
+                metrics.row_loaded();
+            }
+            Some(r)
+        }
+        Err(e) => {
+            summary.rows_rejected += 1;
+            if let Some(quarantine) = &options.quarantine {
+                quarantine.add(line_index, line, &e);
+            }
+            let warning = LoadWarning::row_error(line_index, row, e);
+            if let Some(metrics) = &options.metrics {
+                metrics.row_rejected(warning.kind());
+            }
+            load_warnings.push(warning);
+            None
+        }
+    }
+}
+
 impl DataFile {
     /// Attempt to load a file and parse its rows and fields.
     ///
@@ -49,31 +399,259 @@ impl DataFile {
     /// This method should ensure a decent level of tolerance for non-data lines such as
     /// headers, boilerplate metadata, and such. It can also be used as a way to filter
     /// rows meeting certain criteria.
+    ///
+    /// Fails with `DataFileError::DuplicateFieldName` if two definitions without conditions
+    /// share a name or alias, and with `DataFileError::OverlappingFields` if two fields overlap
+    /// without `DataFieldDef::allow_overlaps()`. Fails with `DataFileError::ColumnUnitMismatch` if
+    /// a field states a unit other than that of the LoadOptions, and with
+    /// `DataFileError::TooManyErrors` if more rows are rejected than `LoadOptions::max_errors`.
     pub fn try_load(path: &Path, row_defs: &Vec<DataFieldDef>) -> Result<DataFile> {
-        let data = fs::read_to_string(path);
+        Self::try_load_with_options(path, row_defs, &LoadOptions::default())
+    }
+
+    /// Attempt to load a file as with `try_load()`, using the provided LoadOptions.
+    ///
+    /// Line numbers reported in warnings always refer to the position in the file,
+    /// regardless of how many lines were skipped.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let options = LoadOptions { skip_rows: 3, max_rows: Some(5000), ..Default::default() };
+    /// let df = DataFile::try_load_with_options(Path::new("extract.txt"), &defs, &options).unwrap();
+    /// ```
+    pub fn try_load_with_options(path: &Path, row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> Result<DataFile> {
+        let start = Stopwatch::start();
+        let data = fs::read(path);
         if let Err(e) = data {
             return Err(DataFileError::FileError(path.into(), e))
         }
         let data = data.unwrap();
 
-        if !data.is_ascii() {
-            return Err(DataFileError::NonASCIIFile);
-        }
+        Self::parse(path, &data, row_defs, options, start)
+    }
+
+    /// Parse data held in memory, as with `try_load_with_options()`. This does not touch the
+    /// filesystem, so it can be used where there is none, e.g. in `wasm32-unknown-unknown`.
+    ///
+    /// The DataFile has no path, so `verify_roundtrip()` can't be used on it.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let data = format!("{:<200}\n", "5412345678");
+    /// let df = DataFile::try_from_bytes(data.as_bytes(), &defs, &LoadOptions::default()).unwrap();
+    /// assert_eq!(df.rows()[0].fields()[0].data(), "5412345678");
+    /// ```
+    pub fn try_from_bytes(data: &[u8], row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> Result<DataFile> {
+        Self::parse(Path::new(""), data, row_defs, options, Stopwatch::start())
+    }
+
+    /// Parse text held in memory; see `try_from_bytes()`.
+    pub fn try_from_str(data: &str, row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> Result<DataFile> {
+        Self::try_from_bytes(data.as_bytes(), row_defs, options)
+    }
+
+    pub(crate) fn parse(path: &Path, data: &[u8], row_defs: &Vec<DataFieldDef>, options: &LoadOptions, start: Stopwatch)
+        -> Result<DataFile> {
+        let options = options.for_source(path);
+        LoadSpan::new(path).in_scope(|| Self::parse_in_span(path, data, row_defs, &options, start))
+    }
+
+    fn parse_in_span(path: &Path, data: &[u8], row_defs: &Vec<DataFieldDef>, options: &LoadOptions, start: Stopwatch)
+        -> Result<DataFile> {
+        check_layout(row_defs)?;
+        check_units(row_defs, options)?;
+        options.bytes_read(data.len());
+        let source_hash = options.compute_hash.then(|| SourceHash {
+            path: path.into(),
+            byte_count: data.len(),
+            sha256: hex(&Sha256::digest(data))
+        });
 
         let mut rows: Vec<DataRow> = vec![];
         let mut load_warnings: Vec<LoadWarning> = vec![];
+        let mut summary = LoadSummary::default();
 
-        for (line_index, row) in data.lines().enumerate() {
-            match DataRow::try_create(row, row_defs) {
-                Ok(r) => rows.push(r),
-                Err(e) => load_warnings.push(LoadWarning::new(line_index, Box::new(e)))
+        let (lines, rows_read) = Self::read_records(path, data, options, &mut load_warnings)?;
+        summary.rows_read = rows_read;
+        load_warnings.iter().for_each(trace::warning);
+
+        let mut tally = ControlTally::default();
+        let mut seen = SeenKeys::default();
+        match options.threads.filter(|n| *n > 1) {
+            Some(threads) => {
+                let (parsed, mut warnings, counts) = load_records_parallel(&lines, row_defs, options, threads);
+                summary.rows_skipped = counts.rows_skipped;
+                summary.rows_rejected = counts.rows_rejected;
+                if options.duplicates.is_some() {
+                    let mut records = lines.iter();
+                    rows = parsed.into_iter()
+                        .filter_map(|r| {
+                            let line_index = r.line_index().unwrap_or_default();
+                            let (_, line) = records.find(|(i, _)| *i == line_index).expect("row parsed from a record");
+                            seen.check(options, line, r, &mut warnings, &mut summary)
+                        })
+                        .collect();
+                    // sorted, so that the warnings of duplicates are with those of their lines
+                    warnings.sort_by_key(|w| w.line_index());
+                } else {
+                    rows = parsed;
+                }
+                load_warnings.extend(warnings);
+                check_error_limit(options, &summary)?;
+                lines.iter().for_each(|(i, l)| tally.record(options, *i, l));
+                rows.iter().for_each(|r| tally.row(options, row_defs, r));
+            }
+            None => for (line_index, row) in lines.iter().map(|(i, l)| (*i, l.as_ref())) {
+                tally.record(options, line_index, row);
+                let loaded = load_record(line_index, row, row_defs, options, &mut load_warnings, &mut summary)
+                    .and_then(|r| seen.check(options, row, r, &mut load_warnings, &mut summary));
+                if let Some(row) = loaded {
+                    tally.row(options, row_defs, &row);
+                    rows.push(row);
+                }
+                check_error_limit(options, &summary)?;
             }
         }
+        let first_warning = load_warnings.len();
+        tally.finish(options, row_defs, rows_read.saturating_sub(1), &mut load_warnings)?;
+        load_warnings[first_warning..].iter().for_each(trace::warning);
+
+        summary.rows_loaded = rows.len();
+        if let Some(warning) = LoadWarning::no_rows(data.is_empty(), &summary, options.skip_rows) {
+            trace::warning(&warning);
+            load_warnings.push(warning);
+        }
+        summary.count_warnings(&load_warnings);
+        summary.elapsed = start.elapsed();
+        options.finished(&summary);
+
+        Ok(Self::from_parts(path, rows, load_warnings, summary, source_hash, row_defs, options))
+    }
+
+    /// Assemble a DataFile from rows already loaded with the provided definitions and options.
+    pub(crate) fn from_parts(path: &Path, mut rows: Vec<DataRow>, load_warnings: Vec<LoadWarning>, summary: LoadSummary,
+                             source_hash: Option<SourceHash>, row_defs: &[DataFieldDef], options: &LoadOptions)
+        -> DataFile {
+        for (i, row) in rows.iter_mut().enumerate().filter(|(_, r)| r.row_id().is_none()) {
+            row.set_row_id(i);
+        }
+        let redactions = row_defs.iter()
+            .filter_map(|d| d.redaction.clone().map(|r| (d.name.clone(), r)))
+            .collect();
+        let blanks = row_defs.iter()
+            .filter_map(|d| d.blank.map(|b| (d.name.clone(), b)))
+            .collect();
+        let implied_decimals = row_defs.iter()
+            .filter(|d| d.implied_decimals > 0)
+            .map(|d| (d.name.clone(), d.implied_decimals))
+            .collect();
+        let audit_log = match options.audit {
+            true => rows.iter().flat_map(|r| AuditEntry::changes(r.line_index().unwrap_or_default(), r)).collect(),
+            false => vec![]
+        };
 
-        Ok(DataFile{
+        DataFile{
             rows,
-            load_warnings
-        })
+            load_warnings,
+            summary,
+            redactions,
+            blanks,
+            implied_decimals,
+            source_hash,
+            path: path.into(),
+            options: options.clone(),
+            indexes: HashMap::new(),
+            audit_log
+        }
+    }
+
+    /// Combine loaded files into one, with the given summary and path, keeping the redactions
+    /// and other settings of the first.
+    pub(crate) fn combine(path: &Path, files: impl IntoIterator<Item = DataFile>, summary: LoadSummary) -> DataFile {
+        let mut files = files.into_iter();
+        let Some(mut combined) = files.next() else {
+            return DataFile::from_parts(path, vec![], vec![], summary, None, &[], &LoadOptions::default());
+        };
+        for file in files {
+            combined.rows.extend(file.rows);
+            combined.load_warnings.extend(file.load_warnings);
+            combined.audit_log.extend(file.audit_log);
+        }
+        for (i, row) in combined.rows.iter_mut().enumerate() {
+            row.set_row_id(i);
+        }
+        combined.summary = summary;
+        combined.source_hash = None;
+        combined.path = path.into();
+        combined.indexes.clear();
+        combined
+    }
+
+    /// Split the file contents into records and apply the line selection options, returning the
+    /// (possibly joined) records with their line indexes and the number of lines read.
+    pub(crate) fn read_records<'a>(path: &Path, data: &'a [u8], options: &LoadOptions, load_warnings: &mut Vec<LoadWarning>)
+        -> Result<(IndexedRecords<'a>, usize)> {
+        let lines = options.record_format.split(data)?.into_iter()
+            .enumerate()
+            .skip(options.skip_rows)
+            .take(options.max_rows.unwrap_or(usize::MAX))
+            .map(|(i, r)| match check_line_length(r, options).and_then(|_| decode(r, i, options)) {
+                Ok((line, warning)) => {
+                    load_warnings.extend(warning);
+                    Ok((i, line))
+                }
+                Err(e) => Err(e.at(path, i, r.as_ptr() as usize - data.as_ptr() as usize, r))
+            })
+            .collect::<Result<IndexedRecords>>()?;
+        let rows_read = lines.len();
+
+        let lines = match &options.continuation {
+            Some(c) => c.join(lines, load_warnings),
+            None => lines
+        };
+
+        Ok((lines, rows_read))
+    }
+
+    /// Re-render each loaded row with the fixed-width writer and compare it with the original
+    /// line in the source file, returning a warning for each row that differs.
+    ///
+    /// The field definitions should be those used to load the file. Differences usually point
+    /// to columns not covered by any field, post-processing that alters values, or alignment
+    /// settings that don't match the file.
+    pub fn verify_roundtrip(&self, row_defs: &Vec<DataFieldDef>) -> Result<Vec<LoadWarning>> {
+        let data = fs::read(&self.path)
+            .map_err(|e| DataFileError::FileError(self.path.clone(), e))?;
+        let (lines, _) = Self::read_records(&self.path, &data, &self.options, &mut vec![])?;
+        let lines: HashMap<usize, &str> = lines.iter().map(|(i, l)| (*i, l.as_ref())).collect();
+
+        let mut warnings = vec![];
+        for row in &self.rows {
+            let original = match row.line_index().and_then(|i| lines.get(&i)) {
+                Some(l) => *l,
+                None => continue
+            };
+            let rendered = render_row(row, row_defs);
+            if let Some(message) = roundtrip_difference(original, &rendered) {
+                warnings.push(LoadWarning::roundtrip_mismatch(row.line_index().unwrap_or_default(), original, message));
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Iterate over the rows contained in the DataFile.
+    /// `&DataFile` and `DataFile` also implement `IntoIterator`, so a DataFile can be used
+    /// directly in `for` loops and iterator chains.
+    pub fn iter(&self) -> std::slice::Iter<'_, DataRow> {
+        self.rows.iter()
     }
 
     /// Get a reference to the rows contained in the DataFile.
@@ -81,28 +659,896 @@ impl DataFile {
         &self.rows
     }
 
+    /// Get a mutable reference to the rows; indexes built with `index_by()` must be kept valid.
+    pub(crate) fn rows_mut(&mut self) -> &mut Vec<DataRow> {
+        &mut self.rows
+    }
+
+    /// Estimate the memory held by the rows, in bytes, which for a large file is nearly all of
+    /// the memory used by the DataFile. Field names are shared by all rows, so they aren't
+    /// counted.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let df = DataFile::try_from_str(&format!("{:<200}\n", "5412345678"), &defs, &LoadOptions::default()).unwrap();
+    /// println!("{} bytes per row", df.memory_usage() / df.rows().len());
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        (self.rows.capacity() - self.rows.len()) * size_of::<DataRow>()
+            + self.rows.iter().map(|r| r.memory_usage()).sum::<usize>()
+    }
+
     /// Get a reference to the list of warnings generated during creation.
     pub fn warnings(&self) -> &Vec<LoadWarning> {
         &self.load_warnings
     }
 
+    /// Get the warnings of the given kind.
+    pub fn warnings_of_kind(&self, kind: WarningKind) -> Vec<&LoadWarning> {
+        self.load_warnings.iter().filter(|w| w.kind() == kind).collect()
+    }
+
+    /// Get the warnings pertaining to the named field.
+    pub fn warnings_for_field(&self, field_name: &str) -> Vec<&LoadWarning> {
+        self.load_warnings.iter().filter(|w| w.field_name() == Some(field_name)).collect()
+    }
+
+    /// Get the warnings for the line with the given 0-based index.
+    pub fn warnings_for_line(&self, line_index: usize) -> Vec<&LoadWarning> {
+        self.load_warnings.iter().filter(|w| w.line_index() == line_index).collect()
+    }
+
+    /// Summarize the warnings as a WarningGroup per kind and field, in the order of their
+    /// first warnings, each with the first `WarningAggregator::DEFAULT_EXAMPLES` in full; see
+    /// `warnings_in()` for the rest.
+    pub fn warning_groups(&self) -> Vec<WarningGroup> {
+        let mut aggregator = WarningAggregator::default();
+        aggregator.extend(self.load_warnings.iter().cloned());
+        aggregator.take_groups()
+    }
+
+    /// Get all the warnings of a group, e.g. one of `warning_groups()`.
+    pub fn warnings_in(&self, group: &WarningGroup) -> Vec<&LoadWarning> {
+        self.load_warnings.iter().filter(|w| group.matches(w)).collect()
+    }
+
+    /// Take the warnings, leaving none, e.g. to hand them off once a file is processed in a
+    /// long-lived process. The summary still counts them.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let mut df = DataFile::try_from_str("short\n", &defs, &LoadOptions::default()).unwrap();
+    /// assert!(df.has_errors());
+    /// let warnings = df.take_warnings();
+    /// assert_eq!(warnings.len(), 1);
+    /// assert!(!df.has_errors() && df.warnings().is_empty());
+    /// assert_eq!(df.summary().warning_count(), 1);
+    /// ```
+    pub fn take_warnings(&mut self) -> Vec<LoadWarning> {
+        std::mem::take(&mut self.load_warnings)
+    }
+
+    /// Discard the warnings. The summary still counts them.
+    pub fn clear_warnings(&mut self) {
+        self.load_warnings.clear();
+    }
+
+    /// Get the changes post-processing made to the values of the loaded rows, in file order,
+    /// if `LoadOptions::audit` was set; empty otherwise.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn upper(value: String) -> DataFieldResult<String> {
+    ///     Ok(value.to_uppercase())
+    /// }
+    /// let defs = vec![DataFieldDef::new("Name", 0, 10, &upper)];
+    /// let data = format!("{:<200}\n{:<200}\n", "BOB", "ann");
+    /// let df = DataFile::loader(&defs).audit(true).load_str(&data).unwrap();
+    /// assert_eq!(df.audit_log()[0].to_string(), r#"line 2, Name: "ann" -> "ANN""#);
+    /// ```
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        &self.audit_log
+    }
+
+    /// Determine whether any row was rejected; see `LoadWarning::is_error()`.
+    pub fn has_errors(&self) -> bool {
+        self.load_warnings.iter().any(|w| w.is_error())
+    }
+
+    /// Determine whether there are any warnings that aren't errors: problems with rows that
+    /// were kept, or lines that were skipped.
+    pub fn has_warnings(&self) -> bool {
+        self.load_warnings.iter().any(|w| !w.is_error())
+    }
+
+    /// Get the summary of the load: row counts, warnings by kind, and elapsed time.
+    /// The summary implements `Display` for concise logging.
+    pub fn summary(&self) -> &LoadSummary {
+        &self.summary
+    }
+
+    /// Create a new DataFile containing only the rows matching the predicate, with only the
+    /// named fields (in the order given). An empty list of names keeps all fields.
+    ///
+    /// Warnings about the retained rows (e.g. fields given fallback values) are kept; warnings
+    /// about other lines are not. Fails if a named field is not found.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+    ///     DataFieldDef::new("Status", 16, 23, &post_function),
+    /// ];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let active = df.select(|row| row.fields()[1].data() == "A", &["AccountNo1"]).unwrap();
+    /// ```
+    pub fn select<P: Fn(&DataRow) -> bool>(&self, predicate: P, field_names: &[&str]) -> DataRowResult<DataFile> {
+        let field_list = field_names.to_vec();
+        let mut rows = vec![];
+        for row in self.rows.iter().filter(|r| predicate(r)) {
+            rows.push(if field_list.is_empty() {
+                row.clone()
+            } else {
+                DataRow::from_fields(row.get_ordered_fields(&field_list)?, row.line_index())
+                    .with_name_matching(row.name_matching())
+                    .with_row_id(row.row_id())
+            });
+        }
+        Ok(self.with_rows(rows, &field_list))
+    }
+
+    /// Create a new DataFile of rows taken from this one, with only the named fields, or all
+    /// fields if none are named, as described for `select()`.
+    pub(crate) fn with_rows(&self, rows: Vec<DataRow>, field_list: &[&str]) -> DataFile {
+        let retained_lines: HashSet<usize> = rows.iter().filter_map(|r| r.line_index()).collect();
+        let load_warnings: Vec<LoadWarning> = self.load_warnings.iter()
+            .filter(|w| retained_lines.contains(&w.line_index()))
+            .cloned()
+            .collect();
+        // the names of the fields kept, however they were asked for
+        let kept: HashSet<&str> = field_list.iter()
+            .filter_map(|name| self.rows.iter().find_map(|r| r.get(name)).map(|f| f.name()))
+            .collect();
+        let keep = |name: &str| field_list.is_empty() || kept.contains(name);
+        let redactions = self.redactions.iter()
+            .filter(|(name, _)| keep(name))
+            .map(|(name, r)| (name.clone(), r.clone()))
+            .collect();
+        let blanks = self.blanks.iter()
+            .filter(|(name, _)| keep(name))
+            .map(|(name, b)| (name.clone(), *b))
+            .collect();
+        let implied_decimals = self.implied_decimals.iter()
+            .filter(|(name, _)| keep(name))
+            .map(|(name, d)| (name.clone(), *d))
+            .collect();
+
+        let audit_log = self.audit_log.iter()
+            .filter(|e| retained_lines.contains(&e.line_index))
+            .filter(|e| keep(&e.field))
+            .cloned()
+            .collect();
+
+        let mut summary = self.summary.clone();
+        summary.rows_loaded = rows.len();
+        summary.count_warnings(&load_warnings);
+
+        DataFile {
+            rows,
+            load_warnings,
+            summary,
+            redactions,
+            blanks,
+            implied_decimals,
+            source_hash: self.source_hash.clone(),
+            path: self.path.clone(),
+            options: self.options.clone(),
+            indexes: HashMap::new(),
+            audit_log
+        }
+    }
+
+    /// Build a hash index on a field so that `find_by_key()` lookups on it take constant time.
+    /// Rows without the field are not indexed. Rebuilding an existing index is harmless.
+    pub fn index_by(&mut self, field_name: &str) {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            if let Some(f) = row.get(field_name) {
+                index.entry(f.data()).or_default().push(i);
+            }
+        }
+        self.indexes.insert(field_name.to_string(), index);
+    }
+
+    /// Find the first row whose field has the given value.
+    /// This uses the index built by `index_by()` if there is one, otherwise it scans the rows.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)];
+    /// let mut df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// df.index_by("AccountNo1");
+    /// let row = df.find_by_key("AccountNo1", "5412345678");
+    /// ```
+    pub fn find_by_key(&self, field_name: &str, key: &str) -> Option<&DataRow> {
+        self.find_all_by_key(field_name, key).into_iter().next()
+    }
+
+    /// Find all rows whose field has the given value, in file order.
+    /// This uses the index built by `index_by()` if there is one, otherwise it scans the rows.
+    pub fn find_all_by_key(&self, field_name: &str, key: &str) -> Vec<&DataRow> {
+        match self.indexes.get(field_name) {
+            Some(index) => index.get(key)
+                .map(|positions| positions.iter().map(|i| &self.rows[*i]).collect())
+                .unwrap_or_default(),
+            None => self.rows.iter()
+                .filter(|r| r.get(field_name).is_some_and(|f| f.data() == key))
+                .collect()
+        }
+    }
+
+    /// Get the number of implied decimal places of a field, 0 if it has none. The field may
+    /// be named as for `DataRow::get()`.
+    pub(crate) fn implied_decimals(&self, field_name: &str) -> usize {
+        let name = self.rows.iter().find_map(|r| r.get(field_name)).map_or(field_name, |f| f.name());
+        self.implied_decimals.get(name).copied().unwrap_or(0)
+    }
+
+    /// Get the fields with implied decimal places, by field name.
+    pub(crate) fn implied_decimal_fields(&self) -> &HashMap<String, usize> {
+        &self.implied_decimals
+    }
+
+    /// Set the number of implied decimal places of a field.
+    pub(crate) fn set_implied_decimals(&mut self, field_name: String, places: usize) {
+        self.implied_decimals.insert(field_name, places);
+    }
+
+    /// Replace the audit log, e.g. with one read from a saved DataFile.
+    pub(crate) fn set_audit_log(&mut self, audit_log: Vec<AuditEntry>) {
+        self.audit_log = audit_log;
+    }
+
+    /// Get the path the file was loaded from; empty when loaded from memory.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the hash, path and size of the loaded file, if `LoadOptions::compute_hash` was set.
+    pub fn source_hash(&self) -> Option<&SourceHash> {
+        self.source_hash.as_ref()
+    }
+
+    /// Get the redactions applied to sensitive fields in redacted exports, by field name.
+    /// These are taken from the field definitions used to load the file.
+    pub fn redactions(&self) -> &HashMap<String, Redaction> {
+        &self.redactions
+    }
+
+    /// Mark a field as sensitive (or change how it is masked) for redacted exports.
+    pub fn set_redaction(&mut self, field_name: impl ToString, redaction: Redaction) {
+        self.redactions.insert(field_name.to_string(), redaction);
+    }
+
+    /// Get the fields whose blank values are written in a particular way in exports, by field
+    /// name. These are taken from the field definitions used to load the file, and take
+    /// precedence over `ExportOptions::blanks`.
+    pub fn blanks(&self) -> &HashMap<String, BlankValue> {
+        &self.blanks
+    }
+
+    /// Set how a field's blank values are written in exports.
+    pub fn set_blank(&mut self, field_name: impl ToString, blank: BlankValue) {
+        self.blanks.insert(field_name.to_string(), blank);
+    }
+
+    /// Generate a JSON report of the warnings encountered during load, as an array of
+    /// objects with `line`, `kind`, `field`, `raw` and `message` members.
+    #[cfg(feature = "json")]
+    pub fn warnings_report_json(&self) -> String {
+        let opt = |v: Option<&str>| v.map(json_string).unwrap_or("null".to_string());
+        let entries = self.load_warnings.iter().map(|w| {
+            format!("{{\"line\": {}, \"kind\": {}, \"field\": {}, \"raw\": {}, \"message\": {}}}",
+                    w.line_number(), json_string(&w.kind().to_string()), opt(w.field_name()),
+                    opt(w.raw()), json_string(&w.message()))
+        }).collect::<Vec<String>>();
+
+        format!("[{}]", entries.join(",\n"))
+    }
+
+    /// Generate a CSV report of the warnings encountered during load, with a header row of
+    /// `line,kind,field,raw,message`.
+    #[cfg(feature = "csv")]
+    pub fn warnings_report_csv(&self) -> String {
+        let mut report = String::from("line,kind,field,raw,message\n");
+        for w in &self.load_warnings {
+            report.push_str(&format!("{},{},{},{},{}\n", w.line_number(), w.kind(),
+                                     csv_field(w.field_name().unwrap_or("")),
+                                     csv_field(w.raw().unwrap_or("")),
+                                     csv_field(&w.message())));
+        }
+        report
+    }
+
     /// Generate a json version of the data.
     ///
     /// This function works for basic data but should be checked for more complex cases
     /// todo: build test suite for this function and improve robustness
+    #[cfg(feature = "json")]
     pub fn jsonify(&self) -> String {
-        let mut json_row_list = vec![];
-        for row in &self.rows {
-            let mut json_row = String::from("{");
-            let mut kv_list = vec![];
-            for field in row.fields() {
-                kv_list.push(format!("\"{}\": \"{}\"", field.name(), field.data()));
+        self.jsonify_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+}
+
+impl Display for DataFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} rows, {} warnings", self.path.to_string_lossy(), self.rows.len(), self.load_warnings.len())
+    }
+}
+
+impl Debug for DataFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataFile")
+            .field("path", &self.path)
+            .field("rows", &Preview(&self.rows))
+            .field("warnings", &Preview(&self.load_warnings))
+            .field("summary", &self.summary)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Items of a list in Debug output, up to `PREVIEW_LENGTH` of them, followed by the number of
+/// items left out.
+struct Preview<'a, T>(&'a [T]);
+
+impl<T> Preview<'_, T> {
+    /// Most items shown.
+    const PREVIEW_LENGTH: usize = 3;
+}
+
+impl<T: Debug> Debug for Preview<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        list.entries(self.0.iter().take(Self::PREVIEW_LENGTH));
+        if self.0.len() > Self::PREVIEW_LENGTH {
+            list.entry(&format_args!("... {} more", self.0.len() - Self::PREVIEW_LENGTH));
+        }
+        list.finish()
+    }
+}
+
+impl IntoIterator for DataFile {
+    type Item = DataRow;
+    type IntoIter = std::vec::IntoIter<DataRow>;
+
+    /// Consume the DataFile, yielding its rows. Warnings are discarded.
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DataFile {
+    type Item = &'a DataRow;
+    type IntoIter = std::slice::Iter<'a, DataRow>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::{DataFieldResult, NameMatching, Sample};
+    use super::*;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    /// Write the provided contents to a uniquely-named temporary file.
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ffreader_{}_{}.txt", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Build a row long enough to pass the minimum row length check.
+    fn padded_row(prefix: &str) -> String {
+        format!("{:<200}", prefix)
+    }
+
+    #[test]
+    fn skip_and_max_rows_honored() {
+        let mut contents = String::from("HEADER LINE\nSECOND HEADER\n");
+        for i in 0..10 {
+            contents.push_str(&padded_row(&format!("ROW{}", i)));
+            contents.push('\n');
+        }
+        let path = temp_file("skip_max", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert_eq!(df.rows().len(), 10);
+        assert_eq!(df.warnings().len(), 2);
+
+        let options = LoadOptions { skip_rows: 2, max_rows: Some(3), ..Default::default() };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 3);
+        assert!(df.warnings().is_empty());
+        assert_eq!(df.rows()[0].fields()[0].data(), "ROW0");
+        assert_eq!(df.rows()[2].fields()[0].data(), "ROW2");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sampled_rows_deterministic() {
+        let contents: String = (0..1000).map(|i| format!("{}\n", padded_row(&format!("ROW{}", i)))).collect();
+        let path = temp_file("sample", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 7, &echo_ok)];
+
+        let options = LoadOptions { skip_rows: 1, ..Default::default() }.sample(Sample::EveryNth(10), 3);
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 100);
+        assert_eq!(df.rows()[0].fields()[0].data(), "ROW3");
+        assert_eq!(df.summary().rows_skipped, 899);
+
+        let options = LoadOptions::default().sample(Sample::Fraction(0.1), 7);
+        let first = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        let second = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert!((50..150).contains(&first.rows().len()));
+        let lines = |df: &DataFile| df.rows().iter().map(|r| r.line_index()).collect::<Vec<_>>();
+        assert_eq!(lines(&first), lines(&second));
+        let other = DataFile::try_load_with_options(&path, &defs, &options.clone().sample(Sample::Fraction(0.1), 8)).unwrap();
+        assert_ne!(lines(&first), lines(&other));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn blank_and_comment_lines_skipped() {
+        let contents = format!("{}\n\n   \n# a comment\n{}\n", padded_row("ROW0"), padded_row("ROW1"));
+        let path = temp_file("blank_comment", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.warnings().len(), 3);
+
+        let mut options = LoadOptions {
+            skip_blank_lines: true,
+            comment_prefix: Some("#".to_string()),
+            ..Default::default()
+        };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert!(df.warnings().is_empty());
+
+        assert_eq!(df.summary().rows_read, 5);
+        assert_eq!(df.summary().rows_skipped, 3);
+        assert_eq!(df.summary().rows_loaded, 2);
+
+        options.warn_on_skipped_lines = true;
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.summary().warnings_by_kind.get(&crate::WarningKind::SkippedLine), Some(&3));
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.warnings()[2].to_string(), "Line 4 Skipped comment line");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(all(feature = "csv", feature = "json"))]
+    #[test]
+    fn warnings_report_structured() {
+        fn fail(_: String) -> DataFieldResult<String> {
+            Err(crate::DataFieldError::Problem(Box::new("bad \"value\"")))
+        }
+        let contents = format!("SHORT\n{}\n", padded_row("ROW1"));
+        let path = temp_file("warning_report", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 5, &fail)];
+
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        let w = &df.warnings()[1];
+        assert_eq!(df.warnings()[0].kind(), crate::WarningKind::BadRowLength);
+        assert_eq!(w.kind(), crate::WarningKind::FieldError);
+        assert_eq!(w.line_number(), 2);
+        assert_eq!(w.field_name(), Some("id"));
+        assert_eq!(w.raw(), Some("ROW1 "));
+
+        let csv = df.warnings_report_csv();
+        assert!(csv.starts_with("line,kind,field,raw,message\n1,BadRowLength,,SHORT,"));
+        assert!(csv.contains("2,FieldError,id,ROW1 ,\"Problem: bad \"\"value\"\""));
+
+        let json = df.warnings_report_json();
+        assert!(json.contains("{\"line\": 2, \"kind\": \"FieldError\", \"field\": \"id\", \"raw\": \"ROW1 \""));
+        assert!(json.contains("\"field\": null"));
+
+        let mut df = df;
+        assert_eq!(df.warnings_of_kind(crate::WarningKind::FieldError).len(), 1);
+        assert_eq!(df.warnings_for_field("id")[0].line_index(), 1);
+        assert_eq!(df.warnings_for_line(0)[0].kind(), crate::WarningKind::BadRowLength);
+        assert!(df.has_errors() && !df.has_warnings());
+        assert_eq!(df.take_warnings().len(), 2);
+        assert!(!df.has_errors() && df.warnings().is_empty());
+        assert_eq!(df.summary().warning_count(), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn fixed_length_records_loaded() {
+        let contents = format!("{}{}", padded_row("ROW0"), padded_row("ROW1"));
+        let path = temp_file("fixed_length", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+
+        let options = LoadOptions { record_format: crate::RecordFormat::FixedLength(200), ..Default::default() };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.rows()[1].fields()[0].data(), "ROW1");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn continuation_lines_joined() {
+        let contents = format!("{:<100}C\n{:<100}E\n{}\n", "ROW0", "PART2", padded_row("ROW1"));
+        let path = temp_file("continuation", &contents);
+        let defs = vec![
+            DataFieldDef::new("id", 0, 5, &echo_ok),
+            DataFieldDef::new("part", 101, 106, &echo_ok),
+        ];
+
+        let options = LoadOptions { continuation: Some(crate::Continuation::new(100, 'C')), ..Default::default() };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.rows()[0].fields()[1].data(), "PART2");
+        assert_eq!(df.summary().rows_read, 3);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn source_hash_recorded() {
+        let path = temp_file("source_hash", "abc");
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert!(df.source_hash().is_none());
+
+        let options = LoadOptions { compute_hash: true, ..Default::default() };
+        let df = DataFile::try_load_with_options(&path, &defs, &options).unwrap();
+        let hash = df.source_hash().unwrap();
+        assert_eq!(hash.path, path);
+        assert_eq!(hash.byte_count, 3);
+        assert_eq!(hash.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn roundtrip_differences_reported() {
+        fn upper(s: String) -> DataFieldResult<String> { Ok(s.to_uppercase()) }
+        let contents = [format!("{:<10}{:>10}", "abc", "12"),
+                        format!("{:<10}{:>10}X", "DEF", "34"),
+                        format!("{:<10}{:>10}", "GHI", "56")]
+            .map(|l| padded_row(&l) + "\n")
+            .concat();
+        let path = temp_file("roundtrip", &contents);
+        let defs = vec![
+            DataFieldDef::new("name", 0, 10, &upper),
+            DataFieldDef::new("amount", 10, 20, &echo_ok).with_alignment(crate::Alignment::Right),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert_eq!(df.rows().len(), 3);
+        let warnings = df.verify_roundtrip(&defs).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].line_number(), 1);
+        assert_eq!(warnings[0].kind(), crate::WarningKind::RoundTripMismatch);
+        assert_eq!(warnings[1].line_number(), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn select_filters_and_projects() {
+        fn fail_b(s: String) -> DataFieldResult<String> {
+            if s == "B" { Err(crate::DataFieldError::Problem(Box::new("B"))) } else { Ok(s) }
+        }
+        let contents = ["1 A", "2 B", "3 A"].map(|l| padded_row(l) + "\n").concat();
+        let path = temp_file("select", &contents);
+        let defs = vec![
+            DataFieldDef::new("id", 0, 1, &echo_ok),
+            DataFieldDef::new("status", 2, 3, &fail_b).with_on_error(crate::FieldErrorPolicy::WarnAndBlank),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert_eq!(df.warnings().len(), 1);
+
+        let selected = df.select(|r| r.fields()[0].data() != "3", &["id"]).unwrap();
+        assert_eq!(selected.rows().len(), 2);
+        assert_eq!(selected.rows()[1].fields().len(), 1);
+        assert_eq!(selected.warnings().len(), 1);
+        assert_eq!(selected.summary().rows_loaded, 2);
+
+        let selected = df.select(|r| r.fields()[1].data() == "A", &[]).unwrap();
+        assert_eq!(selected.rows().len(), 2);
+        assert_eq!(selected.rows()[1].fields()[0].data(), "3");
+        assert!(selected.warnings().is_empty());
+
+        assert!(df.select(|_| true, &["missing"]).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn select_by_alias_keeps_redactions() {
+        let defs = vec![
+            DataFieldDef::new("AccountNo", 0, 11, &echo_ok).with_alias("ACCT").with_redaction(Redaction::Token("XXX".to_string())),
+            DataFieldDef::new("Amount", 11, 14, &echo_ok).with_implied_decimals(2),
+            DataFieldDef::new("Status", 14, 15, &echo_ok),
+        ];
+        let data = format!("{:<200}\n", "12345678901400A");
+        let options = LoadOptions { name_matching: NameMatching::Normalized, ..Default::default() };
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+
+        let selected = df.select(|_| true, &["ACCT", "amount", "Status"]).unwrap();
+        assert_eq!(selected.redactions().get("AccountNo"), Some(&Redaction::Token("XXX".to_string())));
+        assert_eq!(selected.implied_decimal_fields().get("Amount"), Some(&2));
+        assert!(df.select(|_| true, &["Status"]).unwrap().redactions().is_empty());
+    }
+
+    #[test]
+    fn iterates_rows() {
+        let contents = ["1", "2", "3"].map(|l| padded_row(l) + "\n").concat();
+        let path = temp_file("iterate", &contents);
+        let defs = vec![DataFieldDef::new("id", 0, 1, &echo_ok)];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        let mut ids = vec![];
+        for row in &df {
+            ids.push(row.fields()[0].data());
+        }
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        assert_eq!(df.iter().filter(|r| r.fields()[0].data() != "2").count(), 2);
+
+        let owned: Vec<DataRow> = df.into_iter().collect();
+        assert_eq!(owned.len(), 3);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn displayed_and_debugged_briefly() {
+        let contents = ["1A", "2B", "3C", "4D", "5"].map(|l| padded_row(l) + "\n").concat();
+        let defs = vec![DataFieldDef::new("id", 0, 1, &echo_ok), DataFieldDef::new("code", 1, 2, &echo_ok)];
+        let df = DataFile::try_from_str(&contents, &defs, &LoadOptions::default()).unwrap();
+
+        assert_eq!(df.rows()[0].to_string(), "id=1, code=A");
+        assert_eq!(df.rows()[4].to_string(), "id=5, code=");
+        assert!(df.to_string().ends_with(": 5 rows, 0 warnings"));
+        assert_eq!(format!("{:?}", df.rows()[1]), r#"DataRow { line_index: Some(1), fields: {"id": "2", "code": "B"} }"#);
+        let debug = format!("{:?}", df);
+        assert!(debug.contains(r#""code": "C""#) && !debug.contains(r#""code": "D""#));
+        assert!(debug.contains("... 2 more"));
+    }
+
+    #[test]
+    fn key_lookup_with_and_without_index() {
+        let contents = ["1 A", "2 B", "3 A"].map(|l| padded_row(l) + "\n").concat();
+        let path = temp_file("index", &contents);
+        let defs = vec![
+            DataFieldDef::new("id", 0, 1, &echo_ok),
+            DataFieldDef::new("status", 2, 3, &echo_ok),
+        ];
+        let mut df = DataFile::try_load(&path, &defs).unwrap();
+
+        assert_eq!(df.find_by_key("id", "2").unwrap().get("status").unwrap().data(), "B");
+        assert_eq!(df.find_all_by_key("status", "A").len(), 2);
+
+        df.index_by("status");
+        df.index_by("id");
+        assert_eq!(df.find_by_key("id", "2").unwrap().get("status").unwrap().data(), "B");
+        let found = df.find_all_by_key("status", "A");
+        assert_eq!(found.iter().map(|r| r.get("id").unwrap().data()).collect::<Vec<String>>(), vec!["1", "3"]);
+        assert!(df.find_by_key("id", "9").is_none());
+        assert!(df.find_by_key("missing", "1").is_none());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn duplicate_names_rejected() {
+        let data = padded_row("0001A");
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &echo_ok).with_alias("ACCT"),
+            DataFieldDef::new("Status", 4, 5, &echo_ok),
+        ];
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(df.rows()[0].get("ACCT").unwrap().data(), "0001");
+
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &echo_ok),
+            DataFieldDef::new("Status", 4, 5, &echo_ok).with_alias("Id"),
+        ];
+        match DataFile::try_from_str(&data, &defs, &LoadOptions::default()) {
+            Err(DataFileError::DuplicateFieldName(n)) => assert_eq!(n, "Id"),
+            _ => panic!()
+        }
+
+        let defs = vec![
+            DataFieldDef::new("Kind", 4, 5, &echo_ok),
+            DataFieldDef::new("Amount", 0, 2, &echo_ok).only_when("Kind", &["A"]),
+            DataFieldDef::new("Amount", 2, 4, &echo_ok).only_when("Kind", &["B"]),
+        ];
+        assert!(DataFile::try_from_str(&data, &defs, &LoadOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn display_width_columns() {
+        let data = format!("{:<200}\n{:<200}\n", "山田太郎    0042", "SMITH       0017");
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 12, &echo_ok),
+            DataFieldDef::new("Count", 12, 16, &echo_ok),
+        ];
+        assert!(matches!(DataFile::try_from_str(&data, &defs, &LoadOptions::default()),
+                         Err(e) if matches!(e.root(), DataFileError::NonASCIIFile)));
+
+        let df = DataFile::loader(&defs).columns(crate::ColumnUnit::DisplayWidth).load_str(&data).unwrap();
+        let values: Vec<(String, String)> = df.iter()
+            .map(|r| (r.get("Name").unwrap().data(), r.get("Count").unwrap().data()))
+            .collect();
+        assert_eq!(values, [("山田太郎".to_string(), "0042".to_string()), ("SMITH".to_string(), "0017".to_string())]);
+
+        let stated = vec![DataFieldDef::new("Name", 0, 12, &echo_ok).with_unit(crate::ColumnUnit::Chars)];
+        match DataFile::loader(&stated).columns(crate::ColumnUnit::DisplayWidth).load_str(&data) {
+            Err(e) => assert_eq!(e.to_string(), "Data File Error: Field Name is measured in chars, not display_width"),
+            Ok(_) => panic!()
+        }
+
+        let invalid = [b"ABC\xff".as_slice(), &[b' '; 200]].concat();
+        assert!(matches!(DataFile::loader(&defs).columns(crate::ColumnUnit::DisplayWidth).load_bytes(&invalid),
+                         Err(e) if matches!(e.root(), DataFileError::InvalidUtf8File)));
+    }
+
+    #[test]
+    fn errors_chain_to_source() {
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+        let missing = std::env::temp_dir().join("ffreader_no_such_file.txt");
+        let e = DataFile::try_load(&missing, &defs).err().unwrap();
+        assert_eq!(e.io_error_kind(), Some(std::io::ErrorKind::NotFound));
+        assert!(e.source().unwrap().is::<std::io::Error>());
+
+        let e = DataFileError::from(DataRowError::FieldNameNotFound("x".to_string()));
+        assert!(matches!(e.source().unwrap().downcast_ref::<DataRowError>(), Some(DataRowError::FieldNameNotFound(_))));
+        assert_eq!(e.io_error_kind(), None);
+    }
+
+    #[test]
+    fn error_location_reported() {
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+        let path = temp_file("located.txt", "first line\nsecond lïne\n");
+        let e = DataFile::try_load(&path, &defs).err().unwrap();
+        let location = e.location().unwrap();
+        assert_eq!(location.path, path);
+        assert_eq!(location.line_number, 2);
+        assert_eq!(location.byte_offset, 19);
+        assert_eq!(location.snippet, "second lïne");
+        assert_eq!(location.hex_dump(), "00000000  73 65 63 6f 6e 64 20 6c c3 af 6e 65              |second l..ne|");
+        assert!(e.to_string().starts_with("Data File Error: Non ASCII file. at line 2 of "));
+        assert!(e.source().unwrap().downcast_ref::<DataFileError>().is_some());
+
+        let data = b"ok\n\xff\n";
+        let e = DataFile::loader(&defs).columns(crate::ColumnUnit::Chars).load_bytes(data).err().unwrap();
+        assert!(matches!(e.root(), DataFileError::InvalidUtf8File));
+        assert_eq!(e.location().unwrap().byte_offset, 3);
+        assert_eq!(e.to_string(), "Data File Error: Invalid UTF-8 file. at line 2, byte 3 (\"\u{fffd}\")");
+    }
+
+    #[test]
+    fn load_aborted_after_max_errors() {
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok)];
+        let data = format!("{:<200}\nshort\nshort\n{:<200}\n", "00001", "00002");
+        let options = LoadOptions::default().max_errors(2);
+        assert_eq!(DataFile::try_from_str(&data, &defs, &options).unwrap().rows().len(), 2);
+        let options = LoadOptions::default().max_errors(1);
+        match DataFile::loader(&defs).max_errors(1).load_str(&data) {
+            Err(DataFileError::TooManyErrors(1)) => {}
+            _ => panic!()
+        }
+        let mut reader = crate::DataFileReader::new(std::io::Cursor::new(data), &defs, options);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next(), Some(Err(DataFileError::TooManyErrors(1)))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn short_rows_padded() {
+        let defs = vec![DataFieldDef::new("id", 0, 5, &echo_ok), DataFieldDef::new("note", 5, 200, &echo_ok)];
+        let data = format!("{}\n{:<250}\n", "00001NOTE", "00002");
+        assert_eq!(DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap().rows().len(), 1);
+
+        let df = DataFile::loader(&defs).pad_short_rows(' ').load_str(&data).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.rows()[0].get("note").unwrap().data(), "NOTE");
+        assert_eq!(df.warnings().len(), 1);
+        assert_eq!(df.warnings()[0].kind(), crate::WarningKind::PaddedRow);
+        assert_eq!(df.warnings()[0].message(), "Padded short row from 9 to 200 columns");
+    }
+
+    #[test]
+    fn row_ids_stable_across_subsets() {
+        fn numeric(s: String) -> DataFieldResult<String> {
+            match s.chars().all(|c| c.is_ascii_digit()) {
+                true => Ok(s),
+                false => Err(DataFieldError::Problem(Box::new("Not numeric")))
             }
-            json_row.push_str(kv_list.join(",").as_str());
-            json_row.push('}');
-            json_row_list.push(json_row);
         }
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric), DataFieldDef::new("Name", 4, 8, &echo_ok)];
+        let data = format!("{}\n\n{}\n{}\n{}\n", padded_row("0001ANN"), padded_row("00X2BOB"),
+                           padded_row("0003CY"), padded_row("0004DEE"));
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        let ids = |f: &DataFile| f.rows().iter().map(|r| (r.row_id(), r.line_number())).collect::<Vec<_>>();
+        assert_eq!(ids(&df), [(Some(0), Some(1)), (Some(1), Some(4)), (Some(2), Some(5))]);
+        assert_eq!(df.warnings().iter().map(LoadWarning::line_number).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(ids(&df.select(|r| r.get("Name").unwrap().data() != "CY", &["Name"]).unwrap()),
+                   [(Some(0), Some(1)), (Some(2), Some(5))]);
+
+        let streamed: Vec<Option<usize>> = crate::DataFileReader::new(std::io::Cursor::new(&data), &defs, LoadOptions::default())
+            .filter_map(|r| r.ok().map(|r| r.row_id()))
+            .collect();
+        assert_eq!(streamed, [Some(0), Some(1), Some(2)]);
+        let load = || DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        let combined = DataFile::combine(Path::new(""), [load(), load()], LoadSummary::default());
+        assert_eq!(combined.rows().iter().map(|r| r.row_id().unwrap()).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+
+        #[cfg(feature = "csv")]
+        {
+            let options = crate::ExportOptions { row_ids: true, fields: Some(vec!["Name".to_string()]), ..Default::default() };
+            assert_eq!(df.query("Id > 1").unwrap().to_csv_with_options(&options).unwrap(), "row_id,Name\n1,CY\n2,DEE\n");
+        }
+    }
 
-        format!("[{}]", json_row_list.join(",\n"))
+    #[test]
+    fn empty_and_header_only_files_load() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+        let load = |data: &str, options: LoadOptions| {
+            let df = DataFile::try_from_str(data, &defs, &options).unwrap();
+            assert!(df.rows().is_empty());
+            assert_eq!(df.summary().warnings_by_kind.get(&crate::WarningKind::NoRows), Some(&1));
+            df.warnings()[0].message()
+        };
+        assert_eq!(load("", LoadOptions::default()), "Empty file");
+        assert_eq!(load("HEADER 2024-01-31\n", LoadOptions { skip_rows: 1, ..Default::default() }),
+                   "No lines after the 1 skipped by skip_rows");
+        let options = LoadOptions { skip_blank_lines: true, comment_prefix: Some("#".to_string()), ..Default::default() };
+        assert_eq!(load("# no data today\n\n", options.clone()), "All 2 lines were skipped");
+
+        let mut reader = crate::DataFileReader::new(std::io::Cursor::new(""), &defs, LoadOptions::default());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.summary().warnings_by_kind.get(&crate::WarningKind::NoRows), Some(&1));
+        let rejected = DataFile::try_from_str("short\n", &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(rejected.warnings().len(), 1);
+        assert_eq!(rejected.warnings()[0].kind(), crate::WarningKind::BadRowLength);
+
+        let path = temp_file("empty_versioned", "");
+        let mut versions = crate::schema::SchemaVersions::new();
+        let header = |value: &str| crate::schema::VersionSelector::HeaderValue {
+            line_index: 0, start_idx: 0, end_idx: 2, value: value.to_string()
+        };
+        versions.register("v1", header("V1"), vec![DataFieldDef::new("Id", 0, 4, &echo_ok)]);
+        assert!(versions.load(&path, &LoadOptions::default()).unwrap().rows().is_empty());
+        fs::write(&path, "V9\n").unwrap();
+        assert!(matches!(versions.load(&path, &LoadOptions::default()), Err(DataFileError::NoMatchingLayout(_))));
+        fs::remove_file(&path).unwrap();
     }
-}
\ No newline at end of file
+}
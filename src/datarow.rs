@@ -1,11 +1,30 @@
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
-use crate::{DataField, DataFieldDef, DataFieldError};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use crate::{ColumnUnit, DataField, DataFieldDef, DataFieldError, ExtraDataPolicy, InvalidByte, LoadOptions, NameMatching};
+use sha2::{Digest, Sha256};
+use crate::extradata::extra_data;
+use crate::format::hex;
+use crate::invalidbytes::{decode, first_invalid};
 
 /// Holds a list of the fields found in a row.
-#[derive(Debug)]
+///
+/// Displayed as the name and processed data of each field, e.g. `AccountNo=5412345678,
+/// Status=OP`; the Debug output shows the same with the row's line index.
+///
+/// Rows are equal, hashed and ordered by their fields, in order (see DataField), so that they
+/// can be compared in tests and deduplicated in sets; where they were loaded from isn't
+/// compared.
+#[derive(Clone)]
 pub struct DataRow {
-    fields: Vec<DataField>
+    fields: Vec<DataField>,
+    line_index: Option<usize>,
+    row_id: Option<usize>,
+    name_matching: NameMatching,
+    raw: Option<String>,
+    source: Option<Arc<str>>
 }
 
 /// Errors that DataRows may encounter.
@@ -15,7 +34,21 @@ pub enum DataRowError {
     /// A row length is out of bounds.
     BadRowLength(usize),
     /// A field name was specified but not found.
-    FieldNameNotFound(String)
+    FieldNameNotFound(String),
+    /// Several fields failed (only produced when collecting all field errors).
+    MultipleFieldErrors(Vec<DataFieldError>),
+    /// A row given as bytes contains a byte that isn't valid text; the first one is given.
+    InvalidByte(InvalidByte),
+    /// A row has text beyond the last defined column, rejected by `ExtraDataPolicy::Error`;
+    /// the column it starts at and the text are given.
+    ExtraData(usize, String),
+    /// A row has characters outside printable ASCII, rejected by `PrintableAscii::reject`;
+    /// the field holding the first (None if outside every field) and their columns in it are
+    /// given.
+    Unprintable(Option<String>, Vec<usize>),
+    /// A row has the same key as an earlier row, rejected by `DuplicatePolicy::Error`; the key
+    /// and the 1-based line number of the earlier row are given.
+    DuplicateRow(String, usize)
 }
 
 /// Convenient Result shorthand for DataRowError results.
@@ -26,7 +59,14 @@ impl Display for DataRowError {
         let s = match self {
             DataRowError::FieldError(fe) => fe.to_string(),
             DataRowError::BadRowLength(l) => format!("Bad Row Length ({})", l),
-            DataRowError::FieldNameNotFound(n) => format!("Field Name Not Found ({})", n)
+            DataRowError::FieldNameNotFound(n) => format!("Field Name Not Found ({})", n),
+            DataRowError::MultipleFieldErrors(v) => format!("Multiple Field Errors ({}): {}", v.len(),
+                v.iter().map(|e| e.to_string()).collect::<Vec<String>>().join("; ")),
+            DataRowError::InvalidByte(b) => format!("Invalid Byte (0x{:02X} at column {})", b.value, b.column),
+            DataRowError::ExtraData(c, t) => format!("Extra Data (at column {}: \"{}\")", c, t),
+            DataRowError::Unprintable(n, c) => format!("Unprintable Characters (in {} at columns {})",
+                n.as_deref().unwrap_or("no field"), c.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(", ")),
+            DataRowError::DuplicateRow(k, l) => format!("Duplicate Row ({}, first at line {})", k, l)
         };
         write!(f, "{}", s)
     }
@@ -34,11 +74,20 @@ impl Display for DataRowError {
 
 impl Debug for DataRowError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", self)
     }
 }
 
-impl Error for DataRowError {}
+impl Error for DataRowError {
+    /// The field error for `FieldError`, or the first of them for `MultipleFieldErrors`.
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataRowError::FieldError(e) => Some(e),
+            DataRowError::MultipleFieldErrors(v) => v.first().map(|e| e as &(dyn Error + 'static)),
+            _ => None
+        }
+    }
+}
 
 impl From<DataFieldError> for DataRowError {
     fn from(value: DataFieldError) -> Self {
@@ -47,28 +96,146 @@ impl From<DataFieldError> for DataRowError {
 }
 
 impl DataRow {
-    const MINIMUM_LENGTH: usize = 183; // todo: make this configurable
+    pub(crate) const MINIMUM_LENGTH: usize = 183; // todo: make this configurable
 
     /// Try to create a DataRow structure using the definitions provided.
     pub fn try_create(row: &str, row_defs: &Vec<DataFieldDef>) -> Result<DataRow> {
-        if row.len() < Self::MINIMUM_LENGTH {
-            return Err(DataRowError::BadRowLength(row.len()))
+        Self::try_create_with_options(row, row_defs, &LoadOptions::default())
+    }
+
+    /// Try to create a DataRow structure using the definitions and LoadOptions provided.
+    ///
+    /// Normally the first failing field aborts the row. If `collect_all_field_errors` is set,
+    /// every field is attempted and, when more than one fails, all of the failures are returned
+    /// together as `DataRowError::MultipleFieldErrors`.
+    pub fn try_create_with_options(row: &str, row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> Result<DataRow> {
+        Self::try_create_with_warnings(row, row_defs, options).map(|(r, _)| r)
+    }
+
+    /// Try to create a DataRow from raw bytes, e.g. a buffer read from a network stream or a
+    /// memory map, without first converting it to a `&str`. The bytes must be ASCII (or UTF-8,
+    /// when measuring in another unit); see `try_create_bytes_with_options()`.
+    /// ```
+    /// use ffreader::{DataRow, DataRowError, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let row = DataRow::try_create_bytes(format!("{:<200}", "5412345678").as_bytes(), &defs).unwrap();
+    /// assert_eq!(row.get("Field_1").unwrap().data(), "5412345678");
+    /// let bad = format!("{:<200}", "54123\u{e9}");
+    /// assert!(matches!(DataRow::try_create_bytes(bad.as_bytes(), &defs), Err(DataRowError::InvalidByte(b)) if b.column == 5));
+    /// ```
+    pub fn try_create_bytes(row: &[u8], row_defs: &Vec<DataFieldDef>) -> Result<DataRow> {
+        Self::try_create_bytes_with_options(row, row_defs, &LoadOptions::default())
+    }
+
+    /// Try to create a DataRow from raw bytes as with `try_create_bytes()`, using the
+    /// LoadOptions provided. The bytes are checked once, in the unit set by
+    /// `LoadOptions::columns`, and bytes that aren't valid text are handled as set by
+    /// `LoadOptions::invalid_bytes`; when replaced or stripped, no warning is recorded.
+    pub fn try_create_bytes_with_options(row: &[u8], row_defs: &Vec<DataFieldDef>, options: &LoadOptions)
+        -> Result<DataRow> {
+        match decode(row, 0, options) {
+            Ok((text, _)) => Self::try_create_with_options(&text, row_defs, options),
+            Err(_) => match first_invalid(row, options.columns) {
+                Some((column, value)) => Err(DataRowError::InvalidByte(InvalidByte { line_index: 0, column, value })),
+                // decode only fails on an invalid byte, but if none is found the bytes read as is
+                None => Self::try_create_with_options(&String::from_utf8_lossy(row), row_defs, options)
+            }
         }
+    }
 
-        let tfs = DataField::try_from_row;
-        let mut fields = Vec::new();
+    /// Try to create a DataRow as with `try_create_with_options()`, also returning the
+    /// errors of any fields that were downgraded to warnings by their FieldErrorPolicy, and
+    /// any possible truncations.
+    pub fn try_create_with_warnings(row: &str, row_defs: &Vec<DataFieldDef>, options: &LoadOptions)
+        -> Result<(DataRow, Vec<DataFieldError>)> {
+        let unit = options.columns;
+        Self::check_length(row, unit)?;
+        Self::check_extra_data(row, row_defs, options)?;
+        Self::check_printable(row, row_defs, options)?;
+
+        let mut fields = Vec::with_capacity(row_defs.len());
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let ascii = row.is_ascii();
 
         for row_def in row_defs {
-            fields.push(tfs(row, row_def)?);
+            if row_def.condition.as_ref().is_some_and(|c| !c.is_met(&fields, options.name_matching)) {
+                fields.push(DataField::empty(row_def));
+            } else {
+                match DataField::extract(row, row_def, options, ascii) {
+                    Ok((f, warning)) => {
+                        fields.push(match options.provenance {
+                            true => f.with_provenance(row, row_def, unit),
+                            false => f
+                        });
+                        warnings.extend(warning);
+                    }
+                    Err(e) if options.collect_all_field_errors => {
+                        errors.push(e);
+                        continue;
+                    }
+                    Err(e) => return Err(e.into())
+                }
+            }
+
+            if let Some(splitter) = &row_def.splitter {
+                let value = fields.last().map(|f| f.data()).unwrap_or_default();
+                fields.extend(splitter.fields(&value));
+            }
+        }
+
+        if errors.len() == 1 {
+            return Err(errors.remove(0).into());
+        }
+        if !errors.is_empty() {
+            return Err(DataRowError::MultipleFieldErrors(errors));
+        }
+
+        Ok((DataRow {
+            fields,
+            line_index: None,
+            row_id: None,
+            name_matching: options.name_matching,
+            raw: options.keep_raw_lines.then(|| row.to_string()),
+            source: None
+        }, warnings))
+    }
+
+    /// Check that a row is long enough to be parsed, measured in the given unit.
+    pub(crate) fn check_length(row: &str, unit: ColumnUnit) -> Result<()> {
+        match unit.len(row) {
+            len if len < Self::MINIMUM_LENGTH => Err(DataRowError::BadRowLength(len)),
+            _ => Ok(())
         }
+    }
 
-        Ok(DataRow {
-            fields
-        })
+    /// Check that a row has no text beyond the last defined column, if rejected by
+    /// `LoadOptions::extra_data`.
+    pub(crate) fn check_extra_data(row: &str, row_defs: &[DataFieldDef], options: &LoadOptions) -> Result<()> {
+        if options.extra_data != ExtraDataPolicy::Error {
+            return Ok(());
+        }
+        match extra_data(row, row_defs, options.columns) {
+            Some((column, text)) => Err(DataRowError::ExtraData(column, text)),
+            None => Ok(())
+        }
+    }
+
+    /// Check that a row holds only printable ASCII, if rejected otherwise by
+    /// `LoadOptions::printable_ascii`.
+    pub(crate) fn check_printable(row: &str, row_defs: &[DataFieldDef], options: &LoadOptions) -> Result<()> {
+        let Some(check) = options.printable_ascii.as_ref().filter(|c| c.reject) else { return Ok(()) };
+        match check.find(row, row_defs, options.columns).into_iter().next() {
+            Some(u) => Err(DataRowError::Unprintable(u.field_name, u.chars.into_iter().map(|(c, _)| c).collect())),
+            None => Ok(())
+        }
     }
 
     /// Get a copy of the row with specified fields in order. This is useful for constructing
-    /// certain output formats, e.g., CSV.
+    /// certain output formats, e.g., CSV. Names are matched as set by `LoadOptions::name_matching`.
     ///
     /// ```
     /// use ffreader::{DataRow, DataFieldDef, DataFieldResult};
@@ -94,8 +261,8 @@ impl DataRow {
         let mut list = vec![];
 
         for f in field_list {
-            if let Some(c) = self.fields.iter().find(|n| n.name() == f) {
-                list.push((*c).clone());
+            if let Some(c) = self.get(f) {
+                list.push(c.clone());
             }
             else {
                 return Err(DataRowError::FieldNameNotFound(f.to_string()));
@@ -105,15 +272,272 @@ impl DataRow {
         Ok(list)
     }
 
+    /// Estimate the memory held by the row and its fields, in bytes; see
+    /// `DataField::memory_usage()`.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<DataRow>() + (self.fields.capacity() - self.fields.len()) * size_of::<DataField>()
+            + self.fields.iter().map(|f| f.memory_usage()).sum::<usize>()
+            + self.raw.as_ref().map_or(0, |r| r.capacity())
+    }
+
+    /// Get the field with the given name or alias, if the row has one. Names are matched as set
+    /// by `LoadOptions::name_matching`.
+    pub fn get(&self, name: &str) -> Option<&DataField> {
+        self.position(name).map(|i| &self.fields[i])
+    }
+
+    /// Find the index of the field with the given name or alias, as for `get()`.
+    fn position(&self, name: &str) -> Option<usize> {
+        let matches = |n: &str| self.name_matching.matches(name, n);
+        self.fields.iter().position(|f| matches(f.name()))
+            .or_else(|| self.fields.iter().position(|f| f.aliases().iter().any(|a| matches(a))))
+    }
+
+    /// Replace the processed data of the named field, e.g. in a transform of a Pipeline. The
+    /// raw text is kept and any typed value is dropped. Fails if the field is not found.
+    /// ```
+    /// use ffreader::{DataRow, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Name", 0, 5, &post_function)];
+    /// let mut row = DataRow::try_create(&format!("{:<200}", "ann"), &defs).unwrap();
+    /// row.set("Name", "ANN".to_string()).unwrap();
+    /// assert_eq!(row.get("Name").unwrap().data(), "ANN");
+    /// assert_eq!(row.get("Name").unwrap().raw(), "ann  ");
+    /// ```
+    pub fn set(&mut self, name: &str, data: String) -> Result<()> {
+        match self.position(name) {
+            Some(i) => {
+                self.fields[i].set_data(data);
+                Ok(())
+            }
+            None => Err(DataRowError::FieldNameNotFound(name.to_string()))
+        }
+    }
+
+    /// Join the values of the named fields into a key, e.g. a composite key of account and
+    /// serial numbers, so that indexes, joins and duplicate checks all key rows the same way.
+    /// Fails if a field is not found.
+    /// ```
+    /// use ffreader::{DataRow, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 4, &post_function),
+    ///     DataFieldDef::new("ThingSerial", 4, 8, &post_function),
+    /// ];
+    /// let row = DataRow::try_create(&format!("{:<200}", "0001A123"), &defs).unwrap();
+    /// assert_eq!(row.key(&["AccountNo1", "ThingSerial"], "|").unwrap(), "0001|A123");
+    /// ```
+    pub fn key(&self, names: &[&str], separator: &str) -> Result<String> {
+        Ok(self.key_values(names)?.join(separator))
+    }
+
+    /// Hash the values of the named fields into a key as lowercase hexadecimal SHA-256, for
+    /// keys that are long or sensitive. Unlike a joined key, values containing a separator
+    /// can't run together. Fails if a field is not found.
+    pub fn key_hash(&self, names: &[&str]) -> Result<String> {
+        let mut hasher = Sha256::new();
+        for value in self.key_values(names)? {
+            hasher.update((value.len() as u64).to_be_bytes());
+            hasher.update(value.as_bytes());
+        }
+        Ok(hex(&hasher.finalize()))
+    }
+
+    /// Get the values of the named fields, in order.
+    pub(crate) fn key_values(&self, names: &[&str]) -> Result<Vec<String>> {
+        names.iter()
+            .map(|n| self.get(n).map(|f| f.data()).ok_or_else(|| DataRowError::FieldNameNotFound(n.to_string())))
+            .collect()
+    }
+
+    /// Get the typed value of the named field, if it has one of type `T`; see
+    /// `DataFieldDef::with_parser()`.
+    pub fn value<T: std::any::Any>(&self, name: &str) -> Option<&T> {
+        self.get(name).and_then(|f| f.value())
+    }
+
+    /// Create a row of the given fields, e.g. a projection of another row.
+    pub(crate) fn from_fields(fields: Vec<DataField>, line_index: Option<usize>) -> DataRow {
+        DataRow {
+            fields,
+            line_index,
+            row_id: None,
+            name_matching: NameMatching::Exact,
+            raw: None,
+            source: None
+        }
+    }
+
+    /// Consume the row, yielding its fields.
+    pub(crate) fn into_fields(self) -> Vec<DataField> {
+        self.fields
+    }
+
+    /// Set how field names are matched, e.g. to keep that of the row a projection was made from.
+    pub(crate) fn with_name_matching(mut self, name_matching: NameMatching) -> Self {
+        self.name_matching = name_matching;
+        self
+    }
+
+    /// Set the id of the row, e.g. to keep that of the row a projection was made from.
+    pub(crate) fn with_row_id(mut self, row_id: Option<usize>) -> Self {
+        self.row_id = row_id;
+        self
+    }
+
+    /// Set the id of the row to its position among the rows loaded.
+    pub(crate) fn set_row_id(&mut self, row_id: usize) {
+        self.row_id = Some(row_id);
+    }
+
+    /// Keep the source line the row was parsed from, as `LoadOptions::keep_raw_lines` does.
+    pub(crate) fn with_raw(mut self, raw: Option<String>) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Record the index of the source line the row was loaded from.
+    pub(crate) fn with_line_index(mut self, line_index: usize) -> Self {
+        self.line_index = Some(line_index);
+        for field in &mut self.fields {
+            field.set_line_index(line_index);
+        }
+        self
+    }
+
+    /// Get the exact source line the row was parsed from, if `LoadOptions::keep_raw_lines`
+    /// was set.
+    /// ```
+    /// use ffreader::{DataRow, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let line = format!("{:<200}", "  54123456");
+    /// let options = LoadOptions { keep_raw_lines: true, ..Default::default() };
+    /// let row = DataRow::try_create_with_options(&line, &defs, &options).unwrap();
+    /// assert_eq!(row.raw(), Some(line.as_str()));
+    /// assert_eq!(DataRow::try_create(&line, &defs).unwrap().raw(), None);
+    /// ```
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Get the name of the file the row was loaded from, when several files were loaded
+    /// together with `DataFile::try_load_dir()` or `Loader::load_glob()`.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Record the name of the file the row was loaded from.
+    pub(crate) fn set_source(&mut self, source: Arc<str>) {
+        self.source = Some(source);
+    }
+
+    /// Get the 0-based index of the source line the row was loaded from, if it was loaded
+    /// from a file.
+    pub fn line_index(&self) -> Option<usize> {
+        self.line_index
+    }
+
+    /// Get the 1-based number of the source line the row was loaded from, as given by
+    /// `LoadWarning::line_number()` for warnings about it.
+    pub fn line_number(&self) -> Option<usize> {
+        self.line_index.map(|i| i + 1)
+    }
+
+    /// Get the id of the row: its 0-based position among the rows successfully loaded from the
+    /// file, so that skipped and rejected lines don't leave gaps. Ids are kept by `select()` and
+    /// `query()`, so a row has the same id in every subset, diff and export
+    /// (see `ExportOptions::row_ids`) made of the file, and by files saved with
+    /// `DataFile::save()`. Rows of files combined from several are numbered in order across
+    /// them. None for rows not loaded from a file.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let data = format!("{:<200}\n\n{:<200}\n", "54123456", "54123457");
+    /// let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+    /// assert_eq!(df.rows()[1].row_id(), Some(1));
+    /// assert_eq!(df.rows()[1].line_number(), Some(3));
+    /// ```
+    pub fn row_id(&self) -> Option<usize> {
+        self.row_id
+    }
+
+    /// Get how field names are matched in this row.
+    pub fn name_matching(&self) -> NameMatching {
+        self.name_matching
+    }
+
     /// Get a reference to the DataFields contained in the struct.
     pub fn fields(&self) -> &Vec<DataField> {
         &self.fields
     }
 }
 
+impl Display for DataRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let fields = self.fields.iter()
+            .map(|field| format!("{}={}", field.name(), field.data_ref()))
+            .collect::<Vec<String>>();
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+impl Debug for DataRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataRow")
+            .field("line_index", &self.line_index)
+            .field("fields", &DebugFields(&self.fields))
+            .finish()
+    }
+}
+
+impl PartialEq for DataRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields
+    }
+}
+
+impl Eq for DataRow {}
+
+impl Hash for DataRow {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fields.hash(state)
+    }
+}
+
+impl PartialOrd for DataRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DataRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.fields.cmp(&other.fields)
+    }
+}
+
+/// The fields of a row, in Debug output as a map of names to processed data.
+struct DebugFields<'a>(&'a [DataField]);
+
+impl Debug for DebugFields<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.0.iter().map(|field| (field.name(), field.data_ref()))).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::DataFieldResult;
+    use crate::{DataFieldResult, FieldErrorPolicy, Provenance, Splitter};
     use super::*;
 
     fn test_row() -> String {
@@ -163,4 +587,252 @@ mod tests {
         assert_eq!(fields.iter().find(|s| s.name() == "ThingSize").unwrap().data(), "0");
         assert_eq!(fields.iter().find(|s| s.name() == "Demo_Name").unwrap().data(), "123 TEST PERSN");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn all_field_errors_collected() {
+        fn fail(s: String) -> DataFieldResult<String> {
+            Err(DataFieldError::Problem(Box::new(format!("bad value {}", s))))
+        }
+        let row = test_row();
+        let defs = vec![
+            DataFieldDef::new("AccountNo1", 0, 11, &fail),
+            DataFieldDef::new("CyclNo1", 11, 16, &echo_ok),
+            DataFieldDef::new("Status", 16, 23, &fail),
+        ];
+
+        match DataRow::try_create(&row, &defs).unwrap_err() {
+            DataRowError::FieldError(_) => {}
+            _ => panic!()
+        }
+
+        let options = LoadOptions { collect_all_field_errors: true, ..Default::default() };
+        match DataRow::try_create_with_options(&row, &defs, &options).unwrap_err() {
+            e @ DataRowError::MultipleFieldErrors(_) => {
+                let DataRowError::MultipleFieldErrors(v) = &e else { unreachable!() };
+                assert_eq!(v.len(), 2);
+                assert!(matches!(e.source().unwrap().downcast_ref::<DataFieldError>(), Some(DataFieldError::PostProcess(_, _))));
+            }
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn error_policy_downgrades_to_warning() {
+        fn fail(_: String) -> DataFieldResult<String> {
+            Err(DataFieldError::Problem(Box::new("unparseable")))
+        }
+        let row = test_row();
+        let defs = vec![
+            DataFieldDef::new("AccountNo1", 0, 11, &echo_ok),
+            DataFieldDef::new("CyclNo1", 11, 16, &fail)
+                .with_on_error(FieldErrorPolicy::WarnAndUseDefault("00".to_string())),
+            DataFieldDef::new("Status", 16, 23, &fail)
+                .with_on_error(FieldErrorPolicy::WarnAndBlank),
+        ];
+
+        let (datarow, warnings) = DataRow::try_create_with_warnings(&row, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(datarow.fields()[0].data(), "5412345678");
+        assert_eq!(datarow.fields()[1].data(), "00");
+        assert_eq!(datarow.fields()[1].raw(), " 54  ");
+        assert_eq!(datarow.fields()[2].data(), "");
+    }
+
+    #[test]
+    fn conditional_fields_absent_when_unmet() {
+        fn fail(_: String) -> DataFieldResult<String> {
+            Err(DataFieldError::Problem(Box::new("should not be parsed")))
+        }
+        let row = test_row();
+        let defs = vec![
+            DataFieldDef::new("Special", 237, 242, &echo_ok),
+            DataFieldDef::new("WhenR", 0, 11, &echo_ok).only_when("Special", &["R", "S"]),
+            DataFieldDef::new("WhenX", 11, 16, &fail).only_when("Special", &["X"]),
+        ];
+
+        let datarow = DataRow::try_create(&row, &defs).unwrap();
+        assert_eq!(datarow.fields()[1].data(), "5412345678");
+        assert_eq!(datarow.fields()[2].data(), "");
+        assert_eq!(datarow.fields()[2].raw(), "");
+    }
+
+    #[test]
+    fn conditions_find_controlling_field_by_alias() {
+        let defs = vec![
+            DataFieldDef::new("Special", 237, 242, &echo_ok).with_alias("SPECIAL_CD"),
+            DataFieldDef::new("WhenR", 0, 11, &echo_ok).only_when("special cd", &["R"]),
+        ];
+        let options = LoadOptions { name_matching: NameMatching::Normalized, ..Default::default() };
+        let row = format!("{:<200}", test_row());
+
+        let datarow = DataRow::try_create_with_options(&row, &defs, &options).unwrap();
+        assert_eq!(datarow.get("WhenR").unwrap().data(), "5412345678");
+        let lazy = crate::LazyFile::try_from_str(&row, &defs, &options).unwrap();
+        assert_eq!(lazy.get(0).unwrap().get("WhenR").unwrap().data(), "5412345678");
+        let parser = crate::RowParser::new(&defs, options).unwrap();
+        let mut buf = parser.buffer();
+        parser.parse_into(&mut buf, &row).unwrap();
+        assert_eq!(buf.get("WhenR"), Some("5412345678"));
+
+        let exact = DataRow::try_create_with_options(&row, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(exact.get("WhenR").unwrap().data(), "");
+    }
+
+    #[test]
+    fn post_process_warning_keeps_value() {
+        let status = crate::processors::lookup(Default::default(), crate::processors::UnknownCode::Warn);
+        let row = test_row();
+        let defs = vec![
+            DataFieldDef::new("Special", 237, 242, &status),
+        ];
+
+        let (datarow, warnings) = DataRow::try_create_with_warnings(&row, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(datarow.fields()[0].data(), "R");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "Warning: Unknown code R (Special [237-242] \"   R\")");
+    }
+
+    #[test]
+    fn possible_truncation_warned() {
+        let row = test_row();
+        let defs = vec![
+            DataFieldDef::new("Full", 0, 10, &echo_ok).warn_on_truncation(),
+            DataFieldDef::new("Padded", 10, 15, &echo_ok).with_alignment(crate::Alignment::Right).warn_on_truncation(),
+            DataFieldDef::new("Unchecked", 15, 16, &echo_ok),
+        ];
+
+        let (datarow, mut warnings) = DataRow::try_create_with_warnings(&row, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(datarow.fields()[0].data(), "5412345678");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "Possible truncation (Full [0-10] \"5412345678\")");
+        assert_eq!(crate::LoadWarning::field_downgraded(0, warnings.remove(0)).kind(), crate::WarningKind::PossibleTruncation);
+    }
+
+    #[test]
+    fn created_from_bytes() {
+        let defs = vec![DataFieldDef::new("Name", 0, 4, &echo_ok)];
+        let options = LoadOptions { columns: crate::ColumnUnit::Chars, ..Default::default() };
+        let row = format!("{:<200}", "Zoë");
+        let datarow = DataRow::try_create_bytes_with_options(row.as_bytes(), &defs, &options).unwrap();
+        assert_eq!(datarow.get("Name").unwrap().data(), "Zoë");
+
+        let mut bad = row.clone().into_bytes();
+        bad[1] = 0xff;
+        match DataRow::try_create_bytes_with_options(&bad, &defs, &options).unwrap_err() {
+            DataRowError::InvalidByte(b) => assert_eq!((b.column, b.value), (1, 0xff)),
+            e => panic!("{}", e)
+        }
+        let options = LoadOptions { invalid_bytes: crate::InvalidBytePolicy::Replace('?'), ..options };
+        let datarow = DataRow::try_create_bytes_with_options(&bad, &defs, &options).unwrap();
+        assert_eq!(datarow.get("Name").unwrap().data(), "Z?ë");
+    }
+
+    #[test]
+    fn names_shared_between_rows() {
+        let defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &echo_ok).with_alias("Account")];
+        let a = DataRow::try_create(&test_row(), &defs).unwrap();
+        let b = DataRow::try_create(&test_row(), &defs).unwrap();
+        assert!(std::ptr::eq(a.fields()[0].name(), b.fields()[0].name()));
+        assert!(std::ptr::eq(a.fields()[0].aliases(), b.fields()[0].aliases()));
+        assert_eq!(a.get("Account").unwrap().data(), "5412345678");
+        let field = &a.fields()[0];
+        assert_eq!(a.memory_usage(), size_of::<DataRow>() + field.memory_usage());
+        assert_eq!(field.memory_usage(), size_of::<DataField>() + field.raw().len() + field.data().len());
+    }
+
+    #[test]
+    fn normalized_names_matched() {
+        let row = test_row();
+        let defs = test_field_defs();
+
+        let exact = DataRow::try_create(&row, &defs).unwrap();
+        assert!(exact.get("demo name").is_none());
+        assert!(exact.get_ordered_fields(&vec!["accountno1"]).is_err());
+
+        let options = LoadOptions { name_matching: NameMatching::Normalized, ..Default::default() };
+        let normalized = DataRow::try_create_with_options(&row, &defs, &options).unwrap();
+        assert_eq!(normalized.get("DEMO NAME").unwrap().data(), "123 TEST PERSN");
+        let ordered = normalized.get_ordered_fields(&vec!["accountno1", "thing_id"]).unwrap();
+        assert_eq!(ordered.iter().map(|f| f.name()).collect::<Vec<&str>>(), ["AccountNo1", "ThingID"]);
+    }
+
+    #[test]
+    fn composite_keys_joined_and_hashed() {
+        let defs = vec![
+            DataFieldDef::new("A", 0, 2, &echo_ok),
+            DataFieldDef::new("B", 2, 4, &echo_ok),
+            DataFieldDef::new("C", 4, 6, &echo_ok),
+        ];
+        let row = DataRow::try_create(&format!("{:<200}", "a|b"), &defs).unwrap();
+        let other = DataRow::try_create(&format!("{:<200}", "a |b"), &defs).unwrap();
+        assert_eq!(row.key(&["A", "B"], "|").unwrap(), "a||b");
+        assert_eq!(other.key(&["A", "B"], "|").unwrap(), "a||b");
+        assert_ne!(row.key_hash(&["A", "B"]).unwrap(), other.key_hash(&["A", "B"]).unwrap());
+        assert_eq!(row.key_hash(&["A", "B"]).unwrap().len(), 64);
+        assert!(matches!(row.key(&["A", "D"], "|"), Err(DataRowError::FieldNameNotFound(n)) if n == "D"));
+    }
+
+    #[test]
+    fn rows_compared_by_values() {
+        use std::collections::HashSet;
+        let defs = vec![DataFieldDef::new("Id", 0, 2, &echo_ok), DataFieldDef::new("Code", 2, 4, &echo_ok)];
+        let shared = vec![DataFieldDef::new("Id", 0, 2, &echo_ok), DataFieldDef::new("Code", 2, 4, &echo_ok).with_dictionary()];
+        let options = LoadOptions { keep_raw_lines: true, ..Default::default() };
+        let row = |text: &str, defs| DataRow::try_create_with_options(&format!("{:<200}", text), defs, &options).unwrap();
+
+        assert_eq!(row("01AB", &defs), row("01AB", &shared));
+        assert_ne!(row("01AB", &defs), row("01 AB", &defs));
+        assert_ne!(row("01 A", &defs), row("01A ", &defs));
+        assert!(row("01AB", &defs) < row("02AA", &defs));
+
+        let rows: HashSet<DataRow> = ["01AB", "02AB", "01AB"].iter().map(|t| row(t, &defs)).collect();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn provenance_traces_bytes() {
+        let upper = |s: String| Ok(s.to_uppercase());
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 4, &upper),
+            DataFieldDef::new("Flag", 4, 5, &echo_ok),
+            DataFieldDef::new("Unit", 5, 8, &echo_ok).only_when("Flag", &["U"]),
+        ];
+        let data = format!("{:<200}\n{:<200}\n", "écolN123", "ABCDU123");
+        let options = LoadOptions { provenance: true, columns: ColumnUnit::Chars, ..Default::default() };
+        let df = crate::DataFile::try_from_str(&data, &defs, &options).unwrap();
+
+        let name = df.rows()[0].get("Name").unwrap().provenance().unwrap();
+        assert_eq!(name, &Provenance { line_index: Some(0), byte_range: 0..5, altered: true });
+        assert_eq!(&data[name.byte_range.clone()], "écol");
+        assert!(df.rows()[0].get("Unit").unwrap().provenance().is_none());
+        let unit = df.rows()[1].get("Unit").unwrap().provenance().unwrap();
+        assert_eq!((unit.line_index, unit.byte_range.clone(), unit.altered), (Some(1), 5..8, false));
+        assert!(df.rows()[1].get("Name").unwrap().provenance().is_some_and(|p| !p.altered));
+        assert!(DataRow::try_create(&format!("{:<200}", "ABCD"), &defs).unwrap().get("Name").unwrap().provenance().is_none());
+    }
+
+    #[test]
+    fn split_fields_added_to_row() {
+        let defs = vec![
+            DataFieldDef::new("Flag", 0, 1, &echo_ok),
+            DataFieldDef::new("Key", 1, 12, &echo_ok).only_when("Flag", &["K"])
+                .with_splitter(Splitter::delimiter('-', &["Branch", "Account"])),
+            DataFieldDef::new("Code", 12, 16, &echo_ok).with_splitter(Splitter::ranges(&[("Prefix", 0, 2), ("Rest", 2, 9)])),
+        ];
+        let row = DataRow::try_create(&format!("{:<200}", "K7 -12345   AB9"), &defs).unwrap();
+        let values: Vec<(&str, String)> = row.fields().iter().map(|f| (f.name(), f.data())).collect();
+        assert_eq!(values, [("Flag", "K".to_string()), ("Key", "7 -12345".to_string()), ("Branch", "7".to_string()),
+                            ("Account", "12345".to_string()), ("Code", "AB9".to_string()), ("Prefix", "AB".to_string()),
+                            ("Rest", "9".to_string())]);
+
+        let unmet = DataRow::try_create(&format!("{:<200}", "N7-12345    AB"), &defs).unwrap();
+        assert!(unmet.get("Branch").unwrap().is_blank());
+        assert!(unmet.get("Rest").unwrap().is_blank());
+
+        let clashing = vec![
+            DataFieldDef::new("Key", 0, 5, &echo_ok).with_splitter(Splitter::delimiter('-', &["Code"])),
+            DataFieldDef::new("Code", 5, 9, &echo_ok),
+        ];
+        assert!(matches!(crate::datafile::check_layout(&clashing), Err(crate::DataFileError::DuplicateFieldName(n)) if n == "Code"));
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// The distinct values of a dictionary-encoded field, shared by every row loaded with its
+/// definition; see `DataFieldDef::with_dictionary()`.
+#[derive(Debug, Default)]
+pub(crate) struct Dictionary(Mutex<HashSet<Arc<str>>>);
+
+impl Dictionary {
+    /// Get the shared copy of a value, adding it if it is new.
+    pub(crate) fn intern(&self, value: &str) -> Arc<str> {
+        let mut values = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match values.get(value) {
+            Some(v) => v.clone(),
+            None => {
+                let v: Arc<str> = Arc::from(value);
+                values.insert(v.clone());
+                v
+            }
+        }
+    }
+
+    /// Get the number of distinct values.
+    pub(crate) fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+/// The text of a field: owned, or shared with other fields through a Dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Text {
+    Owned(String),
+    Shared(Arc<str>)
+}
+
+impl Text {
+    /// Get the memory held by this copy of the text; shared text is counted by its dictionary.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Text::Owned(s) => s.capacity(),
+            Text::Shared(_) => 0
+        }
+    }
+
+    /// Take the text as a String, copying it if it is shared.
+    pub(crate) fn into_string(self) -> String {
+        match self {
+            Text::Owned(s) => s,
+            Text::Shared(s) => s.to_string()
+        }
+    }
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Text::Owned(s) => s,
+            Text::Shared(s) => s
+        }
+    }
+}
+
+impl From<String> for Text {
+    fn from(value: String) -> Self {
+        Text::Owned(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_shared() {
+        let dictionary = Dictionary::default();
+        let a = dictionary.intern("OPEN");
+        let b = dictionary.intern("OPEN");
+        dictionary.intern("CLOSED");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(&*Text::Shared(a), "OPEN");
+    }
+}
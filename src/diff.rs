@@ -0,0 +1,201 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use crate::{DataFile, DataRow};
+
+/// A field whose value differs between two rows with the same key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// The name of the field.
+    pub name: String,
+    /// The value in the old row, or None if the field is absent from it.
+    pub old: Option<String>,
+    /// The value in the new row, or None if the field is absent from it.
+    pub new: Option<String>
+}
+
+/// A pair of rows with the same key and different values.
+#[derive(Debug, Clone)]
+pub struct RowChange {
+    /// The values of the key fields.
+    pub key: Vec<String>,
+    /// The row in the old file.
+    pub old: DataRow,
+    /// The row in the new file.
+    pub new: DataRow,
+    /// The fields that changed, in the order they appear in the old row.
+    pub fields: Vec<FieldChange>
+}
+
+/// The differences between two DataFiles, as produced by `DataFile::diff()`.
+#[derive(Debug, Clone, Default)]
+pub struct DataFileDiff {
+    /// Rows whose key is only in the new file.
+    pub added: Vec<DataRow>,
+    /// Rows whose key is only in the old file.
+    pub removed: Vec<DataRow>,
+    /// Rows present in both files with some fields changed.
+    pub changed: Vec<RowChange>
+}
+
+impl DataFileDiff {
+    /// True if both files have the same rows.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for DataFileDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = |v: &Option<String>| v.as_ref().map(|s| format!("\"{}\"", s)).unwrap_or("(absent)".to_string());
+        for a in &self.added {
+            writeln!(f, "+ {}", describe(a))?;
+        }
+        for r in &self.removed {
+            writeln!(f, "- {}", describe(r))?;
+        }
+        for c in &self.changed {
+            let fields = c.fields.iter()
+                .map(|fc| format!("{} {} -> {}", fc.name, value(&fc.old), value(&fc.new)))
+                .collect::<Vec<String>>();
+            writeln!(f, "~ {}: {}", c.key.join("|"), fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A short description of a row: its line number, if known, and its values.
+fn describe(row: &DataRow) -> String {
+    let values = row.fields().iter().map(|f| f.data()).collect::<Vec<String>>().join("|");
+    match row.line_index() {
+        Some(i) => format!("line {}: {}", i + 1, values),
+        None => values
+    }
+}
+
+/// The values of the key fields of a row; absent fields are blank.
+fn key_of(row: &DataRow, key: &[&str]) -> Vec<String> {
+    key.iter().map(|k| row.get(k).map(|f| f.data()).unwrap_or_default()).collect()
+}
+
+/// The fields whose values differ between two rows, matched by name.
+fn changed_fields(old: &DataRow, new: &DataRow) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    for o in old.fields() {
+        let n = new.get(o.name()).map(|f| f.data());
+        if n.as_deref() != Some(o.data().as_str()) {
+            changes.push(FieldChange { name: o.name().to_string(), old: Some(o.data()), new: n });
+        }
+    }
+    for n in new.fields().iter().filter(|n| old.get(n.name()).is_none()) {
+        changes.push(FieldChange { name: n.name().to_string(), old: None, new: Some(n.data()) });
+    }
+    changes
+}
+
+impl DataFile {
+    /// Compare this file (the old one) with another (the new one), matching rows by the values
+    /// of the key fields and reporting added, removed, and changed rows.
+    ///
+    /// Rows sharing a key are paired in the order they appear in each file, so a key repeated
+    /// more often in one file shows the extra rows as added or removed. Rows missing a key field
+    /// are keyed on a blank value for it.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+    ///     DataFieldDef::new("Status", 16, 23, &post_function),
+    /// ];
+    /// let yesterday = DataFile::try_load(Path::new("extract_0101.txt"), &defs).unwrap();
+    /// let today = DataFile::try_load(Path::new("extract_0102.txt"), &defs).unwrap();
+    /// print!("{}", yesterday.diff(&today, &["AccountNo1"]));
+    /// ```
+    pub fn diff(&self, other: &DataFile, key: &[&str]) -> DataFileDiff {
+        let mut result = DataFileDiff::default();
+
+        let mut new_rows: HashMap<Vec<String>, VecDeque<&DataRow>> = HashMap::new();
+        for row in other.rows() {
+            new_rows.entry(key_of(row, key)).or_default().push_back(row);
+        }
+
+        let mut matched = HashMap::new();
+        for row in self.rows() {
+            let k = key_of(row, key);
+            match new_rows.get_mut(&k).and_then(|rows| rows.pop_front()) {
+                Some(new) => {
+                    *matched.entry(k.clone()).or_insert(0) += 1;
+                    let fields = changed_fields(row, new);
+                    if !fields.is_empty() {
+                        result.changed.push(RowChange { key: k, old: row.clone(), new: new.clone(), fields });
+                    }
+                }
+                None => result.removed.push(row.clone())
+            }
+        }
+
+        let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+        for row in other.rows() {
+            let k = key_of(row, key);
+            let count = seen.entry(k.clone()).or_insert(0);
+            *count += 1;
+            if *count > matched.get(&k).copied().unwrap_or(0) {
+                result.added.push(row.clone());
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataField, DataFieldDef, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn file(path_name: &str, rows: &[&str]) -> DataFile {
+        let path = std::env::temp_dir().join(format!("ffreader_diff_{}_{}.txt", path_name, std::process::id()));
+        let contents: String = rows.iter().map(|r| format!("{:<200}\n", r)).collect();
+        std::fs::write(&path, contents).unwrap();
+        let defs = vec![
+            DataFieldDef::new("AccountNo1", 0, 3, &echo_ok),
+            DataFieldDef::new("Status", 3, 4, &echo_ok),
+            DataFieldDef::new("Amount", 4, 8, &echo_ok),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        std::fs::remove_file(path).unwrap();
+        df
+    }
+
+    #[test]
+    fn rows_matched_by_key() {
+        let old = file("old", &["001A  10", "002A  20", "003A  30", "003B  31"]);
+        let new = file("new", &["003A  30", "001I  10", "004A  40", "003B  31"]);
+
+        let diff = old.diff(&new, &["AccountNo1"]);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].get("AccountNo1").unwrap().data(), "002");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].get("AccountNo1").unwrap().data(), "004");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields, vec![FieldChange {
+            name: "Status".to_string(), old: Some("A".to_string()), new: Some("I".to_string())
+        }]);
+        assert_eq!(diff.to_string(), "+ line 3: 004|A|40\n- line 2: 002|A|20\n~ 001: Status \"A\" -> \"I\"\n");
+
+        assert!(old.diff(&old, &["AccountNo1", "Status"]).is_empty());
+    }
+
+    #[test]
+    fn absent_fields_reported() {
+        let old = DataRow::from_fields(vec![DataField::new("A", "1".to_string())], None);
+        let new = DataRow::from_fields(vec![DataField::new("B", "2".to_string())], None);
+        let changes = changed_fields(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[0].new.clone(), changes[1].old.clone()), (None, None));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::{DataFieldDef, DataFile, DataFileError, DataFileResult, DataRow, LoadOptions, LoadSummary, Loader};
+use crate::glob::glob_match;
+use crate::loadsummary::Stopwatch;
+
+/// The files of a directory or glob pattern loaded by `DataFile::try_load_dir()`, each with its
+/// own result, in path order.
+pub struct DirectoryLoad {
+    path: PathBuf,
+    files: Vec<(PathBuf, DataFileResult<DataFile>)>,
+    summary: LoadSummary
+}
+
+impl DirectoryLoad {
+    /// Get the path of each file with its result, in path order.
+    pub fn files(&self) -> &[(PathBuf, DataFileResult<DataFile>)] {
+        &self.files
+    }
+
+    /// Iterate over the files that loaded.
+    pub fn loaded(&self) -> impl Iterator<Item = (&Path, &DataFile)> {
+        self.files.iter().filter_map(|(p, r)| r.as_ref().ok().map(|df| (p.as_path(), df)))
+    }
+
+    /// Iterate over the files that failed to load, with their errors.
+    pub fn failed(&self) -> impl Iterator<Item = (&Path, &DataFileError)> {
+        self.files.iter().filter_map(|(p, r)| r.as_ref().err().map(|e| (p.as_path(), e)))
+    }
+
+    /// Iterate over the rows of all the files that loaded, file by file.
+    pub fn rows(&self) -> impl Iterator<Item = &DataRow> {
+        self.loaded().flat_map(|(_, df)| df.iter())
+    }
+
+    /// Get the summary of all the files that loaded, with their counts added together; the
+    /// elapsed time is that of the whole load.
+    pub fn summary(&self) -> &LoadSummary {
+        &self.summary
+    }
+
+    /// Combine the files that loaded into a single DataFile, whose path is the directory or
+    /// pattern loaded. Line indexes (in rows and warnings) are those of each row's own file,
+    /// and there is no source hash.
+    pub fn into_combined(self) -> DataFile {
+        let files = self.files.into_iter().filter_map(|(_, r)| r.ok());
+        DataFile::combine(&self.path, files, self.summary)
+    }
+
+    /// Combine the files into a single DataFile as with `into_combined()`, failing with the
+    /// error of the first file that failed to load, if any.
+    pub fn try_into_combined(mut self) -> DataFileResult<DataFile> {
+        if let Some(i) = self.files.iter().position(|(_, r)| r.is_err()) {
+            return Err(self.files.swap_remove(i).1.err().unwrap());
+        }
+        Ok(self.into_combined())
+    }
+}
+
+impl DataFile {
+    /// Load the files matching a glob pattern (or of a directory) as a single DataFile, in path
+    /// order, with each row tagged with the name of its file (see `DataRow::source()`). This
+    /// is `try_load_dir()` followed by `DirectoryLoad::try_into_combined()`, so fails if any
+    /// file does.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let df = DataFile::loader(&defs).skip_rows(1).load_glob(Path::new("/feeds/EXTRACT_2024*.TXT")).unwrap();
+    /// for row in &df {
+    ///     println!("{} line {}", row.source().unwrap(), row.line_index().unwrap() + 1);
+    /// }
+    /// ```
+    pub fn try_load_glob(pattern: &Path, row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> DataFileResult<DataFile> {
+        Self::try_load_dir_with_options(pattern, row_defs, options)?.try_into_combined()
+    }
+
+    /// Load every file of a directory, or every file matching a glob pattern in its last
+    /// component (e.g. `/feeds/EXTRACT_2024*.TXT`, where `*` matches any run of characters and
+    /// `?` any one), parsing them concurrently with a thread per file. A path to a single file
+    /// loads just that file.
+    ///
+    /// Files are listed in path order, and each row is tagged with the name of its file (see
+    /// `DataRow::source()`). A file that fails doesn't stop the others; each file's
+    /// result is kept in the DirectoryLoad, including a thread that can't be started
+    /// (`DataFileError::FileError`) or that panics (`DataFileError::LoadPanicked`). Fails only
+    /// if the directory can't be listed.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let batch = DataFile::try_load_dir(Path::new("/feeds/nightly"), &defs).unwrap();
+    /// for (path, e) in batch.failed() {
+    ///     eprintln!("{}: {}", path.display(), e);
+    /// }
+    /// println!("{}", batch.summary());
+    /// let combined = batch.into_combined();
+    /// ```
+    pub fn try_load_dir(dir_or_glob: &Path, row_defs: &Vec<DataFieldDef>) -> DataFileResult<DirectoryLoad> {
+        Self::try_load_dir_with_options(dir_or_glob, row_defs, &LoadOptions::default())
+    }
+
+    /// Load the files of a directory or glob pattern as with `try_load_dir()`, using the
+    /// provided LoadOptions for each file.
+    pub fn try_load_dir_with_options(dir_or_glob: &Path, row_defs: &Vec<DataFieldDef>, options: &LoadOptions)
+        -> DataFileResult<DirectoryLoad> {
+        let start = Stopwatch::start();
+        let paths = matching_files(dir_or_glob).map_err(|e| DataFileError::FileError(dir_or_glob.into(), e))?;
+
+        let files: Vec<(PathBuf, DataFileResult<DataFile>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = paths.into_iter().map(|path| {
+                let thread_path = path.clone();
+                let handle = std::thread::Builder::new().spawn_scoped(scope, move || {
+                    let source: Arc<str> = thread_path.file_name().unwrap_or_default().to_string_lossy().into();
+                    DataFile::try_load_with_options(&thread_path, row_defs, options).map(|mut df| {
+                        df.rows_mut().iter_mut().for_each(|r| r.set_source(Arc::clone(&source)));
+                        df
+                    })
+                });
+                (path, handle)
+            }).collect();
+            handles.into_iter().map(|(path, handle)| {
+                let result = match handle {
+                    Ok(handle) => handle.join().unwrap_or_else(|_| Err(DataFileError::LoadPanicked(path.clone()))),
+                    Err(e) => Err(DataFileError::FileError(path.clone(), e))
+                };
+                (path, result)
+            }).collect()
+        });
+
+        let mut summary = LoadSummary::default();
+        for s in files.iter().filter_map(|(_, r)| r.as_ref().ok()).map(DataFile::summary) {
+            summary.rows_read += s.rows_read;
+            summary.rows_loaded += s.rows_loaded;
+            summary.rows_skipped += s.rows_skipped;
+            summary.rows_rejected += s.rows_rejected;
+            for (kind, count) in &s.warnings_by_kind {
+                *summary.warnings_by_kind.entry(*kind).or_insert(0) += count;
+            }
+        }
+        summary.elapsed = start.elapsed();
+
+        Ok(DirectoryLoad { path: dir_or_glob.into(), files, summary })
+    }
+}
+
+/// List the files of a directory, or those matching a glob pattern in the last component of
+/// the path, in path order. Any other path is taken as a single file.
+pub(crate) fn matching_files(dir_or_glob: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let pattern = dir_or_glob.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| n.contains(['*', '?']));
+    let (dir, pattern) = match pattern {
+        Some(p) => (dir_or_glob.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new(".")), p),
+        None if dir_or_glob.is_dir() => (dir_or_glob, "*".to_string()),
+        None => return Ok(vec![dir_or_glob.into()])
+    };
+
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && glob_match(&pattern, &entry.file_name().to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+impl<'a> Loader<'a> {
+    /// Load the files of a directory or glob pattern with these options; see
+    /// `DataFile::try_load_dir()`.
+    pub fn load_dir(self, dir_or_glob: &Path) -> DataFileResult<DirectoryLoad> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::try_load_dir_with_options(dir_or_glob, row_defs, &options)
+    }
+
+    /// Load the files matching a glob pattern as a single DataFile with these options; see
+    /// `DataFile::try_load_glob()`.
+    pub fn load_glob(self, pattern: &Path) -> DataFileResult<DataFile> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::try_load_glob(pattern, row_defs, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn directory_loaded_in_parallel() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+        let dir = std::env::temp_dir().join(format!("ffreader_dir_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..5 {
+            let contents: String = (0..3).map(|j| format!("{:<200}\n", format!("{}{:03}", i, j))).collect();
+            fs::write(dir.join(format!("EXTRACT_{}.TXT", i)), contents).unwrap();
+        }
+        fs::write(dir.join("EXTRACT_9.TXT"), "short\n").unwrap();
+        fs::write(dir.join("README"), "not an extract").unwrap();
+
+        let batch = DataFile::try_load_dir(&dir.join("EXTRACT_*.TXT"), &defs).unwrap();
+        assert_eq!(batch.files().len(), 6);
+        assert_eq!(batch.failed().count(), 0);
+        assert_eq!(batch.rows().map(|r| r.get("Id").unwrap().data()).take(4).collect::<Vec<String>>(),
+                   ["0000", "0001", "0002", "1000"]);
+        assert_eq!(batch.summary().rows_loaded, 15);
+        assert_eq!(batch.summary().rows_rejected, 1);
+
+        let combined = batch.into_combined();
+        assert_eq!(combined.rows().len(), 15);
+        assert_eq!(combined.warnings().len(), 1);
+        assert_eq!(combined.rows()[3].source(), Some("EXTRACT_1.TXT"));
+        assert_eq!(combined.rows()[3].line_index(), Some(0));
+
+        let globbed = DataFile::loader(&defs).skip_rows(2).load_glob(&dir.join("EXTRACT_?.TXT")).unwrap();
+        assert_eq!(globbed.iter().map(|r| r.source().unwrap()).collect::<Vec<&str>>(),
+                   ["EXTRACT_0.TXT", "EXTRACT_1.TXT", "EXTRACT_2.TXT", "EXTRACT_3.TXT", "EXTRACT_4.TXT"]);
+        fs::write(dir.join("EXTRACT_8.TXT"), b"\xff").unwrap();
+        let e = DataFile::loader(&defs).load_glob(&dir.join("EXTRACT_*.TXT")).err().unwrap();
+        assert!(matches!(e.root(), DataFileError::NonASCIIFile));
+        assert_eq!(DataFile::try_load_dir(&dir, &defs).unwrap().files().len(), 8);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
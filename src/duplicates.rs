@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use crate::{DataRow, DataRowError, LoadOptions, LoadSummary, LoadWarning};
+
+/// What to do with a row whose key was already seen in the load; see
+/// `LoadOptions::on_duplicate()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the row and record a `WarningKind::DuplicateRow` warning (the default).
+    #[default]
+    Keep,
+    /// Leave the row out, counted as skipped, and record a `WarningKind::DuplicateRow` warning.
+    Skip,
+    /// Reject the row with `DataRowError::DuplicateRow`.
+    Error
+}
+
+/// Checks the rows of a load for duplicates, by the values of key fields or, with no key
+/// fields, of every field; see `LoadOptions::on_duplicate()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DuplicateCheck {
+    /// The fields whose values identify a row, or none to compare every field.
+    pub key_fields: Vec<String>,
+    /// What to do with each row after the first with the same key.
+    pub policy: DuplicatePolicy
+}
+
+impl DuplicateCheck {
+    /// Describe the key of a row, e.g. `Id=0001, Branch=12`; fields missing from the row are
+    /// taken as blank.
+    fn key(&self, row: &DataRow) -> String {
+        match self.key_fields.is_empty() {
+            true => row.to_string(),
+            false => self.key_fields.iter()
+                .map(|name| format!("{}={}", name, row.get(name).map(|f| f.data()).unwrap_or_default()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+    }
+}
+
+impl LoadOptions {
+    /// Check each row loaded for an earlier one with the same values of the key fields, or of
+    /// every field if none are given, and keep, skip or reject it by the policy. Keys are
+    /// checked as rows are loaded, holding only the keys seen rather than the rows, so that
+    /// duplicates are dealt with in the same pass even when streaming with DataFileReader.
+    /// ```
+    /// use ffreader::{DataFieldDef, DataFieldResult, DataFile, DuplicatePolicy, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Id", 0, 4, &post_function), DataFieldDef::new("Name", 4, 10, &post_function)];
+    /// let data = format!("{:<200}\n{:<200}\n{:<200}\n", "0001ANN", "0002BOB", "0001ANNE");
+    /// let options = LoadOptions::default().on_duplicate(&["Id"], DuplicatePolicy::Skip);
+    /// let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+    /// assert_eq!(df.rows().len(), 2);
+    /// assert_eq!(df.warnings()[0].message(), "Skipped duplicate of line 1 (Id=0001)");
+    /// ```
+    pub fn on_duplicate(mut self, key_fields: &[&str], policy: DuplicatePolicy) -> Self {
+        self.duplicates = Some(DuplicateCheck { key_fields: key_fields.iter().map(|f| f.to_string()).collect(), policy });
+        self
+    }
+}
+
+/// The keys seen so far in a load, each with the index of the line it was first seen on.
+#[derive(Default)]
+pub(crate) struct SeenKeys {
+    seen: HashMap<String, usize>
+}
+
+impl SeenKeys {
+    /// Check a loaded row against the keys seen before it by `LoadOptions::duplicates`, if
+    /// set, returning it unless it is skipped or rejected as a duplicate, with the outcome
+    /// recorded in the warnings and summary.
+    pub(crate) fn check(&mut self, options: &LoadOptions, line: &str, row: DataRow,
+                        load_warnings: &mut Vec<LoadWarning>, summary: &mut LoadSummary) -> Option<DataRow> {
+        let Some(check) = &options.duplicates else {
+            return Some(row);
+        };
+        let line_index = row.line_index().unwrap_or_default();
+        let key = check.key(&row);
+        let Some(&first) = self.seen.get(&key) else {
+            self.seen.insert(key, line_index);
+            return Some(row);
+        };
+
+        match check.policy {
+            DuplicatePolicy::Keep => {
+                load_warnings.push(LoadWarning::duplicate_row(line_index, line, "Duplicate", first, &key));
+                Some(row)
+            }
+            DuplicatePolicy::Skip => {
+                summary.rows_skipped += 1;
+                load_warnings.push(LoadWarning::duplicate_row(line_index, line, "Skipped duplicate", first, &key));
+                None
+            }
+            DuplicatePolicy::Error => {
+                summary.rows_rejected += 1;
+                let error = DataRowError::DuplicateRow(key, first + 1);
+                if let Some(quarantine) = &options.quarantine {
+                    quarantine.add(line_index, line, &error);
+                }
+                let warning = LoadWarning::row_error(line_index, line, error);
+                if let Some(metrics) = &options.metrics {
+                    metrics.row_rejected(warning.kind());
+                }
+                load_warnings.push(warning);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldDef, DataFieldResult, DataFile, DataFileReader, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn duplicates_kept_skipped_or_rejected() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok), DataFieldDef::new("Name", 4, 10, &echo_ok)];
+        let data = format!("{:<200}\n{:<200}\n{:<200}\n{:<200}\n", "0001ANN", "0002BOB", "0001ANNE", "0002BOB");
+        let load = |options: LoadOptions| DataFile::try_from_str(&data, &defs, &options).unwrap();
+
+        assert!(load(LoadOptions::default()).warnings().is_empty());
+
+        let kept = load(LoadOptions::default().on_duplicate(&[], DuplicatePolicy::Keep));
+        assert_eq!(kept.rows().len(), 4);
+        assert_eq!(kept.warnings().len(), 1);
+        assert_eq!(kept.warnings()[0].kind(), WarningKind::DuplicateRow);
+        assert_eq!(kept.warnings()[0].line_index(), 3);
+        assert_eq!(kept.warnings()[0].message(), "Duplicate of line 2 (Id=0002, Name=BOB)");
+
+        let skipped = load(LoadOptions::default().on_duplicate(&["Id"], DuplicatePolicy::Skip));
+        assert_eq!(skipped.rows().len(), 2);
+        assert_eq!((skipped.summary().rows_skipped, skipped.summary().rows_rejected), (2, 0));
+        assert!(skipped.warnings().iter().all(|w| !w.is_error()));
+
+        let options = LoadOptions::default().on_duplicate(&["Id"], DuplicatePolicy::Error);
+        for threads in [None, Some(3)] {
+            let rejected = load(LoadOptions { threads, ..options.clone() });
+            assert_eq!(rejected.rows().len(), 2);
+            assert_eq!(rejected.summary().rows_rejected, 2);
+            assert!(rejected.warnings()[0].is_error());
+            assert_eq!(rejected.warnings().iter().map(|w| w.line_index()).collect::<Vec<_>>(), [2, 3]);
+            assert_eq!(rejected.warnings()[1].message(), "Duplicate Row (Id=0002, first at line 2)");
+        }
+
+        let reader = DataFileReader::new(Cursor::new(data.clone()), &defs, options);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap().len(), 2);
+    }
+}
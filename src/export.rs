@@ -0,0 +1,708 @@
+use std::collections::HashMap;
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+use std::collections::{BTreeMap, HashSet};
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+use std::fs;
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use crate::{DataField, DataFile, DataRowResult};
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+use crate::{DataFileError, DataFileResult, DataRowError};
+use crate::format::hex;
+#[cfg(feature = "csv")]
+use crate::format::csv_field;
+#[cfg(feature = "json")]
+use crate::format::json_string;
+#[cfg(feature = "markdown")]
+use crate::format::markdown_cell;
+#[cfg(feature = "html")]
+use crate::format::html_escape;
+
+/// How a sensitive field is masked in exports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redaction {
+    /// Replace all but the last N characters with `*`, e.g. `****1234`.
+    MaskAllButLast(usize),
+    /// Replace with the hex SHA-256 digest of the salt followed by the value, so equal values
+    /// still match across exports without revealing them. Use a secret salt for short values
+    /// such as account numbers, which could otherwise be recovered by brute force.
+    Hash(String),
+    /// Replace with a fixed token, e.g. `REDACTED`.
+    Token(String)
+}
+
+impl Redaction {
+    /// Apply the redaction to a value. Blank values are left blank.
+    pub fn apply(&self, value: &str) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        match self {
+            Redaction::MaskAllButLast(n) => {
+                let masked = value.chars().count().saturating_sub(*n);
+                value.chars().enumerate().map(|(i, c)| if i < masked { '*' } else { c }).collect()
+            }
+            Redaction::Hash(salt) => {
+                hex(&Sha256::digest(format!("{}{}", salt, value).as_bytes()))
+            }
+            Redaction::Token(t) => t.clone()
+        }
+    }
+}
+
+/// How blank fields (see `DataField::is_blank()`) are written in exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlankValue {
+    /// Write the field's data unchanged: usually an empty string, unless post-processing
+    /// supplied a value for the blank field.
+    #[default]
+    AsIs,
+    /// Write an empty string.
+    Empty,
+    /// Write a null: `null` in JSON and nil in MessagePack and CBOR. Formats without nulls, such
+    /// as CSV, write an empty string.
+    Null
+}
+
+/// How CSV exports neutralize values that a spreadsheet would run as a formula (those
+/// starting with `=`, `+`, `-`, `@`, a tab or a carriage return), for exports opened in Excel
+/// by end users. Values that are numbers, such as `-12.50`, are left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormulaGuard {
+    /// Write values unchanged (the default).
+    #[default]
+    Off,
+    /// Prefix the value with `'`, which spreadsheets take to mean text and don't display.
+    Prefix,
+    /// Prefix the value with `'` as for `Prefix`, and enclose it in quotes, for importers that
+    /// also treat quoted fields as text.
+    Quote
+}
+
+impl FormulaGuard {
+    /// Determine whether a spreadsheet could take a value for a formula.
+    #[cfg(feature = "csv")]
+    fn is_formula(value: &str) -> bool {
+        value.starts_with(['=', '+', '-', '@', '\t', '\r']) && value.parse::<f64>().is_err()
+    }
+
+    /// Quote a value for CSV output, neutralizing it if it could be taken for a formula.
+    #[cfg(feature = "csv")]
+    pub(crate) fn csv_field(&self, value: &str) -> String {
+        match self {
+            FormulaGuard::Prefix if Self::is_formula(value) => csv_field(&format!("'{}", value)),
+            FormulaGuard::Quote if Self::is_formula(value) => format!("\"'{}\"", value.replace('"', "\"\"")),
+            _ => csv_field(value)
+        }
+    }
+}
+
+/// Fields exported as one column, with their values joined, e.g. the two lines of a name or
+/// address that are continuations of one value. The column is named `name` and takes the
+/// place of the first of the fields exported; blank values are left out of the join, and a
+/// column whose values are all nulls is a null. See `ExportOptions::joined`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinedField {
+    /// The name of the column.
+    pub name: String,
+    /// The names of the fields joined, found as by `DataRow::get()` (so an alias or a name
+    /// matched by the row's NameMatching will do); they are joined in the order they are exported.
+    pub fields: Vec<String>,
+    /// What the values are joined with.
+    pub separator: String
+}
+
+impl JoinedField {
+    /// Export the fields as one column with the given name, joining their values with the
+    /// separator.
+    pub fn new(name: impl ToString, fields: &[&str], separator: impl ToString) -> Self {
+        JoinedField {
+            name: name.to_string(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            separator: separator.to_string()
+        }
+    }
+}
+
+/// An output format for the exports of a DataFile. Each format is available with the cargo
+/// feature of the same name (lowercase); all are enabled by default.
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// CSV with a header row, as produced by `to_csv_with_options()`.
+    #[cfg(feature = "csv")]
+    Csv,
+    /// A JSON array of objects, as produced by `jsonify_with_options()`.
+    #[cfg(feature = "json")]
+    Json,
+    /// A Markdown table, as produced by `to_markdown_with_options()`.
+    #[cfg(feature = "markdown")]
+    Markdown,
+    /// An HTML table, as produced by `to_html_with_options()`.
+    #[cfg(feature = "html")]
+    Html
+}
+
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+impl ExportFormat {
+    /// The file extension conventionally used for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "csv")]
+            ExportFormat::Csv => "csv",
+            #[cfg(feature = "json")]
+            ExportFormat::Json => "json",
+            #[cfg(feature = "markdown")]
+            ExportFormat::Markdown => "md",
+            #[cfg(feature = "html")]
+            ExportFormat::Html => "html"
+        }
+    }
+}
+
+/// The exported name and value of each field of a row; a None value is a null.
+#[cfg(any(feature = "json", feature = "msgpack", feature = "cbor", feature = "pgcopy"))]
+pub(crate) type Record = Vec<(String, Option<String>)>;
+
+/// Options controlling the exports of a DataFile.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// The fields to export, in order. All fields are exported in row order if None.
+    pub fields: Option<Vec<String>>,
+    /// Replace the values of sensitive fields (those with a Redaction) with masked values.
+    /// The data held in the DataFile is not changed.
+    pub redact: bool,
+    /// The maximum number of rows to export, or None to export all of them.
+    pub max_rows: Option<usize>,
+    /// How blank fields are written, unless the field definition says otherwise; see
+    /// `DataFieldDef::with_blank()`.
+    pub blanks: BlankValue,
+    /// How CSV exports neutralize values that a spreadsheet would run as a formula.
+    pub formula_guard: FormulaGuard,
+    /// Fields exported as one column with their values joined; see JoinedField. The name of
+    /// a joined field can be given in `fields` to export the fields it joins.
+    pub joined: Vec<JoinedField>,
+    /// Export each row's id (see `DataRow::row_id()`) as a first column named `row_id`, so
+    /// that exported rows can be traced back to the file and matched across exports.
+    pub row_ids: bool
+}
+
+impl ExportOptions {
+    /// Get the names of the fields to export, with the names of joined fields replaced by
+    /// the fields they join.
+    fn field_names(&self) -> Option<Vec<&str>> {
+        self.fields.as_ref().map(|fields| fields.iter()
+            .flat_map(|name| match self.joined.iter().find(|j| &j.name == name) {
+                Some(joined) => joined.fields.iter().map(String::as_str).collect(),
+                None => vec![name.as_str()]
+            })
+            .collect())
+    }
+
+    /// Select the fields of a row to export.
+    fn select(&self, row: &crate::DataRow) -> DataRowResult<Vec<DataField>> {
+        match self.field_names() {
+            Some(names) => row.get_ordered_fields(&names),
+            None => Ok(row.fields().clone())
+        }
+    }
+
+    /// Combine the values of joined fields, each into the place of the first of its fields.
+    /// The fields are found in the row as by `DataRow::get()`, or by their exact names if
+    /// there is no row.
+    fn join(&self, mut values: Vec<(String, Option<String>)>, row: Option<&crate::DataRow>)
+        -> Vec<(String, Option<String>)> {
+        for joined in &self.joined {
+            let names: Vec<&str> = joined.fields.iter()
+                .map(|n| row.and_then(|r| r.get(n)).map_or(n.as_str(), DataField::name))
+                .collect();
+            let members: Vec<usize> = (0..values.len()).filter(|&i| names.contains(&values[i].0.as_str())).collect();
+            let Some(&first) = members.first() else {
+                continue;
+            };
+            let all_null = members.iter().all(|&i| values[i].1.is_none());
+            let parts: Vec<&str> = members.iter()
+                .filter_map(|&i| values[i].1.as_deref())
+                .filter(|v| !v.is_empty())
+                .collect();
+            let value = (!all_null).then(|| parts.join(&joined.separator));
+            values[first] = (joined.name.clone(), value);
+            for &i in members[1..].iter().rev() {
+                values.remove(i);
+            }
+        }
+        values
+    }
+
+    /// Put the row id first among the exported values of a row, if ids are exported.
+    fn with_row_id(&self, row_id: Option<usize>, mut values: Vec<(String, Option<String>)>)
+        -> Vec<(String, Option<String>)> {
+        if self.row_ids {
+            values.insert(0, ("row_id".to_string(), row_id.map(|id| id.to_string())));
+        }
+        values
+    }
+
+    /// Get the exported name and value of each field of a row, applying any blank handling,
+    /// redaction and joins, given those of the fields; a None value is a null.
+    pub(crate) fn row_values(&self, row: &crate::DataRow, blanks: &HashMap<String, BlankValue>,
+                             redactions: &HashMap<String, Redaction>) -> DataRowResult<Vec<(String, Option<String>)>> {
+        let values = self.select(row)?.iter()
+            .map(|f| (f.name().to_string(), self.value_with(f, blanks, redactions)))
+            .collect();
+        Ok(self.with_row_id(row.row_id(), self.join(values, Some(row))))
+    }
+
+    /// Get the exported names of the fields of rows of a layout, as `row_values()` would give
+    /// them for any row. Fails if a selected field is not in the layout.
+    #[cfg(any(feature = "csv", feature = "json"))]
+    pub(crate) fn header(&self, row_defs: &[crate::DataFieldDef], name_matching: crate::NameMatching)
+        -> DataRowResult<Vec<String>> {
+        let fields = row_defs.iter()
+            .flat_map(|d| std::iter::once(DataField::empty(d)).chain(d.splitter.iter().flat_map(|s| s.fields(""))))
+            .collect();
+        let row = crate::DataRow::from_fields(fields, None).with_name_matching(name_matching);
+        let values = self.row_values(&row, &HashMap::new(), &HashMap::new())?;
+        Ok(values.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Get the header and the exported values of each row, up to `max_rows` rows.
+    pub(crate) fn cells(&self, data_file: &DataFile, max_rows: Option<usize>)
+        -> DataRowResult<(Vec<String>, Vec<Vec<String>>)> {
+        let header = match data_file.rows().first() {
+            Some(row) => self.values(row, data_file)?.into_iter().map(|(name, _)| name).collect(),
+            None => {
+                let names = self.field_names().unwrap_or_default().into_iter().map(|n| (n.to_string(), None)).collect();
+                self.with_row_id(None, self.join(names, None)).into_iter().map(|(name, _)| name).collect()
+            }
+        };
+        let mut rows = vec![];
+        for row in data_file.rows().iter().take(max_rows.unwrap_or(usize::MAX)) {
+            rows.push(self.values(row, data_file)?.into_iter().map(|(_, v)| v.unwrap_or_default()).collect());
+        }
+        Ok((header, rows))
+    }
+
+    /// Get the exported name and value of each field of each row, up to `max_rows` rows.
+    #[cfg(any(feature = "json", feature = "msgpack", feature = "cbor", feature = "pgcopy"))]
+    pub(crate) fn records(&self, data_file: &DataFile) -> DataRowResult<Vec<Record>> {
+        let mut records = vec![];
+        for row in data_file.rows().iter().take(self.max_rows.unwrap_or(usize::MAX)) {
+            records.push(self.values(row, data_file)?);
+        }
+        Ok(records)
+    }
+
+    /// Get the exported name and value of each field of a row of the file, as for
+    /// `row_values()`.
+    fn values(&self, row: &crate::DataRow, data_file: &DataFile) -> DataRowResult<Vec<(String, Option<String>)>> {
+        self.row_values(row, data_file.blanks(), data_file.redactions())
+    }
+
+    /// Get the exported value of a field, applying any blank handling and redaction, given
+    /// those of the fields; None is a null.
+    fn value_with(&self, field: &DataField, blanks: &HashMap<String, BlankValue>,
+                             redactions: &HashMap<String, Redaction>) -> Option<String> {
+        let blanks = blanks.get(field.name()).copied().unwrap_or(self.blanks);
+        match (field.is_blank(), blanks) {
+            (true, BlankValue::Empty) => return Some(String::new()),
+            (true, BlankValue::Null) => return None,
+            _ => ()
+        }
+        Some(match redactions.get(field.name()) {
+            Some(r) if self.redact => r.apply(&field.data()),
+            _ => field.data()
+        })
+    }
+}
+
+/// Format an exported record as a JSON object.
+#[cfg(feature = "json")]
+pub(crate) fn json_record(record: &[(String, Option<String>)]) -> String {
+    format!("{{{}}}", record.iter()
+        .map(|(name, value)| format!("{}: {}", json_string(name),
+                                     value.as_deref().map(json_string).unwrap_or("null".to_string())))
+        .collect::<Vec<String>>()
+        .join(","))
+}
+
+impl DataFile {
+    /// Generate a CSV version of the data, with a header row of field names.
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self) -> String {
+        self.to_csv_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate a CSV version of the data using the provided ExportOptions.
+    /// Fails if a selected field name is not found in a row.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, ExportOptions, Redaction};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("Name", 0, 20, &post_function),
+    ///     DataFieldDef::new("AccountNo", 20, 31, &post_function)
+    ///         .with_redaction(Redaction::MaskAllButLast(4)),
+    /// ];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let options = ExportOptions { redact: true, ..Default::default() };
+    /// let sanitized = df.to_csv_with_options(&options).unwrap();
+    /// ```
+    #[cfg(feature = "csv")]
+    pub fn to_csv_with_options(&self, options: &ExportOptions) -> DataRowResult<String> {
+        let (header, rows) = options.cells(self, options.max_rows)?;
+        let mut csv = String::new();
+        for values in std::iter::once(&header).chain(&rows) {
+            csv.push_str(&values.iter().map(|v| options.formula_guard.csv_field(v)).collect::<Vec<String>>().join(","));
+            csv.push('\n');
+        }
+
+        Ok(csv)
+    }
+
+    /// Generate a json version of the data using the provided ExportOptions.
+    /// Fails if a selected field name is not found in a row.
+    #[cfg(feature = "json")]
+    pub fn jsonify_with_options(&self, options: &ExportOptions) -> DataRowResult<String> {
+        let json_row_list = options.records(self)?.iter()
+            .map(|record| json_record(record))
+            .collect::<Vec<String>>();
+
+        Ok(format!("[{}]", json_row_list.join(",\n")))
+    }
+
+    /// Generate the data in the given format using the provided ExportOptions.
+    #[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+    pub fn export(&self, format: ExportFormat, options: &ExportOptions) -> DataRowResult<String> {
+        match format {
+            #[cfg(feature = "csv")]
+            ExportFormat::Csv => self.to_csv_with_options(options),
+            #[cfg(feature = "json")]
+            ExportFormat::Json => self.jsonify_with_options(options),
+            #[cfg(feature = "markdown")]
+            ExportFormat::Markdown => self.to_markdown_with_options(options),
+            #[cfg(feature = "html")]
+            ExportFormat::Html => self.to_html_with_options(options)
+        }
+    }
+
+    /// Generate a Markdown (GitHub style) table of the data, with a header row of field names.
+    #[cfg(feature = "markdown")]
+    pub fn to_markdown(&self) -> String {
+        self.to_markdown_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate a Markdown table using the provided ExportOptions, e.g. the first few rows of
+    /// selected fields for pasting into a ticket. Fails if a selected field is not found.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, ExportOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let options = ExportOptions { max_rows: Some(10), ..Default::default() };
+    /// println!("{}", df.to_markdown_with_options(&options).unwrap());
+    /// ```
+    #[cfg(feature = "markdown")]
+    pub fn to_markdown_with_options(&self, options: &ExportOptions) -> DataRowResult<String> {
+        let (header, rows) = options.cells(self, options.max_rows)?;
+        let line = |values: &[String]| format!("| {} |\n", values.iter()
+            .map(|v| markdown_cell(v))
+            .collect::<Vec<String>>()
+            .join(" | "));
+
+        let mut markdown = line(&header);
+        markdown.push_str(&line(&vec!["---".to_string(); header.len()]));
+        for row in &rows {
+            markdown.push_str(&line(row));
+        }
+
+        Ok(markdown)
+    }
+
+    /// Generate an HTML table of the data, with a header row of field names.
+    #[cfg(feature = "html")]
+    pub fn to_html(&self) -> String {
+        self.to_html_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate an HTML table using the provided ExportOptions.
+    /// Fails if a selected field is not found.
+    #[cfg(feature = "html")]
+    pub fn to_html_with_options(&self, options: &ExportOptions) -> DataRowResult<String> {
+        let (header, rows) = options.cells(self, options.max_rows)?;
+        let line = |tag: &str, values: &[String]| format!("<tr>{}</tr>\n", values.iter()
+            .map(|v| format!("<{}>{}</{}>", tag, html_escape(v), tag))
+            .collect::<String>());
+
+        let mut html = String::from("<table>\n<thead>\n");
+        html.push_str(&line("th", &header));
+        html.push_str("</thead>\n<tbody>\n");
+        for row in &rows {
+            html.push_str(&line("td", row));
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        Ok(html)
+    }
+
+    /// Write the rows to separate files in `dir`, one per distinct value of the field `by`,
+    /// returning the paths written in order of value. Fails if no row has the field.
+    ///
+    /// Files are named after the value with the format's extension (e.g. `01.csv`); characters
+    /// other than ASCII letters, digits, `-` and `_` are replaced with `_`, and rows where the
+    /// field is blank or absent are written to `_.csv`. Names that would be the same once
+    /// cleaned up, ignoring case, are told apart with a suffix (`A_B.csv`, `A_B_2.csv`), and
+    /// Windows device names such as `CON` are prefixed with `_`. The directory must already
+    /// exist.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, ExportFormat, ExportOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+    ///     DataFieldDef::new("CyclNo1", 11, 13, &post_function),
+    /// ];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// let files = df.write_partitioned(Path::new("cycles"), "CyclNo1", ExportFormat::Csv, &ExportOptions::default()).unwrap();
+    /// ```
+    #[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+    pub fn write_partitioned(&self, dir: &Path, by: &str, format: ExportFormat, options: &ExportOptions)
+        -> DataFileResult<Vec<PathBuf>> {
+        if !self.rows().is_empty() && self.rows().iter().all(|r| r.get(by).is_none()) {
+            return Err(DataFileError::ExportError(DataRowError::FieldNameNotFound(by.to_string())));
+        }
+        let mut partitions: BTreeMap<String, Vec<crate::DataRow>> = BTreeMap::new();
+        for row in self.rows() {
+            let value = row.get(by).map(|f| f.data()).unwrap_or_default();
+            partitions.entry(value).or_default().push(row.clone());
+        }
+
+        let mut used = HashSet::new();
+        let mut paths = vec![];
+        for (value, rows) in partitions {
+            let contents = self.with_rows(rows, &[]).export(format, options).map_err(DataFileError::ExportError)?;
+            let name = unique_name(&partition_name(&value), &mut used);
+            let path = dir.join(format!("{}.{}", name, format.extension()));
+            fs::write(&path, contents).map_err(|e| DataFileError::FileError(path.clone(), e))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+}
+
+/// A file name for a partition value, using only ASCII letters, digits, `-` and `_`, and not
+/// a Windows device name.
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+fn partition_name(value: &str) -> String {
+    if value.is_empty() {
+        return "_".to_string();
+    }
+    let name: String = value.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let upper = name.to_ascii_uppercase();
+    let device = ["CON", "PRN", "AUX", "NUL"].contains(&upper.as_str())
+        || (upper.len() == 4 && (upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.ends_with(|c: char| c.is_ascii_digit()));
+    match device {
+        true => format!("_{}", name),
+        false => name
+    }
+}
+
+/// Make a name unique among those already used, ignoring case, by adding a numbered suffix.
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut unique = name.to_string();
+    let mut n = 1;
+    while !used.insert(unique.to_ascii_lowercase()) {
+        n += 1;
+        unique = format!("{}_{}", name, n);
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redactions_applied() {
+        assert_eq!(Redaction::MaskAllButLast(4).apply("5412345678"), "******5678");
+        assert_eq!(Redaction::MaskAllButLast(4).apply("12"), "12");
+        assert_eq!(Redaction::MaskAllButLast(1).apply("JOSÉ"), "***É");
+        assert_eq!(Redaction::MaskAllButLast(2).apply("JOSÉ"), "**SÉ");
+        assert_eq!(Redaction::Token("REDACTED".to_string()).apply("x"), "REDACTED");
+        assert_eq!(Redaction::Token("REDACTED".to_string()).apply(""), "");
+        assert_eq!(Redaction::Hash(String::new()).apply("abc"),
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[cfg(all(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+    #[test]
+    fn csv_and_json_exports() {
+        fn echo_ok(s: String) -> crate::DataFieldResult<String> { Ok(s) }
+        let path = std::env::temp_dir().join(format!("ffreader_export_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\n{:<200}\n", "ANN  5412345678", "BOB, 5499999999")).unwrap();
+        let defs = vec![
+            crate::DataFieldDef::new("Name", 0, 5, &echo_ok),
+            crate::DataFieldDef::new("AccountNo", 5, 15, &echo_ok)
+                .with_redaction(Redaction::MaskAllButLast(4)),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        assert_eq!(df.to_csv(), "Name,AccountNo\nANN,5412345678\n\"BOB,\",5499999999\n");
+
+        let options = ExportOptions { redact: true, fields: Some(vec!["AccountNo".to_string()]), ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "AccountNo\n******5678\n******9999\n");
+        assert_eq!(df.jsonify_with_options(&options).unwrap(),
+                   "[{\"AccountNo\": \"******5678\"},\n{\"AccountNo\": \"******9999\"}]");
+        assert_eq!(df.rows()[0].fields()[1].data(), "5412345678");
+
+        let options = ExportOptions { fields: Some(vec!["Missing".to_string()]), ..Default::default() };
+        assert!(df.to_csv_with_options(&options).is_err());
+
+        let options = ExportOptions { max_rows: Some(1), ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Name,AccountNo\nANN,5412345678\n");
+        assert_eq!(df.to_markdown_with_options(&options).unwrap(),
+                   "| Name | AccountNo |\n| --- | --- |\n| ANN | 5412345678 |\n");
+        assert_eq!(df.to_html_with_options(&options).unwrap(), "<table>\n<thead>\n<tr><th>Name</th><th>AccountNo</th></tr>\n\
+            </thead>\n<tbody>\n<tr><td>ANN</td><td>5412345678</td></tr>\n</tbody>\n</table>\n");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "csv", feature = "json"))]
+    fn joined_fields_exported_as_one_column() {
+        fn echo_ok(s: String) -> crate::DataFieldResult<String> { Ok(s) }
+        let defs = vec![
+            crate::DataFieldDef::new("Id", 0, 2, &echo_ok),
+            crate::DataFieldDef::new("Demo_Name", 2, 12, &echo_ok).with_alias("first line"),
+            crate::DataFieldDef::new("DemoLine2", 12, 22, &echo_ok).with_redaction(Redaction::Token("X".to_string())),
+        ];
+        let data = format!("{:<200}\n{:<200}\n", "01ANN       LEE", "02");
+        let df = DataFile::try_from_str(&data, &defs, &crate::LoadOptions::default()).unwrap();
+        let joined = vec![JoinedField::new("Name", &["Demo_Name", "DemoLine2"], " / ")];
+        let by_alias = vec![JoinedField::new("Name", &["first line", "DemoLine2"], " / ")];
+        let options = ExportOptions { joined: by_alias, ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Id,Name\n01,ANN / LEE\n02,\n");
+
+        let options = ExportOptions { joined: joined.clone(), redact: true, ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Id,Name\n01,ANN / X\n02,\n");
+        let options = ExportOptions { joined: joined.clone(), blanks: BlankValue::Null, ..Default::default() };
+        assert_eq!(df.jsonify_with_options(&options).unwrap(),
+                   "[{\"Id\": \"01\",\"Name\": \"ANN / LEE\"},\n{\"Id\": \"02\",\"Name\": null}]");
+
+        let fields = Some(vec!["Name".to_string(), "Id".to_string()]);
+        let options = ExportOptions { joined, fields, ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Name,Id\nANN / LEE,01\n,02\n");
+        let empty = DataFile::try_from_str("", &defs, &crate::LoadOptions::default()).unwrap();
+        assert_eq!(empty.to_csv_with_options(&options).unwrap(), "Name,Id\n");
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn formula_values_guarded() {
+        let guarded = |guard: FormulaGuard, value: &str| guard.csv_field(value);
+        assert_eq!(guarded(FormulaGuard::Off, "=1+2"), "=1+2");
+        assert_eq!(guarded(FormulaGuard::Prefix, "=1+2"), "'=1+2");
+        assert_eq!(guarded(FormulaGuard::Prefix, "@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(guarded(FormulaGuard::Prefix, "+1, \"x\""), "\"'+1, \"\"x\"\"\"");
+        assert_eq!(guarded(FormulaGuard::Quote, "-2+3"), "\"'-2+3\"");
+        assert_eq!(guarded(FormulaGuard::Quote, "-2.5"), "-2.5");
+        assert_eq!(guarded(FormulaGuard::Prefix, "\tcmd"), "'\tcmd");
+        assert_eq!(guarded(FormulaGuard::Quote, "A=B"), "A=B");
+
+        fn echo_ok(s: String) -> crate::DataFieldResult<String> { Ok(s) }
+        let defs = vec![crate::DataFieldDef::new("Note", 0, 20, &echo_ok)];
+        let data = format!("{:<200}\n{:<200}\n", "=HYPERLINK(1)", "-12.50");
+        let df = DataFile::try_from_str(&data, &defs, &crate::LoadOptions::default()).unwrap();
+        let options = ExportOptions { formula_guard: FormulaGuard::Prefix, ..Default::default() };
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Note\n'=HYPERLINK(1)\n-12.50\n");
+    }
+
+    #[cfg(all(feature = "csv", feature = "json"))]
+    #[test]
+    fn blanks_exported_consistently() {
+        fn zero_if_blank(s: String) -> crate::DataFieldResult<String> {
+            Ok(if s.is_empty() { "0".to_string() } else { s })
+        }
+        fn echo_ok(s: String) -> crate::DataFieldResult<String> { Ok(s) }
+        let data = format!("{:<200}\n{:<200}\n", "ANN  02", "     03");
+        let defs = vec![
+            crate::DataFieldDef::new("Name", 0, 5, &echo_ok),
+            crate::DataFieldDef::new("Count", 5, 7, &zero_if_blank),
+            crate::DataFieldDef::new("Note", 7, 10, &zero_if_blank).with_blank(BlankValue::AsIs),
+        ];
+        let df = DataFile::try_from_str(&data, &defs, &crate::LoadOptions::default()).unwrap();
+        assert!(df.rows()[1].get("Name").unwrap().is_blank());
+        assert!(!df.rows()[1].get("Count").unwrap().is_blank());
+        assert!(df.rows()[1].get("Note").unwrap().is_blank());
+
+        assert_eq!(df.jsonify(), "[{\"Name\": \"ANN\",\"Count\": \"02\",\"Note\": \"0\"},\n\
+            {\"Name\": \"\",\"Count\": \"03\",\"Note\": \"0\"}]");
+        let options = ExportOptions { blanks: BlankValue::Null, max_rows: Some(2), ..Default::default() };
+        assert_eq!(df.jsonify_with_options(&options).unwrap(), "[{\"Name\": \"ANN\",\"Count\": \"02\",\"Note\": \"0\"},\n\
+            {\"Name\": null,\"Count\": \"03\",\"Note\": \"0\"}]");
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Name,Count,Note\nANN,02,0\n,03,0\n");
+
+        let mut df = df;
+        df.set_blank("Note", BlankValue::Empty);
+        assert_eq!(df.to_csv_with_options(&options).unwrap(), "Name,Count,Note\nANN,02,\n,03,\n");
+    }
+
+    #[cfg(all(feature = "csv", feature = "json"))]
+    #[test]
+    fn partitions_written_by_value() {
+        fn echo_ok(s: String) -> crate::DataFieldResult<String> { Ok(s) }
+        let dir = std::env::temp_dir().join(format!("ffreader_partitions_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("extract.txt");
+        fs::write(&path, format!("{:<200}\n{:<200}\n{:<200}\n", "ANN  02", "BOB  01", "CAT  02")).unwrap();
+        let defs = vec![
+            crate::DataFieldDef::new("Name", 0, 5, &echo_ok),
+            crate::DataFieldDef::new("CyclNo1", 5, 7, &echo_ok),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        let paths = df.write_partitioned(&dir, "CyclNo1", ExportFormat::Csv, &ExportOptions::default()).unwrap();
+        assert_eq!(paths, vec![dir.join("01.csv"), dir.join("02.csv")]);
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "Name,CyclNo1\nANN,02\nCAT,02\n");
+
+        let options = ExportOptions { fields: Some(vec!["Missing".to_string()]), ..Default::default() };
+        assert!(matches!(df.write_partitioned(&dir, "CyclNo1", ExportFormat::Json, &options),
+            Err(DataFileError::ExportError(_))));
+        assert_eq!(partition_name("A/B"), "A_B");
+        assert_eq!(partition_name("con"), "_con");
+        assert_eq!(partition_name("COM1"), "_COM1");
+        assert_eq!(partition_name("COMB"), "COMB");
+        assert!(matches!(df.write_partitioned(&dir, "CyclNo", ExportFormat::Csv, &ExportOptions::default()),
+            Err(DataFileError::ExportError(DataRowError::FieldNameNotFound(_)))));
+
+        fs::write(&path, format!("{:<200}\n{:<200}\n{:<200}\n", "ANN  A/B", "BOB  A_B", "CAT  a_b")).unwrap();
+        let defs = vec![
+            crate::DataFieldDef::new("Name", 0, 5, &echo_ok),
+            crate::DataFieldDef::new("CyclNo1", 5, 8, &echo_ok),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        let paths = df.write_partitioned(&dir, "CyclNo1", ExportFormat::Csv, &ExportOptions::default()).unwrap();
+        assert_eq!(paths, vec![dir.join("A_B.csv"), dir.join("A_B_2.csv"), dir.join("a_b_3.csv")]);
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "Name,CyclNo1\nANN,A/B\n");
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "Name,CyclNo1\nBOB,A_B\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
\ No newline at end of file
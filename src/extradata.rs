@@ -0,0 +1,66 @@
+use crate::{Anchor, ColumnUnit, DataFieldDef};
+
+/// What to do with a row that has text beyond the last defined column, which is otherwise
+/// dropped without notice. Trailing whitespace, e.g. padding to the minimum row length, is
+/// not extra data. Rows with any end-anchored field have none, as the layout reaches the end
+/// of the row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtraDataPolicy {
+    /// Drop the extra text (the default).
+    #[default]
+    Ignore,
+    /// Keep the row and record a `WarningKind::ExtraData` warning, whose raw text is the extra
+    /// text.
+    Warn,
+    /// Reject the row with `DataRowError::ExtraData`.
+    Error
+}
+
+/// Find the text of a row beyond the last column of the definitions, returning the column it
+/// starts at and the text, if it isn't just whitespace.
+pub(crate) fn extra_data(row: &str, row_defs: &[DataFieldDef], unit: ColumnUnit) -> Option<(usize, String)> {
+    if row_defs.iter().any(|d| d.anchor == Anchor::End) {
+        return None;
+    }
+
+    let layout_end = row_defs.iter().map(|d| d.end_idx).max()?;
+    let len = unit.len(row);
+    if len <= layout_end {
+        return None;
+    }
+    let text = unit.slice(row, layout_end, len).trim_end();
+    (!text.is_empty()).then(|| (layout_end, text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFile, DataFieldResult, DataRow, DataRowError, LoadOptions, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn extra_data_ignored_warned_or_rejected() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok), DataFieldDef::new("Name", 4, 10, &echo_ok)];
+        let data = format!("{:<200}\n{:<200}\n", "0001ALICE", format!("0002BOB   {:>12}", "LEFTOVER"));
+        let load = |policy| DataFile::try_from_str(&data, &defs, &LoadOptions { extra_data: policy, ..Default::default() }).unwrap();
+
+        assert!(load(ExtraDataPolicy::Ignore).warnings().is_empty());
+
+        let warned = load(ExtraDataPolicy::Warn);
+        assert_eq!(warned.rows().len(), 2);
+        assert_eq!(warned.warnings().len(), 1);
+        assert_eq!(warned.warnings()[0].kind(), WarningKind::ExtraData);
+        assert_eq!(warned.warnings()[0].line_index(), 1);
+        assert_eq!(warned.warnings()[0].raw(), Some("    LEFTOVER"));
+
+        let rejected = load(ExtraDataPolicy::Error);
+        assert_eq!(rejected.rows().len(), 1);
+        assert_eq!(rejected.warnings()[0].kind(), WarningKind::ExtraData);
+
+        let options = LoadOptions { extra_data: ExtraDataPolicy::Error, ..Default::default() };
+        let row = format!("{:<200}", "0003CAROL X");
+        assert!(matches!(DataRow::try_create_with_options(&row, &defs, &options),
+                         Err(DataRowError::ExtraData(10, t)) if t == "X"));
+    }
+}
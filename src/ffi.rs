@@ -0,0 +1,225 @@
+//! C interface for loading files and reading their rows, enabled with the `ffi` feature.
+//!
+//! Build a shared or static library with, e.g.,
+//! `cargo rustc --release --features ffi --crate-type cdylib`. The layout is given as a JSON
+//! schema document, as read by `schema::import_json()`.
+//!
+//! ```c
+//! FfreaderFile *df = ffreader_load("extract.txt", schema_json);
+//! if (df == NULL) {
+//!     char *e = ffreader_last_error();
+//!     fprintf(stderr, "%s\n", e);
+//!     ffreader_string_free(e);
+//!     return 1;
+//! }
+//! for (size_t r = 0; r < ffreader_row_count(df); r++) {
+//!     for (size_t f = 0; f < ffreader_field_count(df, r); f++) {
+//!         char *name = ffreader_field_name(df, r, f);
+//!         char *value = ffreader_field_value(df, r, f);
+//!         printf("%s=%s ", name, value);
+//!         ffreader_string_free(name);
+//!         ffreader_string_free(value);
+//!     }
+//!     printf("\n");
+//! }
+//! ffreader_free(df);
+//! ```
+//!
+//! Strings returned by these functions are owned by the caller and must be released with
+//! `ffreader_string_free()`. Functions returning strings return NULL for an out of range row,
+//! field, or warning.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::ptr;
+use crate::DataFile;
+use crate::schema::import_json;
+
+/// A loaded file, as returned by `ffreader_load()`.
+pub struct FfreaderFile {
+    data_file: DataFile
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Convert a string for return to C; the file is ASCII, so only an interior NUL can fail.
+fn to_c(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', " ")).map(CString::into_raw).unwrap_or(ptr::null_mut())
+}
+
+/// Read a C string argument, recording an error if it is NULL or not UTF-8.
+///
+/// # Safety
+/// `s` must be NULL or point to a NUL-terminated string.
+unsafe fn from_c<'a>(s: *const c_char, what: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error(format!("{} is NULL", what));
+        return None;
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_last_error(format!("{} is not UTF-8", what));
+            None
+        }
+    }
+}
+
+/// Load a file with a layout described by a JSON schema, returning NULL on failure; the reason
+/// is then available from `ffreader_last_error()`. Release the file with `ffreader_free()`.
+///
+/// # Safety
+/// `path` and `schema_json` must be NULL or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_load(path: *const c_char, schema_json: *const c_char) -> *mut FfreaderFile {
+    let (Some(path), Some(schema)) = (from_c(path, "path"), from_c(schema_json, "schema")) else {
+        return ptr::null_mut();
+    };
+    let defs = match import_json(schema) {
+        Ok(d) => d,
+        Err(e) => {
+            set_last_error(e.to_string());
+            return ptr::null_mut();
+        }
+    };
+    match DataFile::try_load(Path::new(path), &defs) {
+        Ok(data_file) => Box::into_raw(Box::new(FfreaderFile { data_file })),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Get the message for the last failure on this thread, or NULL if there was none.
+#[no_mangle]
+pub extern "C" fn ffreader_last_error() -> *mut c_char {
+    LAST_ERROR.with(|e| e.borrow().as_deref().map(to_c).unwrap_or(ptr::null_mut()))
+}
+
+/// Release a file returned by `ffreader_load()`. NULL is ignored.
+///
+/// # Safety
+/// `file` must be NULL or a pointer returned by `ffreader_load()` not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_free(file: *mut FfreaderFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// Release a string returned by one of these functions. NULL is ignored.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by one of these functions not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Get the number of rows loaded.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_row_count(file: *const FfreaderFile) -> usize {
+    (*file).data_file.rows().len()
+}
+
+/// Get the number of fields in a row, or 0 if the row is out of range.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_field_count(file: *const FfreaderFile, row: usize) -> usize {
+    (*file).data_file.rows().get(row).map(|r| r.fields().len()).unwrap_or(0)
+}
+
+/// Get the name of a field in a row.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_field_name(file: *const FfreaderFile, row: usize, field: usize) -> *mut c_char {
+    (*file).data_file.rows().get(row).and_then(|r| r.fields().get(field))
+        .map(|f| to_c(f.name()))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Get the value of a field in a row; blank fields are empty strings.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_field_value(file: *const FfreaderFile, row: usize, field: usize) -> *mut c_char {
+    (*file).data_file.rows().get(row).and_then(|r| r.fields().get(field))
+        .map(|f| to_c(&f.data()))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Get the number of warnings generated while loading.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_warning_count(file: *const FfreaderFile) -> usize {
+    (*file).data_file.warnings().len()
+}
+
+/// Get a warning as text, including its line number.
+///
+/// # Safety
+/// `file` must be a valid pointer returned by `ffreader_load()`.
+#[no_mangle]
+pub unsafe extern "C" fn ffreader_warning(file: *const FfreaderFile, warning: usize) -> *mut c_char {
+    (*file).data_file.warnings().get(warning)
+        .map(|w| to_c(&w.to_string()))
+        .unwrap_or(ptr::null_mut())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(s: *mut c_char) -> String {
+        let owned = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { ffreader_string_free(s) };
+        owned
+    }
+
+    #[test]
+    fn load_and_iterate() {
+        let path = std::env::temp_dir().join(format!("ffreader_ffi_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\nshort\n", "001A")).unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+        let schema = CString::new(r#"[{"name": "Id", "start": 0, "end": 3}, {"name": "Status", "start": 3, "end": 4}]"#).unwrap();
+
+        unsafe {
+            let df = ffreader_load(c_path.as_ptr(), schema.as_ptr());
+            assert!(!df.is_null());
+            assert_eq!(ffreader_row_count(df), 1);
+            assert_eq!(ffreader_field_count(df, 0), 2);
+            assert_eq!(take(ffreader_field_name(df, 0, 1)), "Status");
+            assert_eq!(take(ffreader_field_value(df, 0, 1)), "A");
+            assert!(ffreader_field_value(df, 0, 2).is_null());
+            assert_eq!(ffreader_warning_count(df), 1);
+            assert!(take(ffreader_warning(df, 0)).starts_with("Line 2 "));
+            ffreader_free(df);
+
+            let bad = CString::new("[{}]").unwrap();
+            assert!(ffreader_load(c_path.as_ptr(), bad.as_ptr()).is_null());
+            assert_eq!(take(ffreader_last_error()), "Schema error: Field without a name");
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
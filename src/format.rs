@@ -0,0 +1,107 @@
+//! Small helpers shared by the text output formats.
+
+/// Quote and escape a string for use as a JSON string value.
+/// Schemas are always written as JSON, so this is available without the `json` feature.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quote a value for CSV output if it contains a delimiter, quote, or line break.
+#[cfg(feature = "csv")]
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Escape a value for use in a Markdown table cell.
+#[cfg(feature = "markdown")]
+pub(crate) fn markdown_cell(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Escape a value for use as HTML text or an attribute value.
+#[cfg(feature = "html")]
+pub(crate) fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c)
+        }
+    }
+    out
+}
+
+/// Render bytes as lowercase hexadecimal.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Most bytes shown by `hex_dump()`; the rest are counted but not shown.
+const HEX_DUMP_LIMIT: usize = 128;
+
+/// Render bytes as a hex and ASCII dump, 16 bytes to a line, each line starting with the offset
+/// (from `start`) of its first byte. Bytes that aren't printable ASCII are shown as `.` in the
+/// ASCII column, so that tabs, NULs and other invisible characters stand out.
+pub(crate) fn hex_dump(bytes: &[u8], start: usize) -> String {
+    let shown = &bytes[..bytes.len().min(HEX_DUMP_LIMIT)];
+    let mut lines: Vec<String> = shown.chunks(16).enumerate().map(|(i, chunk)| {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk.iter()
+            .map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' })
+            .collect();
+        format!("{:08x}  {:<47}  |{}|", start + i * 16, hex.join(" "), ascii)
+    }).collect();
+    if bytes.len() > shown.len() {
+        lines.push(format!("... ({} more bytes)", bytes.len() - shown.len()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping_works() {
+        assert_eq!(json_string("a \"b\" \\ \t"), "\"a \\\"b\\\" \\\\ \\t\"");
+        #[cfg(feature = "csv")]
+        {
+            assert_eq!(csv_field("plain"), "plain");
+            assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        }
+        #[cfg(feature = "markdown")]
+        assert_eq!(markdown_cell("a|b"), "a\\|b");
+        #[cfg(feature = "html")]
+        assert_eq!(html_escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn hex_dump_bounded() {
+        assert_eq!(hex_dump(b"AB\t\0 z", 32), "00000020  41 42 09 00 20 7a                                |AB.. z|");
+        let dump = hex_dump(&[b'x'; 200], 0);
+        assert_eq!(dump.lines().count(), 9);
+        assert!(dump.starts_with("00000000  78 78"));
+        assert!(dump.ends_with("\n... (72 more bytes)"));
+    }
+}
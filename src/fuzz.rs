@@ -0,0 +1,116 @@
+//! Entry points for fuzzing the parser with `cargo fuzz` (see the targets in `fuzz/`). Each
+//! takes arbitrary bytes and parses them with a fixed layout, covering start- and
+//! end-anchored, conditional, overlapping and typed fields, under LoadOptions chosen by the
+//! first two bytes, so that a failing input reproduces exactly. Malformed input must only
+//! ever produce errors; a panic is a bug.
+//! ```
+//! use ffreader::fuzz;
+//! assert!(fuzz::parse_row_bytes(b"\x00\x00short").is_err());
+//! assert!(fuzz::parse_record(b"\x01\x02\xff\xfe\n\x00").is_err());
+//! let row = format!("\x00\x00{:<200}", "0000000042A000000012345");
+//! assert!(fuzz::parse_row_bytes(row.as_bytes()).is_ok());
+//! ```
+
+use crate::{Anchor, ColumnUnit, Continuation, DataFieldDef, DataFieldError, DataFieldResult, DataFile,
+            DataFileResult, DataRow, DataRowResult, ExtraDataPolicy, FieldErrorPolicy, InvalidBytePolicy,
+            LoadOptions, RecordFormat};
+
+/// Check that a value is all digits.
+fn digits(value: String) -> DataFieldResult<String> {
+    match value.bytes().all(|b| b.is_ascii_digit()) {
+        true => Ok(value),
+        false => Err(DataFieldError::Problem(Box::new("Not a number")))
+    }
+}
+
+/// Convert an amount.
+fn amount(value: &str) -> DataFieldResult<u64> {
+    value.parse().map_err(|e| DataFieldError::Parse(Box::new(e)))
+}
+
+/// The layout inputs are parsed with.
+fn layout() -> Vec<DataFieldDef<'static>> {
+    vec![
+        DataFieldDef::new_owned("Id", 0, 10, digits),
+        DataFieldDef::new_owned("Kind", 10, 11, Ok),
+        DataFieldDef::new_owned("Amount", 11, 23, Ok).with_parser(&amount).with_implied_decimals(2)
+            .only_when("Kind", &["A"]),
+        DataFieldDef::new_owned("Note", 11, 23, Ok).only_when("Kind", &["N"]),
+        DataFieldDef::new_owned("Code", 23, 27, digits).with_on_error(FieldErrorPolicy::WarnAndUseDefault("0".to_string())),
+        DataFieldDef::new_owned("Name", 27, 57, |s: String| Ok(s.to_uppercase())).warn_on_truncation().with_dictionary(),
+        DataFieldDef::new_owned("Prefix", 27, 30, Ok).allow_overlaps(),
+        DataFieldDef::new_owned("Check", 0, 1, digits).with_anchor(Anchor::End).with_on_error(FieldErrorPolicy::WarnAndBlank),
+    ]
+}
+
+/// Choose LoadOptions from the first two bytes of an input, returning them and the rest of it.
+fn options(data: &[u8]) -> (LoadOptions, &[u8]) {
+    let (config, rest) = data.split_at(data.len().min(2));
+    let (a, b) = (config.first().copied().unwrap_or(0), config.get(1).copied().unwrap_or(0));
+    let options = LoadOptions {
+        columns: [ColumnUnit::Bytes, ColumnUnit::Chars, ColumnUnit::DisplayWidth, ColumnUnit::Bytes][usize::from(a & 3)],
+        invalid_bytes: [InvalidBytePolicy::Error, InvalidBytePolicy::Replace('?'), InvalidBytePolicy::Strip,
+                        InvalidBytePolicy::Replace('\u{fffd}')][usize::from(a >> 2 & 3)],
+        record_format: match a >> 4 & 3 {
+            0 => RecordFormat::Lines,
+            1 => RecordFormat::Separator(vec![0x1e]),
+            2 => RecordFormat::FixedLength(200),
+            _ => RecordFormat::Rdw
+        },
+        pad_short_rows: (a & 0x40 != 0).then_some(' '),
+        collect_all_field_errors: a & 0x80 != 0,
+        extra_data: [ExtraDataPolicy::Ignore, ExtraDataPolicy::Warn, ExtraDataPolicy::Error,
+                     ExtraDataPolicy::Ignore][usize::from(b & 3)],
+        continuation: (b & 4 != 0).then(|| Continuation::new(199, '+')),
+        skip_blank_lines: b & 8 != 0,
+        keep_raw_lines: b & 0x10 != 0,
+        comment_prefix: (b & 0x20 != 0).then(|| "#".to_string()),
+        threads: (b & 0x40 != 0).then_some(2),
+        ..Default::default()
+    };
+    (options, rest)
+}
+
+/// Parse an input as a single row, as with `DataRow::try_create_bytes_with_options()`.
+pub fn parse_row_bytes(data: &[u8]) -> DataRowResult<DataRow> {
+    let (options, row) = options(data);
+    DataRow::try_create_bytes_with_options(row, &layout(), &options)
+}
+
+/// Parse an input as a whole file, as with `DataFile::try_from_bytes()`, splitting it into
+/// records as set by the options.
+pub fn parse_record(data: &[u8]) -> DataFileResult<DataFile> {
+    let (options, data) = options(data);
+    DataFile::try_from_bytes(data, &layout(), &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loadoptions::splitmix64;
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        let mut state = 0u64;
+        let mut random = || {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            splitmix64(state)
+        };
+        let alphabet = b"0123456789 ANB+#\n\r\t\"\x1e\x00\xff\xc3\xa9\xe6\x9d\xb1";
+        for _ in 0..2000 {
+            let len = (random() % 1200) as usize;
+            let mut data: Vec<u8> = (0..len)
+                .map(|_| alphabet[(random() % alphabet.len() as u64) as usize])
+                .collect();
+            if let Some(config) = data.get_mut(..2) {
+                config.copy_from_slice(&random().to_le_bytes()[..2]);
+            }
+            let _ = parse_row_bytes(&data);
+            if let Ok(df) = parse_record(&data) {
+                for row in df.iter() {
+                    crate::render_row(row, &layout());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Minimal filename pattern matching.
+
+/// Match a name against a pattern where `*` matches any run of characters (including none)
+/// and `?` matches exactly one character. All other characters match themselves.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        match p.get(pi) {
+            Some('*') => {
+                backtrack = Some((pi, ni));
+                pi += 1;
+            }
+            Some(c) if *c == '?' || *c == n[ni] => {
+                pi += 1;
+                ni += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bn)) => {
+                    pi = bp + 1;
+                    ni = bn + 1;
+                    backtrack = Some((bp, bn + 1));
+                }
+                None => return false
+            }
+        }
+    }
+
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match() {
+        assert!(glob_match("EXTRACT_2024*.TXT", "EXTRACT_20240101.TXT"));
+        assert!(glob_match("*.TXT", ".TXT"));
+        assert!(glob_match("A?C", "ABC"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("A?C", "AC"));
+        assert!(!glob_match("EXTRACT_2024*.TXT", "EXTRACT_2023.TXT"));
+        assert!(glob_match("*a*b", "xaxxab"));
+    }
+}
@@ -0,0 +1,315 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use crate::{DataFieldDef, DataFileError, DataFileResult, DataRow, LoadOptions, LoadSummary, LoadWarning, RecordFormat};
+use crate::datafile::{check_error_limit, check_layout, check_units, load_record};
+use crate::invalidbytes::decode;
+
+/// A parent record and the child records that follow it, as assembled by a GroupAssembler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordGroup {
+    /// The parent record.
+    pub parent: DataRow,
+    /// The child records, in file order, each with the name of its layout.
+    pub children: Vec<(String, DataRow)>
+}
+
+impl RecordGroup {
+    /// Get the children of the named layout, in file order.
+    pub fn children_of<'b>(&'b self, layout: &'b str) -> impl Iterator<Item = &'b DataRow> + 'b {
+        self.children.iter().filter(move |(name, _)| name == layout).map(|(_, row)| row)
+    }
+}
+
+/// Groups the records of a hierarchical feed, where each parent (master) record is followed by
+/// any number of child (detail) records in other layouts, so that each parent comes with its
+/// children without a state machine of one's own. The layout of each record is told by the
+/// text it starts with, e.g. a record type code.
+///
+/// Records are read as DataFileReader reads them, with the LoadOptions set by `with_options()`,
+/// except that continuation lines and tailing aren't supported. Blank, comment and trailer
+/// lines are skipped as usual. Records starting with none of the prefixes, and children with no
+/// parent before them or whose parent was rejected, are left out with a
+/// `WarningKind::UngroupedRecord` warning; rows that fail to load are rejected as usual.
+/// ```
+/// use std::io::Cursor;
+/// use ffreader::{DataFieldDef, DataFieldResult, GroupAssembler};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let order = vec![DataFieldDef::new("Type", 0, 1, &post_function), DataFieldDef::new("OrderNo", 1, 7, &post_function)];
+/// let line = vec![DataFieldDef::new("Type", 0, 1, &post_function), DataFieldDef::new("Sku", 1, 9, &post_function)];
+/// let data = [format!("{:<200}", "H000123"), format!("{:<200}", "DWIDGET01"), format!("{:<200}", "DWIDGET02"),
+///             format!("{:<200}", "H000124")].join("\n");
+/// let groups: Vec<_> = GroupAssembler::new("H", &order)
+///     .with_child("line", "D", &line)
+///     .assemble(Cursor::new(data))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(groups.len(), 2);
+/// assert_eq!(groups[0].parent.get("OrderNo").unwrap().data(), "000123");
+/// assert_eq!(groups[0].children_of("line").count(), 2);
+/// assert!(groups[1].children.is_empty());
+/// ```
+pub struct GroupAssembler<'a> {
+    parent_prefix: String,
+    parent_defs: &'a Vec<DataFieldDef<'a>>,
+    children: Vec<ChildLayout<'a>>,
+    options: LoadOptions
+}
+
+/// A layout of child records.
+struct ChildLayout<'a> {
+    name: String,
+    prefix: String,
+    defs: &'a Vec<DataFieldDef<'a>>
+}
+
+impl<'a> GroupAssembler<'a> {
+    /// Make an assembler of groups whose parent records start with `parent_prefix` and are
+    /// parsed with `parent_defs`.
+    pub fn new(parent_prefix: impl ToString, parent_defs: &'a Vec<DataFieldDef<'a>>) -> Self {
+        GroupAssembler {
+            parent_prefix: parent_prefix.to_string(),
+            parent_defs,
+            children: vec![],
+            options: LoadOptions::default()
+        }
+    }
+
+    /// Add a layout of child records, which start with `prefix` and are parsed with `defs`.
+    /// The name is given with each child in `RecordGroup::children`. The parent prefix, then
+    /// child prefixes in the order added, are tried in turn.
+    pub fn with_child(mut self, name: impl ToString, prefix: impl ToString, defs: &'a Vec<DataFieldDef<'a>>) -> Self {
+        self.children.push(ChildLayout { name: name.to_string(), prefix: prefix.to_string(), defs });
+        self
+    }
+
+    /// Read records with these LoadOptions.
+    pub fn with_options(mut self, options: LoadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Assemble the groups of records read from a buffered stream.
+    pub fn assemble<R: BufRead>(self, reader: R) -> RecordGroups<'a, R> {
+        RecordGroups {
+            assembler: self,
+            reader,
+            path: PathBuf::new(),
+            next_index: 0,
+            offset: 0,
+            taken: 0,
+            checked: false,
+            done: false,
+            current: None,
+            orphaned: false,
+            warnings: vec![],
+            summary: LoadSummary::default()
+        }
+    }
+
+    /// Assemble the groups of records of a file.
+    pub fn open(self, path: &Path) -> DataFileResult<RecordGroups<'a, BufReader<File>>> {
+        let file = File::open(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+        let mut groups = self.assemble(BufReader::new(file));
+        groups.path = path.into();
+        Ok(groups)
+    }
+
+    /// Check every layout as DataFileReader checks its layout.
+    fn check(&self) -> DataFileResult<()> {
+        for defs in std::iter::once(self.parent_defs).chain(self.children.iter().map(|c| c.defs)) {
+            check_layout(defs)?;
+            check_units(defs, &self.options)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the groups of records assembled by a GroupAssembler. Each group is returned
+/// once the next parent record, or the end of input, is reached.
+pub struct RecordGroups<'a, R> {
+    assembler: GroupAssembler<'a>,
+    reader: R,
+    path: PathBuf,
+    next_index: usize,
+    offset: u64,
+    taken: usize,
+    checked: bool,
+    done: bool,
+    current: Option<RecordGroup>,
+    orphaned: bool,
+    warnings: Vec<LoadWarning>,
+    summary: LoadSummary
+}
+
+impl<R: BufRead> RecordGroups<'_, R> {
+    /// Warnings encountered so far.
+    pub fn warnings(&self) -> &Vec<LoadWarning> {
+        &self.warnings
+    }
+
+    /// Summary of the records read so far, parents and children alike.
+    pub fn summary(&self) -> LoadSummary {
+        let mut summary = self.summary.clone();
+        summary.count_warnings(&self.warnings);
+        summary
+    }
+
+    /// Read the next record after `skip_rows` and within `max_rows`, with its line index.
+    fn next_record(&mut self) -> DataFileResult<Option<(usize, String)>> {
+        let options = &self.assembler.options;
+        while options.max_rows.is_none_or(|m| self.taken < m) {
+            let limit = options.line_limit();
+            let Some(record) = options.record_format.read_record(&mut self.reader, &self.path, self.offset, limit)? else {
+                break;
+            };
+            if !record.complete && options.record_format == RecordFormat::Rdw {
+                return Err(DataFileError::InvalidRecordDescriptor(self.offset as usize));
+            }
+            let record_offset = self.offset as usize + options.record_format.header_length();
+            self.offset += record.consumed as u64;
+            let line_index = self.next_index;
+            self.next_index += 1;
+            if record.bytes.len() > limit {
+                return Err(DataFileError::LineTooLong(limit).at(&self.path, line_index, record_offset, &record.bytes));
+            }
+            if line_index < options.skip_rows {
+                continue;
+            }
+            self.taken += 1;
+            self.summary.rows_read += 1;
+
+            let (line, warning) = decode(&record.bytes, line_index, options)
+                .map_err(|e| e.at(&self.path, line_index, record_offset, &record.bytes))?;
+            self.warnings.extend(warning);
+            return Ok(Some((line_index, line.into_owned())));
+        }
+        Ok(None)
+    }
+
+    /// Add a record to the group being assembled, returning the group it completes, if any.
+    fn add(&mut self, line_index: usize, line: &str) -> DataFileResult<Option<RecordGroup>> {
+        let assembler = &self.assembler;
+        let mut load = |defs| {
+            let row = load_record(line_index, line, defs, &assembler.options, &mut self.warnings, &mut self.summary)
+                .map(|r| r.with_row_id(Some(self.summary.rows_loaded)));
+            self.summary.rows_loaded += row.is_some() as usize;
+            row
+        };
+
+        let mut completed = None;
+        if assembler.options.skip_reason(line).is_some() {
+            // skipped, and counted, as when loading
+            load(assembler.parent_defs);
+        } else if line.starts_with(&assembler.parent_prefix) {
+            let parent = load(assembler.parent_defs);
+            self.orphaned = parent.is_none();
+            completed = std::mem::replace(&mut self.current, parent.map(|parent| RecordGroup { parent, children: vec![] }));
+        } else if let Some(child) = assembler.children.iter().find(|c| line.starts_with(&c.prefix)) {
+            match self.current.as_mut() {
+                Some(group) => group.children.extend(load(child.defs).map(|row| (child.name.clone(), row))),
+                None => {
+                    let reason = if self.orphaned { "Child record of a rejected parent" } else { "Child record before any parent" };
+                    self.warnings.push(LoadWarning::ungrouped_record(line_index, line, reason));
+                    self.summary.rows_rejected += 1;
+                }
+            }
+        } else {
+            self.warnings.push(LoadWarning::ungrouped_record(line_index, line, "Record matches no layout"));
+            self.summary.rows_rejected += 1;
+        }
+        check_error_limit(&self.assembler.options, &self.summary)?;
+        Ok(completed)
+    }
+}
+
+impl<R: BufRead> Iterator for RecordGroups<'_, R> {
+    type Item = DataFileResult<RecordGroup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.checked {
+            self.checked = true;
+            if let Err(e) = self.assembler.check() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        while !self.done {
+            let result = self.next_record().and_then(|record| match record {
+                Some((line_index, line)) => self.add(line_index, &line),
+                None => {
+                    self.done = true;
+                    Ok(self.current.take())
+                }
+            });
+            match result {
+                Ok(Some(group)) => return Some(Ok(group)),
+                Ok(None) => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldError, DataFieldResult, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn children_grouped_under_parents() {
+        let header = vec![DataFieldDef::new("Type", 0, 2, &echo_ok), DataFieldDef::new("Account", 2, 8, &numeric)];
+        let detail = vec![DataFieldDef::new("Type", 0, 2, &echo_ok), DataFieldDef::new("Amount", 2, 8, &numeric)];
+        let note = vec![DataFieldDef::new("Type", 0, 2, &echo_ok), DataFieldDef::new("Text", 2, 20, &echo_ok)];
+        let lines = ["FILE HEADER", "20000100", "01000001", "20000050", "30PAID IN FULL", "", "01000002",
+                     "01ABCDEF", "20000999", "99UNKNOWN", "01000003", "20000070"];
+        let data: String = lines.iter().map(|l| format!("{:<200}\n", l)).collect();
+        let options = LoadOptions { skip_rows: 1, skip_blank_lines: true, ..Default::default() };
+
+        let mut groups = GroupAssembler::new("01", &header)
+            .with_child("detail", "20", &detail)
+            .with_child("note", "30", &note)
+            .with_options(options)
+            .assemble(Cursor::new(data));
+        let assembled: Vec<RecordGroup> = groups.by_ref().collect::<DataFileResult<_>>().unwrap();
+        let summary: Vec<(String, Vec<String>)> = assembled.iter()
+            .map(|g| (g.parent.get("Account").unwrap().data(), g.children.iter().map(|(n, r)| format!("{}:{}", n, r.fields()[1].data())).collect()))
+            .collect();
+        assert_eq!(summary, [
+            ("000001".to_string(), vec!["detail:000050".to_string(), "note:PAID IN FULL".to_string()]),
+            ("000002".to_string(), vec![]),
+            ("000003".to_string(), vec!["detail:000070".to_string()]),
+        ]);
+        assert_eq!(assembled[0].children_of("detail").count(), 1);
+
+        let warnings: Vec<(usize, WarningKind, String)> = groups.warnings().iter()
+            .map(|w| (w.line_number(), w.kind(), w.message()))
+            .collect();
+        assert_eq!(warnings[0], (2, WarningKind::UngroupedRecord, "Child record before any parent".to_string()));
+        assert_eq!(warnings[1].1, WarningKind::FieldError);
+        assert_eq!(warnings[2], (9, WarningKind::UngroupedRecord, "Child record of a rejected parent".to_string()));
+        assert_eq!(warnings[3], (10, WarningKind::UngroupedRecord, "Record matches no layout".to_string()));
+        let summary = groups.summary();
+        assert_eq!((summary.rows_read, summary.rows_loaded, summary.rows_skipped, summary.rows_rejected), (11, 6, 1, 4));
+
+        let broken = vec![DataFieldDef::new("Type", 0, 2, &echo_ok), DataFieldDef::new("Type", 2, 4, &echo_ok)];
+        let mut groups = GroupAssembler::new("01", &header).with_child("detail", "20", &broken).assemble(Cursor::new(""));
+        assert!(matches!(groups.next(), Some(Err(DataFileError::DuplicateFieldName(_)))));
+    }
+}
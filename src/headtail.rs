@@ -0,0 +1,178 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use crate::{DataFieldDef, DataFile, DataFileError, DataFileReader, DataFileResult, LoadOptions, Loader, RecordFormat};
+use crate::datafile::{check_layout, check_units};
+use crate::loadsummary::Stopwatch;
+
+/// The number of bytes read at a time when scanning back from the end of a file.
+const TAIL_CHUNK: usize = 64 * 1024;
+
+impl DataFile {
+    /// Load only the first N records of a file, for a quick look at a large one. The file is
+    /// streamed and reading stops after the Nth record.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let first = DataFile::head(Path::new("extract.txt"), &defs, 10).unwrap();
+    /// let last = DataFile::tail(Path::new("extract.txt"), &defs, 10).unwrap();
+    /// ```
+    pub fn head<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, n: usize) -> DataFileResult<DataFile> {
+        Self::head_with_options(path, row_defs, n, &LoadOptions::default())
+    }
+
+    /// Load the first N records of a file as with `head()`, using the provided LoadOptions.
+    /// `skip_rows` is applied before the N records are taken.
+    pub fn head_with_options<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, n: usize, options: &LoadOptions)
+        -> DataFileResult<DataFile> {
+        check_layout(row_defs)?;
+        check_units(row_defs, options)?;
+        let options = LoadOptions { max_rows: Some(options.max_rows.map_or(n, |m| m.min(n))), ..options.clone() };
+        let mut reader = DataFileReader::open(path, row_defs, options.clone())?;
+        let rows = (&mut reader).collect::<DataFileResult<Vec<_>>>()?;
+        let (summary, warnings) = reader.finish();
+        Ok(DataFile::from_parts(path, rows, warnings, summary, None, row_defs, &options))
+    }
+
+    /// Load only the last N records of a file, found by scanning back from the end rather than
+    /// reading the whole file.
+    ///
+    /// The lines before the last N aren't read, so line indexes (in rows and warnings) count from
+    /// the first of the N. Records in `RecordFormat::Rdw` can't be found from the end, so
+    /// loading them fails with `DataFileError::SeekUnsupported`.
+    pub fn tail<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, n: usize) -> DataFileResult<DataFile> {
+        Self::tail_with_options(path, row_defs, n, &LoadOptions::default())
+    }
+
+    /// Load the last N records of a file as with `tail()`, using the provided LoadOptions.
+    /// `skip_rows` and `max_rows` are applied to the N records, and no source hash is computed.
+    pub fn tail_with_options<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, n: usize, options: &LoadOptions)
+        -> DataFileResult<DataFile> {
+        let start = Stopwatch::start();
+        let io_error = |e| DataFileError::FileError(path.into(), e);
+        let mut file = File::open(path).map_err(io_error)?;
+        let length = file.metadata().map_err(io_error)?.len() as usize;
+
+        let separator: &[u8] = match &options.record_format {
+            RecordFormat::Lines => b"\n",
+            RecordFormat::Separator(s) => s,
+            RecordFormat::FixedLength(record_length) => {
+                let records = length / record_length.max(&1);
+                let offset = records.saturating_sub(n) * record_length;
+                let data = read_from(&mut file, offset, length - offset).map_err(io_error)?;
+                return Self::parse_tail(path, &data, row_defs, options, start);
+            }
+            RecordFormat::Rdw => return Err(DataFileError::SeekUnsupported)
+        };
+
+        // a separator at the very end of the file does not start a new record
+        let ends_with_separator = length >= separator.len()
+            && read_from(&mut file, length - separator.len(), separator.len()).map_err(io_error)? == separator;
+        let body = length - if ends_with_separator { separator.len() } else { 0 };
+
+        // chunks read back from the end, last first, and the separators found in them so far
+        let mut chunks: Vec<Vec<u8>> = vec![];
+        let mut found = 0;
+        let mut position = length;
+        let offset = loop {
+            if n == 0 {
+                break length;
+            }
+            if position == 0 || separator.is_empty() {
+                break 0;
+            }
+            let chunk = TAIL_CHUNK.min(position);
+            position -= chunk;
+            let mut bytes = read_from(&mut file, position, chunk).map_err(io_error)?;
+            // with the start of the chunk after, for a separator spanning the two
+            let overlap = chunks.last().map_or(0, |c: &Vec<u8>| (separator.len() - 1).min(c.len()));
+            let chunk_len = bytes.len();
+            if let Some(after) = chunks.last() {
+                bytes.extend_from_slice(&after[..overlap]);
+            }
+            let starts: Vec<usize> = memchr::memmem::find_iter(&bytes, separator)
+                .map(|i| position + i)
+                .filter(|i| i + separator.len() <= body)
+                .collect();
+            bytes.truncate(chunk_len);
+            chunks.push(bytes);
+            if found + starts.len() >= n {
+                break starts[starts.len() - (n - found)] + separator.len();
+            }
+            found += starts.len();
+        };
+        chunks.reverse();
+        let data = chunks.concat();
+
+        Self::parse_tail(path, &data[offset - position..], row_defs, options, start)
+    }
+
+    fn parse_tail(path: &Path, data: &[u8], row_defs: &Vec<DataFieldDef>, options: &LoadOptions, start: Stopwatch)
+        -> DataFileResult<DataFile> {
+        let options = LoadOptions { compute_hash: false, ..options.clone() };
+        Self::parse(path, data, row_defs, &options, start)
+    }
+}
+
+/// Read the given number of bytes from the offset.
+fn read_from(file: &mut File, offset: usize, length: usize) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![0; length];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+impl<'a> Loader<'a> {
+    /// Load the first N records of a file with these options; see `DataFile::head()`.
+    pub fn head(self, path: &Path, n: usize) -> DataFileResult<DataFile> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::head_with_options(path, row_defs, n, &options)
+    }
+
+    /// Load the last N records of a file with these options; see `DataFile::tail()`.
+    pub fn tail(self, path: &Path, n: usize) -> DataFileResult<DataFile> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::tail_with_options(path, row_defs, n, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn first_and_last_records_loaded() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+        let path = std::env::temp_dir().join(format!("ffreader_headtail_{}.txt", std::process::id()));
+        let contents: String = (0..5000).map(|i| format!("{:<200}\n", format!("{:04}", i))).collect();
+        std::fs::write(&path, &contents).unwrap();
+        let ids = |df: DataFile| df.iter().map(|r| r.get("Id").unwrap().data()).collect::<Vec<String>>();
+
+        assert_eq!(ids(DataFile::head(&path, &defs, 3).unwrap()), ["0000", "0001", "0002"]);
+        let tail = DataFile::tail(&path, &defs, 3).unwrap();
+        assert_eq!(tail.rows()[0].line_index(), Some(0));
+        assert_eq!(ids(tail), ["4997", "4998", "4999"]);
+        assert_eq!(ids(DataFile::tail(&path, &defs, 6000).unwrap()).len(), 5000);
+        assert!(DataFile::tail(&path, &defs, 0).unwrap().rows().is_empty());
+
+        let fixed = DataFile::loader(&defs).record_format(RecordFormat::FixedLength(201)).tail(&path, 2).unwrap();
+        assert_eq!(ids(fixed), ["4998", "4999"]);
+
+        // 255 byte records put a separator across each chunk boundary counted from the end
+        let contents: String = (0..1000).map(|i| format!("{:<253}~|", format!("{:04}", i))).collect();
+        std::fs::write(&path, &contents).unwrap();
+        assert_eq!((contents.len() - TAIL_CHUNK) % 255, 254);
+        for n in 255..=259 {
+            let tail = DataFile::loader(&defs).record_format(RecordFormat::Separator(b"~|".to_vec())).tail(&path, n).unwrap();
+            assert_eq!(ids(tail).first().map(String::as_str), Some(format!("{:04}", 1000 - n).as_str()));
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+use crate::{DataFile, DataFileResult, DataRow};
+
+impl DataFile {
+    /// Find the rows whose key has no match in another file, e.g. the transactions whose
+    /// `AccountNo1` isn't in the master file, a standard reconciliation step after loading
+    /// paired extracts. The values of `fields` in this file's rows are matched with those of
+    /// `key` in the other's, in order, so a composite key is matched field by field. The
+    /// orphaned rows are returned in file order.
+    ///
+    /// Fails if a row of either file doesn't have a field of its key. Panics if `fields` and
+    /// `key` are of different lengths.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let master_defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)];
+    /// let txn_defs = vec![DataFieldDef::new("Account", 8, 19, &post_function)];
+    /// let master = DataFile::try_load(Path::new("master.txt"), &master_defs).unwrap();
+    /// let transactions = DataFile::try_load(Path::new("transactions.txt"), &txn_defs).unwrap();
+    /// for row in transactions.orphans(&["Account"], &master, &["AccountNo1"]).unwrap() {
+    ///     println!("No account for line {}", row.line_index().unwrap() + 1);
+    /// }
+    /// ```
+    pub fn orphans(&self, fields: &[&str], other: &DataFile, key: &[&str]) -> DataFileResult<Vec<&DataRow>> {
+        assert_eq!(fields.len(), key.len(), "fields and key must have the same number of fields");
+        let keys = other.rows().iter().map(|r| r.key_values(key)).collect::<Result<HashSet<Vec<String>>, _>>()?;
+        let mut orphans = vec![];
+        for row in self.rows() {
+            if !keys.contains(&row.key_values(fields)?) {
+                orphans.push(row);
+            }
+        }
+        Ok(orphans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataFieldDef, DataFieldResult, DataFile, LoadOptions};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn orphaned_rows_found() {
+        let master_defs = vec![DataFieldDef::new("AccountNo1", 0, 4, &echo_ok), DataFieldDef::new("Serial", 4, 6, &echo_ok)];
+        let txn_defs = vec![DataFieldDef::new("Account", 2, 6, &echo_ok), DataFieldDef::new("Serial", 6, 8, &echo_ok)];
+        let rows = |values: &[&str]| values.iter().map(|v| format!("{:<200}\n", v)).collect::<String>();
+        let master = DataFile::try_from_str(&rows(&["0001AA", "0002AA", "0002BB"]), &master_defs, &LoadOptions::default()).unwrap();
+        let txns = DataFile::try_from_str(&rows(&["T 0001AA", "T 0003AA", "T 0002BB", "T 0001BB"]), &txn_defs,
+                                          &LoadOptions::default()).unwrap();
+
+        let orphans = txns.orphans(&["Account"], &master, &["AccountNo1"]).unwrap();
+        assert_eq!(orphans.iter().map(|r| r.line_index().unwrap()).collect::<Vec<usize>>(), [1]);
+        let orphans = txns.orphans(&["Account", "Serial"], &master, &["AccountNo1", "Serial"]).unwrap();
+        assert_eq!(orphans.iter().map(|r| r.line_index().unwrap()).collect::<Vec<usize>>(), [1, 3]);
+        assert!(txns.orphans(&["Missing"], &master, &["AccountNo1"]).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use crate::{ColumnUnit, DataFile, DataFileError, DataFileResult, LoadOptions, LoadWarning};
+
+/// What to do with bytes that aren't valid text in the column unit a file is loaded with:
+/// non-ASCII bytes when measuring in bytes, or invalid UTF-8 otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidBytePolicy {
+    /// Fail the load with `DataFileError::NonASCIIFile` or `DataFileError::InvalidUtf8File`
+    /// (the default).
+    #[default]
+    Error,
+    /// Replace each invalid byte with the character (e.g. `?` or U+FFFD) and record a warning.
+    /// When measuring in bytes, an ASCII replacement keeps every column in place; any other is
+    /// itself non-ASCII, so the fields of the row are rejected.
+    Replace(char),
+    /// Remove invalid bytes and record a warning. Columns after a removed byte move left.
+    Strip
+}
+
+/// A byte that isn't valid text in the column unit a file is loaded with; see
+/// `DataFile::find_invalid_bytes()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidByte {
+    /// The 0-based index of the record (line) containing the byte.
+    pub line_index: usize,
+    /// The 0-based position of the byte in the record, which is its column when measuring in
+    /// bytes.
+    pub column: usize,
+    /// The value of the byte.
+    pub value: u8
+}
+
+impl InvalidByte {
+    /// Get the 1-based line number of the record containing the byte.
+    pub fn line_number(&self) -> usize {
+        self.line_index + 1
+    }
+}
+
+impl Display for InvalidByte {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: 0x{:02X}", self.line_number(), self.column, self.value)
+    }
+}
+
+/// Find the positions of the bytes of a record that aren't valid text in the unit.
+fn invalid_positions(record: &[u8], unit: ColumnUnit) -> Vec<usize> {
+    if unit.ascii_only() {
+        return record.iter().enumerate().filter(|(_, b)| !b.is_ascii()).map(|(i, _)| i).collect();
+    }
+
+    let mut positions = vec![];
+    let mut position = 0;
+    for chunk in record.utf8_chunks() {
+        position += chunk.valid().len();
+        positions.extend(position..position + chunk.invalid().len());
+        position += chunk.invalid().len();
+    }
+    positions
+}
+
+/// Find the first byte of a record that isn't valid text in the unit, with its position.
+pub(crate) fn first_invalid(record: &[u8], unit: ColumnUnit) -> Option<(usize, u8)> {
+    invalid_positions(record, unit).first().and_then(|&column| record.get(column).map(|&value| (column, value)))
+}
+
+/// Read a record as text: ASCII when measuring in bytes, otherwise UTF-8. Invalid bytes are
+/// handled as set by `LoadOptions::invalid_bytes`; when they are replaced or stripped, a warning
+/// listing them is returned along with the text.
+pub(crate) fn decode<'a>(record: &'a [u8], line_index: usize, options: &LoadOptions)
+    -> Result<(Cow<'a, str>, Option<LoadWarning>), DataFileError> {
+    let unit = options.columns;
+    let valid = if unit.ascii_only() { record.is_ascii() } else { std::str::from_utf8(record).is_ok() };
+    if valid {
+        // checked above, so this can't fail
+        return Ok((Cow::Borrowed(std::str::from_utf8(record).unwrap_or_default()), None));
+    }
+
+    let replacement = match options.invalid_bytes {
+        InvalidBytePolicy::Error if unit.ascii_only() => return Err(DataFileError::NonASCIIFile),
+        InvalidBytePolicy::Error => return Err(DataFileError::InvalidUtf8File),
+        InvalidBytePolicy::Replace(c) => Some(c),
+        InvalidBytePolicy::Strip => None
+    };
+
+    let positions = invalid_positions(record, unit);
+    let mut text = String::with_capacity(record.len());
+    let mut start = 0;
+    for &position in &positions {
+        // the bytes between invalid ones are valid text
+        text.push_str(std::str::from_utf8(&record[start..position]).unwrap_or_default());
+        text.extend(replacement);
+        start = position + 1;
+    }
+    text.push_str(std::str::from_utf8(&record[start..]).unwrap_or_default());
+
+    let bytes: Vec<InvalidByte> = positions.into_iter()
+        .map(|column| InvalidByte { line_index, column, value: record[column] })
+        .collect();
+    let warning = LoadWarning::invalid_bytes(line_index, &text, &bytes, replacement.is_some());
+    Ok((Cow::Owned(text), Some(warning)))
+}
+
+impl DataFile {
+    /// List every byte of a file that isn't valid text in the column unit of the LoadOptions,
+    /// with its line, column and value, to find what needs fixing in a file that fails to load
+    /// with `DataFileError::NonASCIIFile`. Records are split as set by the options, but every
+    /// record is checked, including any `skip_rows`.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, LoadOptions};
+    /// for byte in DataFile::find_invalid_bytes(Path::new("extract.txt"), &LoadOptions::default()).unwrap() {
+    ///     println!("{}", byte); // e.g. "line 3, column 17: 0xC3"
+    /// }
+    /// ```
+    pub fn find_invalid_bytes(path: &Path, options: &LoadOptions) -> DataFileResult<Vec<InvalidByte>> {
+        let data = fs::read(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+        Self::find_invalid_bytes_in(&data, options)
+    }
+
+    /// List the invalid bytes of data held in memory; see `find_invalid_bytes()`.
+    pub fn find_invalid_bytes_in(data: &[u8], options: &LoadOptions) -> DataFileResult<Vec<InvalidByte>> {
+        let records = options.record_format.split(data)?;
+        Ok(records.into_iter()
+            .enumerate()
+            .flat_map(|(line_index, record)| invalid_positions(record, options.columns).into_iter()
+                .map(move |column| InvalidByte { line_index, column, value: record[column] }))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldDef, DataFieldResult, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn invalid_bytes_listed() {
+        let data = "ok\nAé?\n".as_bytes();
+        let bytes = DataFile::find_invalid_bytes_in(data, &LoadOptions::default()).unwrap();
+        let listed: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+        assert_eq!(listed, ["line 2, column 1: 0xC3", "line 2, column 2: 0xA9"]);
+
+        let options = LoadOptions { columns: ColumnUnit::Chars, ..Default::default() };
+        assert!(DataFile::find_invalid_bytes_in(data, &options).unwrap().is_empty());
+        let bytes = DataFile::find_invalid_bytes_in(b"a\xffb", &options).unwrap();
+        assert_eq!((bytes[0].column, bytes[0].value), (1, 0xff));
+    }
+
+    #[test]
+    fn invalid_bytes_replaced_or_stripped() {
+        let defs = vec![DataFieldDef::new("Name", 0, 6, &echo_ok)];
+        let data = format!("{:<200}\n", "Renée");
+
+        let options = LoadOptions { invalid_bytes: InvalidBytePolicy::Replace('?'), ..Default::default() };
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+        assert_eq!(df.rows()[0].get("Name").unwrap().data(), "Ren??e");
+        assert_eq!(df.warnings()[0].kind(), WarningKind::InvalidBytes);
+        assert_eq!(df.warnings()[0].to_string(),
+                   "Line 1 Replaced 2 invalid bytes: column 3 (0xC3), column 4 (0xA9)");
+
+        let options = LoadOptions { invalid_bytes: InvalidBytePolicy::Strip, ..Default::default() };
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+        assert_eq!(df.rows()[0].get("Name").unwrap().data(), "Rene");
+
+        let options = LoadOptions { columns: ColumnUnit::Chars, invalid_bytes: InvalidBytePolicy::Replace('\u{FFFD}'),
+                                    ..Default::default() };
+        let mut data = format!("{:<200}\n", "Ab").into_bytes();
+        data[1] = 0xff;
+        let df = DataFile::try_from_bytes(&data, &defs, &options).unwrap();
+        assert_eq!(df.rows()[0].get("Name").unwrap().data(), "A\u{FFFD}");
+    }
+}
@@ -0,0 +1,296 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use crate::{DataField, DataFieldDef, DataFile, DataFileError, DataFileResult, DataRow, DataRowError, DataRowResult,
+            LoadOptions, LoadSummary, LoadWarning};
+use crate::datafile::{check_error_limit, check_layout, check_units, load_record_with};
+use crate::loadsummary::Stopwatch;
+use crate::trace::{self, LoadSpan};
+
+/// A stored source line and the fields extracted from it so far.
+struct LazyLine {
+    line_index: usize,
+    text: String,
+    fields: OnceLock<Box<[OnceLock<Option<DataField>>]>>
+}
+
+/// Holds the source lines of a file and only extracts a field from a line when it is first
+/// accessed, caching the result. For workflows that only touch a few of many fields per row,
+/// this avoids most of the parsing (and post-processing) work of a DataFile.
+///
+/// Lines are skipped, sampled and checked for length as a DataFile would, and rejected lines are
+/// recorded as LoadWarnings. Field errors are only found when a field is accessed, so they
+/// and downgraded fields are not recorded as warnings; `LazyRow::to_row()` parses a whole row
+/// as a DataFile would.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult, LazyFile, LoadOptions};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("Id", 0, 10, &post_function),
+///     DataFieldDef::new("Status", 10, 12, &post_function),
+/// ];
+/// let lazy = LazyFile::try_load_with_options(Path::new("extract.txt"), &defs, &LoadOptions::default()).unwrap();
+/// let open = lazy.iter().filter(|r| r.get("Status").is_some_and(|f| f.data() == "OP")).count();
+/// ```
+pub struct LazyFile<'a> {
+    row_defs: &'a Vec<DataFieldDef<'a>>,
+    parts: Vec<(usize, usize)>,
+    lines: Vec<LazyLine>,
+    options: LoadOptions,
+    load_warnings: Vec<LoadWarning>,
+    summary: LoadSummary
+}
+
+/// A row of a LazyFile, whose fields are extracted as they are accessed.
+pub struct LazyRow<'f, 'a> {
+    file: &'f LazyFile<'a>,
+    line: &'f LazyLine
+}
+
+impl<'a> LazyFile<'a> {
+    /// Load a file's lines, to be parsed as their fields are accessed; see
+    /// `DataFile::try_load_with_options()`.
+    pub fn try_load_with_options(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: &LoadOptions)
+        -> DataFileResult<LazyFile<'a>> {
+        let start = Stopwatch::start();
+        let data = fs::read(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+        LoadSpan::new(path).in_scope(|| Self::parse(path, &data, row_defs, options, start))
+    }
+
+    /// Load lines held in memory; see `DataFile::try_from_str()`.
+    pub fn try_from_str(data: &str, row_defs: &'a Vec<DataFieldDef<'a>>, options: &LoadOptions)
+        -> DataFileResult<LazyFile<'a>> {
+        Self::parse(Path::new(""), data.as_bytes(), row_defs, options, Stopwatch::start())
+    }
+
+    fn parse(path: &Path, data: &[u8], row_defs: &'a Vec<DataFieldDef<'a>>, options: &LoadOptions, start: Stopwatch)
+        -> DataFileResult<LazyFile<'a>> {
+        let options = &*options.for_source(path);
+        check_layout(row_defs)?;
+        check_units(row_defs, options)?;
+        options.bytes_read(data.len());
+
+        let mut load_warnings = vec![];
+        let mut summary = LoadSummary::default();
+        let (records, rows_read) = DataFile::read_records(path, data, options, &mut load_warnings)?;
+        summary.rows_read = rows_read;
+        load_warnings.iter().for_each(trace::warning);
+
+        let mut lines = vec![];
+        for (line_index, record) in records {
+            let checked = load_record_with(line_index, &record, row_defs, options, &mut load_warnings, &mut summary, |row| {
+                DataRow::check_length(row, options.columns)?;
+                DataRow::check_extra_data(row, row_defs, options)?;
+                DataRow::check_printable(row, row_defs, options).map(|_| (row.to_string(), vec![]))
+            });
+            if let Some(text) = checked {
+                lines.push(LazyLine { line_index, text, fields: OnceLock::new() });
+            }
+            check_error_limit(options, &summary)?;
+        }
+
+        summary.rows_loaded = lines.len();
+        if let Some(warning) = LoadWarning::no_rows(data.is_empty(), &summary, options.skip_rows) {
+            trace::warning(&warning);
+            load_warnings.push(warning);
+        }
+        summary.count_warnings(&load_warnings);
+        summary.elapsed = start.elapsed();
+        options.finished(&summary);
+
+        // the definition and position of each split part, cached after the fields of the definitions
+        let parts = row_defs.iter().enumerate()
+            .flat_map(|(i, d)| (0..d.splitter.as_ref().map_or(0, |s| s.names().len())).map(move |k| (i, k)))
+            .collect();
+        Ok(LazyFile { row_defs, parts, lines, options: options.clone(), load_warnings, summary })
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Determine whether there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Get the row at the given index.
+    pub fn get(&self, index: usize) -> Option<LazyRow<'_, 'a>> {
+        self.lines.get(index).map(|line| LazyRow { file: self, line })
+    }
+
+    /// Iterate over the rows.
+    pub fn iter(&self) -> impl Iterator<Item = LazyRow<'_, 'a>> {
+        self.lines.iter().map(|line| LazyRow { file: self, line })
+    }
+
+    /// Get a reference to the list of warnings generated during loading.
+    pub fn warnings(&self) -> &Vec<LoadWarning> {
+        &self.load_warnings
+    }
+
+    /// Get the summary of the load.
+    pub fn summary(&self) -> &LoadSummary {
+        &self.summary
+    }
+}
+
+impl LazyRow<'_, '_> {
+    /// Get the 0-based index of the source line the row was loaded from.
+    pub fn line_index(&self) -> usize {
+        self.line.line_index
+    }
+
+    /// Get the source line of the row.
+    pub fn raw(&self) -> &str {
+        &self.line.text
+    }
+
+    /// Find the index of the field with the given name or alias, matched as set by
+    /// `LoadOptions::name_matching`: that of its definition, or for a split part, the number of
+    /// definitions plus its position among the parts.
+    fn position(&self, name: &str) -> Option<usize> {
+        let defs = self.file.row_defs;
+        let matches = |n: &str| self.file.options.name_matching.matches(name, n);
+        defs.iter().position(|d| matches(&d.name))
+            .or_else(|| defs.iter().position(|d| d.aliases.iter().any(|a| matches(a))))
+            .or_else(|| self.file.parts.iter()
+                .position(|(i, k)| defs[*i].splitter.as_ref().is_some_and(|s| matches(s.names()[*k])))
+                .map(|p| defs.len() + p))
+    }
+
+    /// Get the index of the definition of the field at the index, the one split for a part.
+    fn definition(&self, index: usize) -> usize {
+        index.checked_sub(self.file.row_defs.len()).map_or(index, |p| self.file.parts[p].0)
+    }
+
+    /// Get the field at the index, extracting it on first access. None if the field failed.
+    fn field(&self, index: usize) -> Option<&DataField> {
+        let cache = self.line.fields.get_or_init(|| {
+            (0..self.file.row_defs.len() + self.file.parts.len()).map(|_| OnceLock::new()).collect()
+        });
+        cache[index].get_or_init(|| self.extract(index)).as_ref()
+    }
+
+    fn extract(&self, index: usize) -> Option<DataField> {
+        let defs = self.file.row_defs;
+        if let Some(p) = index.checked_sub(defs.len()) {
+            let (i, k) = self.file.parts[p];
+            let value = self.field(i)?.data();
+            return defs[i].splitter.as_ref().map(|s| s.fields(&value).swap_remove(k));
+        }
+        let def = &defs[index];
+        if let Some(condition) = &def.condition {
+            let controlling = condition.controlling(&defs[..index], self.file.options.name_matching)
+                .and_then(|i| self.field(i));
+            if !controlling.is_some_and(|f| condition.values.contains(&f.data())) {
+                return Some(DataField::empty(def));
+            }
+        }
+        let unit = self.file.options.columns;
+        let (mut field, _) = DataField::extract(self.raw(), def, &self.file.options, self.raw().is_ascii()).ok()?;
+        if self.file.options.provenance {
+            field = field.with_provenance(self.raw(), def, unit);
+            field.set_line_index(self.line.line_index);
+        }
+        Some(field)
+    }
+
+    /// Get the field with the given name or alias, extracting it if it hasn't been yet. None if
+    /// there is no such field or it failed; see `try_get()`.
+    pub fn get(&self, name: &str) -> Option<&DataField> {
+        self.position(name).and_then(|i| self.field(i))
+    }
+
+    /// Get the field with the given name or alias as with `get()`, or the reason it isn't
+    /// available. The errors of failed fields aren't cached, so the field is extracted again
+    /// to report its error.
+    pub fn try_get(&self, name: &str) -> DataRowResult<&DataField> {
+        let index = self.position(name).ok_or_else(|| DataRowError::FieldNameNotFound(name.to_string()))?;
+        self.field(index).ok_or_else(|| {
+            let def = &self.file.row_defs[self.definition(index)];
+            match DataField::extract(self.raw(), def, &self.file.options, self.raw().is_ascii()) {
+                Err(e) => e.into(),
+                Ok(_) => DataRowError::FieldNameNotFound(name.to_string())
+            }
+        })
+    }
+
+    /// Parse every field of the row, as a DataFile would have.
+    pub fn to_row(&self) -> DataRowResult<DataRow> {
+        DataRow::try_create_with_options(self.raw(), self.file.row_defs, &self.file.options)
+            .map(|r| r.with_line_index(self.line.line_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::{DataFieldError, DataFieldResult};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counted(s: String) -> DataFieldResult<String> {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        Ok(s)
+    }
+
+    fn reject_x(s: String) -> DataFieldResult<String> {
+        match s.as_str() {
+            "X" => Err(DataFieldError::Problem(Box::new("X"))),
+            _ => Ok(s)
+        }
+    }
+
+    #[test]
+    fn fields_extracted_on_access() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 3, &counted),
+            DataFieldDef::new("Flag", 3, 4, &reject_x),
+            DataFieldDef::new("Unit", 4, 6, &counted).only_when("Flag", &["U"]),
+        ];
+        let data = format!("{:<200}\nshort\n{:<200}\n", "001U42", "002X42");
+        let lazy = LazyFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(lazy.len(), 2);
+        assert_eq!(lazy.warnings().len(), 1);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+
+        let first = lazy.get(0).unwrap();
+        assert_eq!(first.get("Unit").unwrap().data(), "42");
+        assert_eq!(first.get("Unit").unwrap().data(), "42");
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(first.to_row().unwrap().get("Id").unwrap().data(), "001");
+
+        let second = lazy.get(1).unwrap();
+        assert_eq!(second.line_index(), 2);
+        assert_eq!(second.get("Id").unwrap().data(), "002");
+        assert!(second.get("Flag").is_none());
+        assert!(matches!(second.try_get("Flag"), Err(DataRowError::FieldError(DataFieldError::PostProcess(..)))));
+        assert!(second.get("Unit").unwrap().data().is_empty());
+        assert!(matches!(second.try_get("Missing"), Err(DataRowError::FieldNameNotFound(_))));
+        assert!(second.to_row().is_err());
+    }
+
+    #[test]
+    fn split_parts_extracted_on_access() {
+        let defs = vec![
+            DataFieldDef::new("Key", 0, 6, &reject_x).with_splitter(crate::Splitter::delimiter('-', &["Branch", "Account"])),
+            DataFieldDef::new("Status", 6, 7, &reject_x),
+        ];
+        let data = format!("{:<200}\n{:<200}\n", "01-234A", "X     A");
+        let lazy = LazyFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+
+        let first = lazy.get(0).unwrap();
+        assert_eq!(first.get("Account").unwrap().data(), "234");
+        assert_eq!(first.get("Branch").unwrap().data(), "01");
+        assert_eq!(first.get("Status").unwrap().data(), "A");
+        let second = lazy.get(1).unwrap();
+        assert!(second.get("Branch").is_none());
+        assert!(matches!(second.try_get("Branch"), Err(DataRowError::FieldError(DataFieldError::PostProcess(..)))));
+    }
+}
@@ -6,27 +6,134 @@
 //! It includes the following features:
 //! - Field definitions based on column offset.
 //! - Custom post-processing callbacks for each field.
-//! - CSV and JSON output.
+//! - CSV, JSON, Markdown and HTML output, and Excel, MessagePack, CBOR and Postgres COPY
+//!   output with the `xlsx`, `msgpack`, `cbor` and `pgcopy` features.
+//! - C and Python bindings with the `ffi` and `python` features.
 //!
-//! Currently only ASCII text is supported.
+//! ASCII text is expected, unless `LoadOptions::columns` is set to measure fields in characters
+//! or display columns of UTF-8 text. Stray bytes that don't fit can be listed with
+//! `DataFile::find_invalid_bytes()`, or replaced or stripped by setting `LoadOptions::invalid_bytes`.
 //!
 //! Usage is intended to be simple:
 //! 1. Create any post-processing (validation and/or alteration) functions needed
 //! 2. Create a `Vec` of `DataFieldDef` objects describing the fields and assigning post-processing functions
-//! 3. Use `DataFile::try_load()` to open and process your file.
+//! 3. Use `DataFile::try_load()` to open and process your file, or `DataFile::loader()` to set
+//!    LoadOptions first.
 //! 4. Do what you need to with the data obtained. For example, you could obtain a subset of fields and
-//! turn them into a CSV-formatted file using `DataFile::get_ordered_fields()`, use the rows directly
-//! with `DataFile::rows()`, or check for problems with `DataFile::warnings()`.
+//!    turn them into a CSV-formatted file using `DataFile::get_ordered_fields()`, use the rows directly
+//!    with `DataFile::rows()`, or check for problems with `DataFile::warnings()`.
+//!
+//! ## Cargo features
+//! Output formats and bindings are behind features, so that users of just the parser can
+//! disable the ones they don't need with `default-features = false`.
+//! - `csv`, `json`, `markdown`, `html` (default): text exports, which have no dependencies.
+//! - `xlsx`: Excel export.
+//! - `msgpack`, `cbor`: compact binary exports.
+//! - `pgcopy`: Postgres `COPY ... FROM STDIN` text exports, and `PgCopySink` for pipelines.
+//! - `ffi`, `python`: C and Python bindings.
+//! - `derive`: `#[derive(FixedWidthRecord)]`, declaring a layout on the struct it's read into.
+//! - `proptest`: strategies generating rows of a layout, and a round-trip check, for
+//!   property-based testing in the `testing` module.
+//! - `object_store`: streaming files from S3, GCS, Azure or HTTP with
+//!   `DataFileReader::open_url()`.
+//! - `tracing`: a span for each load, with events for skipped lines, warnings and the load
+//!   summary, emitted with the `tracing` crate.
+//! - `chrono-tz`: `processors::timestamp()`, reading local timestamps in a time zone and
+//!   writing them in UTC as RFC 3339.
 
 mod datafield;
+mod dictionary;
 mod datarow;
 mod datafile;
 mod loadwarning;
+mod warninggroup;
+mod loadoptions;
+mod loader;
+mod loadsummary;
+mod audit;
+mod quarantine;
+mod snapshot;
+mod format;
+mod records;
+mod columns;
+mod invalidbytes;
+mod extradata;
+mod controlchars;
+mod printable;
+mod duplicates;
+mod numberformat;
+mod trace;
+mod metrics;
+mod export;
+mod writer;
+mod convert;
+mod groups;
+mod glob;
+mod query;
+mod reader;
+#[cfg(any(feature = "csv", feature = "json"))]
+mod pipeline;
+mod sink;
+mod validate;
+mod partial;
+mod controltotal;
+mod headtail;
+mod directory;
+mod parallel;
+mod diff;
+mod integrity;
+mod aggregate;
+mod columnar;
+mod lazy;
+mod rowparser;
+mod testgen;
+mod anonymize;
+mod table;
+mod model;
+#[cfg(feature = "object_store")]
+mod remote;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+mod binary;
+#[cfg(feature = "pgcopy")]
+mod pgcopy;
+
+pub mod processors;
+pub mod schema;
+pub mod bench_support;
+pub mod fuzz;
+#[cfg(feature = "proptest")]
+pub mod testing;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(all(test, feature = "derive"))]
+extern crate self as ffreader;
+
+/// Support for code generated by `#[derive(FixedWidthRecord)]`; not part of the public API.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __derive {
+    pub use crate::model::{parse, parse_optional};
+}
 
 pub use datafield::DataField;
 pub use datafield::DataFieldDef;
+pub use datafield::Anchor;
 pub use datafield::DataFieldError;
+pub use datafield::FieldErrorContext;
+pub use datafield::FieldErrorPolicy;
+pub use datafield::FieldCondition;
+pub use datafield::Splitter;
 pub use datafield::Result as DataFieldResult;
+pub use datafield::PostProcess;
+pub use datafield::FieldProcessor;
+pub use datafield::TypedParser;
+pub use datafield::TypedValue;
+pub use datafield::Provenance;
 
 pub use datarow::DataRow;
 pub use datarow::DataRowError;
@@ -34,6 +141,132 @@ pub use datarow::Result as DataRowResult;
 
 pub use datafile::DataFile;
 pub use datafile::DataFileError;
+pub use datafile::ErrorLocation;
+pub use datafile::SourceHash;
 pub use datafile::Result as DataFileResult;
 
+pub use reader::DataFileReader;
+pub use reader::Checkpoint;
+#[cfg(any(feature = "csv", feature = "json"))]
+pub use pipeline::Pipeline;
+pub use sink::OutputSink;
+#[cfg(feature = "csv")]
+pub use sink::CsvSink;
+#[cfg(feature = "json")]
+pub use sink::JsonLinesSink;
+#[cfg(feature = "json")]
+pub use sink::StreamSink;
+#[cfg(feature = "pgcopy")]
+pub use pgcopy::PgCopySink;
+#[cfg(feature = "object_store")]
+pub use remote::ObjectReader;
+pub use validate::Validation;
+pub use partial::PartialLoad;
+pub use directory::DirectoryLoad;
+pub use controltotal::ControlTotal;
+pub use controltotal::ControlMeasure;
+
 pub use loadwarning::LoadWarning;
+pub use loadwarning::WarningKind;
+pub use warninggroup::WarningGroup;
+pub use warninggroup::WarningAggregator;
+
+pub use loadoptions::LoadOptions;
+pub use loadoptions::Sample;
+pub use loadoptions::NameMatching;
+pub use loader::Loader;
+pub use metrics::Metrics;
+pub use records::RecordFormat;
+pub use columns::ColumnUnit;
+pub use invalidbytes::InvalidByte;
+pub use invalidbytes::InvalidBytePolicy;
+pub use extradata::ExtraDataPolicy;
+pub use controlchars::ControlCharPolicy;
+pub use printable::PrintableAscii;
+pub use duplicates::DuplicateCheck;
+pub use duplicates::DuplicatePolicy;
+pub use numberformat::NumberFormat;
+pub use numberformat::SignPosition;
+pub use records::Continuation;
+
+pub use loadsummary::LoadSummary;
+pub use audit::AuditEntry;
+pub use audit::AuditWriter;
+pub use quarantine::Quarantine;
+pub use quarantine::QuarantinedRow;
+
+#[cfg(any(feature = "csv", feature = "json", feature = "markdown", feature = "html"))]
+pub use export::ExportFormat;
+pub use export::ExportOptions;
+pub use export::Redaction;
+pub use export::BlankValue;
+pub use export::FormulaGuard;
+pub use export::JoinedField;
+
+pub use writer::Alignment;
+pub use writer::render_row;
+pub use writer::FixedWidthWriter;
+pub use writer::AtomicFile;
+pub use convert::LayoutConverter;
+pub use groups::GroupAssembler;
+pub use groups::RecordGroup;
+pub use groups::RecordGroups;
+
+pub use diff::DataFileDiff;
+pub use diff::RowChange;
+pub use diff::FieldChange;
+
+pub use aggregate::ColumnAggregate;
+pub use columnar::ColumnarFile;
+pub use lazy::LazyFile;
+pub use lazy::LazyRow;
+pub use rowparser::RowParser;
+pub use rowparser::DataRowBuf;
+pub use testgen::TestGen;
+pub use testgen::TestValue;
+pub use model::FixedWidthRecord;
+#[cfg(feature = "derive")]
+pub use ffreader_derive::FixedWidthRecord;
+
+pub use query::Query;
+pub use query::QueryError;
+pub use query::Result as QueryResult;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn core_types_are_send_and_sync() {
+        assert_send_sync::<DataField>();
+        assert_send_sync::<DataFieldDef<'static>>();
+        assert_send_sync::<DataFieldError>();
+        assert_send_sync::<DataRow>();
+        assert_send_sync::<DataRowError>();
+        assert_send_sync::<DataFile>();
+        assert_send_sync::<DataFileError>();
+        assert_send_sync::<LoadWarning>();
+        assert_send_sync::<LoadOptions>();
+        assert_send_sync::<LoadSummary>();
+        assert_send_sync::<Query>();
+        assert_send_sync::<DataFileReader<'static, std::io::BufReader<std::fs::File>>>();
+    }
+
+    #[test]
+    fn data_file_shared_across_threads() {
+        use std::sync::Arc;
+        let data = format!("{:<200}\n{:<200}\n", "001A", "002B");
+        let keep = |s| Ok(s);
+        let defs = vec![DataFieldDef::new("Status", 3, 4, &keep)];
+        let data_file = Arc::new(DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap());
+
+        let handles: Vec<_> = (0..2).map(|i| {
+            let data_file = Arc::clone(&data_file);
+            std::thread::spawn(move || data_file.rows()[i].get("Status").unwrap().data())
+        }).collect();
+        let values: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(values, ["A", "B"]);
+    }
+}
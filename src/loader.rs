@@ -0,0 +1,271 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use crate::{AuditWriter, ColumnUnit, DuplicatePolicy, Continuation, ControlCharPolicy, DataFieldDef, DataFile, DataFileReader, DataFileResult, ExtraDataPolicy, InvalidBytePolicy, LoadOptions, Metrics, NameMatching, PrintableAscii, Quarantine, RecordFormat, Sample};
+
+/// Builder for loading a DataFile, created by `DataFile::loader()`.
+///
+/// Each method sets one of the LoadOptions; anything not set keeps its default, so adding
+/// options in future releases doesn't change the behavior of existing code.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+/// let df = DataFile::loader(&defs)
+///     .skip_rows(2)
+///     .skip_blank_lines(true)
+///     .comment_prefix("#")
+///     .load(Path::new("extract.txt"))
+///     .unwrap();
+/// ```
+pub struct Loader<'a> {
+    row_defs: &'a Vec<DataFieldDef<'a>>,
+    options: LoadOptions
+}
+
+impl<'a> Loader<'a> {
+    /// Create a loader for the provided field definitions with the default LoadOptions.
+    pub fn new(row_defs: &'a Vec<DataFieldDef<'a>>) -> Self {
+        Loader { row_defs, options: LoadOptions::default() }
+    }
+
+    /// Replace all of the options at once.
+    pub fn options(mut self, options: LoadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get the options set so far.
+    pub fn load_options(&self) -> &LoadOptions {
+        &self.options
+    }
+
+    /// Set the number of lines to skip at the start of the file; see `LoadOptions::skip_rows`.
+    pub fn skip_rows(mut self, skip_rows: usize) -> Self {
+        self.options.skip_rows = skip_rows;
+        self
+    }
+
+    /// Set the maximum number of lines to process; see `LoadOptions::max_rows`.
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.options.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Set whether blank lines are skipped; see `LoadOptions::skip_blank_lines`.
+    pub fn skip_blank_lines(mut self, skip: bool) -> Self {
+        self.options.skip_blank_lines = skip;
+        self
+    }
+
+    /// Skip lines starting with this prefix; see `LoadOptions::comment_prefix`.
+    pub fn comment_prefix(mut self, prefix: impl ToString) -> Self {
+        self.options.comment_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Set whether skipped lines are reported; see `LoadOptions::warn_on_skipped_lines`.
+    pub fn warn_on_skipped_lines(mut self, warn: bool) -> Self {
+        self.options.warn_on_skipped_lines = warn;
+        self
+    }
+
+    /// Set whether the source line of each row is kept; see `LoadOptions::keep_raw_lines`.
+    pub fn keep_raw_lines(mut self, keep: bool) -> Self {
+        self.options.keep_raw_lines = keep;
+        self
+    }
+
+    /// Set whether the provenance of each field is recorded; see `LoadOptions::provenance`.
+    pub fn provenance(mut self, provenance: bool) -> Self {
+        self.options.provenance = provenance;
+        self
+    }
+
+    /// Set whether changes made by post-processing are recorded; see `LoadOptions::audit`.
+    pub fn audit(mut self, audit: bool) -> Self {
+        self.options.audit = audit;
+        self
+    }
+
+    /// Write changes made by post-processing to an AuditWriter as rows are loaded.
+    pub fn audit_writer(mut self, writer: AuditWriter) -> Self {
+        self.options.audit_writer = Some(writer);
+        self
+    }
+
+    /// Collect rejected rows in a Quarantine for correction and re-ingestion.
+    pub fn quarantine(mut self, quarantine: Quarantine) -> Self {
+        self.options.quarantine = Some(quarantine);
+        self
+    }
+
+    /// Keep, skip or reject rows with the same key as an earlier row; see
+    /// `LoadOptions::on_duplicate()`.
+    pub fn on_duplicate(mut self, key_fields: &[&str], policy: DuplicatePolicy) -> Self {
+        self.options = self.options.on_duplicate(key_fields, policy);
+        self
+    }
+
+    /// Set how control characters inside fields are handled; see ControlCharPolicy.
+    pub fn control_chars(mut self, policy: ControlCharPolicy) -> Self {
+        self.options.control_chars = policy;
+        self
+    }
+
+    /// Check that rows hold only printable ASCII; see PrintableAscii.
+    pub fn printable_ascii(mut self, check: PrintableAscii) -> Self {
+        self.options.printable_ascii = Some(check);
+        self
+    }
+
+    /// Set whether every field of a failing row is attempted; see
+    /// `LoadOptions::collect_all_field_errors`.
+    pub fn collect_all_field_errors(mut self, collect: bool) -> Self {
+        self.options.collect_all_field_errors = collect;
+        self
+    }
+
+    /// Set how the file is divided into records; see `LoadOptions::record_format`.
+    pub fn record_format(mut self, record_format: RecordFormat) -> Self {
+        self.options.record_format = record_format;
+        self
+    }
+
+    /// Join physical lines into logical records; see `LoadOptions::continuation`.
+    pub fn continuation(mut self, continuation: Continuation) -> Self {
+        self.options.continuation = Some(continuation);
+        self
+    }
+
+    /// Set whether the file contents are hashed; see `LoadOptions::compute_hash`.
+    pub fn compute_hash(mut self, compute: bool) -> Self {
+        self.options.compute_hash = compute;
+        self
+    }
+
+    /// Set how field names are matched in the loaded rows; see `LoadOptions::name_matching`.
+    pub fn name_matching(mut self, name_matching: NameMatching) -> Self {
+        self.options.name_matching = name_matching;
+        self
+    }
+
+    /// Set the unit field indexes are measured in; see `LoadOptions::columns`.
+    pub fn columns(mut self, columns: ColumnUnit) -> Self {
+        self.options.columns = columns;
+        self
+    }
+
+    /// Set what is done with bytes that aren't valid text; see `LoadOptions::invalid_bytes`.
+    pub fn invalid_bytes(mut self, policy: InvalidBytePolicy) -> Self {
+        self.options.invalid_bytes = policy;
+        self
+    }
+
+    /// Set what is done with text beyond the last defined column; see `LoadOptions::extra_data`.
+    pub fn extra_data(mut self, policy: ExtraDataPolicy) -> Self {
+        self.options.extra_data = policy;
+        self
+    }
+
+    /// Pad rows shorter than the layout with the character; see `LoadOptions::pad_short_rows`.
+    pub fn pad_short_rows(mut self, pad: char) -> Self {
+        self.options.pad_short_rows = Some(pad);
+        self
+    }
+
+    /// Parse the records on `n` threads; see `LoadOptions::threads`.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.options.threads = Some(n);
+        self
+    }
+
+    /// Fail on records longer than `n` bytes; see `LoadOptions::max_line_length`.
+    pub fn max_line_length(mut self, n: usize) -> Self {
+        self.options.max_line_length = Some(n);
+        self
+    }
+
+    /// Keep only a sample of the rows; see `LoadOptions::sample()`.
+    pub fn sample(mut self, sample: Sample, seed: u64) -> Self {
+        self.options = self.options.sample(sample, seed);
+        self
+    }
+
+    /// Abort once more than `n` rows are rejected; see `LoadOptions::max_errors()`.
+    pub fn max_errors(mut self, n: usize) -> Self {
+        self.options = self.options.max_errors(n);
+        self
+    }
+
+    /// Report counts and timings to the metrics; see `LoadOptions::metrics`.
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.options.metrics = Some(metrics);
+        self
+    }
+
+    /// Take the field definitions and options.
+    pub(crate) fn into_parts(self) -> (&'a Vec<DataFieldDef<'a>>, LoadOptions) {
+        (self.row_defs, self.options)
+    }
+
+    /// Load and parse a file, as with `DataFile::try_load_with_options()`.
+    pub fn load(&self, path: &Path) -> DataFileResult<DataFile> {
+        DataFile::try_load_with_options(path, self.row_defs, &self.options)
+    }
+
+    /// Parse data held in memory, as with `DataFile::try_from_bytes()`.
+    pub fn load_bytes(&self, data: &[u8]) -> DataFileResult<DataFile> {
+        DataFile::try_from_bytes(data, self.row_defs, &self.options)
+    }
+
+    /// Parse text held in memory, as with `DataFile::try_from_str()`.
+    pub fn load_str(&self, data: &str) -> DataFileResult<DataFile> {
+        DataFile::try_from_str(data, self.row_defs, &self.options)
+    }
+
+    /// Open a file for streaming with the same options, rather than loading it all at once.
+    pub fn stream(self, path: &Path) -> DataFileResult<DataFileReader<'a, BufReader<File>>> {
+        DataFileReader::open(path, self.row_defs, self.options)
+    }
+}
+
+impl DataFile {
+    /// Start building a load with the provided field definitions; see `Loader`.
+    pub fn loader<'a>(row_defs: &'a Vec<DataFieldDef<'a>>) -> Loader<'a> {
+        Loader::new(row_defs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn builder_sets_options() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+        let data = format!("BANNER\n{:<200}\n\n# note\n{:<200}\n{:<200}\n", "0001", "0002", "0003");
+
+        let loader = DataFile::loader(&defs)
+            .skip_rows(1)
+            .max_rows(4)
+            .skip_blank_lines(true)
+            .comment_prefix("#");
+        assert_eq!(loader.load_options().skip_rows, 1);
+
+        let df = loader.load_str(&data).unwrap();
+        let ids: Vec<String> = df.iter().map(|r| r.get("Id").unwrap().data()).collect();
+        assert_eq!(ids, ["0001", "0002"]);
+        assert!(df.warnings().is_empty());
+
+        let df = DataFile::loader(&defs).load_str(&data).unwrap();
+        assert_eq!(df.rows().len(), 3);
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::Arc;
+use crate::trace;
+use crate::{Anchor, ColumnUnit, Continuation, ControlCharPolicy, ControlTotal, ExtraDataPolicy, InvalidBytePolicy, LoadSummary, Metrics,
+            PrintableAscii, RecordFormat};
+use crate::{AuditWriter, DataFieldDef, DataRow, DuplicateCheck, Quarantine};
+
+/// Options controlling how a DataFile is loaded.
+///
+/// The defaults process every line of the file, which matches the behavior of
+/// `DataFile::try_load()`.
+/// ```
+/// use ffreader::LoadOptions;
+/// let options = LoadOptions {
+///     skip_rows: 2,          // skip a two line banner
+///     max_rows: Some(1000),  // and only look at the next 1000 lines
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Number of lines at the start of the file to skip entirely (e.g., banners or headers).
+    /// Skipped lines do not generate warnings.
+    pub skip_rows: usize,
+    /// Maximum number of lines to process after skipping, or None to process the whole file.
+    pub max_rows: Option<usize>,
+    /// Silently skip lines that are empty or contain only whitespace instead of
+    /// reporting them as short rows.
+    pub skip_blank_lines: bool,
+    /// Skip lines starting with this prefix (e.g., `#` or `*`) instead of parsing them.
+    pub comment_prefix: Option<String>,
+    /// Record a LoadWarning for each blank or comment line skipped. These are informational
+    /// only; the lines are still skipped.
+    pub warn_on_skipped_lines: bool,
+    /// Attempt every field in a row rather than stopping at the first failure, so that
+    /// warnings describe everything wrong with the row.
+    pub collect_all_field_errors: bool,
+    /// How the file is divided into records; lines by default.
+    pub record_format: RecordFormat,
+    /// Join physical lines into logical records using a continuation indicator, if set.
+    pub continuation: Option<Continuation>,
+    /// Compute a SHA-256 hash of the file contents, available from `DataFile::source_hash()`.
+    pub compute_hash: bool,
+    /// Parse and keep only a sample of the rows, if set; see `sample()`.
+    pub sample: Option<Sample>,
+    /// Seed choosing which rows are sampled.
+    pub sample_seed: u64,
+    /// How field names are matched when looking up fields in the loaded rows.
+    pub name_matching: NameMatching,
+    /// The unit field indexes (and the minimum row length) are measured in. Bytes, and so
+    /// ASCII only, unless set.
+    pub columns: ColumnUnit,
+    /// What to do with bytes that aren't valid text in the column unit; the load fails unless
+    /// set. Use `DataFile::find_invalid_bytes()` to list them.
+    pub invalid_bytes: InvalidBytePolicy,
+    /// What to do with text beyond the last defined column of a row; dropped unless set.
+    pub extra_data: ExtraDataPolicy,
+    /// Pad rows shorter than the layout with this character, up to the end of the last
+    /// defined column (and at least the minimum row length), and record a
+    /// `WarningKind::PaddedRow` warning, so that rows whose trailing blank fields were trimmed
+    /// by the producer still load. Short rows are rejected unless set. End-anchored fields
+    /// are read from the end of the padded row.
+    pub pad_short_rows: Option<char>,
+    /// Abort the load with `DataFileError::TooManyErrors` once more than this many rows have
+    /// been rejected, if set; see `max_errors()`.
+    pub max_errors: Option<usize>,
+    /// Receives counts and timings as the load progresses, if set.
+    pub metrics: Option<Arc<dyn Metrics>>,
+    /// Keep the source line of each row, available from `DataRow::raw()`, e.g. to show the
+    /// untouched input next to parsed values in error reports. This roughly doubles the memory
+    /// held by each row, so it is off unless set.
+    pub keep_raw_lines: bool,
+    /// What to do with control characters (tabs, NULs and the like) inside fields; kept
+    /// unless set.
+    pub control_chars: ControlCharPolicy,
+    /// Check that rows hold only printable ASCII and the allowed characters, warning of (or
+    /// rejecting) others, if set; see PrintableAscii.
+    pub printable_ascii: Option<PrintableAscii>,
+    /// Record where each field was taken from: its source line, byte range and whether
+    /// post-processing altered it, available from `DataField::provenance()`, for data lineage
+    /// tooling. Off unless set, as it adds an allocation to each field.
+    pub provenance: bool,
+    /// Record every change made by post-processing, available from `DataFile::audit_log()`.
+    /// Rows streamed with DataFileReader aren't kept, so stream their changes with
+    /// `audit_writer` instead.
+    pub audit: bool,
+    /// Write every change made by post-processing to this writer as rows are loaded, if set;
+    /// see AuditWriter.
+    pub audit_writer: Option<AuditWriter>,
+    /// Collect rejected rows, with their raw text and the reasons for rejecting them, for
+    /// correction and re-ingestion, if set; see Quarantine.
+    pub quarantine: Option<Quarantine>,
+    /// Keep, skip or reject rows with the same key as an earlier row, if set; see
+    /// `on_duplicate()`.
+    pub duplicates: Option<DuplicateCheck>,
+    /// Totals in a trailer record to balance the loaded rows against; see ControlTotal.
+    /// Trailer lines are skipped rather than loaded.
+    pub control_totals: Vec<ControlTotal>,
+    /// Parse the records of a file loaded into a DataFile on this many threads, for a single
+    /// very large file. The file is split into records serially on the calling thread, and each
+    /// thread then parses a contiguous run of the records. Rows, warnings, quarantined rows and
+    /// audit entries are in file order, as when parsed on one thread. Unset (or 1) parses on
+    /// the calling thread; DataFileReader always does.
+    pub threads: Option<usize>,
+    /// The longest record accepted, in bytes, or None for `LoadOptions::DEFAULT_MAX_LINE_LENGTH`
+    /// (16 MiB). A longer record fails the load with `DataFileError::LineTooLong`, so that a file
+    /// whose line breaks are missing, or binary data, isn't taken as one enormous row.
+    /// DataFileReader stops reading a record at the limit rather than buffering all of it.
+    pub max_line_length: Option<usize>,
+}
+
+/// How field names given to `DataRow::get()`, `DataRow::get_ordered_fields()` and the exports
+/// are matched with the names in the field definitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameMatching {
+    /// Names must be identical.
+    #[default]
+    Exact,
+    /// Names match ignoring case, underscores and spaces, so `AccountNo`, `account_no` and
+    /// `ACCOUNT NO` are the same field. If several fields match, the first is used.
+    Normalized
+}
+
+impl NameMatching {
+    /// Determine whether a name given by the caller refers to the named field.
+    pub fn matches(&self, name: &str, field_name: &str) -> bool {
+        match self {
+            NameMatching::Exact => name == field_name,
+            NameMatching::Normalized => self.key(name) == self.key(field_name)
+        }
+    }
+
+    /// Get the form of a name that matching names share, for looking names up in a map.
+    pub(crate) fn key(&self, name: &str) -> String {
+        match self {
+            NameMatching::Exact => name.to_string(),
+            NameMatching::Normalized => name.chars()
+                .filter(|c| *c != '_' && *c != ' ')
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        }
+    }
+}
+
+/// How many rows to keep when sampling a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample {
+    /// Keep approximately this fraction of rows (between 0 and 1), chosen pseudo-randomly.
+    Fraction(f64),
+    /// Keep one row in every N.
+    EveryNth(usize)
+}
+
+impl LoadOptions {
+    /// The longest record accepted unless `max_line_length` is set.
+    pub const DEFAULT_MAX_LINE_LENGTH: usize = 16 * 1024 * 1024;
+
+    /// Get the longest record accepted, in bytes.
+    pub(crate) fn line_limit(&self) -> usize {
+        self.max_line_length.unwrap_or(Self::DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Parse and keep only a deterministic sample of the rows, which makes it cheap to profile
+    /// or inspect a very large file. Lines not sampled are counted as skipped but not reported.
+    ///
+    /// The same seed selects the same lines on every load, whether the file is loaded as a
+    /// DataFile or streamed. With `EveryNth`, the seed picks which line of each N is kept.
+    /// ```
+    /// use ffreader::{LoadOptions, Sample};
+    /// let options = LoadOptions { skip_rows: 1, ..Default::default() }.sample(Sample::Fraction(0.01), 42);
+    /// ```
+    pub fn sample(mut self, sample: Sample, seed: u64) -> Self {
+        self.sample = Some(sample);
+        self.sample_seed = seed;
+        self
+    }
+
+    /// Abort the load once more than `n` rows have been rejected, a safety valve against a file
+    /// with the wrong layout entirely. Rows kept with a fallback value by their FieldErrorPolicy
+    /// don't count.
+    /// ```
+    /// use ffreader::LoadOptions;
+    /// let options = LoadOptions::default().max_errors(100);
+    /// ```
+    pub fn max_errors(mut self, n: usize) -> Self {
+        self.max_errors = Some(n);
+        self
+    }
+
+    /// Report that the load has finished to the metrics and tracing, if enabled.
+    pub(crate) fn finished(&self, summary: &LoadSummary) {
+        trace::finished(summary);
+        if let Some(metrics) = &self.metrics {
+            metrics.load_finished(summary);
+        }
+    }
+
+    /// Report bytes read to the metrics, if set.
+    pub(crate) fn bytes_read(&self, bytes: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_read(bytes);
+        }
+    }
+
+    /// Determine whether the line at the given index is included in the sample.
+    pub(crate) fn is_sampled(&self, line_index: usize) -> bool {
+        match self.sample {
+            None => true,
+            Some(Sample::EveryNth(n)) => n <= 1 || line_index % n == (self.sample_seed % n as u64) as usize,
+            Some(Sample::Fraction(fraction)) => {
+                // hashed, so that each line is chosen independently of the others
+                let z = splitmix64(self.sample_seed.wrapping_add((line_index as u64).wrapping_mul(0x9E3779B97F4A7C15)));
+                ((z >> 11) as f64 / (1u64 << 53) as f64) < fraction
+            }
+        }
+    }
+
+    /// Pad a row shorter than the layout with `pad_short_rows`, if set, returning the padded row
+    /// and its original length.
+    pub(crate) fn pad_short_row(&self, row: &str, row_defs: &[DataFieldDef]) -> Option<(String, usize)> {
+        let pad = self.pad_short_rows?;
+        let layout_end = row_defs.iter()
+            .filter(|d| d.anchor == Anchor::Start)
+            .map(|d| d.end_idx)
+            .max()
+            .unwrap_or(0)
+            .max(DataRow::MINIMUM_LENGTH);
+        let len = self.columns.len(row);
+        (len < layout_end).then(|| {
+            let mut padded = row.to_string();
+            padded.extend(std::iter::repeat_n(pad, layout_end - len));
+            (padded, len)
+        })
+    }
+
+    /// Determine whether a line should be skipped as blank or as a comment, returning
+    /// a description of the reason if so.
+    pub(crate) fn skip_reason(&self, line: &str) -> Option<&'static str> {
+        if self.skip_blank_lines && line.trim().is_empty() {
+            return Some("Skipped blank line");
+        }
+
+        if self.control_totals.iter().any(|c| c.is_trailer(line)) {
+            return Some("Skipped trailer line");
+        }
+
+        match &self.comment_prefix {
+            Some(prefix) if !prefix.is_empty() && line.starts_with(prefix.as_str()) => Some("Skipped comment line"),
+            _ => None
+        }
+    }
+}
+
+/// Mix a 64-bit value with the splitmix64 finalizer, for cheap deterministic pseudo-randomness.
+pub(crate) fn splitmix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
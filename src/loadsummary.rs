@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+use crate::{LoadWarning, WarningKind};
+
+/// Counts and timing describing how a DataFile load went, suitable for a one line log entry.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadSummary {
+    /// Number of lines examined (after any `skip_rows`/`max_rows` limits were applied).
+    pub rows_read: usize,
+    /// Number of rows successfully loaded.
+    pub rows_loaded: usize,
+    /// Number of blank or comment lines skipped, along with lines left out of a sample.
+    pub rows_skipped: usize,
+    /// Number of rows rejected because of errors.
+    pub rows_rejected: usize,
+    /// Count of warnings generated, by kind.
+    pub warnings_by_kind: BTreeMap<WarningKind, usize>,
+    /// Time taken by the load, including reading the file.
+    pub elapsed: Duration
+}
+
+impl LoadSummary {
+    /// Tally the warnings provided into `warnings_by_kind`.
+    pub(crate) fn count_warnings(&mut self, warnings: &[LoadWarning]) {
+        self.warnings_by_kind.clear();
+        self.add_warnings(warnings);
+    }
+
+    /// Add the warnings provided to the tally in `warnings_by_kind`.
+    pub(crate) fn add_warnings(&mut self, warnings: &[LoadWarning]) {
+        for w in warnings {
+            *self.warnings_by_kind.entry(w.kind()).or_insert(0) += 1;
+        }
+    }
+
+    /// Total number of warnings of all kinds.
+    pub fn warning_count(&self) -> usize {
+        self.warnings_by_kind.values().sum()
+    }
+}
+
+/// Times a load. There is no clock in `wasm32-unknown-unknown`, so there the elapsed time is
+/// always zero.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: Instant
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: Instant::now()
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.start.elapsed();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return Duration::ZERO;
+    }
+}
+
+impl Display for LoadSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} rows read, {} loaded, {} skipped, {} rejected, {} warnings",
+               self.rows_read, self.rows_loaded, self.rows_skipped, self.rows_rejected, self.warning_count())?;
+        if !self.warnings_by_kind.is_empty() {
+            let kinds = self.warnings_by_kind.iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .collect::<Vec<String>>();
+            write!(f, " ({})", kinds.join(", "))?;
+        }
+        write!(f, " in {:.3?}", self.elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_is_one_line() {
+        let mut summary = LoadSummary {
+            rows_read: 10,
+            rows_loaded: 7,
+            rows_skipped: 1,
+            rows_rejected: 2,
+            elapsed: Duration::from_millis(5),
+            ..Default::default()
+        };
+        summary.count_warnings(&[
+            LoadWarning::new(0, Box::new("a")),
+            LoadWarning::new(1, Box::new("b")),
+        ]);
+        assert_eq!(summary.to_string(), "10 rows read, 7 loaded, 1 skipped, 2 rejected, 2 warnings (Other: 2) in 5.000ms");
+    }
+}
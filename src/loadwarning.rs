@@ -1,25 +1,385 @@
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
+use crate::{DataFieldError, DataRowError, InvalidByte, LoadSummary};
+use crate::format::hex_dump;
+use crate::printable::describe_unprintable;
+
+/// The general category of a LoadWarning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    /// A blank or comment line was skipped (informational).
+    SkippedLine,
+    /// A row was rejected because of its length.
+    BadRowLength,
+    /// A row was rejected because a field failed.
+    FieldError,
+    /// A row was rejected because several fields failed.
+    MultipleFieldErrors,
+    /// A field failed but was given a fallback value by its FieldErrorPolicy; the row was kept.
+    FieldDowngraded,
+    /// A post-processing function accepted a field's value but reported a warning about it.
+    FieldWarning,
+    /// A field's value filled its width with no padding, so it may have been cut off.
+    PossibleTruncation,
+    /// Continuation lines did not fit together as expected.
+    ContinuationMismatch,
+    /// A row re-rendered by the fixed-width writer did not match its source line.
+    RoundTripMismatch,
+    /// Bytes that aren't valid text were replaced or stripped from a line.
+    InvalidBytes,
+    /// A row had text beyond the last defined column.
+    ExtraData,
+    /// A row shorter than the layout was padded to its length.
+    PaddedRow,
+    /// The loaded rows did not balance against a control total in the trailer.
+    ControlTotalMismatch,
+    /// Control characters in a field were stripped or replaced; see ControlCharPolicy.
+    ControlCharacters,
+    /// No rows were loaded, and none rejected: the file was empty, held only the lines
+    /// skipped by `skip_rows`, or every line was skipped (informational).
+    NoRows,
+    /// A record matched none of the layouts of a GroupAssembler, or was a child record with no
+    /// parent to belong to; it was left out.
+    UngroupedRecord,
+    /// Characters outside printable ASCII were found in a row; see PrintableAscii.
+    Unprintable,
+    /// A row had the same key as an earlier row; see `LoadOptions::on_duplicate()`.
+    DuplicateRow,
+    /// Any other warning.
+    Other
+}
+
+impl WarningKind {
+    /// Every kind. Saved DataFiles refer to kinds by their position here, so new kinds go at
+    /// the end.
+    pub(crate) const ALL: [WarningKind; 19] = [
+        WarningKind::SkippedLine, WarningKind::BadRowLength, WarningKind::FieldError,
+        WarningKind::MultipleFieldErrors, WarningKind::FieldDowngraded, WarningKind::FieldWarning,
+        WarningKind::PossibleTruncation, WarningKind::ContinuationMismatch, WarningKind::RoundTripMismatch,
+        WarningKind::InvalidBytes, WarningKind::ExtraData, WarningKind::PaddedRow,
+        WarningKind::ControlTotalMismatch, WarningKind::ControlCharacters, WarningKind::Other,
+        WarningKind::NoRows, WarningKind::UngroupedRecord, WarningKind::Unprintable,
+        WarningKind::DuplicateRow
+    ];
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            WarningKind::SkippedLine => "SkippedLine",
+            WarningKind::BadRowLength => "BadRowLength",
+            WarningKind::FieldError => "FieldError",
+            WarningKind::MultipleFieldErrors => "MultipleFieldErrors",
+            WarningKind::FieldDowngraded => "FieldDowngraded",
+            WarningKind::FieldWarning => "FieldWarning",
+            WarningKind::PossibleTruncation => "PossibleTruncation",
+            WarningKind::ContinuationMismatch => "ContinuationMismatch",
+            WarningKind::RoundTripMismatch => "RoundTripMismatch",
+            WarningKind::InvalidBytes => "InvalidBytes",
+            WarningKind::ExtraData => "ExtraData",
+            WarningKind::PaddedRow => "PaddedRow",
+            WarningKind::ControlTotalMismatch => "ControlTotalMismatch",
+            WarningKind::ControlCharacters => "ControlCharacters",
+            WarningKind::NoRows => "NoRows",
+            WarningKind::UngroupedRecord => "UngroupedRecord",
+            WarningKind::Unprintable => "Unprintable",
+            WarningKind::DuplicateRow => "DuplicateRow",
+            WarningKind::Other => "Other"
+        };
+        write!(f, "{}", s)
+    }
+}
 
 /// Simple structure for storing a single warning/error and displaying it.
 pub struct LoadWarning {
     line_index: usize,
-    message: Box<dyn Display>
+    kind: WarningKind,
+    field_name: Option<String>,
+    raw: Option<String>,
+    raw_column: usize,
+    rejected: bool,
+    message: Box<dyn Display + Send + Sync>
 }
 
 impl LoadWarning {
     /// Instantiates a new LoadWarning
     /// A LoadWarning simply contains the line number of the problem and a message
     /// describing the issue.
-    pub fn new(line_index: usize, message: Box<dyn Display>) -> LoadWarning {
+    pub fn new(line_index: usize, message: Box<dyn Display + Send + Sync>) -> LoadWarning {
         LoadWarning {
             line_index,
+            kind: WarningKind::Other,
+            field_name: None,
+            raw: None,
+            raw_column: 0,
+            rejected: false,
             message
         }
     }
+
+    /// Recreate a warning from its parts, e.g. when reading a saved DataFile; the message is
+    /// kept as text.
+    pub(crate) fn from_parts(line_index: usize, kind: WarningKind, field_name: Option<String>, raw: Option<String>,
+                             raw_column: usize, rejected: bool, message: String) -> LoadWarning {
+        LoadWarning { line_index, kind, field_name, raw, raw_column, rejected, message: Box::new(message) }
+    }
+
+    /// Get the column of the line the raw text starts at.
+    pub(crate) fn raw_column(&self) -> usize {
+        self.raw_column
+    }
+
+    /// Create a warning for a load that gave no rows without rejecting any, saying why: the
+    /// data was empty, there was nothing after the `skip_rows` lines, or every line was
+    /// skipped. None if any row was loaded or rejected.
+    pub(crate) fn no_rows(empty: bool, summary: &LoadSummary, skip_rows: usize) -> Option<LoadWarning> {
+        if summary.rows_loaded > 0 || summary.rows_rejected > 0 {
+            return None;
+        }
+        let message = match (empty, summary.rows_read) {
+            (true, _) => "Empty file".to_string(),
+            (false, 0) if skip_rows > 0 => format!("No lines after the {} skipped by skip_rows", skip_rows),
+            (false, 0) => "No lines read".to_string(),
+            (false, n) => format!("All {} lines were skipped", n)
+        };
+        Some(LoadWarning { kind: WarningKind::NoRows, ..Self::new(0, Box::new(message)) })
+    }
+
+    /// Create a warning for a record left out of the groups assembled by a GroupAssembler.
+    pub(crate) fn ungrouped_record(line_index: usize, line: &str, reason: &'static str) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::UngroupedRecord,
+            raw: Some(line.to_string()),
+            rejected: true,
+            ..Self::new(line_index, Box::new(reason))
+        }
+    }
+
+    /// Create a warning for a line skipped as blank or comment.
+    pub(crate) fn skipped_line(line_index: usize, line: &str, reason: &'static str) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::SkippedLine,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(reason))
+        }
+    }
+
+    /// Create a warning for a row that could not be loaded.
+    pub(crate) fn row_error(line_index: usize, line: &str, error: DataRowError) -> LoadWarning {
+        let raw_column = match &error {
+            DataRowError::FieldError(fe) => fe.context().map_or(0, |c| c.start_idx),
+            DataRowError::ExtraData(c, _) => *c,
+            _ => 0
+        };
+        let (kind, field_name, raw) = match &error {
+            DataRowError::FieldError(fe) => (WarningKind::FieldError,
+                                             fe.context().map(|c| c.name.clone()),
+                                             fe.context().map(|c| c.raw.clone())),
+            DataRowError::BadRowLength(_) => (WarningKind::BadRowLength, None, Some(line.to_string())),
+            DataRowError::MultipleFieldErrors(_) => (WarningKind::MultipleFieldErrors, None, Some(line.to_string())),
+            DataRowError::FieldNameNotFound(n) => (WarningKind::Other, Some(n.clone()), None),
+            DataRowError::InvalidByte(_) => (WarningKind::InvalidBytes, None, Some(line.to_string())),
+            DataRowError::ExtraData(_, t) => (WarningKind::ExtraData, None, Some(t.clone())),
+            DataRowError::Unprintable(n, _) => (WarningKind::Unprintable, n.clone(), Some(line.to_string())),
+            DataRowError::DuplicateRow(_, _) => (WarningKind::DuplicateRow, None, Some(line.to_string()))
+        };
+
+        LoadWarning {
+            kind,
+            field_name,
+            raw,
+            raw_column,
+            rejected: true,
+            ..Self::new(line_index, Box::new(error))
+        }
+    }
+
+    /// Create a warning for a field that failed but was kept with a fallback value, or
+    /// whose post-processing function reported a warning, or which may have been truncated.
+    pub(crate) fn field_downgraded(line_index: usize, error: DataFieldError) -> LoadWarning {
+        let kind = match &error {
+            DataFieldError::PossibleTruncation(_) => WarningKind::PossibleTruncation,
+            DataFieldError::ControlCharacters(_, _) => WarningKind::ControlCharacters,
+            e if e.warning_value().is_some() => WarningKind::FieldWarning,
+            _ => WarningKind::FieldDowngraded
+        };
+        LoadWarning {
+            kind,
+            field_name: error.context().map(|c| c.name.clone()),
+            raw: error.context().map(|c| c.raw.clone()),
+            raw_column: error.context().map_or(0, |c| c.start_idx),
+            ..Self::new(line_index, Box::new(error))
+        }
+    }
+
+    /// Create a warning for continuation lines that did not fit together.
+    pub(crate) fn continuation_mismatch(line_index: usize, line: &str, message: String) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::ContinuationMismatch,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(message))
+        }
+    }
+
+    /// Create a warning for a row that did not survive re-rendering unchanged.
+    pub(crate) fn roundtrip_mismatch(line_index: usize, line: &str, message: String) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::RoundTripMismatch,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(message))
+        }
+    }
+
+    /// Create a warning for a line whose invalid bytes were replaced (or stripped, if not).
+    pub(crate) fn invalid_bytes(line_index: usize, line: &str, bytes: &[InvalidByte], replaced: bool) -> LoadWarning {
+        let positions = bytes.iter()
+            .map(|b| format!("column {} (0x{:02X})", b.column, b.value))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let action = if replaced { "Replaced" } else { "Stripped" };
+        LoadWarning {
+            kind: WarningKind::InvalidBytes,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(format!("{} {} invalid bytes: {}", action, bytes.len(), positions)))
+        }
+    }
+
+    /// Create a warning for a row kept despite text beyond the last defined column, given the
+    /// column it starts at and the text.
+    pub(crate) fn extra_data(line_index: usize, column: usize, text: String) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::ExtraData,
+            raw: Some(text.clone()),
+            raw_column: column,
+            ..Self::new(line_index, Box::new(format!("Extra data at column {}: \"{}\"", column, text)))
+        }
+    }
+
+    /// Create a warning for characters outside printable ASCII in a row that was kept, given
+    /// the field holding them (None if outside every field), its raw text (or the line) and the
+    /// column it starts at, and the column and character of each.
+    pub(crate) fn unprintable(line_index: usize, field_name: Option<String>, raw: &str, raw_column: usize,
+                              chars: &[(usize, char)]) -> LoadWarning {
+        let place = match &field_name {
+            Some(name) => format!("in {}", name),
+            None => "outside any field".to_string()
+        };
+        let message = format!("Unprintable characters {} at {}", place, describe_unprintable(chars));
+        LoadWarning {
+            kind: WarningKind::Unprintable,
+            field_name,
+            raw: Some(raw.to_string()),
+            raw_column,
+            ..Self::new(line_index, Box::new(message))
+        }
+    }
+
+    /// Create a warning for a row kept or skipped as a duplicate, given what was done with it,
+    /// the index of the line its key was first seen on, and the key.
+    pub(crate) fn duplicate_row(line_index: usize, line: &str, action: &str, first: usize, key: &str) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::DuplicateRow,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(format!("{} of line {} ({})", action, first + 1, key)))
+        }
+    }
+
+    /// Create a warning for a short row that was padded, given its original length.
+    pub(crate) fn padded_row(line_index: usize, line: &str, len: usize, padded_len: usize) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::PaddedRow,
+            raw: Some(line.to_string()),
+            ..Self::new(line_index, Box::new(format!("Padded short row from {} to {} columns", len, padded_len)))
+        }
+    }
+
+    /// Create a warning for a control total that did not balance, given the trailer line (empty
+    /// if there is none).
+    pub(crate) fn control_total_mismatch(line_index: usize, line: &str, message: String) -> LoadWarning {
+        LoadWarning {
+            kind: WarningKind::ControlTotalMismatch,
+            raw: (!line.is_empty()).then(|| line.to_string()),
+            ..Self::new(line_index, Box::new(message))
+        }
+    }
+
+    /// Get the 0-based index of the line the warning refers to.
+    pub fn line_index(&self) -> usize {
+        self.line_index
+    }
+
+    /// Get the 1-based line number the warning refers to, as shown when displayed.
+    pub fn line_number(&self) -> usize {
+        self.line_index + 1
+    }
+
+    /// Get the kind of warning.
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /// Determine whether the warning records a row that was rejected, rather than a problem
+    /// with a row that was kept or a line that was skipped.
+    pub fn is_error(&self) -> bool {
+        self.rejected
+    }
+
+    /// Get the name of the field involved, if the warning pertains to a single field.
+    pub fn field_name(&self) -> Option<&str> {
+        self.field_name.as_deref()
+    }
+
+    /// Get the raw text involved: the field's text for field warnings, otherwise the line.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Get a hex and ASCII dump of the raw text involved, if any, to diagnose invisible
+    /// characters such as tabs, NULs or stray control codes. The dump is bounded to the first
+    /// 128 bytes; offsets are columns of the line, starting from the field's start index for
+    /// field warnings.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let df = DataFile::try_from_str("short\tline\n", &defs, &LoadOptions::default()).unwrap();
+    /// assert_eq!(df.warnings()[0].hex_dump().unwrap(),
+    ///            "00000000  73 68 6f 72 74 09 6c 69 6e 65                    |short.line|");
+    /// ```
+    pub fn hex_dump(&self) -> Option<String> {
+        self.raw.as_ref().map(|r| hex_dump(r.as_bytes(), self.raw_column))
+    }
+
+    /// Get the message describing the issue.
+    pub fn message(&self) -> String {
+        self.message.to_string()
+    }
+}
+
+impl Clone for LoadWarning {
+    /// Clones the warning; the message is captured as text.
+    fn clone(&self) -> Self {
+        LoadWarning {
+            line_index: self.line_index,
+            kind: self.kind,
+            field_name: self.field_name.clone(),
+            raw: self.raw.clone(),
+            raw_column: self.raw_column,
+            rejected: self.rejected,
+            message: Box::new(self.message())
+        }
+    }
 }
 
 impl Display for LoadWarning {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "Line {} {}", self.line_index + 1, self.message)
     }
-}
\ No newline at end of file
+}
+
+impl Debug for LoadWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::{Debug, Formatter};
+use crate::{LoadSummary, WarningKind};
+
+/// Receives counts and timings as files are loaded, for reporting ingestion health to a
+/// metrics system such as Prometheus. Set one with `LoadOptions::metrics`; every method does
+/// nothing unless implemented.
+///
+/// Calls are made as the load progresses, from the thread doing the loading, so
+/// implementations should be cheap (e.g., incrementing atomic counters).
+/// ```
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions, Metrics};
+/// #[derive(Default)]
+/// struct RowCounter(AtomicUsize);
+/// impl Metrics for RowCounter {
+///     fn row_loaded(&self) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+/// let counter = Arc::new(RowCounter::default());
+/// let options = LoadOptions { metrics: Some(counter.clone()), ..Default::default() };
+/// DataFile::try_from_str(&format!("{:<200}\n", "5412345678"), &defs, &options).unwrap();
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+/// ```
+pub trait Metrics: Send + Sync {
+    /// Called with the number of bytes read from the input: once for a whole file loaded into
+    /// memory, or for each record as a file is streamed.
+    fn bytes_read(&self, _bytes: usize) {}
+
+    /// Called for each row parsed and kept.
+    fn row_loaded(&self) {}
+
+    /// Called for each row rejected, with the kind of warning recorded for it.
+    fn row_rejected(&self, _kind: WarningKind) {}
+
+    /// Called once the load has finished, with its summary, which includes the time taken.
+    /// Not called when the load fails.
+    fn load_finished(&self, _summary: &LoadSummary) {}
+}
+
+impl Debug for dyn Metrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Metrics")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use crate::{DataFile, DataFileReader, DataFieldDef, DataFieldResult, LoadOptions};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<(usize, usize, Vec<WarningKind>, usize)>);
+
+    impl Metrics for Recorder {
+        fn bytes_read(&self, bytes: usize) { self.0.lock().unwrap().0 += bytes; }
+        fn row_loaded(&self) { self.0.lock().unwrap().1 += 1; }
+        fn row_rejected(&self, kind: WarningKind) { self.0.lock().unwrap().2.push(kind); }
+        fn load_finished(&self, summary: &LoadSummary) { self.0.lock().unwrap().3 += summary.rows_read; }
+    }
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn metrics_reported() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("{:<200}\nshort\n{:<200}\n", "001", "002");
+        for streamed in [false, true] {
+            let recorder = Arc::new(Recorder::default());
+            let options = LoadOptions { metrics: Some(recorder.clone()), ..Default::default() };
+            if streamed {
+                DataFileReader::new(Cursor::new(&data), &defs, options).for_each(drop);
+            } else {
+                DataFile::try_from_str(&data, &defs, &options).unwrap();
+            }
+            let recorded = recorder.0.lock().unwrap();
+            assert_eq!(*recorded, (data.len(), 2, vec![WarningKind::BadRowLength], 3));
+        }
+    }
+}
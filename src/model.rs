@@ -0,0 +1,92 @@
+use crate::{DataFieldDef, DataRow, DataRowResult};
+#[cfg(feature = "derive")]
+use std::fmt::Display;
+#[cfg(feature = "derive")]
+use std::str::FromStr;
+#[cfg(feature = "derive")]
+use crate::{DataFieldError, DataRowError};
+
+/// A struct read from the rows of a fixed-width file, whose type declares its own layout, so
+/// that the layout and the model are kept in one place.
+///
+/// With the `derive` feature, `#[derive(FixedWidthRecord)]` implements it from a
+/// `#[field(start = .., end = ..)]` attribute on each struct field, with an optional
+/// `name = ".."` for the field name (the struct field's name by default). Values are
+/// converted with `FromStr`; an `Option` field is None when the value is blank.
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// use ffreader::{DataFile, FixedWidthRecord, LoadOptions};
+/// #[derive(FixedWidthRecord)]
+/// struct Account {
+///     #[field(start = 0, end = 11, name = "AccountNo1")]
+///     account_no: String,
+///     #[field(start = 11, end = 16)]
+///     balance: u32,
+///     #[field(start = 16, end = 23)]
+///     status: Option<String>,
+/// }
+/// let defs = Account::field_defs();
+/// let data = format!("{:<200}", "5412345678 00042OPEN");
+/// let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+/// let account = Account::from_row(&df.rows()[0]).unwrap();
+/// assert_eq!((account.account_no.as_str(), account.balance), ("5412345678", 42));
+/// assert_eq!(account.status.as_deref(), Some("OPEN"));
+/// # }
+/// ```
+pub trait FixedWidthRecord: Sized {
+    /// Get the field definitions of the layout.
+    fn field_defs() -> Vec<DataFieldDef<'static>>;
+
+    /// Build a value from a row loaded with the layout's field definitions. Fails if a field
+    /// is not found or its value can't be converted.
+    fn from_row(row: &DataRow) -> DataRowResult<Self>;
+}
+
+/// Convert the value of a field of a row for a derived `FixedWidthRecord`.
+#[cfg(feature = "derive")]
+pub fn parse<T: FromStr>(row: &DataRow, name: &str) -> DataRowResult<T> where T::Err: Display {
+    let field = row.get(name).ok_or_else(|| DataRowError::FieldNameNotFound(name.to_string()))?;
+    field.data_ref().parse().map_err(|e| {
+        let message = format!("Field {} value \"{}\" could not be converted: {}", name, field.data_ref(), e);
+        DataRowError::FieldError(DataFieldError::Problem(Box::new(message)))
+    })
+}
+
+/// Convert the value of a field of a row for a derived `FixedWidthRecord`, None if it is blank.
+#[cfg(feature = "derive")]
+pub fn parse_optional<T: FromStr>(row: &DataRow, name: &str) -> DataRowResult<Option<T>> where T::Err: Display {
+    match row.get(name) {
+        Some(field) if field.data_ref().is_empty() => Ok(None),
+        _ => parse(row, name).map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::{DataFile, FixedWidthRecord, LoadOptions};
+
+    #[derive(FixedWidthRecord)]
+    struct Reading {
+        #[field(start = 0, end = 4, name = "MeterId")]
+        meter_id: String,
+        #[field(start = 4, end = 10)]
+        usage: i64,
+        #[field(start = 10, end = 12)]
+        flag: Option<char>
+    }
+
+    #[test]
+    fn records_derived_from_struct() {
+        let defs = Reading::field_defs();
+        assert_eq!(defs.iter().map(|d| (d.name.as_str(), d.start_idx, d.end_idx)).collect::<Vec<_>>(),
+                   [("MeterId", 0, 4), ("usage", 4, 10), ("flag", 10, 12)]);
+
+        let data = format!("{:<200}\n{:<200}\n{:<200}\n", "M001000120E", "M002000007", "M003  12XY");
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        let readings: Vec<_> = df.iter().map(Reading::from_row).collect();
+        let first = readings[0].as_ref().unwrap();
+        assert_eq!((first.meter_id.as_str(), first.usage, first.flag), ("M001", 120, Some('E')));
+        assert_eq!(readings[1].as_ref().unwrap().flag, None);
+        assert!(readings[2].is_err());
+    }
+}
@@ -0,0 +1,173 @@
+/// Where the sign of a number may be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignPosition {
+    /// Before or after the digits, e.g. `-123` or `123-` (the default).
+    #[default]
+    Either,
+    /// Only before the digits.
+    Leading,
+    /// Only after the digits, as is common in mainframe extracts.
+    Trailing
+}
+
+/// How the numbers of a field are written, for files using a decimal comma, thousands
+/// separators or a fixed sign position; see `DataFieldDef::with_number_format()`. Values are
+/// read into plain form, a leading `-` (if negative), the digits and a `.` before any decimal
+/// places, so that aggregates, control totals and exports all handle them. Blank values are
+/// left blank, and others that aren't numbers fail the field with `DataFieldError::NotANumber`.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldResult, DataFile, LoadOptions, NumberFormat, SignPosition};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("Amount", 0, 12, &post_function)
+///         .with_number_format(NumberFormat::european().with_sign(SignPosition::Trailing)),
+/// ];
+/// let df = DataFile::try_from_str(&format!("{:<200}\n", "  1.234,56-"), &defs, &LoadOptions::default()).unwrap();
+/// assert_eq!(df.rows()[0].get("Amount").unwrap().data(), "-1234.56");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The character before the decimal places.
+    pub decimal: char,
+    /// The character separating groups of digits before the decimal places, if any is used.
+    /// It must differ from `decimal`.
+    pub grouping: Option<char>,
+    /// Where the sign may be written.
+    pub sign: SignPosition
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat::new()
+    }
+}
+
+impl NumberFormat {
+    /// A decimal point, no grouping, and a sign before or after the digits.
+    pub fn new() -> NumberFormat {
+        NumberFormat { decimal: '.', grouping: None, sign: SignPosition::Either }
+    }
+
+    /// A decimal comma, with `.` separating thousands, e.g. `1.234,56`.
+    pub fn european() -> NumberFormat {
+        NumberFormat { decimal: ',', grouping: Some('.'), sign: SignPosition::Either }
+    }
+
+    /// Set the character before the decimal places.
+    pub fn with_decimal(mut self, decimal: char) -> Self {
+        self.decimal = decimal;
+        self
+    }
+
+    /// Set the character separating groups of digits, e.g. `,`, `.`, `'` or a space.
+    pub fn with_grouping(mut self, grouping: char) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// Set where the sign may be written.
+    pub fn with_sign(mut self, sign: SignPosition) -> Self {
+        self.sign = sign;
+        self
+    }
+
+    /// Read a trimmed value into plain form; None if it isn't a number written this way.
+    pub fn normalize(&self, value: &str) -> Option<String> {
+        if value.is_empty() {
+            return Some(String::new());
+        }
+        let sign = |c: char| c == '-' || c == '+';
+        let (sign, digits) = match self.sign {
+            SignPosition::Leading | SignPosition::Either if value.starts_with(sign) => (value.chars().next(), &value[1..]),
+            SignPosition::Trailing | SignPosition::Either if value.ends_with(sign) => (value.chars().last(), &value[..value.len() - 1]),
+            _ => (None, value)
+        };
+        let digits = digits.trim();
+
+        let mut plain = String::with_capacity(digits.len() + 1);
+        if sign == Some('-') {
+            plain.push('-');
+        }
+        let mut decimals = false;
+        for (i, c) in digits.chars().enumerate() {
+            match c {
+                c if c.is_ascii_digit() => plain.push(c),
+                c if c == self.decimal && !decimals => {
+                    decimals = true;
+                    plain.push('.');
+                }
+                c if Some(c) == self.grouping && !decimals && i > 0 => {}
+                _ => return None
+            }
+        }
+        plain.bytes().any(|b| b.is_ascii_digit()).then_some(plain)
+    }
+
+    /// The name of a sign position in schema documents.
+    pub(crate) fn sign_name(&self) -> &'static str {
+        match self.sign {
+            SignPosition::Either => "either",
+            SignPosition::Leading => "leading",
+            SignPosition::Trailing => "trailing"
+        }
+    }
+
+    /// Get the sign position with the given name in schema documents.
+    pub(crate) fn sign_from_name(name: &str) -> Option<SignPosition> {
+        match name {
+            "either" => Some(SignPosition::Either),
+            "leading" => Some(SignPosition::Leading),
+            "trailing" => Some(SignPosition::Trailing),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldDef, DataFieldError, DataFieldResult, DataFile, DataRowError, FieldErrorPolicy, LoadOptions,
+                RowParser, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn locale_numbers_normalized() {
+        let european = NumberFormat::european();
+        assert_eq!(european.normalize("1.234.567,89").as_deref(), Some("1234567.89"));
+        assert_eq!(european.normalize("-0,5").as_deref(), Some("-0.5"));
+        assert_eq!(european.normalize("12+").as_deref(), Some("12"));
+        assert_eq!(european.normalize("").as_deref(), Some(""));
+        assert_eq!(european.normalize("1,234.56"), None);
+        assert_eq!(european.normalize(".123"), None);
+        assert_eq!(european.normalize("-"), None);
+        let swiss = NumberFormat::new().with_grouping('\'').with_sign(SignPosition::Leading);
+        assert_eq!(swiss.normalize("- 1'000.25").as_deref(), Some("-1000.25"));
+        assert_eq!(swiss.normalize("1000.25-"), None);
+
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &echo_ok),
+            DataFieldDef::new("Amount", 4, 16, &echo_ok).with_number_format(european).with_implied_decimals(2),
+            DataFieldDef::new("Rate", 16, 22, &echo_ok).with_number_format(NumberFormat::new().with_decimal(','))
+                .with_on_error(FieldErrorPolicy::WarnAndBlank),
+        ];
+        let data = format!("{:<200}\n{:<200}\n{:<200}\n", "0001   1.234,5-  0,25", "0002 12.345     1.5", "0003 12,3,4");
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.rows()[0].get("Amount").unwrap().data(), "-1234.5");
+        assert_eq!(df.rows()[0].get("Rate").unwrap().data(), "0.25");
+        assert_eq!(df.rows()[1].get("Amount").unwrap().data(), "12345");
+        assert_eq!(df.rows()[1].get("Rate").unwrap().data(), "");
+        assert_eq!(df.warnings()[0].kind(), WarningKind::FieldDowngraded);
+        assert_eq!(df.warnings()[1].field_name(), Some("Amount"));
+        assert_eq!(df.column_sum("Amount").value, Some(-1234.5 + 123.45));
+
+        let parser = RowParser::new(&defs, LoadOptions::default()).unwrap();
+        let mut buf = parser.buffer();
+        parser.parse_into(&mut buf, &format!("{:<200}", "0001   1.234,5-  0,25")).unwrap();
+        assert!(matches!(parser.parse_into(&mut buf, &format!("{:<200}", "0003 12,3,4")),
+                         Err(DataRowError::FieldError(DataFieldError::NotANumber(_)))));
+    }
+}
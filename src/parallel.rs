@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::{DataFieldDef, DataRow, LoadOptions, LoadSummary, LoadWarning, Quarantine};
+use crate::datafile::load_record;
+use crate::records::IndexedRecords;
+
+/// The rows, warnings and counts of the records parsed by a thread, with the rows it rejected.
+type ChunkResult = (Vec<DataRow>, Vec<LoadWarning>, LoadSummary, Option<Quarantine>);
+
+/// Parse records on several threads, returning the rows and warnings in record order with the
+/// counts of rows skipped and rejected. The records have already been split from the file on
+/// the calling thread; each thread parses a contiguous run of them.
+///
+/// The rows a thread rejects are quarantined, and the changes to the rows it loads written by
+/// the audit writer, once every thread has finished and in record order, as on one thread.
+///
+/// Once more rows have been rejected across the runs than `LoadOptions::max_errors` allows,
+/// every thread stops, so that a file with the wrong layout fails about as quickly as it would
+/// on one thread; the caller reports the error.
+pub(crate) fn load_records_parallel(lines: &IndexedRecords, row_defs: &Vec<DataFieldDef>, options: &LoadOptions,
+                                    threads: usize) -> (Vec<DataRow>, Vec<LoadWarning>, LoadSummary) {
+    let rejected = AtomicUsize::new(0);
+    let chunk_len = lines.len().div_ceil(threads).max(1);
+    let parsed: Vec<ChunkResult> = std::thread::scope(|scope| {
+        let handles: Vec<_> = lines.chunks(chunk_len).map(|chunk| {
+            let rejected = &rejected;
+            scope.spawn(move || {
+                let options = LoadOptions {
+                    quarantine: options.quarantine.as_ref().map(Quarantine::detached),
+                    audit_writer: None,
+                    ..options.clone()
+                };
+                let (mut rows, mut warnings, mut summary) = (vec![], vec![], LoadSummary::default());
+                for (line_index, row) in chunk {
+                    let rejected_before = summary.rows_rejected;
+                    if let Some(row) = load_record(*line_index, row, row_defs, &options, &mut warnings, &mut summary) {
+                        rows.push(row);
+                    }
+                    let total = rejected.fetch_add(summary.rows_rejected - rejected_before, Ordering::Relaxed)
+                        + summary.rows_rejected - rejected_before;
+                    if options.max_errors.is_some_and(|n| total > n) {
+                        break;
+                    }
+                }
+                (rows, warnings, summary, options.quarantine)
+            })
+        }).collect();
+        handles.into_iter().map(|h| h.join().expect("parsing thread panicked")).collect()
+    });
+
+    let (mut rows, mut warnings, mut summary) = (vec![], vec![], LoadSummary::default());
+    for (chunk_rows, chunk_warnings, chunk_summary, chunk_quarantine) in parsed {
+        if let (Some(quarantine), Some(chunk_quarantine)) = (&options.quarantine, chunk_quarantine) {
+            quarantine.extend(chunk_quarantine.take());
+        }
+        if let Some(writer) = &options.audit_writer {
+            for row in &chunk_rows {
+                writer.write_row(row.line_index().unwrap_or_default(), row);
+            }
+        }
+        rows.extend(chunk_rows);
+        warnings.extend(chunk_warnings);
+        summary.rows_skipped += chunk_summary.rows_skipped;
+        summary.rows_rejected += chunk_summary.rows_rejected;
+    }
+    (rows, warnings, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use crate::{AuditWriter, DataFieldDef, DataFieldError, DataFieldResult, DataFile, DataFileError, LoadOptions, Quarantine};
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.lock().unwrap().write(buf) }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    fn upper(s: String) -> DataFieldResult<String> { Ok(s.to_uppercase()) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn parallel_load_matches_sequential() {
+        let defs = vec![DataFieldDef::new("Id", 0, 5, &numeric)];
+        let data: String = (0..1000).map(|i| match i % 97 {
+            0 => format!("{:<200}\n", "BAD"),
+            1 => "\n".to_string(),
+            _ => format!("{:<200}\n", format!("{:05}", i))
+        }).collect();
+        let options = LoadOptions { skip_rows: 2, skip_blank_lines: true, ..Default::default() };
+        let sequential = DataFile::try_from_str(&data, &defs, &options).unwrap();
+        let parallel = DataFile::try_from_str(&data, &defs, &LoadOptions { threads: Some(4), ..options.clone() }).unwrap();
+
+        assert_eq!(parallel.rows().len(), sequential.rows().len());
+        assert!(parallel.iter().zip(sequential.iter())
+            .all(|(p, s)| p.line_index() == s.line_index() && p.get("Id").unwrap().data() == s.get("Id").unwrap().data()));
+        assert_eq!(parallel.warnings().iter().map(|w| w.line_index()).collect::<Vec<usize>>(),
+                   sequential.warnings().iter().map(|w| w.line_index()).collect::<Vec<usize>>());
+        assert_eq!(parallel.summary().rows_rejected, 10);
+        assert_eq!(parallel.summary().rows_skipped, sequential.summary().rows_skipped);
+
+        let limited = LoadOptions { threads: Some(4), ..Default::default() }.max_errors(5);
+        assert!(matches!(DataFile::try_from_str(&data, &defs, &limited), Err(DataFileError::TooManyErrors(5))));
+    }
+
+    #[test]
+    fn quarantine_and_audit_in_file_order() {
+        let defs = vec![DataFieldDef::new("Id", 0, 5, &numeric), DataFieldDef::new("Name", 5, 8, &upper)];
+        let data: String = (0..1000).map(|i| match i % 7 {
+            0 => format!("{:<200}\n", "BAD"),
+            _ => format!("{:<200}\n", format!("{:05}abc", i))
+        }).collect();
+        let load = |threads| {
+            let buffer = Arc::new(Mutex::new(vec![]));
+            let writer = AuditWriter::new(Shared(Arc::clone(&buffer)));
+            let quarantine = Quarantine::new();
+            let options = LoadOptions {
+                threads, quarantine: Some(quarantine.clone()), audit_writer: Some(writer.clone()), ..Default::default()
+            };
+            DataFile::try_from_str(&data, &defs, &options).unwrap();
+            writer.finish().unwrap();
+            let log = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+            (log, quarantine.take())
+        };
+
+        let (sequential_log, sequential_quarantine) = load(None);
+        let (parallel_log, parallel_quarantine) = load(Some(4));
+        assert_eq!(parallel_log.lines().count(), 857);
+        assert_eq!(parallel_log, sequential_log);
+        assert_eq!(parallel_quarantine.len(), 143);
+        assert_eq!(parallel_quarantine, sequential_quarantine);
+    }
+}
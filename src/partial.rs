@@ -0,0 +1,109 @@
+use std::path::Path;
+use crate::{DataFieldDef, DataFile, DataFileError, DataFileReader, DataFileResult, LoadOptions, Loader};
+use crate::datafile::{check_layout, check_units};
+
+/// The outcome of `DataFile::try_load_partial()`: the rows loaded before the load stopped, and
+/// the error that stopped it, if any.
+pub struct PartialLoad {
+    /// The rows loaded before the error, with their warnings and summary.
+    pub data_file: DataFile,
+    /// The number of lines at the start of the file read successfully (including skipped ones),
+    /// so a resumed load can set `LoadOptions::skip_rows` to this to pick up where it stopped.
+    pub lines_read: usize,
+    /// The error that ended the load early, or None if the whole file was loaded.
+    pub error: Option<DataFileError>
+}
+
+impl PartialLoad {
+    /// Determine whether the whole file was loaded.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl DataFile {
+    /// Load a file as with `try_load_with_options()`, but keep the rows parsed before a fatal
+    /// error (e.g., a non-ASCII record or an I/O error partway through) instead of discarding
+    /// them, for best-effort or resumable ingestion.
+    ///
+    /// The file is streamed, so the source hash is not computed. Errors found before any
+    /// line is read, such as a missing file or inconsistent field definitions, still fail.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let partial = DataFile::try_load_partial(Path::new("extract.txt"), &defs, &LoadOptions::default()).unwrap();
+    /// if let Some(e) = &partial.error {
+    ///     println!("Stopped after line {}: {}", partial.lines_read, e);
+    /// }
+    /// println!("{} rows loaded", partial.data_file.rows().len());
+    /// ```
+    pub fn try_load_partial<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: &LoadOptions)
+        -> DataFileResult<PartialLoad> {
+        check_layout(row_defs)?;
+        check_units(row_defs, options)?;
+        let mut reader = DataFileReader::open(path, row_defs, options.clone())?;
+        let mut rows = vec![];
+        let mut error = None;
+        for row in &mut reader {
+            match row {
+                Ok(row) => rows.push(row),
+                Err(e) => error = Some(e)
+            }
+        }
+
+        // an invalid record has already been counted as read by the reader
+        let lines_read = match error.as_ref().and_then(|e| e.location()) {
+            Some(location) => location.line_number - 1,
+            None => reader.position()
+        };
+        let (summary, warnings) = reader.finish();
+        Ok(PartialLoad {
+            data_file: DataFile::from_parts(path, rows, warnings, summary, None, row_defs, options),
+            lines_read,
+            error
+        })
+    }
+}
+
+impl Loader<'_> {
+    /// Load a file with these options, keeping the rows parsed before any fatal error, as with
+    /// `DataFile::try_load_partial()`.
+    pub fn load_partial(self, path: &Path) -> DataFileResult<PartialLoad> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::try_load_partial(path, row_defs, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn good_prefix_kept() {
+        let path = std::env::temp_dir().join(format!("ffreader_partial_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\nshort\n{:<200}\nbád\n{:<200}\n", "0001", "0002", "0003")).unwrap();
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+
+        let partial = DataFile::try_load_partial(&path, &defs, &LoadOptions::default()).unwrap();
+        assert!(!partial.is_complete());
+        assert!(matches!(partial.error.as_ref().unwrap().root(), DataFileError::NonASCIIFile));
+        assert_eq!(partial.lines_read, 3);
+        assert_eq!(partial.data_file.rows().len(), 2);
+        assert_eq!(partial.data_file.warnings().len(), 1);
+
+        let options = LoadOptions { skip_rows: partial.lines_read + 1, ..Default::default() };
+        let rest = DataFile::loader(&defs).options(options).load_partial(&path).unwrap();
+        assert!(rest.is_complete());
+        assert_eq!(rest.lines_read, 5);
+        assert_eq!(rest.data_file.rows()[0].get("Id").unwrap().data(), "0003");
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
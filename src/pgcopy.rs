@@ -0,0 +1,176 @@
+//! Postgres bulk-load export, in the text format of `COPY ... FROM STDIN`: a line per row,
+//! with values separated by tabs, nulls written as `\N` and backslashes, tabs and line breaks
+//! in values escaped.
+use std::io::{self, Write};
+use crate::{DataFile, DataRowResult, ExportOptions, OutputSink};
+
+/// Escape a value for the COPY text format, writing a null as `\N`.
+fn copy_value(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "\\N".to_string();
+    };
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{b}' => out.push_str("\\v"),
+            '\u{c}' => out.push_str("\\f"),
+            c => out.push(c)
+        }
+    }
+    out
+}
+
+/// Format the values of a row as a line of COPY text.
+fn copy_line<'v>(values: impl Iterator<Item = Option<&'v str>>) -> String {
+    values.map(copy_value).collect::<Vec<String>>().join("\t") + "\n"
+}
+
+/// Quote a column name as a Postgres identifier.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl DataFile {
+    /// Generate the data in Postgres COPY text format, for loading with
+    /// `COPY table (columns) FROM STDIN`. Only available with the `pgcopy` feature.
+    pub fn to_pg_copy(&self) -> String {
+        self.to_pg_copy_with_options(&ExportOptions::default())
+            .expect("all fields exported")
+    }
+
+    /// Generate the data in Postgres COPY text format using the provided ExportOptions, the
+    /// columns in the order exported and blank values given as nulls if `BlankValue::Null`.
+    /// Fails if a selected field name is not found in a row.
+    /// Only available with the `pgcopy` feature.
+    /// ```
+    /// use ffreader::{BlankValue, DataFieldDef, DataFieldResult, DataFile, ExportOptions, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Id", 0, 4, &post_function), DataFieldDef::new("Path", 4, 14, &post_function)];
+    /// let data = format!("{:<200}\n{:<200}\n", "0001C:\\TEMP", "0002");
+    /// let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+    /// let options = ExportOptions { blanks: BlankValue::Null, ..Default::default() };
+    /// assert_eq!(df.to_pg_copy_with_options(&options).unwrap(), "0001\tC:\\\\TEMP\n0002\t\\N\n");
+    /// ```
+    pub fn to_pg_copy_with_options(&self, options: &ExportOptions) -> DataRowResult<String> {
+        Ok(options.records(self)?.iter()
+            .map(|record| copy_line(record.iter().map(|(_, v)| v.as_deref())))
+            .collect())
+    }
+}
+
+/// Writes rows in Postgres COPY text format, for streaming a Pipeline into a `COPY ... FROM
+/// STDIN` driven by a database client. With `with_statement()`, the rows are preceded by the
+/// COPY statement and followed by the end-of-data marker, making a script that can be piped
+/// straight into `psql`. Only available with the `pgcopy` feature.
+/// ```
+/// use std::io::Cursor;
+/// use ffreader::{DataFieldDef, DataFieldResult, DataFileReader, LoadOptions, PgCopySink, Pipeline};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Id", 0, 4, &post_function), DataFieldDef::new("Name", 4, 10, &post_function)];
+/// let data = format!("{:<200}\n", "0001ANN");
+/// let mut sink = PgCopySink::new(vec![]).with_statement("staging.accounts");
+/// Pipeline::new(DataFileReader::new(Cursor::new(data), &defs, LoadOptions::default()))
+///     .write_to(&mut sink).unwrap();
+/// assert_eq!(String::from_utf8(sink.into_inner()).unwrap(),
+///            "COPY staging.accounts (\"Id\", \"Name\") FROM STDIN;\n0001\tANN\n\\.\n");
+/// ```
+pub struct PgCopySink<W: Write> {
+    writer: W,
+    table: Option<String>,
+    started: bool
+}
+
+impl<W: Write> PgCopySink<W> {
+    /// Write COPY text to a writer, which is best buffered.
+    pub fn new(writer: W) -> Self {
+        PgCopySink { writer, table: None, started: false }
+    }
+
+    /// Write the COPY statement loading the exported columns into a table before the rows, and
+    /// the end-of-data marker after them. The table name is written as given, so that it can
+    /// name a schema. If the sink is finished without a header, the statement is written
+    /// then, without a column list, so that the script is still valid.
+    pub fn with_statement(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    /// Get the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputSink for PgCopySink<W> {
+    fn write_header(&mut self, names: &[String]) -> io::Result<()> {
+        self.started = true;
+        match &self.table {
+            Some(table) => {
+                let columns = names.iter().map(|n| quote_identifier(n)).collect::<Vec<String>>().join(", ");
+                writeln!(self.writer, "COPY {} ({}) FROM STDIN;", table, columns)
+            }
+            None => Ok(())
+        }
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        self.writer.write_all(copy_line(values.iter().map(|(_, v)| v.as_deref())).as_bytes())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(table) = &self.table {
+            if !self.started {
+                writeln!(self.writer, "COPY {} FROM STDIN;", table)?;
+            }
+            writeln!(self.writer, "\\.")?;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldDef, DataFieldResult, DataFileReader, LoadOptions, Pipeline};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn copy_text_escaped() {
+        assert_eq!(copy_value(None), "\\N");
+        assert_eq!(copy_value(Some("")), "");
+        assert_eq!(copy_value(Some("\\N")), "\\\\N");
+        assert_eq!(copy_value(Some("a\tb\r\nc\u{8}\u{b}\u{c}")), "a\\tb\\r\\nc\\b\\v\\f");
+        assert_eq!(quote_identifier("Acct \"No\""), "\"Acct \"\"No\"\"\"");
+
+        let mut sink = PgCopySink::new(vec![]);
+        sink.write_header(&["Id".to_string()]).unwrap();
+        sink.write_row(&[("Id".to_string(), Some("1".to_string())), ("Note".to_string(), None)]).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "1\t\\N\n");
+
+        let mut sink = PgCopySink::new(vec![]).with_statement("staging.accounts");
+        sink.finish().unwrap();
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "COPY staging.accounts FROM STDIN;\n\\.\n");
+    }
+
+    #[test]
+    fn empty_input_copied() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok)];
+        let mut sink = PgCopySink::new(vec![]).with_statement("staging.accounts");
+        let written = Pipeline::new(DataFileReader::new(Cursor::new(""), &defs, LoadOptions::default()))
+            .write_to(&mut sink).unwrap();
+        assert_eq!(written, 0);
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "COPY staging.accounts (\"Id\") FROM STDIN;\n\\.\n");
+    }
+}
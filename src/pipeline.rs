@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::{BlankValue, DataFieldDef, DataFileError, DataFileReader, DataFileResult, DataRow, DataRowResult};
+use crate::{ExportOptions, LoadOptions, LoadSummary, LoadWarning, Loader, OutputSink, Redaction};
+#[cfg(feature = "csv")]
+use crate::CsvSink;
+#[cfg(feature = "json")]
+use crate::JsonLinesSink;
+
+/// A step applied to each row of a Pipeline.
+enum Step<'a> {
+    Filter(Box<dyn Fn(&DataRow) -> bool + 'a>),
+    Map(Box<dyn Fn(DataRow) -> DataRow + 'a>)
+}
+
+/// Converts a fixed-width file straight to CSV or JSON Lines, or to any OutputSink, in one
+/// pass: rows are read and parsed by a DataFileReader, passed through any filters and
+/// transforms in the order they were added, and written out one at a time, so that only the
+/// row being written is held in memory.
+///
+/// Values are exported as by the exports of a DataFile with the pipeline's ExportOptions,
+/// using the redactions and blank handling of the field definitions. Rejected and skipped
+/// rows are recorded as warnings, available once the pipeline has run.
+/// ```no_run
+/// use std::fs::File;
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult, ExportOptions, LoadOptions, Pipeline};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("AccountNo", 0, 11, &post_function),
+///     DataFieldDef::new("Status", 11, 13, &post_function),
+/// ];
+/// let mut pipeline = Pipeline::open(Path::new("extract.txt"), &defs, LoadOptions::default()).unwrap()
+///     .filter(|row| row.get("Status").is_some_and(|f| f.data() == "OP"))
+///     .export_options(ExportOptions { fields: Some(vec!["AccountNo".to_string()]), ..Default::default() });
+/// pipeline.write_csv(File::create("open.csv").unwrap()).unwrap();
+/// println!("{}", pipeline.summary());
+/// ```
+pub struct Pipeline<'a, R> {
+    reader: DataFileReader<'a, R>,
+    steps: Vec<Step<'a>>,
+    output: Output
+}
+
+/// How the values of a Pipeline's rows are exported.
+struct Output {
+    options: ExportOptions,
+    blanks: HashMap<String, BlankValue>,
+    redactions: HashMap<String, Redaction>
+}
+
+impl Output {
+    /// Get the exported fields of a row, each with its name and value; a None value is a null.
+    fn values(&self, row: &DataRow) -> DataRowResult<Vec<(String, Option<String>)>> {
+        self.options.row_values(row, &self.blanks, &self.redactions)
+    }
+}
+
+impl<'a> Pipeline<'a, BufReader<File>> {
+    /// Open a file to convert with the provided field definitions and LoadOptions.
+    pub fn open(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions) -> DataFileResult<Self> {
+        Ok(Self::new(DataFileReader::open(path, row_defs, options)?))
+    }
+}
+
+impl<'a, R: BufRead> Pipeline<'a, R> {
+    /// Convert the rows read by a DataFileReader.
+    pub fn new(reader: DataFileReader<'a, R>) -> Self {
+        let row_defs = reader.row_defs();
+        Pipeline {
+            reader,
+            steps: vec![],
+            output: Output {
+                options: ExportOptions::default(),
+                blanks: row_defs.iter().filter_map(|d| d.blank.map(|b| (d.name.clone(), b))).collect(),
+                redactions: row_defs.iter().filter_map(|d| d.redaction.clone().map(|r| (d.name.clone(), r))).collect()
+            }
+        }
+    }
+
+    /// Keep only the rows matching the predicate.
+    pub fn filter(mut self, predicate: impl Fn(&DataRow) -> bool + 'a) -> Self {
+        self.steps.push(Step::Filter(Box::new(predicate)));
+        self
+    }
+
+    /// Transform each row.
+    pub fn map(mut self, transform: impl Fn(DataRow) -> DataRow + 'a) -> Self {
+        self.steps.push(Step::Map(Box::new(transform)));
+        self
+    }
+
+    /// Set the fields, redaction, row limit and blank handling of the output.
+    pub fn export_options(mut self, options: ExportOptions) -> Self {
+        self.output.options = options;
+        self
+    }
+
+    /// Warnings encountered so far.
+    pub fn warnings(&self) -> &Vec<LoadWarning> {
+        self.reader.warnings()
+    }
+
+    /// Summary of the rows read so far.
+    pub fn summary(&self) -> LoadSummary {
+        self.reader.summary()
+    }
+
+    /// Write the rows as CSV, with a header row of field names, returning the number of rows
+    /// written. Fails if reading fails, a selected field is not found, or writing fails.
+    #[cfg(feature = "csv")]
+    pub fn write_csv(&mut self, writer: impl Write) -> DataFileResult<usize> {
+        let guard = self.output.options.formula_guard;
+        self.write_to(CsvSink::new(BufWriter::new(writer)).with_formula_guard(guard))
+    }
+
+    /// Write the rows as JSON Lines, an object per line, returning the number of rows written.
+    /// Fails if reading fails, a selected field is not found, or writing fails.
+    #[cfg(feature = "json")]
+    pub fn write_jsonl(&mut self, writer: impl Write) -> DataFileResult<usize> {
+        self.write_to(JsonLinesSink::new(BufWriter::new(writer)))
+    }
+
+    /// Write the header of the exported fields of the layout, then read, filter and transform
+    /// each row, passing its exported values to a sink, and finish the sink, returning the
+    /// number of rows written. The header is written even if no rows are. Fails if reading
+    /// fails, a selected field is not found, or the sink fails.
+/// ```
+/// use std::io::{self, Cursor};
+/// use ffreader::{DataFieldDef, DataFieldResult, DataFileReader, LoadOptions, OutputSink, Pipeline};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// #[derive(Default)]
+/// struct Batches {
+///     pending: Vec<String>,
+///     sent: Vec<Vec<String>>
+/// }
+/// impl OutputSink for Batches {
+///     fn write_header(&mut self, _names: &[String]) -> io::Result<()> {
+///         Ok(())
+///     }
+///     fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+///         self.pending.push(values[0].1.clone().unwrap_or_default());
+///         if self.pending.len() == 2 {
+///             self.sent.push(std::mem::take(&mut self.pending));
+///         }
+///         Ok(())
+///     }
+///     fn finish(&mut self) -> io::Result<()> {
+///         if !self.pending.is_empty() {
+///             self.sent.push(std::mem::take(&mut self.pending));
+///         }
+///         Ok(())
+///     }
+/// }
+/// let defs = vec![DataFieldDef::new("Id", 0, 4, &post_function)];
+/// let data = format!("{:<200}\n{:<200}\n{:<200}\n", "0001", "0002", "0003");
+/// let mut batches = Batches::default();
+/// Pipeline::new(DataFileReader::new(Cursor::new(data), &defs, LoadOptions::default()))
+///     .write_to(&mut batches).unwrap();
+/// assert_eq!(batches.sent, [vec!["0001", "0002"], vec!["0003"]]);
+/// ```
+    pub fn write_to(&mut self, mut sink: impl OutputSink) -> DataFileResult<usize> {
+        let write_error = |e| DataFileError::FileError(PathBuf::new(), e);
+        let names = self.output.options.header(self.reader.row_defs(), self.reader.options().name_matching)
+            .map_err(DataFileError::ExportError)?;
+        sink.write_header(&names).map_err(write_error)?;
+        let mut written = 0;
+        'rows: while self.output.options.max_rows.is_none_or(|m| written < m) {
+            let Some(row) = self.reader.next() else {
+                break;
+            };
+            let mut row = row?;
+            for step in &self.steps {
+                match step {
+                    Step::Filter(predicate) if !predicate(&row) => continue 'rows,
+                    Step::Filter(_) => (),
+                    Step::Map(transform) => row = transform(row)
+                }
+            }
+            let values = self.output.values(&row).map_err(DataFileError::ExportError)?;
+            sink.write_row(&values).map_err(write_error)?;
+            written += 1;
+        }
+        sink.finish().map_err(write_error)?;
+        Ok(written)
+    }
+}
+
+impl<'a> Loader<'a> {
+    /// Open a file to convert straight to CSV or JSON Lines with these options; see Pipeline.
+    pub fn pipeline(self, path: &Path) -> DataFileResult<Pipeline<'a, BufReader<File>>> {
+        let (row_defs, options) = self.into_parts();
+        Pipeline::open(path, row_defs, options)
+    }
+}
+
+#[cfg(all(test, feature = "csv", feature = "json"))]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn rows_converted_in_one_pass() {
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 5, &echo_ok),
+            DataFieldDef::new("AccountNo", 5, 15, &echo_ok).with_redaction(Redaction::MaskAllButLast(4)),
+            DataFieldDef::new("Status", 15, 17, &echo_ok),
+        ];
+        let data = format!("{:<200}\n{:<200}\nshort\n{:<200}\n", "ANN  5412345678OP", "BOB  5499999999CL", "CAT, 5400000000OP");
+        let pipeline = || Pipeline::new(DataFileReader::new(Cursor::new(data.clone()), &defs, LoadOptions::default()))
+            .filter(|r| r.get("Status").unwrap().data() == "OP")
+            .map(|mut r| {
+                let name = r.get("Name").unwrap().data().to_lowercase();
+                r.set("Name", name).unwrap();
+                r
+            });
+
+        let mut csv = vec![];
+        let mut p = pipeline().export_options(ExportOptions { redact: true, ..Default::default() });
+        assert_eq!(p.write_csv(&mut csv).unwrap(), 2);
+        assert_eq!(String::from_utf8(csv).unwrap(), "Name,AccountNo,Status\nann,******5678,OP\n\"cat,\",******0000,OP\n");
+        assert_eq!(p.warnings().len(), 1);
+        assert_eq!(p.summary().rows_loaded, 3);
+
+        let mut jsonl = vec![];
+        let options = ExportOptions { fields: Some(vec!["Name".to_string()]), max_rows: Some(1), ..Default::default() };
+        pipeline().export_options(options).write_jsonl(&mut jsonl).unwrap();
+        assert_eq!(String::from_utf8(jsonl).unwrap(), "{\"Name\": \"ann\"}\n");
+
+        let mut csv = vec![];
+        let mut p = pipeline().filter(|_| false);
+        assert_eq!(p.write_csv(&mut csv).unwrap(), 0);
+        assert_eq!(String::from_utf8(csv).unwrap(), "Name,AccountNo,Status\n");
+        let mut csv = vec![];
+        let options = ExportOptions { fields: Some(vec!["Status".to_string()]), row_ids: true, ..Default::default() };
+        Pipeline::new(DataFileReader::new(Cursor::new(""), &defs, LoadOptions::default()))
+            .export_options(options).write_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "row_id,Status\n");
+        let options = ExportOptions { fields: Some(vec!["Missing".to_string()]), ..Default::default() };
+        assert!(pipeline().export_options(options).write_csv(vec![]).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{ColumnUnit, DataFieldDef};
+
+/// A data-quality check for feeds required to be plain printable ASCII, space to `~`: any
+/// other character in a row (tabs, control codes, or non-ASCII letters when measuring in
+/// characters) is reported with its field and column, unless it is one of the allowed
+/// characters. Unlike ControlCharPolicy, the whole row is checked, padding and text outside
+/// the fields included, and nothing is changed.
+/// ```
+/// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions, PrintableAscii, WarningKind};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Name", 0, 10, &post_function)];
+/// let options = LoadOptions { printable_ascii: Some(PrintableAscii::new()), ..Default::default() };
+/// let df = DataFile::try_from_str(&format!("{:<200}\n", "ANN\tLEE"), &defs, &options).unwrap();
+/// assert_eq!(df.warnings()[0].kind(), WarningKind::Unprintable);
+/// assert_eq!(df.warnings()[0].message(), "Unprintable characters in Name at column 3 (0x09)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PrintableAscii {
+    /// Characters allowed besides printable ASCII, e.g. a tab.
+    pub allowed: Vec<char>,
+    /// Reject rows with unprintable characters with `DataRowError::Unprintable`, rather than
+    /// keeping them with a `WarningKind::Unprintable` warning for each field involved.
+    pub reject: bool
+}
+
+impl PrintableAscii {
+    /// Warn of every character outside printable ASCII.
+    pub fn new() -> PrintableAscii {
+        PrintableAscii::default()
+    }
+
+    /// Also allow these characters.
+    pub fn allow(mut self, allowed: &[char]) -> Self {
+        self.allowed.extend_from_slice(allowed);
+        self
+    }
+
+    /// Reject rows with unprintable characters rather than warning of them.
+    pub fn reject(mut self) -> Self {
+        self.reject = true;
+        self
+    }
+
+    /// Determine whether a character passes the check.
+    fn allows(&self, c: char) -> bool {
+        (' '..='~').contains(&c) || self.allowed.contains(&c)
+    }
+
+    /// Find the unprintable characters of a row, grouped by the field whose columns hold them,
+    /// in column order.
+    pub(crate) fn find<'r>(&self, row: &'r str, row_defs: &[DataFieldDef], unit: ColumnUnit) -> Vec<Unprintable<'r>> {
+        let mut found: Vec<Unprintable> = vec![];
+        if row.chars().all(|c| self.allows(c)) {
+            return found;
+        }
+
+        let row_len = unit.len(row);
+        for (i, c) in row.char_indices().filter(|(_, c)| !self.allows(*c)) {
+            let column = unit.len(&row[..i]);
+            let field = row_defs.iter()
+                .map(|d| (d, d.span(row_len)))
+                .find(|(_, (start, end))| (*start..*end).contains(&column));
+            let (field_name, start, raw) = match field {
+                Some((d, (start, end))) => (Some(d.name.clone()), start, unit.slice(row, start, end.min(row_len))),
+                None => (None, 0, row)
+            };
+            match found.iter_mut().find(|u| u.field_name == field_name) {
+                Some(u) => u.chars.push((column, c)),
+                None => found.push(Unprintable { field_name, start, raw, chars: vec![(column, c)] })
+            }
+        }
+        found
+    }
+}
+
+/// The unprintable characters of a row in one field, as found by `PrintableAscii::find()`.
+pub(crate) struct Unprintable<'r> {
+    /// The name of the field, or None for text outside every field.
+    pub field_name: Option<String>,
+    /// The column the field starts at; 0 outside every field.
+    pub start: usize,
+    /// The raw text of the field, or the row outside every field.
+    pub raw: &'r str,
+    /// The column and character of each.
+    pub chars: Vec<(usize, char)>
+}
+
+/// Describe unprintable characters by column and code, e.g. `column 3 (0x09)` or
+/// `column 7 (U+00E9)`.
+pub(crate) fn describe_unprintable(chars: &[(usize, char)]) -> String {
+    let described = chars.iter()
+        .map(|(column, c)| match c.is_ascii() {
+            true => format!("{} (0x{:02X})", column, *c as u32),
+            false => format!("{} (U+{:04X})", column, *c as u32)
+        })
+        .collect::<Vec<String>>();
+    format!("column{} {}", if chars.len() == 1 { "" } else { "s" }, described.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFile, DataFieldResult, DataRow, DataRowError, LoadOptions, WarningKind};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn unprintable_characters_flagged_by_field() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &echo_ok), DataFieldDef::new("Name", 4, 12, &echo_ok)];
+        let data = format!("{:<200}\n{:<200}\n{:<200}\n", "0001ANN LEE", "0002BO\tB\u{1b}", format!("0\x7f03{:<10}|\0", "CAROL"));
+        let load = |check| {
+            let options = LoadOptions { printable_ascii: Some(check), ..Default::default() };
+            DataFile::try_from_str(&data, &defs, &options).unwrap()
+        };
+
+        let warned = load(PrintableAscii::new());
+        assert_eq!(warned.rows().len(), 3);
+        let messages: Vec<(usize, Option<&str>, String)> = warned.warnings().iter()
+            .inspect(|w| assert_eq!(w.kind(), WarningKind::Unprintable))
+            .map(|w| (w.line_index(), w.field_name(), w.message()))
+            .collect();
+        assert_eq!(messages, [
+            (1, Some("Name"), "Unprintable characters in Name at columns 6 (0x09), 8 (0x1B)".to_string()),
+            (2, Some("Id"), "Unprintable characters in Id at column 1 (0x7F)".to_string()),
+            (2, None, "Unprintable characters outside any field at column 15 (0x00)".to_string()),
+        ]);
+        assert_eq!(warned.warnings()[0].raw(), Some("BO\tB\u{1b}   "));
+
+        let allowed = load(PrintableAscii::new().allow(&['\t', '\u{1b}']));
+        assert_eq!(allowed.warnings().len(), 2);
+
+        let rejected = load(PrintableAscii::new().reject());
+        assert_eq!(rejected.rows().len(), 1);
+        assert_eq!(rejected.warnings()[0].kind(), WarningKind::Unprintable);
+        assert!(rejected.warnings()[0].is_error());
+        let options = LoadOptions { printable_ascii: Some(PrintableAscii::new().reject()), ..Default::default() };
+        assert!(matches!(DataRow::try_create_with_options(&format!("{:<200}", "0002BO\tB"), &defs, &options),
+                         Err(DataRowError::Unprintable(Some(n), c)) if n == "Name" && c == [6]));
+
+        let options = LoadOptions { columns: crate::ColumnUnit::Chars, printable_ascii: Some(PrintableAscii::new()), ..Default::default() };
+        let df = DataFile::try_from_str(&format!("{:<200}\n", "0004JOSÉ"), &defs, &options).unwrap();
+        assert_eq!(df.warnings()[0].message(), "Unprintable characters in Name at column 7 (U+00C9)");
+    }
+}
@@ -0,0 +1,436 @@
+//! Ready-made post-processing functions.
+//!
+//! Each function here builds a closure suitable for use as a `DataFieldDef` post-processing
+//! function. Keep the closure in a variable that outlives the field definitions:
+//! ```
+//! use std::collections::HashMap;
+//! use ffreader::DataFieldDef;
+//! use ffreader::processors::{lookup, UnknownCode};
+//! let labels = HashMap::from([("A".to_string(), "Active".to_string())]);
+//! let status = lookup(labels, UnknownCode::Error);
+//! let field_def = DataFieldDef::new("Status", 16, 23, &status);
+//! ```
+
+use std::collections::HashMap;
+#[cfg(feature = "chrono-tz")]
+use chrono::{LocalResult, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+#[cfg(feature = "chrono-tz")]
+pub use chrono_tz::Tz;
+use crate::{DataFieldError, DataFieldResult, DataRow, DataRowError, DataRowResult};
+use crate::aggregate::parse_number;
+
+/// What a lookup post-processor does with a code not found in its table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownCode {
+    /// Keep the code unchanged.
+    PassThrough,
+    /// Fail the field with a `DataFieldError::Problem`.
+    Error,
+    /// Keep the code unchanged but record a warning.
+    Warn
+}
+
+/// Build a post-processor that replaces codes with descriptions from a table
+/// (e.g. status codes to labels). Blank values are passed through unchanged.
+pub fn lookup(table: HashMap<String, String>, unknown: UnknownCode) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    move |value: String| {
+        if value.is_empty() {
+            return Ok(value);
+        }
+        match (table.get(&value), unknown) {
+            (Some(description), _) => Ok(description.clone()),
+            (None, UnknownCode::PassThrough) => Ok(value),
+            (None, UnknownCode::Error) => Err(DataFieldError::Problem(Box::new(format!("Unknown code {}", value)))),
+            (None, UnknownCode::Warn) => {
+                let message = format!("Unknown code {}", value);
+                Err(DataFieldError::Warning(value, message))
+            }
+        }
+    }
+}
+
+/// Build a post-processor that multiplies numeric values by a factor, written with the given
+/// number of decimal places (e.g. `scale(0.001, 3)` for readings stored in thousandths).
+/// Surrounding spaces are ignored and a sign may lead or trail the digits; implied decimals
+/// aren't applied, so scale by them instead. Blank values are passed through unchanged, and
+/// others that aren't numbers fail the field.
+pub fn scale(factor: f64, decimals: usize) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    move |value: String| {
+        if value.trim().is_empty() {
+            return Ok(value);
+        }
+        match parse_number(&value, 0) {
+            Some(number) => Ok(format!("{:.*}", decimals, number * factor)),
+            None => Err(DataFieldError::Problem(Box::new(format!("Not a number: {}", value.trim()))))
+        }
+    }
+}
+
+/// Build a post-processor that divides numeric values by a divisor; see `scale()`.
+pub fn divide(divisor: f64, decimals: usize) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    scale(1.0 / divisor, decimals)
+}
+
+/// Build a post-processor that rounds numeric values to the given number of decimal places;
+/// see `scale()`.
+pub fn round(decimals: usize) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    scale(1.0, decimals)
+}
+
+/// A unit of measure for `convert()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Watt-hours.
+    WattHours,
+    /// Kilowatt-hours.
+    KilowattHours,
+    /// Megawatt-hours.
+    MegawattHours,
+    /// Liters.
+    Liters,
+    /// Cubic meters.
+    CubicMeters,
+    /// Cubic feet.
+    CubicFeet,
+    /// Hundreds of cubic feet (CCF).
+    HundredCubicFeet,
+    /// US gallons.
+    UsGallons
+}
+
+impl Unit {
+    /// Get the quantity the unit measures and its size in the base unit of that quantity
+    /// (watt-hours or liters).
+    fn base(&self) -> (&'static str, f64) {
+        match self {
+            Unit::WattHours => ("energy", 1.0),
+            Unit::KilowattHours => ("energy", 1_000.0),
+            Unit::MegawattHours => ("energy", 1_000_000.0),
+            Unit::Liters => ("volume", 1.0),
+            Unit::CubicMeters => ("volume", 1_000.0),
+            Unit::CubicFeet => ("volume", 28.316846592),
+            Unit::HundredCubicFeet => ("volume", 2831.6846592),
+            Unit::UsGallons => ("volume", 3.785411784)
+        }
+    }
+}
+
+/// Build a post-processor that converts numeric values from one unit to another, written with
+/// the given number of decimal places; see `scale()`. Panics if the units don't measure the
+/// same quantity (e.g. energy and volume).
+/// ```
+/// use ffreader::processors::{convert, Unit};
+/// let to_kwh = convert(Unit::WattHours, Unit::KilowattHours, 2);
+/// assert_eq!(to_kwh("0012345".to_string()).unwrap(), "12.35");
+/// ```
+pub fn convert(from: Unit, to: Unit, decimals: usize) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    let ((from_quantity, from_size), (to_quantity, to_size)) = (from.base(), to.base());
+    assert_eq!(from_quantity, to_quantity, "cannot convert {:?} to {:?}", from, to);
+    scale(from_size / to_size, decimals)
+}
+
+/// Get the digits of a value, ignoring punctuation and spaces, or None if it has any other
+/// characters.
+fn digits(value: &str) -> Option<String> {
+    value.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')' | '+' | '/'))
+        .map(|c| c.is_ascii_digit().then_some(c))
+        .collect()
+}
+
+/// Fail a field whose value isn't a valid identifier of the kind described.
+fn invalid(kind: &str, value: &str) -> DataFieldError {
+    DataFieldError::Problem(Box::new(format!("Invalid {}: {}", kind, value.trim())))
+}
+
+/// Build a post-processor that normalizes North American phone numbers to their ten digits,
+/// ignoring punctuation and a leading country code of 1, and masking all but the last four
+/// digits with `*` if set. Blank values are passed through unchanged; others that aren't ten
+/// digits fail the field.
+/// ```
+/// use ffreader::processors::phone;
+/// assert_eq!(phone(false)("+1 (555) 123-4567".to_string()).unwrap(), "5551234567");
+/// assert_eq!(phone(true)("555.123.4567".to_string()).unwrap(), "******4567");
+/// ```
+pub fn phone(mask: bool) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    move |value: String| {
+        if value.trim().is_empty() {
+            return Ok(value);
+        }
+        let number = match digits(&value) {
+            Some(d) if d.len() == 11 && d.starts_with('1') => d[1..].to_string(),
+            Some(d) if d.len() == 10 => d,
+            _ => return Err(invalid("phone number", &value))
+        };
+        Ok(if mask { format!("******{}", &number[6..]) } else { number })
+    }
+}
+
+/// Build a post-processor that normalizes US ZIP codes to five digits, or ZIP+4 as
+/// `12345-6789`. Blank values are passed through unchanged; others that aren't five or nine
+/// digits fail the field.
+pub fn zip() -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    move |value: String| {
+        if value.trim().is_empty() {
+            return Ok(value);
+        }
+        match digits(&value) {
+            Some(d) if d.len() == 5 => Ok(d),
+            Some(d) if d.len() == 9 => Ok(format!("{}-{}", &d[..5], &d[5..])),
+            _ => Err(invalid("ZIP code", &value))
+        }
+    }
+}
+
+/// Build a post-processor that normalizes US Social Security numbers to `123-45-6789`, or
+/// `***-**-6789` if masked. Blank values are passed through unchanged; others that aren't
+/// nine digits, or that can't be issued (an area of 000, 666 or 900 and above, a group of 00
+/// or a serial of 0000), fail the field.
+/// ```
+/// use ffreader::processors::ssn;
+/// assert_eq!(ssn(true)("123456789".to_string()).unwrap(), "***-**-6789");
+/// assert!(ssn(false)("666-12-3456".to_string()).is_err());
+/// ```
+pub fn ssn(mask: bool) -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    move |value: String| {
+        if value.trim().is_empty() {
+            return Ok(value);
+        }
+        let number = digits(&value).filter(|d| d.len() == 9).ok_or_else(|| invalid("SSN", &value))?;
+        let (area, group, serial) = (&number[..3], &number[3..5], &number[5..]);
+        if area == "000" || area == "666" || area >= "900" || group == "00" || serial == "0000" {
+            return Err(invalid("SSN", &value));
+        }
+        Ok(match mask {
+            true => format!("***-**-{}", serial),
+            false => format!("{}-{}-{}", area, group, serial)
+        })
+    }
+}
+
+/// How `timestamp()` writes the times it reads.
+#[cfg(feature = "chrono-tz")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampOutput {
+    /// RFC 3339 in UTC, e.g. `2024-03-01T11:30:00Z`.
+    Utc,
+    /// RFC 3339 with the offset of the source time zone, e.g. `2024-03-01T12:30:00+01:00`.
+    Offset
+}
+
+/// Build a post-processor that reads local timestamps written in the `strftime`-style format
+/// (which must give both a date and a time, e.g. `%Y%m%d%H%M%S`) as times in the zone, and
+/// writes them as RFC 3339, so that extracts from different regions line up. A time repeated
+/// when clocks go back is taken to be the first; one skipped when they go forward fails the
+/// field, as do values that don't match the format. Blank values are passed through unchanged.
+/// Zones can be named, e.g. `"Europe/Berlin".parse::<Tz>()`.
+/// ```
+/// use ffreader::processors::{timestamp, TimestampOutput, Tz};
+/// let berlin = timestamp("%Y%m%d%H%M%S", Tz::Europe__Berlin, TimestampOutput::Utc);
+/// assert_eq!(berlin("20240301123000".to_string()).unwrap(), "2024-03-01T11:30:00Z");
+/// let offset = timestamp("%d.%m.%Y %H:%M", Tz::Europe__Berlin, TimestampOutput::Offset);
+/// assert_eq!(offset("01.07.2024 12:30".to_string()).unwrap(), "2024-07-01T12:30:00+02:00");
+/// ```
+#[cfg(feature = "chrono-tz")]
+pub fn timestamp(format: impl ToString, zone: Tz, output: TimestampOutput)
+    -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    let format = format.to_string();
+    move |value: String| {
+        if value.trim().is_empty() {
+            return Ok(value);
+        }
+        let local = NaiveDateTime::parse_from_str(value.trim(), &format)
+            .map_err(|e| DataFieldError::Parse(Box::new(e)))?;
+        let time = match zone.from_local_datetime(&local) {
+            LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t,
+            LocalResult::None => return Err(DataFieldError::Problem(Box::new(
+                format!("Time {} does not exist in {}", value.trim(), zone.name()))))
+        };
+        Ok(match output {
+            TimestampOutput::Utc => time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TimestampOutput::Offset => time.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+        })
+    }
+}
+
+/// Trim a value and collapse each run of whitespace within it to a single space.
+fn collapse(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Build a post-processor that trims values and collapses each run of whitespace within them
+/// to a single space, e.g. for the street name fields of an address.
+pub fn collapse_whitespace() -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    |value: String| Ok(collapse(&value))
+}
+
+/// Street directions, spelled out and abbreviated.
+const DIRECTIONS: [(&str, &str); 8] = [
+    ("NORTH", "N"), ("SOUTH", "S"), ("EAST", "E"), ("WEST", "W"),
+    ("NORTHEAST", "NE"), ("NORTHWEST", "NW"), ("SOUTHEAST", "SE"), ("SOUTHWEST", "SW")
+];
+
+/// Build a post-processor that normalizes street directions to their postal abbreviations,
+/// ignoring case, periods and spacing: `North`, `N.` and `n` all become `N`, and
+/// `SOUTH WEST` and `S.W.` become `SW`. Other values are trimmed and otherwise unchanged.
+/// ```
+/// use ffreader::processors::street_direction;
+/// assert_eq!(street_direction()(" North ".to_string()).unwrap(), "N");
+/// assert_eq!(street_direction()("s.w.".to_string()).unwrap(), "SW");
+/// ```
+pub fn street_direction() -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    |value: String| {
+        let key: String = value.chars().filter(|c| !c.is_whitespace() && *c != '.').collect::<String>().to_uppercase();
+        Ok(match DIRECTIONS.iter().find(|(word, abbr)| key == *word || key == *abbr) {
+            Some((_, abbr)) => abbr.to_string(),
+            None => collapse(&value)
+        })
+    }
+}
+
+/// Unit designators, spelled out and with their postal abbreviations.
+const UNIT_DESIGNATORS: [(&str, &str); 12] = [
+    ("APARTMENT", "APT"), ("BASEMENT", "BSMT"), ("BUILDING", "BLDG"), ("DEPARTMENT", "DEPT"),
+    ("FLOOR", "FL"), ("LOT", "LOT"), ("OFFICE", "OFC"), ("PENTHOUSE", "PH"), ("ROOM", "RM"),
+    ("SPACE", "SPC"), ("SUITE", "STE"), ("UNIT", "UNIT")
+];
+
+/// Build a post-processor that normalizes unit designators to their postal abbreviations,
+/// ignoring case and periods: `Apartment 4B` and `apt. 4b` become `APT 4B`, and `#4B` becomes
+/// `# 4B`. Values without a known designator are trimmed, with whitespace collapsed, and
+/// upper-cased.
+/// ```
+/// use ffreader::processors::unit_designator;
+/// assert_eq!(unit_designator()("Suite  200".to_string()).unwrap(), "STE 200");
+/// assert_eq!(unit_designator()("#12".to_string()).unwrap(), "# 12");
+/// ```
+pub fn unit_designator() -> impl Fn(String) -> DataFieldResult<String> + Send + Sync {
+    |value: String| {
+        let value = collapse(&value).to_uppercase();
+        if let Some(rest) = value.strip_prefix('#') {
+            return Ok(format!("# {}", rest.trim_start()));
+        }
+        let (first, rest) = value.split_once(' ').unwrap_or((&value, ""));
+        let first = first.trim_end_matches('.');
+        Ok(match UNIT_DESIGNATORS.iter().find(|(word, abbr)| first == *word || first == *abbr) {
+            Some((_, abbr)) if rest.is_empty() => abbr.to_string(),
+            Some((_, abbr)) => format!("{} {}", abbr, rest),
+            None => value
+        })
+    }
+}
+
+/// Assemble a single address line from the component fields of a row, in the order given
+/// (e.g. `["StreetNumber", "StreetDirection", "StreetName", "StreetUnit"]`), skipping blank
+/// components and collapsing whitespace. Values are used as post-processed, so normalize the
+/// components with the processors above first. Fails if a field is not found.
+/// ```
+/// use ffreader::{DataFieldDef, DataRow};
+/// use ffreader::processors::{address, street_direction, unit_designator};
+/// let keep = |s| Ok(s);
+/// let direction = street_direction();
+/// let unit = unit_designator();
+/// let defs = vec![
+///     DataFieldDef::new("StreetNumber", 0, 6, &keep),
+///     DataFieldDef::new("StreetDirection", 6, 12, &direction),
+///     DataFieldDef::new("StreetName", 12, 30, &keep),
+///     DataFieldDef::new("StreetUnit", 30, 40, &unit),
+/// ];
+/// let line = format!("{:<6}{:<6}{:<18}{:<170}", "123", "north", "Main   St", "apt. 4");
+/// let row = DataRow::try_create(&line, &defs).unwrap();
+/// let fields = ["StreetNumber", "StreetDirection", "StreetName", "StreetUnit"];
+/// assert_eq!(address(&row, &fields).unwrap(), "123 N Main St APT 4");
+/// ```
+pub fn address(row: &DataRow, fields: &[&str]) -> DataRowResult<String> {
+    let mut parts = vec![];
+    for name in fields {
+        let field = row.get(name).ok_or_else(|| DataRowError::FieldNameNotFound(name.to_string()))?;
+        let part = collapse(&field.data());
+        if !part.is_empty() {
+            parts.push(part);
+        }
+    }
+    Ok(parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataField, DataFieldDef};
+
+    fn table() -> HashMap<String, String> {
+        HashMap::from([
+            ("A".to_string(), "Active".to_string()),
+            ("I".to_string(), "Inactive".to_string()),
+        ])
+    }
+
+    #[test]
+    fn lookup_replaces_codes() {
+        let pass = lookup(table(), UnknownCode::PassThrough);
+        assert_eq!(pass("A".to_string()).unwrap(), "Active");
+        assert_eq!(pass("Z".to_string()).unwrap(), "Z");
+        assert_eq!(pass("".to_string()).unwrap(), "");
+
+        let error = lookup(table(), UnknownCode::Error);
+        assert!(error("Z".to_string()).is_err());
+
+        let warn = lookup(table(), UnknownCode::Warn);
+        let def = DataFieldDef::new("Status", 0, 3, &warn);
+        let e = DataField::try_from_row("Z  ", &def).unwrap_err();
+        assert_eq!(e.warning_value(), Some("Z"));
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn local_timestamps_normalized() {
+        let eastern = timestamp("%Y-%m-%d %H:%M:%S", Tz::America__New_York, TimestampOutput::Utc);
+        assert_eq!(eastern("2024-01-15 08:00:00".to_string()).unwrap(), "2024-01-15T13:00:00Z");
+        assert_eq!(eastern("2024-07-15 08:00:00".to_string()).unwrap(), "2024-07-15T12:00:00Z");
+        assert_eq!(eastern("2024-11-03 01:30:00".to_string()).unwrap(), "2024-11-03T05:30:00Z");
+        assert!(eastern("2024-03-10 02:30:00".to_string()).unwrap_err().to_string().contains("does not exist in America/New_York"));
+        assert!(matches!(eastern("2024-13-01 00:00:00".to_string()), Err(DataFieldError::Parse(_))));
+        assert_eq!(eastern("   ".to_string()).unwrap(), "   ");
+
+        let zone: Tz = "Asia/Kolkata".parse().unwrap();
+        let kolkata = timestamp("%Y%m%d%H%M%S%.f", zone, TimestampOutput::Offset);
+        assert_eq!(kolkata("20240301120000.250".to_string()).unwrap(), "2024-03-01T12:00:00.250+05:30");
+    }
+
+    #[test]
+    fn numbers_scaled_and_converted() {
+        assert_eq!(scale(0.01, 2)("  000150".to_string()).unwrap(), "1.50");
+        assert_eq!(scale(10.0, 0)("12-".to_string()).unwrap(), "-120");
+        assert_eq!(divide(8.0, 3)("1".to_string()).unwrap(), "0.125");
+        assert_eq!(round(1)("2.26".to_string()).unwrap(), "2.3");
+        assert_eq!(round(1)("   ".to_string()).unwrap(), "   ");
+        assert!(round(1)("N/A".to_string()).is_err());
+        assert_eq!(convert(Unit::HundredCubicFeet, Unit::UsGallons, 1)("2".to_string()).unwrap(), "1496.1");
+    }
+
+    #[test]
+    fn identifiers_normalized() {
+        assert_eq!(phone(false)("1-555-123-4567".to_string()).unwrap(), "5551234567");
+        assert!(phone(false)("555-1234".to_string()).is_err());
+        assert!(phone(false)("555-123-456X".to_string()).is_err());
+        assert_eq!(zip()("12345".to_string()).unwrap(), "12345");
+        assert_eq!(zip()("123456789".to_string()).unwrap(), "12345-6789");
+        assert!(zip()("1234".to_string()).is_err());
+        assert_eq!(ssn(false)("123 45 6789".to_string()).unwrap(), "123-45-6789");
+        assert!(ssn(false)("123-00-6789".to_string()).is_err());
+        assert!(ssn(false)("912-34-5678".to_string()).is_err());
+        assert_eq!(ssn(true)("".to_string()).unwrap(), "");
+    }
+
+    #[test]
+    fn address_components_normalized() {
+        assert_eq!(collapse_whitespace()("  Old   Mill \t Rd ".to_string()).unwrap(), "Old Mill Rd");
+        assert_eq!(street_direction()("NE".to_string()).unwrap(), "NE");
+        assert_eq!(street_direction()("south east".to_string()).unwrap(), "SE");
+        assert_eq!(street_direction()(" Upper ".to_string()).unwrap(), "Upper");
+        assert_eq!(street_direction()("".to_string()).unwrap(), "");
+        assert_eq!(unit_designator()("Apartment 4B".to_string()).unwrap(), "APT 4B");
+        assert_eq!(unit_designator()("bldg.".to_string()).unwrap(), "BLDG");
+        assert_eq!(unit_designator()("rear house".to_string()).unwrap(), "REAR HOUSE");
+    }
+}
@@ -0,0 +1,83 @@
+//! Python bindings, enabled with the `python` feature.
+//!
+//! Build an importable module with maturin (enabling `pyo3/extension-module`), then:
+//! ```python
+//! import ffreader
+//! schema = '[{"name": "AccountNo1", "start": 0, "end": 11}, {"name": "Status", "start": 16, "end": 23}]'
+//! rows, warnings = ffreader.load("extract.txt", schema)
+//! active = [r for r in rows if r["Status"] == "A"]
+//! ```
+//!
+//! The layout is given as a JSON schema document, as read by `schema::import_json()`.
+
+use std::path::Path;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use crate::DataFile;
+use crate::schema::import_json;
+
+/// Load a file with a layout described by a JSON schema, returning a list of rows, each a dict
+/// of field names to values, and a list of warnings, each a dict with `line`, `kind`, `field`,
+/// `raw` and `message` keys.
+///
+/// Raises ValueError for an invalid schema and IOError if the file can't be loaded.
+#[pyfunction]
+fn load<'py>(py: Python<'py>, path: &str, schema: &str) -> PyResult<(Bound<'py, PyList>, Bound<'py, PyList>)> {
+    let defs = import_json(schema).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let data_file = DataFile::try_load(Path::new(path), &defs).map_err(|e| PyIOError::new_err(e.to_string()))?;
+
+    let rows = PyList::empty(py);
+    for row in data_file.rows() {
+        let dict = PyDict::new(py);
+        for field in row.fields() {
+            dict.set_item(field.name(), field.data())?;
+        }
+        rows.append(dict)?;
+    }
+
+    let warnings = PyList::empty(py);
+    for w in data_file.warnings() {
+        let dict = PyDict::new(py);
+        dict.set_item("line", w.line_number())?;
+        dict.set_item("kind", w.kind().to_string())?;
+        dict.set_item("field", w.field_name())?;
+        dict.set_item("raw", w.raw())?;
+        dict.set_item("message", w.message())?;
+        warnings.append(dict)?;
+    }
+
+    Ok((rows, warnings))
+}
+
+/// The `ffreader` Python module.
+#[pymodule]
+fn ffreader(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_returned_as_dicts() {
+        let path = std::env::temp_dir().join(format!("ffreader_python_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\nshort\n", "001A")).unwrap();
+        let schema = r#"[{"name": "Id", "start": 0, "end": 3}, {"name": "Status", "start": 3, "end": 4}]"#;
+
+        Python::initialize();
+        Python::attach(|py| {
+            let (rows, warnings) = load(py, path.to_str().unwrap(), schema).unwrap();
+            assert_eq!(rows.len(), 1);
+            let status: String = rows.get_item(0).unwrap().get_item("Status").unwrap().extract().unwrap();
+            assert_eq!(status, "A");
+            let line: usize = warnings.get_item(0).unwrap().get_item("line").unwrap().extract().unwrap();
+            assert_eq!(line, 2);
+            assert!(load(py, path.to_str().unwrap(), "[{}]").is_err());
+        });
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::{AtomicFile, DataRowError, LoadOptions};
+
+/// A row rejected by a load, as kept by a Quarantine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedRow {
+    /// The path of the file it was read from; empty when loading from memory.
+    pub path: PathBuf,
+    /// The 0-based index of the line in the file it was read from.
+    pub line_index: usize,
+    /// The line as read, before any padding, for correcting and loading again.
+    pub line: String,
+    /// Why the row was rejected: a reason for each field that failed, or for the row.
+    pub reasons: Vec<String>
+}
+
+/// Collects the rows rejected by loads, with their raw text and the reasons for rejecting
+/// them, for a repair-and-replay workflow: the lines are written back out as a fixed-width
+/// file, corrected, and loaded again with the same layout. Set one with
+/// `LoadOptions::quarantine`; clones share the same rows, so that rows rejected by a
+/// DataFileReader or on several threads are collected together. Each row records the file it
+/// was read from, so that one quarantine can collect the rows of a directory or glob load.
+///
+/// Rejected rows are still recorded as warnings and counted in the summary.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldError, DataFieldResult, DataFile, LoadOptions, Quarantine};
+/// fn numeric(value: String) -> DataFieldResult<String> {
+///     match value.chars().all(|c| c.is_ascii_digit()) {
+///         true => Ok(value),
+///         false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+///     }
+/// }
+/// let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric)];
+/// let quarantine = Quarantine::new();
+/// let options = LoadOptions { quarantine: Some(quarantine.clone()), ..Default::default() };
+/// let data = format!("{:<200}\n{:<200}\n", "0001", "00X2");
+/// let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+/// assert_eq!(df.rows().len(), 1);
+///
+/// let mut corrected = vec![];
+/// quarantine.write_lines(&mut corrected).unwrap();
+/// let corrected = String::from_utf8(corrected).unwrap().replace("00X2", "0002");
+/// let replayed = DataFile::try_from_str(&corrected, &defs, &LoadOptions::default()).unwrap();
+/// assert_eq!(replayed.rows()[0].get("Id").unwrap().data(), "0002");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Quarantine {
+    rows: Arc<Mutex<Vec<QuarantinedRow>>>,
+    path: PathBuf
+}
+
+impl Quarantine {
+    /// Create an empty quarantine.
+    pub fn new() -> Quarantine {
+        Quarantine::default()
+    }
+
+    /// Get a handle to the same rows that records the rows it keeps as read from the path.
+    pub(crate) fn for_source(&self, path: &Path) -> Quarantine {
+        Quarantine { rows: Arc::clone(&self.rows), path: path.into() }
+    }
+
+    /// Get an empty quarantine of rows read from the same path, not sharing this one's rows.
+    pub(crate) fn detached(&self) -> Quarantine {
+        Quarantine { rows: Arc::default(), path: self.path.clone() }
+    }
+
+    /// Keep a rejected row with the error that rejected it.
+    pub(crate) fn add(&self, line_index: usize, line: &str, error: &DataRowError) {
+        let reasons = match error {
+            DataRowError::MultipleFieldErrors(errors) => errors.iter().map(|e| e.to_string()).collect(),
+            e => vec![e.to_string()]
+        };
+        self.lock().push(QuarantinedRow { path: self.path.clone(), line_index, line: line.to_string(), reasons });
+    }
+
+    /// Keep rows quarantined elsewhere, such as by a thread parsing part of a file.
+    pub(crate) fn extend(&self, rows: Vec<QuarantinedRow>) {
+        self.lock().extend(rows);
+    }
+
+    /// Lock the rows, whether or not a thread panicked holding them.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<QuarantinedRow>> {
+        self.rows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sort rows by the file they were read from, then in the order they were read.
+    fn sort(rows: &mut [QuarantinedRow]) {
+        rows.sort_by(|a, b| (&a.path, a.line_index).cmp(&(&b.path, b.line_index)));
+    }
+
+    /// Get the rows in quarantine, by file and in the order they were read.
+    pub fn rows(&self) -> Vec<QuarantinedRow> {
+        let mut rows = self.lock().clone();
+        Self::sort(&mut rows);
+        rows
+    }
+
+    /// Get the number of rows in quarantine.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Determine whether no rows are in quarantine.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Take the rows in quarantine, by file and in the order they were read, leaving it empty.
+    pub fn take(&self) -> Vec<QuarantinedRow> {
+        let mut rows = std::mem::take(&mut *self.lock());
+        Self::sort(&mut rows);
+        rows
+    }
+
+    /// Write the lines of the rows in quarantine, by file and in the order they were read, as
+    /// a fixed-width file, returning the number written.
+    pub fn write_lines(&self, mut writer: impl Write) -> io::Result<usize> {
+        let rows = self.rows();
+        for row in &rows {
+            writeln!(writer, "{}", row.line)?;
+        }
+        writer.flush()?;
+        Ok(rows.len())
+    }
+
+    /// Save the lines of the rows in quarantine to a file, replacing it only once all are
+    /// written, returning the number written.
+    pub fn save(&self, path: &Path) -> io::Result<usize> {
+        let mut file = AtomicFile::create(path)?;
+        let written = self.write_lines(&mut file)?;
+        file.commit()?;
+        Ok(written)
+    }
+
+    /// Describe why each row was rejected, a line per reason giving the file and line number
+    /// it was read from, e.g. to send with the saved lines for correction.
+    pub fn report(&self) -> String {
+        self.rows().iter()
+            .flat_map(|r| {
+                let line = match r.path.as_os_str().is_empty() {
+                    true => format!("Line {}", r.line_index + 1),
+                    false => format!("{} line {}", r.path.display(), r.line_index + 1)
+                };
+                r.reasons.iter().map(move |reason| format!("{}: {}\n", line, reason))
+            })
+            .collect()
+    }
+}
+
+impl LoadOptions {
+    /// Get the options for loading the file at the path, with any quarantine recording the
+    /// rows it keeps as read from there.
+    pub(crate) fn for_source(&self, path: &Path) -> Cow<'_, LoadOptions> {
+        match &self.quarantine {
+            Some(quarantine) => Cow::Owned(LoadOptions { quarantine: Some(quarantine.for_source(path)), ..self.clone() }),
+            None => Cow::Borrowed(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldDef, DataFieldError, DataFieldResult, DataFile, DataFileReader, LoadOptions};
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn rejected_rows_quarantined_and_replayed() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric), DataFieldDef::new("Code", 4, 6, &numeric)];
+        let data = format!("{:<200}\n{:<200}\nshort\n{:<200}\n", "000112", "00X1Y2", "000312");
+        let quarantine = Quarantine::new();
+        let options = LoadOptions { quarantine: Some(quarantine.clone()), collect_all_field_errors: true, ..Default::default() };
+
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+        assert_eq!(df.rows().len(), 2);
+        assert_eq!(df.summary().rows_rejected, 2);
+        let rows = quarantine.rows();
+        assert_eq!(rows.iter().map(|r| (r.line_index, r.line.trim_end())).collect::<Vec<_>>(), [(1, "00X1Y2"), (2, "short")]);
+        assert_eq!(rows[0].reasons.len(), 2);
+        assert_eq!(quarantine.report().lines().count(), 3);
+        assert!(quarantine.report().starts_with("Line 2: "));
+
+        let path = std::env::temp_dir().join(format!("ffreader_quarantine_{}.txt", std::process::id()));
+        assert_eq!(quarantine.save(&path).unwrap(), 2);
+        let corrected = std::fs::read_to_string(&path).unwrap()
+            .replace("00X1Y2", "000212")
+            .replace("short", &format!("{:<200}", "000412"));
+        std::fs::remove_file(&path).unwrap();
+        let replayed = DataFile::try_from_str(&corrected, &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(replayed.rows().len(), 2);
+        assert!(replayed.warnings().is_empty());
+
+        assert_eq!(quarantine.take().len(), 2);
+        assert!(quarantine.is_empty());
+        let options = LoadOptions { quarantine: Some(quarantine.clone()), ..Default::default() };
+        DataFileReader::new(Cursor::new(data), &defs, options).for_each(drop);
+        assert_eq!(quarantine.len(), 2);
+    }
+
+    #[test]
+    fn rows_of_several_files_told_apart() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric)];
+        let dir = std::env::temp_dir().join(format!("ffreader_quarantine_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        std::fs::write(&a, format!("{:<200}\n{:<200}\n", "0001", "00X2")).unwrap();
+        std::fs::write(&b, format!("{:<200}\n{:<200}\n", "X001", "0002")).unwrap();
+        let quarantine = Quarantine::new();
+        let options = LoadOptions { quarantine: Some(quarantine.clone()), ..Default::default() };
+
+        DataFile::try_load_with_options(&b, &defs, &options).unwrap();
+        DataFileReader::open(&a, &defs, options.clone()).unwrap().for_each(drop);
+        let rows = quarantine.rows();
+        assert_eq!(rows.iter().map(|r| (r.path.clone(), r.line_index)).collect::<Vec<_>>(), [(a.clone(), 1), (b.clone(), 0)]);
+        assert!(quarantine.report().starts_with(&format!("{} line 2: ", a.display())));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
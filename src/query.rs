@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::{DataFile, DataRow};
+
+/// An error found while compiling a query expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    /// Character offset in the expression where the problem was found.
+    pub position: usize,
+    /// Description of the problem.
+    pub message: String
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Query error at {}: {}", self.position, self.message)
+    }
+}
+
+impl Error for QueryError { }
+
+/// Convenient Result shorthand for QueryError results.
+pub type Result<T> = std::result::Result<T, QueryError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(String),
+    Str(String),
+    Num(f64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare(Operand, &'static str, Operand),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>)
+}
+
+/// A compiled filter expression over the fields of a row.
+///
+/// Expressions compare fields and literals with `==`, `!=`, `<`, `<=`, `>`, `>=`, combined with
+/// `&&`, `||`, `!` and parentheses. Field names are bare identifiers; strings are quoted with
+/// `'` or `"`. Comparisons are numeric when both sides are numbers (a field compared with a
+/// numeric literal is coerced, and a non-numeric value then matches only `!=`), otherwise
+/// they compare text.
+/// ```
+/// use ffreader::Query;
+/// let query = Query::compile("Status == 'A' && (ThingSize > 0 || Special == \"R\")").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr
+}
+
+impl Query {
+    /// Compile an expression; it can then be evaluated against any number of rows.
+    pub fn compile(expression: &str) -> Result<Query> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens, pos: 0, end: expression.chars().count(), depth: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Query { expr }),
+            Some((p, t)) => Err(QueryError { position: *p, message: format!("Unexpected {:?}", t) })
+        }
+    }
+
+    /// Check whether a row matches the query. Fields not found in the row are treated as blank.
+    pub fn matches(&self, row: &DataRow) -> bool {
+        eval(&self.expr, row)
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            Token::Num(text.parse().map_err(|_| QueryError { position: start, message: format!("Bad number {}", text) })?)
+        } else if c == '\'' || c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(QueryError { position: start, message: "Unterminated string".to_string() });
+            }
+            i += 1;
+            Token::Str(chars[start + 1..i - 1].iter().collect())
+        } else if c == '(' || c == ')' {
+            i += 1;
+            if c == '(' { Token::LParen } else { Token::RParen }
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = ["==", "!=", "<=", ">=", "&&", "||"].into_iter().find(|o| *o == two)
+                .or_else(|| ["<", ">", "!"].into_iter().find(|o| o.starts_with(c)))
+                .ok_or(QueryError { position: start, message: format!("Unexpected character '{}'", c) })?;
+            i += op.len();
+            Token::Op(op)
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+    depth: usize
+}
+
+impl Parser {
+    /// The deepest nesting of `!` and parentheses accepted, so that a hostile expression
+    /// cannot overflow the stack.
+    const MAX_DEPTH: usize = 128;
+
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some((_, Token::Op(o))) if *o == op)
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        QueryError {
+            position: self.tokens.get(self.pos).map(|(p, _)| *p).unwrap_or(self.end),
+            message: message.to_string()
+        }
+    }
+
+    /// Enter a `!` or parenthesis, failing if nested too deeply.
+    fn nest(&mut self) -> Result<()> {
+        if self.depth == Self::MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.peek_op("||") {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.peek_op("&&") {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek_op("!") {
+            self.nest()?;
+            let inner = self.unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        if let Some((_, Token::LParen)) = self.tokens.get(self.pos) {
+            self.nest()?;
+            let inner = self.or()?;
+            self.depth -= 1;
+            return match self.tokens.get(self.pos) {
+                Some((_, Token::RParen)) => {
+                    self.pos += 1;
+                    Ok(inner)
+                }
+                _ => Err(self.error("Expected )"))
+            };
+        }
+
+        let left = self.operand()?;
+        let op = match self.tokens.get(self.pos) {
+            Some((_, Token::Op(o))) if ["==", "!=", "<", "<=", ">", ">="].contains(o) => *o,
+            _ => return Err(self.error("Expected comparison operator"))
+        };
+        self.pos += 1;
+        let right = self.operand()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        let operand = match self.tokens.get(self.pos) {
+            Some((_, Token::Ident(i))) => Operand::Field(i.clone()),
+            Some((_, Token::Str(s))) => Operand::Str(s.clone()),
+            Some((_, Token::Num(n))) => Operand::Num(*n),
+            _ => return Err(self.error("Expected field name or value"))
+        };
+        self.pos += 1;
+        Ok(operand)
+    }
+}
+
+fn eval(expr: &Expr, row: &DataRow) -> bool {
+    match expr {
+        Expr::And(l, r) => eval(l, row) && eval(r, row),
+        Expr::Or(l, r) => eval(l, row) || eval(r, row),
+        Expr::Not(e) => !eval(e, row),
+        Expr::Compare(l, op, r) => compare(l, op, r, row)
+    }
+}
+
+fn compare(left: &Operand, op: &str, right: &Operand, row: &DataRow) -> bool {
+    let value = |o: &Operand| match o {
+        Operand::Field(name) => row.get(name).map(|f| f.data()).unwrap_or_default(),
+        Operand::Str(s) => s.clone(),
+        Operand::Num(n) => n.to_string()
+    };
+    let numeric = |o: &Operand| match o {
+        Operand::Num(n) => Some(*n),
+        Operand::Str(_) => None,
+        Operand::Field(_) => value(o).parse::<f64>().ok()
+    };
+    let is_num = |o: &Operand| matches!(o, Operand::Num(_));
+
+    let ordering = match (numeric(left), numeric(right)) {
+        (Some(l), Some(r)) if !matches!(left, Operand::Str(_)) && !matches!(right, Operand::Str(_)) => l.partial_cmp(&r),
+        _ if is_num(left) || is_num(right) => None,
+        _ => Some(value(left).cmp(&value(right)))
+    };
+
+    match ordering {
+        None => op == "!=",
+        Some(o) => match op {
+            "==" => o == Ordering::Equal,
+            "!=" => o != Ordering::Equal,
+            "<" => o == Ordering::Less,
+            "<=" => o != Ordering::Greater,
+            ">" => o == Ordering::Greater,
+            ">=" => o != Ordering::Less,
+            _ => false
+        }
+    }
+}
+
+impl DataFile {
+    /// Create a new DataFile containing only the rows matching a query expression; see Query.
+    pub fn query(&self, expression: &str) -> Result<DataFile> {
+        let query = Query::compile(expression)?;
+        Ok(self.select(|r| query.matches(r), &[]).expect("all fields retained"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataField, DataFieldDef, DataFieldResult, LoadOptions};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn row(status: &str, size: &str) -> DataRow {
+        let defs = [
+            DataFieldDef::new("Status", 0, 2, &echo_ok),
+            DataFieldDef::new("ThingSize", 2, 8, &echo_ok),
+        ];
+        let fields = defs.iter()
+            .map(|d| DataField::try_from_row(&format!("{:<2}{:<6}", status, size), d).unwrap())
+            .collect();
+        DataRow::from_fields(fields, None)
+    }
+
+    #[test]
+    fn expressions_evaluated() {
+        let q = Query::compile("Status == 'A' && ThingSize > 0").unwrap();
+        assert!(q.matches(&row("A", "10")));
+        assert!(!q.matches(&row("A", "0")));
+        assert!(!q.matches(&row("B", "10")));
+        assert!(!q.matches(&row("A", "abc")));
+
+        let q = Query::compile("!(Status == \"A\") || ThingSize >= 9.5").unwrap();
+        assert!(q.matches(&row("B", "1")));
+        assert!(q.matches(&row("A", "10")));
+        assert!(!q.matches(&row("A", "9")));
+
+        // numeric comparison, not text: "10" < "9" as text
+        assert!(Query::compile("ThingSize > 9").unwrap().matches(&row("A", "10")));
+        assert!(Query::compile("ThingSize != 9").unwrap().matches(&row("A", "x")));
+        assert!(Query::compile("Status < 'B'").unwrap().matches(&row("A", "")));
+    }
+
+    #[test]
+    fn fields_found_by_alias() {
+        let defs = vec![
+            DataFieldDef::new("Status", 0, 2, &echo_ok).with_alias("STAT_CD"),
+            DataFieldDef::new("ThingSize", 2, 8, &echo_ok),
+        ];
+        let data = format!("{:<200}\n{:<200}\n", "A 10", "B 20");
+        let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+        let found = df.query("STAT_CD == 'B'").unwrap();
+        assert_eq!(found.rows().len(), 1);
+        assert_eq!(found.rows()[0].get("ThingSize").unwrap().data(), "20");
+    }
+
+    #[test]
+    fn errors_located() {
+        assert_eq!(Query::compile("Status = 'A'").unwrap_err().position, 7);
+        assert_eq!(Query::compile("Status == 'A").unwrap_err().position, 10);
+        assert_eq!(Query::compile("(Status == 'A'").unwrap_err().position, 14);
+        assert_eq!(Query::compile("Status == 'A' Status").unwrap_err().position, 14);
+
+        let deep = Query::compile(&"(".repeat(200_000)).unwrap_err();
+        assert_eq!((deep.position, deep.message.as_str()), (128, "Nested too deeply"));
+        assert_eq!(Query::compile(&"!".repeat(200_000)).unwrap_err().position, 128);
+        let nested = format!("{}Status == 'A'{}", "!(".repeat(60), ")".repeat(60));
+        assert!(Query::compile(&nested).unwrap().matches(&row("A", "")));
+    }
+}
@@ -0,0 +1,547 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use crate::{DataFieldDef, DataFileError, DataFileResult, DataRow, LoadOptions, LoadSummary, LoadWarning, RecordFormat};
+use crate::datafile::{check_error_limit, check_layout, check_units, load_record};
+use crate::invalidbytes::decode;
+use crate::trace::{self, LoadSpan};
+use crate::loadsummary::Stopwatch;
+use crate::records::Continuation;
+use crate::controltotal::ControlTally;
+use crate::duplicates::SeenKeys;
+
+/// Reads and parses rows one at a time from a stream, for files too large to hold in memory.
+///
+/// Rows are parsed exactly as `DataFile::try_load_with_options()` would parse them, honoring the
+/// same LoadOptions. Rejected and skipped rows are not returned by the iterator; they are
+/// recorded as LoadWarnings, available from `warnings()`. An I/O error or non-ASCII record is
+/// returned as an `Err` and ends the iteration.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFileReader, DataFieldDef, DataFieldResult, LoadOptions};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+/// let mut reader = DataFileReader::open(Path::new("extract.txt"), &defs, LoadOptions::default()).unwrap();
+/// for row in &mut reader {
+///     println!("{}", row.unwrap().get("Field_1").unwrap().data());
+/// }
+/// println!("{}", reader.summary());
+/// ```
+pub struct DataFileReader<'a, R> {
+    reader: R,
+    row_defs: &'a Vec<DataFieldDef<'a>>,
+    options: LoadOptions,
+    path: PathBuf,
+    next_index: usize,
+    offset: u64,
+    taken: usize,
+    done: bool,
+    warnings: Vec<LoadWarning>,
+    summary: LoadSummary,
+    start: Stopwatch,
+    tail: Option<Tail<R>>,
+    span: LoadSpan,
+    controls: ControlTally,
+    seen: SeenKeys,
+    checked: bool
+}
+
+/// Where a DataFileReader has got to, recorded with `DataFileReader::checkpoint()` so that an
+/// interrupted load can later continue from the same place with `DataFileReader::resume()`.
+/// The fields are public so that it can be saved however is convenient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Checkpoint {
+    /// The offset in the file of the next record.
+    pub byte_offset: u64,
+    /// The 0-based index of the next record.
+    pub line_index: usize,
+    /// The number of records taken after `skip_rows`, counted against `max_rows`.
+    pub records_taken: usize,
+    /// The number of lines read so far; see `LoadSummary::rows_read`.
+    pub rows_read: usize,
+    /// The number of rows loaded so far.
+    pub rows_loaded: usize,
+    /// The number of lines skipped so far.
+    pub rows_skipped: usize,
+    /// The number of rows rejected so far.
+    pub rows_rejected: usize
+}
+
+/// How to wait for more input when following a growing file.
+struct Tail<R> {
+    poll_interval: Duration,
+    rewind: fn(&mut R, u64) -> std::io::Result<u64>
+}
+
+impl<'a> DataFileReader<'a, BufReader<File>> {
+    /// Open a file for reading with the provided field definitions and LoadOptions.
+    pub fn open(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions) -> DataFileResult<Self> {
+        let file = File::open(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+        Ok(Self::new(BufReader::new(file), row_defs, options).with_path(path))
+    }
+}
+
+impl<'a, R: BufRead> DataFileReader<'a, R> {
+    /// Read rows from any buffered stream with the provided field definitions and LoadOptions.
+    pub fn new(reader: R, row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions) -> Self {
+        DataFileReader {
+            reader,
+            row_defs,
+            options,
+            path: PathBuf::new(),
+            next_index: 0,
+            offset: 0,
+            taken: 0,
+            done: false,
+            warnings: vec![],
+            summary: LoadSummary::default(),
+            start: Stopwatch::start(),
+            tail: None,
+            span: LoadSpan::new(Path::new("")),
+            controls: ControlTally::default(),
+            seen: SeenKeys::default(),
+            checked: false
+        }
+    }
+
+    /// Record the path the stream is read from, for errors and tracing.
+    pub(crate) fn with_path(mut self, path: &Path) -> Self {
+        self.path = path.into();
+        self.span = LoadSpan::new(path);
+        if let Some(quarantine) = &self.options.quarantine {
+            self.options.quarantine = Some(quarantine.for_source(path));
+        }
+        self
+    }
+
+    /// The field definitions rows are parsed with.
+    #[cfg(any(feature = "csv", feature = "json"))]
+    pub(crate) fn row_defs(&self) -> &'a Vec<DataFieldDef<'a>> {
+        self.row_defs
+    }
+
+    /// Get the options the rows are loaded with.
+    #[cfg(any(feature = "csv", feature = "json"))]
+    pub(crate) fn options(&self) -> &LoadOptions {
+        &self.options
+    }
+
+    /// Warnings encountered so far.
+    pub fn warnings(&self) -> &Vec<LoadWarning> {
+        &self.warnings
+    }
+
+    /// Take the warnings encountered since they were last taken, leaving none, so that a
+    /// reader of a long stream can hand them off in batches rather than hold them all. They
+    /// are still counted in the summary.
+    pub fn take_warnings(&mut self) -> Vec<LoadWarning> {
+        self.summary.add_warnings(&self.warnings);
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Summary of the rows read so far.
+    pub fn summary(&self) -> LoadSummary {
+        let mut summary = self.summary.clone();
+        summary.add_warnings(&self.warnings);
+        summary.elapsed = self.start.elapsed();
+        summary
+    }
+
+    /// Consume the reader, returning its final summary and warnings.
+    pub(crate) fn finish(self) -> (LoadSummary, Vec<LoadWarning>) {
+        (self.summary(), self.warnings)
+    }
+
+    /// The 0-based index of the next record to be read.
+    pub fn position(&self) -> usize {
+        self.next_index
+    }
+
+    /// Record where the reader has got to, after the last row returned, so that a later load
+    /// can resume from here.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            byte_offset: self.offset,
+            line_index: self.next_index,
+            records_taken: self.taken,
+            rows_read: self.summary.rows_read,
+            rows_loaded: self.summary.rows_loaded,
+            rows_skipped: self.summary.rows_skipped,
+            rows_rejected: self.summary.rows_rejected
+        }
+    }
+
+    /// Read the next logical record, joining continuation lines, along with the index of its
+    /// first line. `skip_rows` and `max_rows` are applied to physical records as in DataFile.
+    fn next_record(&mut self) -> DataFileResult<Option<(usize, String)>> {
+        let mut current: Option<(usize, String)> = None;
+
+        while self.options.max_rows.is_none_or(|m| self.taken < m) {
+            let limit = self.options.line_limit();
+            let record = self.options.record_format.read_record(&mut self.reader, &self.path, self.offset, limit)?;
+            if let Some(r) = record.as_ref().filter(|r| r.bytes.len() > limit) {
+                let record_offset = self.offset as usize + self.options.record_format.header_length();
+                return Err(DataFileError::LineTooLong(limit).at(&self.path, self.next_index, record_offset, &r.bytes));
+            }
+            let record = match record {
+                Some(r) if r.complete || self.tail.is_none() => r,
+                _ => match &self.tail {
+                    Some(tail) => {
+                        (tail.rewind)(&mut self.reader, self.offset)
+                            .map_err(|e| DataFileError::FileError(self.path.clone(), e))?;
+                        thread::sleep(tail.poll_interval);
+                        continue;
+                    }
+                    None => break
+                }
+            };
+            if !record.complete && self.options.record_format == RecordFormat::Rdw {
+                return Err(DataFileError::InvalidRecordDescriptor(self.offset as usize));
+            }
+            let bytes = record.bytes;
+            let record_offset = self.offset as usize + self.options.record_format.header_length();
+            self.offset += record.consumed as u64;
+            self.options.bytes_read(record.consumed);
+            let line_index = self.next_index;
+            self.next_index += 1;
+            if line_index < self.options.skip_rows {
+                continue;
+            }
+            self.taken += 1;
+            self.summary.rows_read += 1;
+
+            let (line, warning) = decode(&bytes, line_index, &self.options)
+                .map_err(|e| e.at(&self.path, line_index, record_offset, &bytes))?;
+            let line = line.into_owned();
+            self.warnings.extend(warning);
+            let continues = self.options.continuation.as_ref()
+                .is_some_and(|c| c.continues(line_index, &line, &mut self.warnings));
+
+            match current.as_mut() {
+                Some((_, record)) => record.push_str(&line),
+                None => current = Some((line_index, line))
+            }
+            if !continues {
+                return Ok(current);
+            }
+        }
+
+        if let Some((first, record)) = &current {
+            self.warnings.push(Continuation::unterminated(*first, record));
+        }
+        Ok(current)
+    }
+
+    /// Parse the next record, returning None for a skipped or rejected record. The layout is
+    /// checked before the first record parsed, wherever the reader was started from.
+    fn next_row(&mut self) -> Option<DataFileResult<Option<DataRow>>> {
+        if !self.checked {
+            if let Err(e) = check_layout(self.row_defs).and_then(|_| check_units(self.row_defs, &self.options)) {
+                return Some(Err(e));
+            }
+            self.checked = true;
+        }
+        let first_warning = self.warnings.len();
+        let record = self.next_record();
+        self.warnings[first_warning..].iter().for_each(trace::warning);
+        match record {
+            Ok(None) => None,
+            Ok(Some((line_index, record))) => {
+                self.controls.record(&self.options, line_index, &record);
+                let row = load_record(line_index, &record, self.row_defs, &self.options,
+                                      &mut self.warnings, &mut self.summary)
+                    .and_then(|r| self.seen.check(&self.options, &record, r, &mut self.warnings, &mut self.summary))
+                    .map(|r| r.with_row_id(Some(self.summary.rows_loaded)));
+                if let Some(row) = &row {
+                    self.controls.row(&self.options, self.row_defs, row);
+                }
+                self.summary.rows_loaded += row.is_some() as usize;
+                match check_error_limit(&self.options, &self.summary) {
+                    Ok(()) => Some(Ok(row)),
+                    Err(e) => Some(Err(e))
+                }
+            }
+            Err(e) => Some(Err(e))
+        }
+    }
+}
+
+impl<'a, R: BufRead + Seek> DataFileReader<'a, R> {
+    /// Follow the file as it grows, like `tail -f`: rather than ending at the end of input, wait
+    /// for more to be appended, checking every `poll_interval`. Only complete records are
+    /// returned, so a row being written is picked up once its terminator (or, for fixed-length
+    /// records, its last byte) arrives. Iteration then only ends on an error or after `max_rows`.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// use ffreader::{DataFileReader, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let reader = DataFileReader::open(Path::new("feed.txt"), &defs, LoadOptions::default()).unwrap()
+    ///     .tail(Duration::from_secs(1));
+    /// for row in reader {
+    ///     println!("{}", row.unwrap().get("Field_1").unwrap().data());
+    /// }
+    /// ```
+    pub fn tail(mut self, poll_interval: Duration) -> Self {
+        self.tail = Some(Tail { poll_interval, rewind: |r, offset| r.seek(SeekFrom::Start(offset)) });
+        self
+    }
+
+    /// Move directly to the nth (0-based) record, without reading those before it.
+    ///
+    /// Only available for `RecordFormat::FixedLength` records, where the byte offset of a record
+    /// can be computed; other formats fail with `DataFileError::SeekUnsupported`. `skip_rows`
+    /// still excludes the initial records, and `max_rows` counts again from the new position.
+    pub fn seek_row(&mut self, n: usize) -> DataFileResult<()> {
+        let length = self.options.record_format.record_length().ok_or(DataFileError::SeekUnsupported)?;
+        let offset = (n * length) as u64;
+        self.reader.seek(SeekFrom::Start(offset))
+            .map_err(|e| DataFileError::FileError(self.path.clone(), e))?;
+        self.offset = offset;
+        self.next_index = n;
+        self.taken = 0;
+        self.done = false;
+        Ok(())
+    }
+
+    /// Continue from a checkpoint recorded by an earlier reader of the same file, with the same
+    /// options, so that an interrupted load doesn't have to start over. Line indexes and the
+    /// summary's counts carry on from the checkpoint; earlier warnings aren't kept, and control
+    /// totals and duplicate checks only cover the rows read after resuming.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{Checkpoint, DataFileReader, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// # fn load_saved_checkpoint() -> Checkpoint { Checkpoint::default() }
+    /// # fn save_checkpoint(_: Checkpoint) {}
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let mut reader = DataFileReader::open(Path::new("huge.txt"), &defs, LoadOptions::default()).unwrap();
+    /// reader.resume(&load_saved_checkpoint()).unwrap();
+    /// while let Some(row) = reader.next() {
+    ///     println!("{}", row.unwrap().get("Field_1").unwrap().data());
+    ///     if reader.position() % 100_000 == 0 {
+    ///         save_checkpoint(reader.checkpoint());
+    ///     }
+    /// }
+    /// ```
+    pub fn resume(&mut self, checkpoint: &Checkpoint) -> DataFileResult<()> {
+        self.reader.seek(SeekFrom::Start(checkpoint.byte_offset))
+            .map_err(|e| DataFileError::FileError(self.path.clone(), e))?;
+        self.offset = checkpoint.byte_offset;
+        self.next_index = checkpoint.line_index;
+        self.taken = checkpoint.records_taken;
+        self.summary = LoadSummary {
+            rows_read: checkpoint.rows_read,
+            rows_loaded: checkpoint.rows_loaded,
+            rows_skipped: checkpoint.rows_skipped,
+            rows_rejected: checkpoint.rows_rejected,
+            ..Default::default()
+        };
+        self.warnings.clear();
+        self.controls = ControlTally::default();
+        self.seen = SeenKeys::default();
+        self.done = false;
+        Ok(())
+    }
+
+    /// Seek to the nth (0-based) record and parse just that row, for spot checks and pagination.
+    ///
+    /// Returns None past the end of the file, or if the record was skipped or rejected, in which
+    /// case the reason is added to `warnings()`. The reader is left positioned after the record.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFileReader, DataFieldDef, DataFieldResult, LoadOptions, RecordFormat};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let options = LoadOptions { record_format: RecordFormat::FixedLength(200), ..Default::default() };
+    /// let mut reader = DataFileReader::open(Path::new("extract.dat"), &defs, options).unwrap();
+    /// let row = reader.row_at(1_000_000).unwrap();
+    /// ```
+    pub fn row_at(&mut self, n: usize) -> DataFileResult<Option<DataRow>> {
+        self.seek_row(n)?;
+        self.next_row().transpose().map(Option::flatten)
+    }
+}
+
+impl<R: BufRead> Iterator for DataFileReader<'_, R> {
+    type Item = DataFileResult<DataRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let span = self.span.clone();
+            match span.in_scope(|| self.next_row()) {
+                None => {
+                    self.done = true;
+                    let first_warning = self.warnings.len();
+                    let last_line_index = self.next_index.saturating_sub(1);
+                    if let Err(e) = self.controls.finish(&self.options, self.row_defs, last_line_index, &mut self.warnings) {
+                        return Some(Err(e));
+                    }
+                    self.warnings.extend(LoadWarning::no_rows(self.offset == 0, &self.summary, self.options.skip_rows));
+                    self.warnings[first_warning..].iter().for_each(trace::warning);
+                    self.options.finished(&self.summary());
+                }
+                Some(Ok(None)) => continue,
+                Some(Ok(Some(row))) => return Some(Ok(row)),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::{Cursor, Write};
+    use crate::{Continuation, DataFile, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn records(prefixes: &[&str]) -> String {
+        prefixes.iter().map(|p| format!("{:<200}", p)).collect()
+    }
+
+    #[test]
+    fn streams_rows_with_options() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("header\n{:<200}\n\n{:<200}\nshort\n", "001", "002");
+        let options = LoadOptions { skip_rows: 1, skip_blank_lines: true, ..Default::default() };
+        let mut reader = DataFileReader::new(Cursor::new(data), &defs, options);
+
+        let ids: Vec<String> = (&mut reader).map(|r| r.unwrap().get("Id").unwrap().data()).collect();
+        assert_eq!(ids, vec!["001", "002"]);
+        assert_eq!(reader.warnings().len(), 1);
+        assert_eq!(reader.warnings()[0].line_number(), 5);
+        let summary = reader.summary();
+        assert_eq!((summary.rows_read, summary.rows_loaded, summary.rows_skipped, summary.rows_rejected), (4, 2, 1, 1));
+        assert_eq!(reader.take_warnings().len(), 1);
+        assert!(reader.warnings().is_empty());
+        assert_eq!(reader.summary().warning_count(), 1);
+    }
+
+    #[test]
+    fn resumed_from_checkpoint() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("header\n{:<200}\nshort\n{:<200}\n{:<200}\n", "001", "002", "003");
+        let options = LoadOptions { skip_rows: 1, ..Default::default() };
+        let mut reader = DataFileReader::new(Cursor::new(data.clone()), &defs, options.clone());
+        assert!(reader.next().unwrap().is_ok());
+        let checkpoint = reader.checkpoint();
+        assert_eq!((checkpoint.byte_offset, checkpoint.line_index), (208, 2));
+
+        let mut resumed = DataFileReader::new(Cursor::new(data), &defs, options);
+        resumed.resume(&checkpoint).unwrap();
+        let rows: Vec<DataRow> = (&mut resumed).collect::<DataFileResult<_>>().unwrap();
+        assert_eq!(rows.iter().map(|r| r.line_index().unwrap()).collect::<Vec<usize>>(), [3, 4]);
+        assert_eq!(resumed.warnings()[0].line_index(), 2);
+        let summary = resumed.summary();
+        assert_eq!((summary.rows_read, summary.rows_loaded, summary.rows_rejected), (4, 3, 1));
+    }
+
+    #[test]
+    fn streamed_error_located() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("{:<200}\nbad ñ\n", "001");
+        let mut reader = DataFileReader::new(Cursor::new(data), &defs, LoadOptions::default());
+        assert!(reader.next().unwrap().is_ok());
+        let e = reader.next().unwrap().unwrap_err();
+        let location = e.location().unwrap();
+        assert_eq!((location.line_number, location.byte_offset), (2, 205));
+        assert!(matches!(e.root(), DataFileError::NonASCIIFile));
+    }
+
+    #[test]
+    fn continuation_streamed() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("{:<100}+\n{:<100}\n", "001", "x");
+        let options = LoadOptions { continuation: Some(Continuation::new(100, '+')), ..Default::default() };
+        let rows: Vec<DataRow> = DataFileReader::new(Cursor::new(data), &defs, options)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].line_index(), Some(0));
+    }
+
+    #[test]
+    fn seek_to_fixed_length_record() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = records(&["000", "001", "002", "003"]);
+        let options = LoadOptions { record_format: RecordFormat::FixedLength(200), ..Default::default() };
+        let mut reader = DataFileReader::new(Cursor::new(data.clone()), &defs, options);
+
+        let row = reader.row_at(2).unwrap().unwrap();
+        assert_eq!(row.get("Id").unwrap().data(), "002");
+        assert_eq!(row.line_index(), Some(2));
+        assert_eq!(reader.next().unwrap().unwrap().get("Id").unwrap().data(), "003");
+        assert!(reader.next().is_none());
+        assert!(reader.row_at(4).unwrap().is_none());
+
+        reader.seek_row(1).unwrap();
+        assert_eq!(reader.count(), 3);
+
+        let mut reader = DataFileReader::new(Cursor::new(data.clone()), &defs, LoadOptions::default());
+        assert!(matches!(reader.seek_row(1), Err(DataFileError::SeekUnsupported)));
+
+        let overlapping = vec![DataFieldDef::new("Id", 0, 3, &echo_ok), DataFieldDef::new("Code", 2, 4, &echo_ok)];
+        let options = LoadOptions { record_format: RecordFormat::FixedLength(200), ..Default::default() };
+        let mut reader = DataFileReader::new(Cursor::new(data), &overlapping, options);
+        assert!(matches!(reader.row_at(2), Err(DataFileError::OverlappingFields(..))));
+    }
+
+    #[test]
+    fn tail_waits_for_complete_rows() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let path = std::env::temp_dir().join(format!("ffreader_tail_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\n{:<100}", "001", "002")).unwrap();
+
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let mut file = OpenOptions::new().append(true).open(writer_path).unwrap();
+            write!(file, "{:<100}\n{:<200}\n", "", "003").unwrap();
+        });
+
+        let reader = DataFileReader::open(&path, &defs, LoadOptions::default()).unwrap()
+            .tail(Duration::from_millis(5));
+        let ids: Vec<String> = reader.take(3).map(|r| r.unwrap().get("Id").unwrap().data()).collect();
+        assert_eq!(ids, vec!["001", "002", "003"]);
+
+        writer.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn overlong_records_fail_with_offset() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("{:<200}\n{}\n{:<200}\n", "001", "X".repeat(5000), "003");
+        let options = LoadOptions { max_line_length: Some(1000), ..Default::default() };
+
+        let results: Vec<DataFileResult<DataRow>> = DataFileReader::new(Cursor::new(&data), &defs, options.clone()).collect();
+        assert_eq!(results.len(), 2);
+        let Err(DataFileError::AtLocation(location, e)) = &results[1] else { panic!("expected an error") };
+        assert!(matches!(**e, DataFileError::LineTooLong(1000)));
+        assert_eq!((location.line_number, location.byte_offset), (2, 201));
+
+        let Err(e) = DataFile::try_from_str(&data, &defs, &options) else { panic!("expected an error") };
+        assert_eq!(e.to_string(), format!("Data File Error: Record longer than 1000 bytes (missing line breaks?) at line 2, byte 201 ({:?})",
+                                          "X".repeat(20)));
+        let options = LoadOptions { max_line_length: Some(200), ..Default::default() };
+        assert_eq!(DataFileReader::new(Cursor::new(format!("{:<200}\r\n", "001")), &defs, options).count(), 1);
+    }
+}
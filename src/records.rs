@@ -0,0 +1,356 @@
+use std::borrow::Cow;
+use std::io::{BufRead, Read};
+use std::path::Path;
+use crate::{DataFileError, LoadWarning};
+
+/// Records along with the 0-based index of the line each one starts on.
+pub(crate) type IndexedRecords<'a> = Vec<(usize, Cow<'a, str>)>;
+
+/// A record read from a stream.
+pub(crate) struct StreamRecord {
+    /// The record, without any terminator or descriptor.
+    pub(crate) bytes: Vec<u8>,
+    /// The number of bytes consumed from the stream.
+    pub(crate) consumed: usize,
+    /// False if the input ended before the record did; more may be written later.
+    pub(crate) complete: bool
+}
+
+/// Describes how the contents of a file are divided into records.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RecordFormat {
+    /// Records are lines ending with `\n` or `\r\n` (the default).
+    #[default]
+    Lines,
+    /// Records are terminated by the given byte sequence (e.g., `b"\x1E"` or `b"\0"`) rather
+    /// than a line break. A separator at the very end of the file does not start a new record.
+    Separator(Vec<u8>),
+    /// Records are a fixed number of bytes with no separators between them.
+    /// A trailing line break at the end of the file is ignored.
+    FixedLength(usize),
+    /// Records are variable length, each preceded by a 4 byte record descriptor word (RDW)
+    /// as produced by mainframe transfers: a big-endian 2 byte length that includes the
+    /// RDW itself, followed by 2 reserved bytes. The RDW is not included in the record.
+    Rdw
+}
+
+impl RecordFormat {
+    /// Length of a record descriptor word.
+    const RDW_LENGTH: usize = 4;
+
+    /// Split the data into records according to the format.
+    pub(crate) fn split<'a>(&self, data: &'a [u8]) -> Result<Vec<&'a [u8]>, DataFileError> {
+        match self {
+            RecordFormat::Lines => Ok(split_lines(data)),
+            RecordFormat::Separator(separator) => Ok(split_separator(data, separator)),
+            RecordFormat::FixedLength(length) => Ok(split_fixed(data, *length)),
+            RecordFormat::Rdw => split_rdw(data)
+        }
+    }
+
+    /// Read the next record from a stream, or None at the end of input. Records are the same as
+    /// `split()` would produce, except that a record cut short by the end of input is returned
+    /// marked incomplete rather than rejected; `offset` is the position of the stream in the
+    /// file, used when reporting an invalid record descriptor. Line and separator records are
+    /// read up to `limit` bytes and a little more, so that a record longer than the limit can be
+    /// told from one that isn't without reading all of it.
+    pub(crate) fn read_record<R: BufRead>(&self, reader: &mut R, path: &Path, offset: u64, limit: usize)
+        -> Result<Option<StreamRecord>, DataFileError> {
+        let io_error = |e| DataFileError::FileError(path.into(), e);
+        let mut bytes = vec![];
+        let (consumed, complete) = match self {
+            RecordFormat::Lines => {
+                let mut reader = reader.take((limit as u64).saturating_add(2));
+                let consumed = reader.read_until(b'\n', &mut bytes).map_err(io_error)?;
+                let complete = bytes.ends_with(b"\n");
+                if complete {
+                    bytes.pop();
+                }
+                if bytes.ends_with(b"\r") {
+                    bytes.pop();
+                }
+                (consumed, complete)
+            }
+            RecordFormat::Separator(separator) => match separator.last() {
+                None => (reader.read_to_end(&mut bytes).map_err(io_error)?, true),
+                Some(last) => {
+                    let mut reader = reader.take((limit as u64).saturating_add(separator.len() as u64 + 1));
+                    let mut consumed = 0;
+                    loop {
+                        let n = reader.read_until(*last, &mut bytes).map_err(io_error)?;
+                        consumed += n;
+                        if n == 0 || bytes.ends_with(separator) {
+                            break;
+                        }
+                    }
+                    let complete = bytes.ends_with(separator);
+                    if complete {
+                        bytes.truncate(bytes.len() - separator.len());
+                    }
+                    (consumed, complete)
+                }
+            },
+            RecordFormat::FixedLength(length) => {
+                let consumed = reader.by_ref().take(*length as u64).read_to_end(&mut bytes).map_err(io_error)?;
+                if consumed < *length && bytes.iter().all(|b| *b == b'\r' || *b == b'\n') {
+                    return Ok(None);
+                }
+                (consumed, consumed == *length)
+            }
+            RecordFormat::Rdw => {
+                let mut rdw = vec![];
+                let consumed = reader.by_ref().take(Self::RDW_LENGTH as u64).read_to_end(&mut rdw).map_err(io_error)?;
+                if consumed < Self::RDW_LENGTH {
+                    (consumed, false)
+                } else {
+                    let length = u16::from_be_bytes([rdw[0], rdw[1]]) as usize;
+                    if length < Self::RDW_LENGTH {
+                        return Err(DataFileError::InvalidRecordDescriptor(offset as usize));
+                    }
+                    let payload = length - Self::RDW_LENGTH;
+                    let read = reader.by_ref().take(payload as u64).read_to_end(&mut bytes).map_err(io_error)?;
+                    (consumed + read, read == payload)
+                }
+            }
+        };
+
+        Ok((consumed > 0).then_some(StreamRecord { bytes, consumed, complete }))
+    }
+
+    /// The number of bytes preceding each record's data (its descriptor word, if any).
+    pub(crate) fn header_length(&self) -> usize {
+        match self {
+            RecordFormat::Rdw => Self::RDW_LENGTH,
+            _ => 0
+        }
+    }
+
+    /// The length of every record, if the format has one.
+    pub(crate) fn record_length(&self) -> Option<usize> {
+        match self {
+            RecordFormat::FixedLength(length) => Some(*length),
+            _ => None
+        }
+    }
+}
+
+/// Describes a layout where one logical record spans several physical lines.
+///
+/// A line whose indicator column holds `indicator` is continued by the following line; the lines
+/// are concatenated as-is (indicator included) before field definitions are applied, so offsets
+/// refer to positions in the joined record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Continuation {
+    /// The 0-based column holding the continuation indicator.
+    pub column: usize,
+    /// The indicator value meaning the record continues on the next line.
+    pub indicator: char,
+    /// The indicator value expected on the final line of a record, if the layout has one.
+    /// When set, any other value in the indicator column is reported as a mismatch.
+    pub end_indicator: Option<char>
+}
+
+impl Continuation {
+    /// Convenience function to instantiate a Continuation with no end indicator.
+    pub fn new(column: usize, indicator: char) -> Continuation {
+        Continuation {
+            column,
+            indicator,
+            end_indicator: None
+        }
+    }
+
+    /// Join continued lines into logical records, keeping the index of each record's first line.
+    /// Mismatched indicators and records left open at the end of input are added to `warnings`.
+    pub(crate) fn join<'a>(&self, lines: IndexedRecords<'a>, warnings: &mut Vec<LoadWarning>) -> IndexedRecords<'a> {
+        let mut records = vec![];
+        let mut current: Option<(usize, Cow<'a, str>)> = None;
+
+        for (line_index, line) in lines {
+            let continues = self.continues(line_index, &line, warnings);
+            current = match current.take() {
+                Some((first, mut record)) => {
+                    record.to_mut().push_str(&line);
+                    Some((first, record))
+                }
+                None => Some((line_index, line))
+            };
+
+            if !continues {
+                records.extend(current.take());
+            }
+        }
+
+        if let Some((first, record)) = current {
+            warnings.push(Self::unterminated(first, &record));
+            records.push((first, record));
+        }
+
+        records
+    }
+
+    /// Check whether a line is continued by the next, adding a warning for a mismatched indicator.
+    pub(crate) fn continues(&self, line_index: usize, line: &str, warnings: &mut Vec<LoadWarning>) -> bool {
+        let value = line.as_bytes().get(self.column).map(|b| *b as char);
+        let continues = value == Some(self.indicator);
+        if let (Some(end), Some(v)) = (self.end_indicator, value) {
+            if !continues && v != end {
+                warnings.push(LoadWarning::continuation_mismatch(line_index, line,
+                    format!("Unexpected continuation indicator '{}' at column {}", v, self.column)));
+            }
+        }
+        continues
+    }
+
+    /// The warning for a record still open at the end of input.
+    pub(crate) fn unterminated(line_index: usize, record: &str) -> LoadWarning {
+        LoadWarning::continuation_mismatch(line_index, record, "Record continued past end of input".to_string())
+    }
+}
+
+/// Split data into lines on `\n`, removing any `\r` preceding it, as `str::lines()` does.
+/// Line breaks are found with memchr, which uses SIMD instructions where the CPU has them.
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    let mut records = Vec::with_capacity(data.len() / 128);
+    let mut start = 0;
+    for end in memchr::memchr_iter(b'\n', data) {
+        records.push(&data[start..end]);
+        start = end + 1;
+    }
+    if start < data.len() {
+        records.push(&data[start..]);
+    }
+    records.into_iter()
+        .map(|r| r.strip_suffix(b"\r").unwrap_or(r))
+        .collect()
+}
+
+fn split_separator<'a>(data: &'a [u8], separator: &[u8]) -> Vec<&'a [u8]> {
+    if separator.is_empty() {
+        return if data.is_empty() { vec![] } else { vec![data] };
+    }
+
+    let mut records = vec![];
+    let mut start = 0;
+    for i in memchr::memmem::find_iter(data, separator) {
+        records.push(&data[start..i]);
+        start = i + separator.len();
+    }
+    if start < data.len() {
+        records.push(&data[start..]);
+    }
+    records
+}
+
+fn split_fixed(data: &[u8], length: usize) -> Vec<&[u8]> {
+    if length == 0 {
+        return vec![];
+    }
+    data.chunks(length)
+        .filter(|c| c.len() == length || c.iter().any(|b| *b != b'\r' && *b != b'\n'))
+        .collect()
+}
+
+fn split_rdw(data: &[u8]) -> Result<Vec<&[u8]>, DataFileError> {
+    let mut records = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        let rdw = data.get(offset..offset + RecordFormat::RDW_LENGTH)
+            .ok_or(DataFileError::InvalidRecordDescriptor(offset))?;
+        let length = u16::from_be_bytes([rdw[0], rdw[1]]) as usize;
+        if length < RecordFormat::RDW_LENGTH {
+            return Err(DataFileError::InvalidRecordDescriptor(offset));
+        }
+        let record = data.get(offset + RecordFormat::RDW_LENGTH..offset + length)
+            .ok_or(DataFileError::InvalidRecordDescriptor(offset))?;
+        records.push(record);
+        offset += length;
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_match_str_lines() {
+        for s in ["", "a", "a\n", "a\r\nb", "a\n\nb\n", "\n"] {
+            let expected: Vec<&[u8]> = s.lines().map(|l| l.as_bytes()).collect();
+            assert_eq!(RecordFormat::Lines.split(s.as_bytes()).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn continuations_joined() {
+        let lines = ["AB+", "CD-", "EF ", "GH+"].iter().enumerate()
+            .map(|(i, l)| (i, Cow::Borrowed(*l)))
+            .collect();
+        let mut warnings = vec![];
+        let continuation = Continuation { end_indicator: Some('-'), ..Continuation::new(2, '+') };
+
+        let records = continuation.join(lines, &mut warnings);
+        assert_eq!(records, vec![(0, Cow::from("AB+CD-")), (2, Cow::from("EF ")), (3, Cow::from("GH+"))]);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].line_number(), 3);
+        assert_eq!(warnings[1].message(), "Record continued past end of input");
+    }
+
+    #[test]
+    fn separator_split() {
+        let r = RecordFormat::Separator(b"\x1E".to_vec()).split(b"ab\x1Ecd\x1E").unwrap();
+        assert_eq!(r, vec![&b"ab"[..], b"cd"]);
+        let r = RecordFormat::Separator(b"<>".to_vec()).split(b"a<>b<><>c").unwrap();
+        assert_eq!(r, vec![&b"a"[..], b"b", b"", b"c"]);
+        let r = RecordFormat::Separator(b"\0".to_vec()).split(b"").unwrap();
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn fixed_and_rdw_split() {
+        let r = RecordFormat::FixedLength(3).split(b"abcdefgh\n").unwrap();
+        assert_eq!(r, vec![&b"abc"[..], b"def", b"gh\n"]);
+        let r = RecordFormat::FixedLength(3).split(b"abcdef\r\n").unwrap();
+        assert_eq!(r, vec![&b"abc"[..], b"def"]);
+
+        let r = RecordFormat::Rdw.split(b"\x00\x07\x00\x00abc\x00\x05\x00\x00d").unwrap();
+        assert_eq!(r, vec![&b"abc"[..], b"d"]);
+        match RecordFormat::Rdw.split(b"\x00\x07\x00\x00abc\x00\x09\x00\x00d").unwrap_err() {
+            DataFileError::InvalidRecordDescriptor(7) => {}
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn streamed_records_match_split() {
+        let cases: [(RecordFormat, &[u8]); 6] = [
+            (RecordFormat::Lines, b"a\r\nb\n\nc"),
+            (RecordFormat::Separator(b"<>".to_vec()), b"a<>b<><>c<"),
+            (RecordFormat::Separator(vec![]), b"abc"),
+            (RecordFormat::FixedLength(3), b"abcdefgh\n"),
+            (RecordFormat::FixedLength(3), b"abcdef\r\n"),
+            (RecordFormat::Rdw, b"\x00\x07\x00\x00abc\x00\x05\x00\x00d"),
+        ];
+        for (format, data) in cases {
+            let mut reader = data;
+            let mut records = vec![];
+            while let Some(r) = format.read_record(&mut reader, Path::new(""), 0, usize::MAX).unwrap() {
+                records.push(r.bytes);
+            }
+            assert_eq!(records, format.split(data).unwrap(), "{:?}", format);
+        }
+
+        let mut reader = &b"ab\ncd"[..];
+        assert!(RecordFormat::Lines.read_record(&mut reader, Path::new(""), 0, usize::MAX).unwrap().unwrap().complete);
+        assert!(!RecordFormat::Lines.read_record(&mut reader, Path::new(""), 3, usize::MAX).unwrap().unwrap().complete);
+
+        let mut reader = &b"\x00\x07\x00\x00abc\x00\x09\x00\x00d\x00"[..];
+        let format = RecordFormat::Rdw;
+        assert!(format.read_record(&mut reader, Path::new(""), 0, usize::MAX).unwrap().unwrap().complete);
+        let truncated = format.read_record(&mut reader, Path::new(""), 7, usize::MAX).unwrap().unwrap();
+        assert_eq!((truncated.consumed, truncated.complete), (6, false));
+        let mut reader = &b"\x00\x02\x00\x00"[..];
+        assert!(matches!(format.read_record(&mut reader, Path::new(""), 0, usize::MAX),
+            Err(DataFileError::InvalidRecordDescriptor(0))));
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::{ControlCharPolicy, DataFieldDef, DataFieldError, DataFileError, DataFileResult, DataRow, DataRowError, DataRowResult, FieldErrorContext,
+            FieldErrorPolicy, LoadOptions, NameMatching};
+use crate::datafield::trim;
+use crate::datafile::{check_layout, check_units};
+
+/// Parses rows into a reusable DataRowBuf, for services parsing millions of rows a minute
+/// where allocating a DataRow of DataFields for each one is too costly. The definitions are
+/// checked, and the field names and conditions resolved, once, when the parser is made; each
+/// row is then parsed into buffers left over from the last, so that once they have grown to
+/// fit, rows are parsed without allocating (unless the post-processing functions allocate).
+///
+/// Rows are parsed as `DataRow::try_create_with_options()` parses them, except that typed
+/// parsers (see `DataFieldDef::with_parser()`) aren't run and raw lines aren't kept.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldResult, LoadOptions, RowParser};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+///     DataFieldDef::new("Status", 11, 13, &post_function),
+/// ];
+/// let parser = RowParser::new(&defs, LoadOptions::default()).unwrap();
+/// let mut buf = parser.buffer();
+/// for line in [format!("{:<200}", "5412345678 OP"), format!("{:<200}", "5499999999 CL")] {
+///     parser.parse_into(&mut buf, &line).unwrap();
+///     println!("{} {}", buf.get("AccountNo1").unwrap(), buf.get("Status").unwrap());
+/// }
+/// ```
+pub struct RowParser<'a> {
+    row_defs: &'a [DataFieldDef<'a>],
+    options: LoadOptions,
+    names: Arc<HashMap<String, usize>>,
+    conditions: Vec<Option<(Option<usize>, &'a [String])>>
+}
+
+/// The fields of a row parsed by a RowParser, held in buffers reused by each row parsed into
+/// it. Make one with `RowParser::buffer()`.
+pub struct DataRowBuf {
+    names: Arc<HashMap<String, usize>>,
+    name_matching: NameMatching,
+    raw: Vec<String>,
+    data: Vec<String>,
+    warnings: Vec<DataFieldError>
+}
+
+impl<'a> RowParser<'a> {
+    /// Make a parser for rows of the layout, loaded with the LoadOptions. Fails as loading
+    /// would if the layout is invalid (see `schema::validate()`), or with
+    /// `DataFileError::SplitterUnsupported` if a field is split into parts, as parts can't be
+    /// parsed into the buffer without allocating.
+    pub fn new(row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions) -> DataFileResult<Self> {
+        check_layout(row_defs)?;
+        check_units(row_defs, &options)?;
+        if let Some(def) = row_defs.iter().find(|d| d.splitter.is_some()) {
+            return Err(DataFileError::SplitterUnsupported(def.name.clone()));
+        }
+
+        let mut names = HashMap::new();
+        for (i, def) in row_defs.iter().enumerate() {
+            names.entry(options.name_matching.key(&def.name)).or_insert(i);
+        }
+        for (i, def) in row_defs.iter().enumerate() {
+            for alias in &def.aliases {
+                names.entry(options.name_matching.key(alias)).or_insert(i);
+            }
+        }
+        let conditions = row_defs.iter().enumerate()
+            .map(|(i, def)| def.condition.as_ref().map(|c| {
+                (c.controlling(&row_defs[..i], options.name_matching), c.values.as_slice())
+            }))
+            .collect();
+
+        Ok(RowParser { row_defs, options, names: Arc::new(names), conditions })
+    }
+
+    /// Make an empty buffer to parse rows into.
+    pub fn buffer(&self) -> DataRowBuf {
+        DataRowBuf {
+            names: Arc::clone(&self.names),
+            name_matching: self.options.name_matching,
+            raw: vec![String::new(); self.row_defs.len()],
+            data: vec![String::new(); self.row_defs.len()],
+            warnings: vec![]
+        }
+    }
+
+    /// Parse a row into the buffer, replacing the row it held. Fails as
+    /// `DataRow::try_create_with_options()` would, leaving the buffer holding part of the row.
+    /// The errors of fields downgraded to warnings by their FieldErrorPolicy, and possible
+    /// truncations, are available from `DataRowBuf::warnings()`.
+    pub fn parse_into(&self, buf: &mut DataRowBuf, row: &str) -> DataRowResult<()> {
+        let unit = self.options.columns;
+        DataRow::check_length(row, unit)?;
+        DataRow::check_extra_data(row, self.row_defs, &self.options)?;
+        DataRow::check_printable(row, self.row_defs, &self.options)?;
+
+        buf.warnings.clear();
+        let ascii = row.is_ascii();
+        let row_len = unit.len(row);
+        let mut errors = vec![];
+        for (i, def) in self.row_defs.iter().enumerate() {
+            buf.raw[i].clear();
+            let mut data = std::mem::take(&mut buf.data[i]);
+            data.clear();
+
+            if let Some((controlling, values)) = self.conditions[i] {
+                if !controlling.is_some_and(|c| values.contains(&buf.data[c])) {
+                    buf.data[i] = data;
+                    continue;
+                }
+            }
+            let (start_idx, end_idx) = def.span(row_len);
+            if start_idx > row_len {
+                buf.data[i] = data;
+                continue;
+            }
+
+            let context = || FieldErrorContext::in_unit(row, def, unit);
+            let end_idx = end_idx.min(row_len);
+            let mut control_warning = None;
+            let result = if start_idx > end_idx {
+                Err(DataFieldError::StartAfterEnd(context()))
+            } else if unit.ascii_only() && !ascii {
+                Err(DataFieldError::NonASCII(context()))
+            } else {
+                let raw = unit.slice(row, start_idx, end_idx);
+                buf.raw[i].push_str(raw);
+                let control = self.options.control_chars;
+                let (text, columns) = control.apply(raw, trim(raw, ascii), start_idx, unit);
+                let number = def.number_format.map(|f| f.normalize(&text));
+                match columns.is_empty() {
+                    false if control == ControlCharPolicy::Error => Err(DataFieldError::ControlCharacters(context(), columns)),
+                    _ if number == Some(None) => Err(DataFieldError::NotANumber(context())),
+                    empty => {
+                        control_warning = (!empty).then(|| DataFieldError::ControlCharacters(context(), columns));
+                        data.push_str(number.flatten().as_deref().unwrap_or(&text));
+                        def.post_process.call(data)
+                            .map_err(|e| {
+                                let context = FieldErrorContext { processor: def.post_process.name().map(String::from), ..context() };
+                                DataFieldError::PostProcess(context, Box::new(e))
+                            })
+                            .and_then(|d| match d.contains('"') {
+                                true => Err(DataFieldError::FieldContainsQuote(context())),
+                                false => Ok(d)
+                            })
+                    }
+                }
+            };
+
+            match (result, &def.on_error) {
+                (Ok(d), _) => {
+                    buf.warnings.extend(control_warning);
+                    if def.truncation_warning && def.fills_width(&buf.raw[i], unit) {
+                        buf.warnings.push(DataFieldError::PossibleTruncation(context()));
+                    }
+                    buf.data[i] = d;
+                }
+                (Err(e), _) if e.warning_value().is_some() => {
+                    buf.data[i] = e.warning_value().unwrap_or_default().to_string();
+                    buf.warnings.push(e);
+                }
+                (Err(e), FieldErrorPolicy::WarnAndUseDefault(d)) => {
+                    buf.data[i] = d.clone();
+                    buf.warnings.push(e);
+                }
+                (Err(e), FieldErrorPolicy::WarnAndBlank) => buf.warnings.push(e),
+                (Err(e), FieldErrorPolicy::Fail) if self.options.collect_all_field_errors => errors.push(e),
+                (Err(e), FieldErrorPolicy::Fail) => return Err(e.into())
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0).into()),
+            _ => Err(DataRowError::MultipleFieldErrors(errors))
+        }
+    }
+}
+
+impl DataRowBuf {
+    /// Get the processed data of the field with the given name or alias, empty if the field
+    /// has none. Names are matched as set by `LoadOptions::name_matching`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|i| self.data[i].as_str())
+    }
+
+    /// Get the raw text of the field with the given name or alias, as for `get()`.
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|i| self.raw[i].as_str())
+    }
+
+    /// Get the processed data of every field, in the order of the definitions.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().map(String::as_str)
+    }
+
+    /// Get the errors of the fields of the last row parsed that were downgraded to warnings,
+    /// and any possible truncations.
+    pub fn warnings(&self) -> &[DataFieldError] {
+        &self.warnings
+    }
+
+    /// Find the index of the field with the given name or alias.
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.get(&self.name_matching.key(name)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn rows_parsed_into_reused_buffer() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &numeric).with_alias("ID_NO"),
+            DataFieldDef::new("Flag", 4, 5, &echo_ok),
+            DataFieldDef::new("Unit", 5, 8, &echo_ok).only_when("Flag", &["U"]),
+            DataFieldDef::new("Code", 8, 10, &numeric).with_on_error(FieldErrorPolicy::WarnAndUseDefault("00".to_string())),
+        ];
+        let parser = RowParser::new(&defs, LoadOptions { name_matching: NameMatching::Normalized, ..Default::default() }).unwrap();
+        let mut buf = parser.buffer();
+
+        let lines = ["0001U12A42", "0002N12AXX", "00X3U12A42"].map(|l| format!("{:<200}", l));
+        parser.parse_into(&mut buf, &lines[0]).unwrap();
+        assert_eq!(buf.values().collect::<Vec<&str>>(), ["0001", "U", "12A", "42"]);
+        let capacity = buf.data[2].capacity();
+
+        parser.parse_into(&mut buf, &lines[1]).unwrap();
+        assert_eq!(buf.get("id_no"), Some("0002"));
+        assert_eq!((buf.get("Unit"), buf.raw("Unit")), (Some(""), Some("")));
+        assert_eq!(buf.get("Code"), Some("00"));
+        assert_eq!(buf.raw("Code"), Some("XX"));
+        assert_eq!(buf.warnings().len(), 1);
+        assert_eq!(buf.data[2].capacity(), capacity);
+
+        for line in &lines {
+            let row = DataRow::try_create_with_options(line, &defs, &LoadOptions::default());
+            assert_eq!(row.is_ok(), parser.parse_into(&mut buf, line).is_ok());
+        }
+        assert!(matches!(parser.parse_into(&mut buf, "short"), Err(DataRowError::BadRowLength(5))));
+        assert!(buf.get("Missing").is_none());
+
+        let split = vec![DataFieldDef::new("Key", 0, 4, &echo_ok).with_splitter(crate::Splitter::delimiter('-', &["A", "B"]))];
+        assert!(matches!(RowParser::new(&split, LoadOptions::default()), Err(DataFileError::SplitterUnsupported(n)) if n == "Key"));
+    }
+}
@@ -0,0 +1,1532 @@
+//! Tools for working with layouts (lists of `DataFieldDef`) as a whole.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::{Alignment, Anchor, BlankValue, ColumnUnit, DataFieldDef, DataFieldResult, DataFile, DataFileError, DataFileResult, FieldErrorPolicy,
+            FieldProcessor, LoadOptions, NumberFormat, PostProcess, Redaction};
+use crate::datafile::check_layout;
+use crate::glob::glob_match;
+use crate::format::json_string;
+#[cfg(any(feature = "markdown", feature = "html"))]
+use crate::SignPosition;
+#[cfg(feature = "markdown")]
+use crate::format::markdown_cell;
+#[cfg(feature = "html")]
+use crate::format::html_escape;
+
+/// The file formats a schema can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFormat {
+    /// A JSON object with a `fields` array.
+    Json,
+    /// A TOML document with a `[[fields]]` table per field.
+    Toml
+}
+
+/// A value in a schema document; rendered as JSON or TOML.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(usize),
+    Bool(bool),
+    List(Vec<Value>),
+    Table(Vec<(String, Value)>)
+}
+
+impl Value {
+    fn str(s: impl ToString) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Value::Str(s) => json_string(s),
+            Value::Int(i) => i.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::List(l) => format!("[{}]", l.iter().map(|v| v.to_json()).collect::<Vec<String>>().join(", ")),
+            Value::Table(t) => format!("{{{}}}", t.iter()
+                .map(|(k, v)| format!("{}: {}", json_string(k), v.to_json()))
+                .collect::<Vec<String>>().join(", "))
+        }
+    }
+
+    /// TOML values; basic strings use the same escapes as JSON and tables are written inline.
+    fn to_toml(&self) -> String {
+        match self {
+            Value::Table(t) => format!("{{ {} }}", t.iter()
+                .map(|(k, v)| format!("{} = {}", k, v.to_toml()))
+                .collect::<Vec<String>>().join(", ")),
+            Value::List(l) => format!("[{}]", l.iter().map(|v| v.to_toml()).collect::<Vec<String>>().join(", ")),
+            v => v.to_json()
+        }
+    }
+}
+
+/// Describe a field definition as a list of keys and values.
+fn describe(def: &DataFieldDef) -> Vec<(String, Value)> {
+    let mut entries = vec![
+        ("name".to_string(), Value::str(&def.name)),
+        ("start".to_string(), Value::Int(def.start_idx)),
+        ("end".to_string(), Value::Int(def.end_idx)),
+        ("length".to_string(), Value::Int(def.end_idx.saturating_sub(def.start_idx))),
+        ("alignment".to_string(), Value::str(match def.alignment {
+            Alignment::Left => "left",
+            Alignment::Right => "right"
+        })),
+        ("pad_char".to_string(), Value::str(def.pad_char)),
+        ("on_error".to_string(), match &def.on_error {
+            FieldErrorPolicy::Fail => Value::str("fail"),
+            FieldErrorPolicy::WarnAndBlank => Value::str("warn_and_blank"),
+            FieldErrorPolicy::WarnAndUseDefault(d) => Value::Table(vec![
+                ("warn_and_use_default".to_string(), Value::str(d))
+            ])
+        }),
+    ];
+
+    if let Some(description) = &def.description {
+        entries.push(("description".to_string(), Value::str(description)));
+    }
+
+    if let Some(process) = def.post_process.name() {
+        entries.push(("process".to_string(), Value::str(process)));
+    }
+
+    if let Some(condition) = &def.condition {
+        entries.push(("condition".to_string(), Value::Table(vec![
+            ("field".to_string(), Value::str(&condition.field)),
+            ("values".to_string(), Value::List(condition.values.iter().map(Value::str).collect())),
+        ])));
+    }
+
+    if let Some(unit) = def.unit {
+        entries.push(("unit".to_string(), Value::str(unit.name())));
+    }
+
+    if def.anchor == Anchor::End {
+        entries.push(("anchor".to_string(), Value::str("end")));
+    }
+
+    if def.overlaps_allowed {
+        entries.push(("overlaps_allowed".to_string(), Value::Bool(true)));
+    }
+
+    if def.truncation_warning {
+        entries.push(("warn_on_truncation".to_string(), Value::Bool(true)));
+    }
+
+    if def.dictionary_len().is_some() {
+        entries.push(("dictionary".to_string(), Value::Bool(true)));
+    }
+
+    if !def.aliases.is_empty() {
+        entries.push(("aliases".to_string(), Value::List(def.aliases.iter().map(Value::str).collect())));
+    }
+
+    if def.implied_decimals > 0 {
+        entries.push(("implied_decimals".to_string(), Value::Int(def.implied_decimals)));
+    }
+
+    if let Some(number_format) = &def.number_format {
+        let mut format = vec![("decimal".to_string(), Value::str(number_format.decimal))];
+        if let Some(grouping) = number_format.grouping {
+            format.push(("grouping".to_string(), Value::str(grouping)));
+        }
+        format.push(("sign".to_string(), Value::str(number_format.sign_name())));
+        entries.push(("number_format".to_string(), Value::Table(format)));
+    }
+
+    if let Some(blank) = def.blank {
+        entries.push(("blank".to_string(), Value::str(match blank {
+            BlankValue::AsIs => "as_is",
+            BlankValue::Empty => "empty",
+            BlankValue::Null => "null"
+        })));
+    }
+
+    // hash salts are secrets, so only the kind of redaction is recorded for them
+    if let Some(redaction) = &def.redaction {
+        entries.push(("redaction".to_string(), match redaction {
+            Redaction::MaskAllButLast(n) => Value::Table(vec![("mask_all_but_last".to_string(), Value::Int(*n))]),
+            Redaction::Hash(_) => Value::str("hash"),
+            Redaction::Token(t) => Value::Table(vec![("token".to_string(), Value::str(t))])
+        }));
+    }
+
+    entries
+}
+
+/// Produce a document describing the layout: field names, offsets, lengths, and options.
+/// Post-processing functions are code and are not included, but the names of named processors
+/// are, so that they can be looked up again on import; see `ProcessorRegistry`.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldResult};
+/// use ffreader::schema::{export, SchemaFormat};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)];
+/// assert!(export(&defs, SchemaFormat::Toml).starts_with("[[fields]]\nname = \"AccountNo1\"\nstart = 0\n"));
+/// ```
+pub fn export(defs: &[DataFieldDef], format: SchemaFormat) -> String {
+    match format {
+        SchemaFormat::Json => {
+            let fields = defs.iter()
+                .map(|d| format!("    {}", Value::Table(describe(d)).to_json()))
+                .collect::<Vec<String>>();
+            format!("{{\n  \"fields\": [\n{}\n  ]\n}}\n", fields.join(",\n"))
+        }
+        SchemaFormat::Toml => {
+            defs.iter()
+                .map(|d| {
+                    let entries = describe(d).iter()
+                        .map(|(k, v)| format!("{} = {}\n", k, v.to_toml()))
+                        .collect::<String>();
+                    format!("[[fields]]\n{}", entries)
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+    }
+}
+
+/// The headings of the columns of a data dictionary; see `document()`.
+#[cfg(any(feature = "markdown", feature = "html"))]
+const DOCUMENT_HEADINGS: [&str; 6] = ["Field", "Positions", "Length", "Type", "Rules", "Description"];
+
+/// Describe each field of a layout for a data dictionary, under `DOCUMENT_HEADINGS`.
+#[cfg(any(feature = "markdown", feature = "html"))]
+fn document_rows(defs: &[DataFieldDef]) -> Vec<[String; 6]> {
+    defs.iter().map(|def| {
+        let length = def.end_idx.saturating_sub(def.start_idx);
+        let positions = match (def.anchor, def.start_idx) {
+            (Anchor::Start, _) if length == 0 => "-".to_string(),
+            (Anchor::Start, start) => format!("{}-{}", start + 1, def.end_idx),
+            (Anchor::End, 0) => "last".to_string(),
+            (Anchor::End, start) => format!("ending {} before the end", start)
+        };
+        let kind = match (&def.number_format, def.implied_decimals) {
+            (None, 0) => "Text".to_string(),
+            (_, 0) => "Number".to_string(),
+            (_, n) => format!("Number, {} implied decimal{}", n, if n == 1 { "" } else { "s" })
+        };
+
+        let mut rules = vec![];
+        if let Some(process) = def.post_process.name() {
+            rules.push(format!("Checked by {}", process));
+        }
+        if let Some(condition) = &def.condition {
+            rules.push(format!("Only when {} is {}", condition.field, condition.values.join(" or ")));
+        }
+        if let Some(format) = &def.number_format {
+            let grouping = format.grouping.map(|g| format!(", grouped with '{}'", g)).unwrap_or_default();
+            let sign = match format.sign {
+                SignPosition::Either => "",
+                SignPosition::Leading => ", sign first",
+                SignPosition::Trailing => ", sign last"
+            };
+            rules.push(format!("Decimal '{}'{}{}", format.decimal, grouping, sign));
+        }
+        if def.alignment == Alignment::Right || def.pad_char != ' ' {
+            let alignment = match def.alignment {
+                Alignment::Left => "Left",
+                Alignment::Right => "Right"
+            };
+            rules.push(format!("{}-aligned, padded with '{}'", alignment, def.pad_char));
+        }
+        match &def.on_error {
+            FieldErrorPolicy::Fail => {}
+            FieldErrorPolicy::WarnAndBlank => rules.push("Blank if invalid".to_string()),
+            FieldErrorPolicy::WarnAndUseDefault(d) => rules.push(format!("\"{}\" if invalid", d))
+        }
+        if def.redaction.is_some() {
+            rules.push("Sensitive".to_string());
+        }
+        if !def.aliases.is_empty() {
+            rules.push(format!("Also called {}", def.aliases.join(", ")));
+        }
+
+        [def.name.clone(), positions, length.to_string(), kind, rules.join("; "),
+         def.description.clone().unwrap_or_default()]
+    }).collect()
+}
+
+/// Produce a Markdown data dictionary of the layout, a table row per field giving its name,
+/// positions (numbered from 1, inclusive, as in most vendor specifications), length, type,
+/// rules and description, so that the documentation handed to vendors is generated from the
+/// definitions the parser uses. Rules come from the field's options, and name its processor if
+/// it is a named FieldProcessor; descriptions are set with `DataFieldDef::with_description()`.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldResult, FieldErrorPolicy};
+/// use ffreader::schema::document;
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![
+///     DataFieldDef::new("AccountNo", 0, 10, &post_function).with_description("Customer account number"),
+///     DataFieldDef::new("Balance", 10, 21, &post_function).with_implied_decimals(2)
+///         .with_on_error(FieldErrorPolicy::WarnAndBlank),
+/// ];
+/// assert_eq!(document(&defs), concat!(
+///     "| Field | Positions | Length | Type | Rules | Description |\n",
+///     "| --- | --- | --- | --- | --- | --- |\n",
+///     "| AccountNo | 1-10 | 10 | Text |  | Customer account number |\n",
+///     "| Balance | 11-21 | 11 | Number, 2 implied decimals | Blank if invalid |  |\n"));
+/// ```
+#[cfg(feature = "markdown")]
+pub fn document(defs: &[DataFieldDef]) -> String {
+    let line = |values: &[String]| format!("| {} |\n", values.iter()
+        .map(|v| markdown_cell(v))
+        .collect::<Vec<String>>()
+        .join(" | "));
+
+    let mut markdown = line(&DOCUMENT_HEADINGS.map(String::from));
+    markdown.push_str(&line(&DOCUMENT_HEADINGS.map(|_| "---".to_string())));
+    for row in document_rows(defs) {
+        markdown.push_str(&line(&row));
+    }
+    markdown
+}
+
+/// Produce the data dictionary of `document()` as an HTML table.
+#[cfg(feature = "html")]
+pub fn document_html(defs: &[DataFieldDef]) -> String {
+    let line = |tag: &str, values: &[String]| format!("<tr>{}</tr>\n", values.iter()
+        .map(|v| format!("<{}>{}</{}>", tag, html_escape(v), tag))
+        .collect::<String>());
+
+    let mut html = String::from("<table>\n<thead>\n");
+    html.push_str(&line("th", &DOCUMENT_HEADINGS.map(String::from)));
+    html.push_str("</thead>\n<tbody>\n");
+    for row in document_rows(defs) {
+        html.push_str(&line("td", &row));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+/// An error found while reading a schema document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// Description of the problem.
+    pub message: String
+}
+
+impl SchemaError {
+    fn new(message: impl ToString) -> SchemaError {
+        SchemaError { message: message.to_string() }
+    }
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Schema error: {}", self.message)
+    }
+}
+
+impl Error for SchemaError { }
+
+/// Convenient Result shorthand for SchemaError results.
+pub type Result<T> = std::result::Result<T, SchemaError>;
+
+/// Reads the subset of JSON used by schema documents: objects, arrays, strings, booleans and
+/// non-negative integers.
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize
+}
+
+impl JsonParser {
+    /// The deepest nesting of arrays and objects accepted, well beyond any schema document, so
+    /// that malicious input fails rather than overflowing the stack.
+    const MAX_DEPTH: usize = 128;
+
+    fn new(text: &str) -> JsonParser {
+        JsonParser { chars: text.chars().collect(), pos: 0, depth: 0 }
+    }
+
+    /// Enter an array or object, failing if nested too deeply.
+    fn nest(&mut self) -> Result<()> {
+        self.depth += 1;
+        match self.depth > Self::MAX_DEPTH {
+            true => Err(self.error("Nested too deeply")),
+            false => Ok(())
+        }
+    }
+
+    fn error(&self, message: &str) -> SchemaError {
+        SchemaError::new(format!("{} at character {}", message, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) != Some(&c) {
+            return Err(self.error(&format!("Expected '{}'", c)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consume `c` if it is the next character, after any whitespace.
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.chars.get(self.pos) == Some(&c);
+        self.pos += found as usize;
+        found
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some('{') => {
+                self.nest()?;
+                self.pos += 1;
+                let mut entries = vec![];
+                if !self.accept('}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        entries.push((key, self.value()?));
+                        if self.accept('}') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                self.depth -= 1;
+                Ok(Value::Table(entries))
+            }
+            Some('[') => {
+                self.nest()?;
+                self.pos += 1;
+                let mut items = vec![];
+                if !self.accept(']') {
+                    loop {
+                        items.push(self.value()?);
+                        if self.accept(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                self.depth -= 1;
+                Ok(Value::List(items))
+            }
+            Some('"') => Ok(Value::Str(self.string()?)),
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                digits.parse().map(Value::Int).map_err(|_| self.error("Number too large"))
+            }
+            Some('t') | Some('f') => {
+                for (word, value) in [("true", true), ("false", false)] {
+                    if self.chars[self.pos..].starts_with(&word.chars().collect::<Vec<char>>()) {
+                        self.pos += word.len();
+                        return Ok(Value::Bool(value));
+                    }
+                }
+                Err(self.error("Expected true or false"))
+            }
+            _ => Err(self.error("Expected a string, number, array or object"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return Err(self.error("Expected a string"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or_else(|| self.error("Unterminated string"))?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let e = *self.chars.get(self.pos).ok_or_else(|| self.error("Unterminated string"))?;
+                    self.pos += 1;
+                    out.push(match e {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            self.pos += 4;
+                            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                                .ok_or_else(|| self.error("Bad unicode escape"))?
+                        }
+                        c => c
+                    });
+                }
+                c => out.push(c)
+            }
+        }
+    }
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(t) => t.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    fn as_bool(&self, what: &str) -> Result<bool> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(SchemaError::new(format!("{} should be true or false", what)))
+        }
+    }
+
+    fn as_str(&self, what: &str) -> Result<&str> {
+        match self {
+            Value::Str(s) => Ok(s),
+            _ => Err(SchemaError::new(format!("{} should be a string", what)))
+        }
+    }
+
+    fn as_int(&self, what: &str) -> Result<usize> {
+        match self {
+            Value::Int(i) => Ok(*i),
+            _ => Err(SchemaError::new(format!("{} should be a number", what)))
+        }
+    }
+}
+
+/// The post-processing function given to imported fields, which leaves values unchanged.
+fn unchanged(value: String) -> DataFieldResult<String> {
+    Ok(value)
+}
+
+/// Build a field definition from its description, the inverse of `describe()`, looking up
+/// the post-processing it names in the registry.
+fn field_from(value: &Value, processors: &ProcessorRegistry) -> Result<DataFieldDef<'static>> {
+    let name = value.get("name").ok_or(SchemaError::new("Field without a name"))?.as_str("name")?;
+    let what = |key: &str| format!("{} of field {}", key, name);
+    let start = value.get("start").ok_or_else(|| SchemaError::new(format!("No start for field {}", name)))?
+        .as_int(&what("start"))?;
+    let end = match (value.get("end"), value.get("length")) {
+        (Some(end), _) => end.as_int(&what("end"))?,
+        (None, Some(length)) => start.checked_add(length.as_int(&what("length"))?)
+            .ok_or_else(|| SchemaError::new(format!("Start and length of field {} too large", name)))?,
+        (None, None) => return Err(SchemaError::new(format!("No end or length for field {}", name)))
+    };
+    let mut def = DataFieldDef::new(name, start, end, &unchanged);
+
+    if let Some(description) = value.get("description") {
+        def.description = Some(description.as_str(&what("description"))?.to_string());
+    }
+    if let Some(process) = value.get("process") {
+        let process = process.as_str(&what("process"))?;
+        def.post_process = processors.get(process)
+            .ok_or_else(|| SchemaError::new(format!("Unknown processor {} for field {}", process, name)))?;
+    }
+
+    if let Some(alignment) = value.get("alignment") {
+        def.alignment = match alignment.as_str(&what("alignment"))? {
+            "left" => Alignment::Left,
+            "right" => Alignment::Right,
+            a => return Err(SchemaError::new(format!("Unknown alignment {} for field {}", a, name)))
+        };
+    }
+    if let Some(pad_char) = value.get("pad_char") {
+        let mut chars = pad_char.as_str(&what("pad_char"))?.chars();
+        def.pad_char = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err(SchemaError::new(format!("{} should be one character", what("pad_char"))))
+        };
+    }
+    if let Some(on_error) = value.get("on_error") {
+        def.on_error = match (on_error, on_error.get("warn_and_use_default")) {
+            (Value::Str(s), _) if s == "fail" => FieldErrorPolicy::Fail,
+            (Value::Str(s), _) if s == "warn_and_blank" => FieldErrorPolicy::WarnAndBlank,
+            (_, Some(default)) => FieldErrorPolicy::WarnAndUseDefault(default.as_str(&what("default"))?.to_string()),
+            _ => return Err(SchemaError::new(format!("Unknown on_error for field {}", name)))
+        };
+    }
+    if let Some(condition) = value.get("condition") {
+        let field = condition.get("field").ok_or_else(|| SchemaError::new(format!("No condition field for {}", name)))?
+            .as_str(&what("condition field"))?;
+        let values = match condition.get("values") {
+            Some(Value::List(l)) => l.iter().map(|v| v.as_str(&what("condition value"))).collect::<Result<Vec<&str>>>()?,
+            _ => return Err(SchemaError::new(format!("No condition values for {}", name)))
+        };
+        def = def.only_when(field, &values);
+    }
+    if let Some(redaction) = value.get("redaction") {
+        def.redaction = Some(match (redaction, redaction.get("mask_all_but_last"), redaction.get("token")) {
+            (_, Some(n), _) => Redaction::MaskAllButLast(n.as_int(&what("mask_all_but_last"))?),
+            (_, _, Some(t)) => Redaction::Token(t.as_str(&what("token"))?.to_string()),
+            (Value::Str(s), _, _) if s == "hash" => return Err(SchemaError::new(format!(
+                "Hash salts are not stored in schemas; remove the redaction of {} and set it with DataFile::set_redaction()", name))),
+            _ => return Err(SchemaError::new(format!("Unknown redaction for field {}", name)))
+        });
+    }
+    if let Some(implied_decimals) = value.get("implied_decimals") {
+        def.implied_decimals = implied_decimals.as_int(&what("implied_decimals"))?;
+    }
+    if let Some(unit) = value.get("unit") {
+        let unit = unit.as_str(&what("unit"))?;
+        def.unit = Some(ColumnUnit::from_name(unit)
+            .ok_or_else(|| SchemaError::new(format!("Unknown unit {} for field {}", unit, name)))?);
+    }
+    if let Some(anchor) = value.get("anchor") {
+        def.anchor = match anchor.as_str(&what("anchor"))? {
+            "start" => Anchor::Start,
+            "end" => Anchor::End,
+            a => return Err(SchemaError::new(format!("Unknown anchor {} for field {}", a, name)))
+        };
+    }
+    if let Some(overlaps_allowed) = value.get("overlaps_allowed") {
+        def.overlaps_allowed = overlaps_allowed.as_bool(&what("overlaps_allowed"))?;
+    }
+    if let Some(truncation_warning) = value.get("warn_on_truncation") {
+        def.truncation_warning = truncation_warning.as_bool(&what("warn_on_truncation"))?;
+    }
+    if let Some(dictionary) = value.get("dictionary") {
+        if dictionary.as_bool(&what("dictionary"))? {
+            def = def.with_dictionary();
+        }
+    }
+    if let Some(aliases) = value.get("aliases") {
+        def.aliases = match aliases {
+            Value::List(l) => l.iter().map(|a| a.as_str(&what("alias")).map(String::from)).collect::<Result<Vec<String>>>()?,
+            _ => return Err(SchemaError::new(format!("Expected a list of aliases for field {}", name)))
+        };
+    }
+    if let Some(number_format) = value.get("number_format") {
+        let char_of = |key: &str| -> Result<Option<char>> {
+            let Some(value) = number_format.get(key) else { return Ok(None) };
+            let mut chars = value.as_str(&what(key))?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Some(c)),
+                _ => Err(SchemaError::new(format!("{} should be one character", what(key))))
+            }
+        };
+        let mut format = NumberFormat::new();
+        format.decimal = char_of("decimal")?.unwrap_or(format.decimal);
+        format.grouping = char_of("grouping")?;
+        if let Some(sign) = number_format.get("sign") {
+            let sign = sign.as_str(&what("sign"))?;
+            format.sign = NumberFormat::sign_from_name(sign)
+                .ok_or_else(|| SchemaError::new(format!("Unknown sign {} for field {}", sign, name)))?;
+        }
+        def.number_format = Some(format);
+    }
+    if let Some(blank) = value.get("blank") {
+        def.blank = Some(match blank.as_str(&what("blank"))? {
+            "as_is" => BlankValue::AsIs,
+            "empty" => BlankValue::Empty,
+            "null" => BlankValue::Null,
+            b => return Err(SchemaError::new(format!("Unknown blank {} for field {}", b, name)))
+        });
+    }
+
+    Ok(def)
+}
+
+/// Read a layout from a JSON document as produced by `export()`: an object with a `fields`
+/// array, or just the array. Each field needs a name, a start, and an end or length; the other
+/// options are optional. Imported fields have no post-processing; use `import_json_with()`
+/// for layouts naming processors with `process`.
+///
+/// Hash redactions cannot be imported, since their salts are not exported.
+/// ```
+/// use ffreader::schema::import_json;
+/// let defs = import_json(r#"{"fields": [{"name": "AccountNo1", "start": 0, "length": 11}]}"#).unwrap();
+/// assert_eq!(defs[0].end_idx, 11);
+/// ```
+pub fn import_json(text: &str) -> Result<Vec<DataFieldDef<'static>>> {
+    import_json_with(text, &ProcessorRegistry::new())
+}
+
+/// Read a layout from a JSON document as `import_json()` does, giving each field that names a
+/// processor with `process` the one registered under that name. Fails if it isn't registered.
+/// ```
+/// use ffreader::processors::collapse_whitespace;
+/// use ffreader::schema::{import_json_with, ProcessorRegistry};
+/// let mut processors = ProcessorRegistry::new();
+/// processors.register("trim_zeros", |v: String| Ok(v.trim_start_matches('0').to_string()));
+/// processors.register("collapse", collapse_whitespace());
+/// let defs = import_json_with(r#"[{"name": "Id", "start": 0, "length": 6, "process": "trim_zeros"}]"#,
+///                             &processors).unwrap();
+/// assert_eq!(defs[0].post_process.call("000042".to_string()).unwrap(), "42");
+/// ```
+pub fn import_json_with(text: &str, processors: &ProcessorRegistry) -> Result<Vec<DataFieldDef<'static>>> {
+    let mut parser = JsonParser::new(text);
+    let document = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("Unexpected text after the document"));
+    }
+
+    match document.get("fields").unwrap_or(&document) {
+        Value::List(fields) => fields.iter().map(|f| field_from(f, processors)).collect(),
+        _ => Err(SchemaError::new("Expected an array of fields"))
+    }
+}
+
+/// A processor registered by name, which reports that name in errors and exported schemas.
+struct NamedProcessor {
+    name: String,
+    process: PostProcess<'static>
+}
+
+impl FieldProcessor for NamedProcessor {
+    fn process(&self, value: String) -> DataFieldResult<String> {
+        self.process.call(value)
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+/// Post-processing functions registered in code under names that schema documents refer to,
+/// so that layouts kept in files can still validate and transform their fields; see
+/// `import_json_with()`. Fields given a registered processor export its name, so that layouts
+/// round-trip.
+#[derive(Default, Clone)]
+pub struct ProcessorRegistry {
+    processors: HashMap<String, PostProcess<'static>>
+}
+
+impl ProcessorRegistry {
+    /// Create an empty registry.
+    pub fn new() -> ProcessorRegistry {
+        ProcessorRegistry { processors: HashMap::new() }
+    }
+
+    /// Register a function under a name. Registering a name again replaces the function.
+    pub fn register(&mut self, name: impl ToString, f: impl Fn(String) -> DataFieldResult<String> + Send + Sync + 'static) {
+        self.register_processor(name, f);
+    }
+
+    /// Register a FieldProcessor under a name, which it is then known by in errors in place of
+    /// its own.
+    pub fn register_processor(&mut self, name: impl ToString, processor: impl FieldProcessor + Send + 'static) {
+        let name = name.to_string();
+        let process = PostProcess::Processor(Arc::new(processor));
+        self.processors.insert(name.clone(), PostProcess::Processor(Arc::new(NamedProcessor { name, process })));
+    }
+
+    /// Get the processor registered under a name, to give to a field's `post_process`.
+    pub fn get(&self, name: &str) -> Option<PostProcess<'static>> {
+        self.processors.get(name).cloned()
+    }
+
+    /// Get the registered names, in no particular order.
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.keys().map(String::as_str).collect()
+    }
+}
+
+/// A field's name and position, as reported by a SchemaDiff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// The name of the field.
+    pub name: String,
+    /// The start index of the field.
+    pub start_idx: usize,
+    /// The (exclusive) end index of the field.
+    pub end_idx: usize
+}
+
+impl FieldSpan {
+    fn of(def: &DataFieldDef) -> FieldSpan {
+        FieldSpan {
+            name: def.name.clone(),
+            start_idx: def.start_idx,
+            end_idx: def.end_idx
+        }
+    }
+}
+
+impl Display for FieldSpan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}-{}]", self.name, self.start_idx, self.end_idx)
+    }
+}
+
+/// The differences between two layouts, as produced by `diff()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Fields only in the new layout.
+    pub added: Vec<FieldSpan>,
+    /// Fields only in the old layout.
+    pub removed: Vec<FieldSpan>,
+    /// Fields at the same position under a different name, as (old, new).
+    pub renamed: Vec<(FieldSpan, FieldSpan)>,
+    /// Fields with the same name that moved or changed size, as (old, new).
+    pub resized: Vec<(FieldSpan, FieldSpan)>
+}
+
+impl SchemaDiff {
+    /// True if the layouts have the same fields at the same positions.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.resized.is_empty()
+    }
+}
+
+impl Display for SchemaDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for a in &self.added {
+            writeln!(f, "+ {}", a)?;
+        }
+        for r in &self.removed {
+            writeln!(f, "- {}", r)?;
+        }
+        for (o, n) in &self.renamed {
+            writeln!(f, "~ {} renamed to {}", o, n.name)?;
+        }
+        for (o, n) in &self.resized {
+            writeln!(f, "* {} now [{}-{}]", o, n.start_idx, n.end_idx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two layouts, reporting added, removed, renamed, and resized fields.
+///
+/// Fields are matched by name. A field removed from the old layout and a field added to the
+/// new layout at exactly the same position are reported as a rename.
+pub fn diff(old: &[DataFieldDef], new: &[DataFieldDef]) -> SchemaDiff {
+    let mut result = SchemaDiff::default();
+
+    for o in old {
+        if let Some(n) = new.iter().find(|n| n.name == o.name) {
+            if (n.start_idx, n.end_idx) != (o.start_idx, o.end_idx) {
+                result.resized.push((FieldSpan::of(o), FieldSpan::of(n)));
+            }
+        }
+    }
+
+    let mut removed: Vec<FieldSpan> = old.iter()
+        .filter(|o| !new.iter().any(|n| n.name == o.name))
+        .map(FieldSpan::of)
+        .collect();
+    for n in new.iter().filter(|n| !old.iter().any(|o| o.name == n.name)) {
+        let added = FieldSpan::of(n);
+        match removed.iter().position(|r| (r.start_idx, r.end_idx) == (added.start_idx, added.end_idx)) {
+            Some(i) => result.renamed.push((removed.remove(i), added)),
+            None => result.added.push(added)
+        }
+    }
+    result.removed = removed;
+
+    result
+}
+
+/// Check a layout for the mistakes that loading with it would report: two fields sharing a name
+/// or alias (`DataFileError::DuplicateFieldName`), or overlapping fields where neither allows
+/// overlaps (`DataFileError::OverlappingFields`). Fields with conditions may share names and
+/// overlap.
+pub fn validate(defs: &[DataFieldDef]) -> DataFileResult<()> {
+    check_layout(defs)
+}
+
+/// A possible problem with a field definition, as found by `check()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldIssue {
+    /// The field was blank in every row checked.
+    AlwaysBlank,
+    /// In this many rows a word runs on past the end of the field, so it may be too short.
+    Truncated(usize),
+    /// In this many rows a word starts before the field, so it may start too late.
+    StartsEarly(usize),
+    /// The last column always holds data running into the next column, but is sometimes
+    /// separated from the rest of the value; it probably belongs to the next field.
+    TakesNextField,
+    /// The first column always holds data running on from the previous column, but is sometimes
+    /// separated from the rest of the value; it probably belongs to the previous field.
+    TakesPreviousField
+}
+
+impl Display for FieldIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldIssue::AlwaysBlank => write!(f, "always blank"),
+            FieldIssue::Truncated(n) => write!(f, "value runs past the end in {} rows", n),
+            FieldIssue::StartsEarly(n) => write!(f, "value starts before the field in {} rows", n),
+            FieldIssue::TakesNextField => write!(f, "last column appears to belong to the next field"),
+            FieldIssue::TakesPreviousField => write!(f, "first column appears to belong to the previous field")
+        }
+    }
+}
+
+/// A possible problem with one field of a layout, as found by `check()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiagnostic {
+    /// The name of the field.
+    pub name: String,
+    /// What appears to be wrong.
+    pub issue: FieldIssue
+}
+
+impl Display for FieldDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.issue)
+    }
+}
+
+/// Check a layout against the first `n` lines of a file, reporting fields that look misplaced,
+/// to catch off-by-one offsets while a layout is being written.
+///
+/// Only the raw text of each line is examined; post-processing isn't run. A word (letters on both
+/// sides of a boundary) cut by a field boundary suggests the boundary is wrong, while a column
+/// that always runs into its neighbor but is sometimes set apart from the rest of its own field
+/// suggests it belongs to the neighbor. Adjacent numeric fields commonly run together, so digits
+/// alone are not reported as truncated. Fields anchored to the end of the row are not checked.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult};
+/// use ffreader::schema::check;
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Name", 0, 20, &post_function)];
+/// for diagnostic in check(Path::new("extract.txt"), &defs, 100).unwrap() {
+///     println!("{}", diagnostic);
+/// }
+/// ```
+pub fn check(path: &Path, defs: &[DataFieldDef], n: usize) -> DataFileResult<Vec<FieldDiagnostic>> {
+    let file = File::open(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+    let lines = BufReader::new(file).lines().take(n)
+        .collect::<std::io::Result<Vec<String>>>()
+        .map_err(|e| DataFileError::FileError(path.into(), e))?;
+
+    let mut diagnostics = vec![];
+    for def in defs {
+        if def.anchor == Anchor::End {
+            continue;
+        }
+        let (start, end) = (def.start_idx, def.end_idx);
+        let at = |line: &str, i: usize| line.as_bytes().get(i).copied().unwrap_or(b' ');
+        let filled: Vec<&str> = lines.iter()
+            .map(String::as_str)
+            .filter(|l| (start..end).any(|i| !at(l, i).is_ascii_whitespace()))
+            .collect();
+        let mut report = |issue| diagnostics.push(FieldDiagnostic { name: def.name.clone(), issue });
+
+        if filled.is_empty() {
+            report(FieldIssue::AlwaysBlank);
+            continue;
+        }
+        if end <= start {
+            continue;
+        }
+        let is_word = |line: &str, a: usize, b: usize| at(line, a).is_ascii_alphabetic() && at(line, b).is_ascii_alphabetic();
+        let solid = |line: &str, a: usize, b: usize| !at(line, a).is_ascii_whitespace() && !at(line, b).is_ascii_whitespace();
+
+        let truncated = filled.iter().filter(|l| is_word(l, end - 1, end)).count();
+        if truncated > 0 {
+            report(FieldIssue::Truncated(truncated));
+        }
+        let early = match start {
+            0 => 0,
+            s => filled.iter().filter(|l| is_word(l, s - 1, s)).count()
+        };
+        if early > 0 {
+            report(FieldIssue::StartsEarly(early));
+        }
+        if end - start >= 2 && filled.iter().all(|l| solid(l, end - 1, end))
+            && filled.iter().any(|l| at(l, end - 2).is_ascii_whitespace()) {
+            report(FieldIssue::TakesNextField);
+        }
+        if start > 0 && end - start >= 2 && filled.iter().all(|l| solid(l, start - 1, start))
+            && filled.iter().any(|l| at(l, start + 1).is_ascii_whitespace()) {
+            report(FieldIssue::TakesPreviousField);
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// How one column of a file is used, as found by `coverage()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnUse {
+    /// The number of lines checked with anything but whitespace in the column.
+    pub filled: usize,
+    /// The first field of the layout covering the column, if any.
+    pub field: Option<String>
+}
+
+/// How often each byte column of a sample of lines holds data, and which field covers it, as
+/// found by `coverage()`. Displayed as a text chart, a row per column, which makes a
+/// mis-specified offset stand out as data in a column no field covers, or a field whose edge
+/// columns are never filled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnCoverage {
+    /// The number of lines checked.
+    pub rows: usize,
+    /// Each column, from the first to the end of the longest line or of the layout, whichever
+    /// is further.
+    pub columns: Vec<ColumnUse>
+}
+
+impl ColumnCoverage {
+    /// Width of the bar charting how often a column is filled.
+    const BAR_WIDTH: usize = 20;
+
+    /// Tally the columns of the lines against the layout.
+    fn of(lines: &[String], defs: &[DataFieldDef]) -> ColumnCoverage {
+        let width = lines.iter().map(String::len)
+            .chain(defs.iter().filter(|d| d.anchor == Anchor::Start).map(|d| d.end_idx))
+            .max()
+            .unwrap_or_default();
+        let columns = (0..width).map(|i| ColumnUse {
+            filled: lines.iter().filter(|l| l.as_bytes().get(i).is_some_and(|b| !b.is_ascii_whitespace())).count(),
+            field: defs.iter()
+                .find(|d| d.anchor == Anchor::Start && (d.start_idx..d.end_idx).contains(&i))
+                .map(|d| d.name.clone())
+        }).collect();
+        ColumnCoverage { rows: lines.len(), columns }
+    }
+
+    /// Get the columns holding data in some line that no field covers.
+    pub fn uncovered(&self) -> Vec<usize> {
+        self.columns.iter().enumerate()
+            .filter(|(_, c)| c.filled > 0 && c.field.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl Display for ColumnCoverage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "column  filled  {:<width$}  field", "", width = Self::BAR_WIDTH)?;
+        for (i, column) in self.columns.iter().enumerate() {
+            let (percent, bar) = match self.rows {
+                0 => (0, 0),
+                rows => ((column.filled * 100 + rows / 2) / rows, (column.filled * Self::BAR_WIDTH + rows / 2) / rows)
+            };
+            writeln!(f, "{:>6}  {:>5}%  {:<width$}  {}", i, percent, "#".repeat(bar),
+                     column.field.as_deref().unwrap_or("(none)"), width = Self::BAR_WIDTH)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scan the first `n` lines of a file and report, for each byte column, how often it holds
+/// anything but whitespace and which field of the layout covers it; see `ColumnCoverage`.
+/// Only the raw text is examined, and fields anchored to the end of the row are left out.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult};
+/// use ffreader::schema::coverage;
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Name", 0, 20, &post_function)];
+/// let coverage = coverage(Path::new("extract.txt"), &defs, 1000).unwrap();
+/// print!("{}", coverage);
+/// println!("Data outside any field in columns {:?}", coverage.uncovered());
+/// ```
+pub fn coverage(path: &Path, defs: &[DataFieldDef], n: usize) -> DataFileResult<ColumnCoverage> {
+    let file = File::open(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+    let lines = BufReader::new(file).lines().take(n)
+        .collect::<std::io::Result<Vec<String>>>()
+        .map_err(|e| DataFileError::FileError(path.into(), e))?;
+    Ok(ColumnCoverage::of(&lines, defs))
+}
+
+/// How a layout version is recognized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSelector {
+    /// The file's header contains a value: the text at `start_idx..end_idx` (in characters) of
+    /// line `line_index` (0-based), trimmed, equals `value`.
+    HeaderValue {
+        /// The line holding the version indicator.
+        line_index: usize,
+        /// The start index of the indicator.
+        start_idx: usize,
+        /// The (exclusive) end index of the indicator.
+        end_idx: usize,
+        /// The indicator value identifying this version.
+        value: String
+    },
+    /// The file name (not the full path) matches a pattern, where `*` matches any run of
+    /// characters and `?` matches one character.
+    FilenamePattern(String)
+}
+
+impl VersionSelector {
+    /// Check whether the selector matches the file.
+    fn matches(&self, path: &Path) -> DataFileResult<bool> {
+        match self {
+            VersionSelector::FilenamePattern(pattern) => Ok(path.file_name()
+                .is_some_and(|n| glob_match(pattern, &n.to_string_lossy()))),
+            VersionSelector::HeaderValue { line_index, start_idx, end_idx, value } => {
+                let file = File::open(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+                let line = BufReader::new(file).lines().nth(*line_index)
+                    .transpose()
+                    .map_err(|e| DataFileError::FileError(path.into(), e))?;
+                Ok(line.is_some_and(|l| ColumnUnit::Chars.slice(&l, *start_idx, *end_idx).trim() == value))
+            }
+        }
+    }
+}
+
+/// One registered version of a layout.
+pub struct SchemaVersion<'a> {
+    /// The name of the version, e.g. "2024Q3".
+    pub version: String,
+    /// How files in this version are recognized.
+    pub selector: VersionSelector,
+    /// The field definitions for the version.
+    pub defs: Vec<DataFieldDef<'a>>
+}
+
+/// A set of layout versions for one feed, from which the right one is picked for each file.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult, LoadOptions};
+/// use ffreader::schema::{SchemaVersions, VersionSelector};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let mut versions = SchemaVersions::new();
+/// versions.register("v1", VersionSelector::FilenamePattern("EXTRACT_2023*.TXT".to_string()),
+///                   vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)]);
+/// versions.register("v2", VersionSelector::FilenamePattern("EXTRACT_2024*.TXT".to_string()),
+///                   vec![DataFieldDef::new("AccountNo1", 0, 12, &post_function)]);
+/// let df = versions.load(Path::new("EXTRACT_20240105.TXT"), &LoadOptions::default()).unwrap();
+/// ```
+#[derive(Default)]
+pub struct SchemaVersions<'a> {
+    versions: Vec<SchemaVersion<'a>>
+}
+
+impl<'a> SchemaVersions<'a> {
+    /// Create an empty set of versions.
+    pub fn new() -> SchemaVersions<'a> {
+        SchemaVersions { versions: vec![] }
+    }
+
+    /// Register a layout version. Versions are checked in the order registered.
+    pub fn register(&mut self, version: impl ToString, selector: VersionSelector, defs: Vec<DataFieldDef<'a>>) {
+        self.versions.push(SchemaVersion {
+            version: version.to_string(),
+            selector,
+            defs
+        });
+    }
+
+    /// Get the registered versions.
+    pub fn versions(&self) -> &Vec<SchemaVersion<'a>> {
+        &self.versions
+    }
+
+    /// Find the first registered version that applies to the file, if any.
+    pub fn select(&self, path: &Path) -> DataFileResult<Option<&SchemaVersion<'a>>> {
+        for v in &self.versions {
+            if v.selector.matches(path)? {
+                return Ok(Some(v));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load a file with the version of the layout that applies to it.
+    /// Fails with `DataFileError::NoMatchingLayout` if no version applies. An empty file, which
+    /// has no header to tell its version by, loads with the first version as a file with no
+    /// rows and a `WarningKind::NoRows` warning.
+    pub fn load(&self, path: &Path, options: &LoadOptions) -> DataFileResult<DataFile> {
+        let version = match self.select(path)? {
+            Some(v) => Some(v),
+            None => self.versions.first().filter(|_| std::fs::metadata(path).is_ok_and(|m| m.len() == 0))
+        };
+        match version {
+            Some(v) => DataFile::try_load_with_options(path, &v.defs, options),
+            None => Err(DataFileError::NoMatchingLayout(path.into()))
+        }
+    }
+}
+
+/// A layout held by a SchemaRegistry.
+pub struct RegisteredLayout<'a> {
+    /// The name of the layout, e.g. the vendor feed it describes.
+    pub name: String,
+    /// The version of the layout, e.g. "2024Q3".
+    pub version: String,
+    /// The file names (not full paths) the layout is used for, where `*` matches any run of
+    /// characters and `?` matches one character; None if it is only looked up by name.
+    pub filename_pattern: Option<String>,
+    /// The field definitions of the layout.
+    pub defs: Vec<DataFieldDef<'a>>
+}
+
+/// Holds the named, versioned layouts of an application reading many feeds, for looking them up
+/// by name and version or by file name, and for checking them all at startup.
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFieldDef, DataFieldResult, LoadOptions};
+/// use ffreader::schema::SchemaRegistry;
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let mut registry = SchemaRegistry::new();
+/// registry.register_for_files("accounts", "v1", "ACCT_*.TXT", vec![DataFieldDef::new("AccountNo1", 0, 11, &post_function)]);
+/// registry.register_json("meters", "2024Q3", Path::new("layouts/meters_2024q3.json")).unwrap();
+/// for (layout, e) in registry.validate() {
+///     panic!("Layout {} {} is invalid: {}", layout.name, layout.version, e);
+/// }
+/// let df = registry.load(Path::new("/feeds/ACCT_20240105.TXT"), &LoadOptions::default()).unwrap();
+/// let meters = registry.latest("meters").unwrap();
+/// ```
+#[derive(Default)]
+pub struct SchemaRegistry<'a> {
+    layouts: Vec<RegisteredLayout<'a>>,
+    processors: ProcessorRegistry
+}
+
+impl<'a> SchemaRegistry<'a> {
+    /// Create an empty registry.
+    pub fn new() -> SchemaRegistry<'a> {
+        SchemaRegistry { layouts: vec![], processors: ProcessorRegistry::new() }
+    }
+
+    /// Look up the processors named by layouts registered with `register_json()` in this
+    /// registry.
+    pub fn with_processors(mut self, processors: ProcessorRegistry) -> SchemaRegistry<'a> {
+        self.processors = processors;
+        self
+    }
+
+    /// Register a version of a layout, looked up by name. Registering a name and version again
+    /// replaces the layout.
+    pub fn register(&mut self, name: impl ToString, version: impl ToString, defs: Vec<DataFieldDef<'a>>) {
+        self.insert(RegisteredLayout { name: name.to_string(), version: version.to_string(), filename_pattern: None, defs });
+    }
+
+    /// Register a version of a layout, also used for the files whose names match the pattern;
+    /// see `for_file()`.
+    pub fn register_for_files(&mut self, name: impl ToString, version: impl ToString, pattern: impl ToString,
+                              defs: Vec<DataFieldDef<'a>>) {
+        self.insert(RegisteredLayout {
+            name: name.to_string(),
+            version: version.to_string(),
+            filename_pattern: Some(pattern.to_string()),
+            defs
+        });
+    }
+
+    /// Register a version of a layout read from a JSON schema document; see `import_json()`.
+    /// Processors it names are looked up in the registry given to `with_processors()`.
+    pub fn register_json(&mut self, name: impl ToString, version: impl ToString, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| SchemaError::new(format!("Could not read {}: {}", path.display(), e)))?;
+        self.register(name, version, import_json_with(&text, &self.processors)?);
+        Ok(())
+    }
+
+    /// Add a layout, replacing any of the same name and version.
+    fn insert(&mut self, layout: RegisteredLayout<'a>) {
+        match self.layouts.iter().position(|l| l.name == layout.name && l.version == layout.version) {
+            Some(i) => self.layouts[i] = layout,
+            None => self.layouts.push(layout)
+        }
+    }
+
+    /// Get the registered layouts, in the order registered.
+    pub fn layouts(&self) -> &Vec<RegisteredLayout<'a>> {
+        &self.layouts
+    }
+
+    /// Get a version of a layout.
+    pub fn get(&self, name: &str, version: &str) -> Option<&RegisteredLayout<'a>> {
+        self.layouts.iter().find(|l| l.name == name && l.version == version)
+    }
+
+    /// Get the last registered version of a layout.
+    pub fn latest(&self, name: &str) -> Option<&RegisteredLayout<'a>> {
+        self.layouts.iter().rev().find(|l| l.name == name)
+    }
+
+    /// Find the first registered layout whose file name pattern matches the file's name.
+    pub fn for_file(&self, path: &Path) -> Option<&RegisteredLayout<'a>> {
+        let file_name = path.file_name()?.to_string_lossy();
+        self.layouts.iter().find(|l| l.filename_pattern.as_ref().is_some_and(|p| glob_match(p, &file_name)))
+    }
+
+    /// Check every registered layout as `validate()` does, returning each invalid layout with
+    /// its error; empty if all are valid.
+    pub fn validate(&self) -> Vec<(&RegisteredLayout<'a>, DataFileError)> {
+        self.layouts.iter().filter_map(|l| validate(&l.defs).err().map(|e| (l, e))).collect()
+    }
+
+    /// Load a file with the layout its name matches; see `for_file()`. Fails with
+    /// `DataFileError::NoMatchingLayout` if none does.
+    pub fn load(&self, path: &Path, options: &LoadOptions) -> DataFileResult<DataFile> {
+        match self.for_file(path) {
+            Some(l) => DataFile::try_load_with_options(path, &l.defs, options),
+            None => Err(DataFileError::NoMatchingLayout(path.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn test_defs() -> Vec<DataFieldDef<'static>> {
+        vec![
+            DataFieldDef::new("Flag", 0, 1, &echo_ok),
+            DataFieldDef::new("Unit", 1, 6, &echo_ok)
+                .only_when("Flag", &["U"])
+                .with_alignment(Alignment::Right)
+                .with_on_error(FieldErrorPolicy::WarnAndUseDefault("0".to_string())),
+        ]
+    }
+
+    #[test]
+    fn json_export() {
+        let json = export(&test_defs(), SchemaFormat::Json);
+        assert_eq!(json, concat!(
+            "{\n  \"fields\": [\n",
+            "    {\"name\": \"Flag\", \"start\": 0, \"end\": 1, \"length\": 1, \"alignment\": \"left\", ",
+            "\"pad_char\": \" \", \"on_error\": \"fail\"},\n",
+            "    {\"name\": \"Unit\", \"start\": 1, \"end\": 6, \"length\": 5, \"alignment\": \"right\", ",
+            "\"pad_char\": \" \", \"on_error\": {\"warn_and_use_default\": \"0\"}, ",
+            "\"condition\": {\"field\": \"Flag\", \"values\": [\"U\"]}}\n",
+            "  ]\n}\n"));
+    }
+
+    #[test]
+    fn json_import() {
+        let mut defs = test_defs();
+        defs[0] = DataFieldDef::new("Flag", 0, 1, &echo_ok)
+            .with_redaction(Redaction::MaskAllButLast(2))
+            .with_implied_decimals(1)
+            .with_blank(BlankValue::Null)
+            .with_alias("FLAG-CD")
+            .with_anchor(Anchor::End)
+            .with_unit(ColumnUnit::Chars)
+            .allow_overlaps()
+            .warn_on_truncation()
+            .with_dictionary()
+            .with_number_format(NumberFormat::european().with_sign(crate::SignPosition::Trailing))
+            .with_pad_char('0')
+            .with_description("Account flag, \"F\" if frozen");
+        let imported = import_json(&export(&defs, SchemaFormat::Json)).unwrap();
+        assert_eq!(export(&imported, SchemaFormat::Json), export(&defs, SchemaFormat::Json));
+
+        let error = |text: &str| import_json(text).err().unwrap().message;
+        assert_eq!(error("[{\"name\": \"A\", \"start\": 0}]"),
+                   "No end or length for field A");
+        assert_eq!(error("{\"fields\": [}"),
+                   "Expected a string, number, array or object at character 12");
+        let hashed = "[{\"name\": \"A\", \"start\": 0, \"end\": 1, \"redaction\": \"hash\"}]";
+        assert!(import_json(hashed).is_err());
+        assert_eq!(error(&format!("[{{\"name\": \"A\", \"start\": {}, \"length\": 2}}]", usize::MAX)),
+                   "Start and length of field A too large");
+        assert_eq!(error(&"[".repeat(200_000)), "Nested too deeply at character 128");
+        assert_eq!(error(&format!("{}{}", "[".repeat(100), "]".repeat(100))), "Field without a name");
+    }
+
+    #[test]
+    fn named_processors_imported() {
+        let mut processors = ProcessorRegistry::new();
+        processors.register("trim_zeros", |v: String| Ok(v.trim_start_matches('0').to_string()));
+        let text = r#"[{"name": "Id", "start": 0, "length": 6, "process": "trim_zeros"}, {"name": "Name", "start": 6, "end": 20}]"#;
+        let defs = import_json_with(text, &processors).unwrap();
+        let df = DataFile::try_from_str(&format!("{:<200}\n", "000042ANN LEE"), &defs, &LoadOptions::default()).unwrap();
+        assert_eq!(df.rows()[0].get("Id").unwrap().data(), "42");
+        assert!(export(&defs, SchemaFormat::Json).contains("\"process\": \"trim_zeros\""));
+        assert_eq!(export(&import_json_with(&export(&defs, SchemaFormat::Json), &processors).unwrap(), SchemaFormat::Json),
+                   export(&defs, SchemaFormat::Json));
+
+        assert_eq!(import_json(text).err().unwrap().message, "Unknown processor trim_zeros for field Id");
+        let path = std::env::temp_dir().join(format!("ffreader_processors_{}.json", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let mut registry = SchemaRegistry::new().with_processors(processors);
+        registry.register_json("ids", "v1", &path).unwrap();
+        assert_eq!(registry.latest("ids").unwrap().defs[0].post_process.name(), Some("trim_zeros"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn toml_export() {
+        let toml = export(&test_defs(), SchemaFormat::Toml);
+        assert!(toml.contains("[[fields]]\nname = \"Unit\"\nstart = 1\nend = 6\nlength = 5\n"));
+        assert!(toml.contains("on_error = { warn_and_use_default = \"0\" }\n"));
+        assert!(toml.contains("condition = { field = \"Flag\", values = [\"U\"] }\n"));
+    }
+
+    #[cfg(all(feature = "markdown", feature = "html"))]
+    #[test]
+    fn data_dictionary_documented() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 6, &echo_ok).with_alignment(Alignment::Right).with_pad_char('0')
+                .with_description("Meter id | serial"),
+            DataFieldDef::new("Reading", 6, 16, &echo_ok)
+                .with_number_format(NumberFormat::european().with_sign(SignPosition::Trailing))
+                .with_alias("READ-AMT"),
+            DataFieldDef::new("Unit", 16, 18, &echo_ok).only_when("Flag", &["U", "V"])
+                .with_on_error(FieldErrorPolicy::WarnAndUseDefault("KW".to_string()))
+                .with_redaction(Redaction::Token("<unit>".to_string())),
+            DataFieldDef::new("Check", 0, 2, &echo_ok).with_anchor(Anchor::End),
+        ];
+        let markdown = document(&defs);
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[2], "| Id | 1-6 | 6 | Text | Right-aligned, padded with '0' | Meter id \\| serial |");
+        assert_eq!(lines[3], "| Reading | 7-16 | 10 | Number | Decimal ',', grouped with '.', sign last; Also called READ-AMT |  |");
+        assert_eq!(lines[4], "| Unit | 17-18 | 2 | Text | Only when Flag is U or V; \"KW\" if invalid; Sensitive |  |");
+        assert_eq!(lines[5], "| Check | last | 2 | Text |  |  |");
+
+        let html = document_html(&defs);
+        assert!(html.starts_with("<table>\n<thead>\n<tr><th>Field</th><th>Positions</th>"));
+        assert!(html.contains("<td>Unit</td><td>17-18</td><td>2</td><td>Text</td><td>Only when Flag is U or V; &quot;KW&quot; if invalid; Sensitive</td>"));
+    }
+
+    #[test]
+    fn layouts_compared() {
+        let old = vec![
+            DataFieldDef::new("Account", 0, 10, &echo_ok),
+            DataFieldDef::new("Cycle", 10, 12, &echo_ok),
+            DataFieldDef::new("Name", 12, 30, &echo_ok),
+            DataFieldDef::new("Legacy", 30, 32, &echo_ok),
+        ];
+        let new = vec![
+            DataFieldDef::new("Account", 0, 11, &echo_ok),
+            DataFieldDef::new("CycleNo", 10, 12, &echo_ok),
+            DataFieldDef::new("Name", 12, 30, &echo_ok),
+            DataFieldDef::new("Email", 32, 60, &echo_ok),
+        ];
+
+        let d = diff(&old, &new);
+        assert_eq!(d.added.iter().map(|f| f.name.as_str()).collect::<Vec<&str>>(), vec!["Email"]);
+        assert_eq!(d.removed.iter().map(|f| f.name.as_str()).collect::<Vec<&str>>(), vec!["Legacy"]);
+        assert_eq!((d.renamed[0].0.name.as_str(), d.renamed[0].1.name.as_str()), ("Cycle", "CycleNo"));
+        assert_eq!(d.resized[0].1.end_idx, 11);
+        assert_eq!(d.to_string(), "+ Email [32-60]\n- Legacy [30-32]\n~ Cycle [10-12] renamed to CycleNo\n* Account [0-10] now [0-11]\n");
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn versions_selected() {
+        let path = std::env::temp_dir().join(format!("ffreader_VERSIONED_{}.TXT", std::process::id()));
+        std::fs::write(&path, "HDR V2\n").unwrap();
+
+        let mut versions = SchemaVersions::new();
+        versions.register("by_name", VersionSelector::FilenamePattern("OTHER*.TXT".to_string()), vec![]);
+        versions.register("v1", VersionSelector::HeaderValue {
+            line_index: 0, start_idx: 4, end_idx: 6, value: "V1".to_string()
+        }, vec![]);
+        versions.register("v2", VersionSelector::HeaderValue {
+            line_index: 0, start_idx: 4, end_idx: 6, value: "V2".to_string()
+        }, vec![]);
+        assert_eq!(versions.select(&path).unwrap().unwrap().version, "v2");
+
+        versions.register("fallback", VersionSelector::FilenamePattern("ffreader_VERSIONED_*".to_string()), vec![]);
+        std::fs::write(&path, "HDR V3\n").unwrap();
+        assert_eq!(versions.select(&path).unwrap().unwrap().version, "fallback");
+        std::fs::write(&path, "HD\u{c9} V2\n").unwrap();
+        assert_eq!(versions.select(&path).unwrap().unwrap().version, "v2");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn check_finds_misplaced_fields() {
+        let path = std::env::temp_dir().join(format!("ffreader_check_{}.txt", std::process::id()));
+        std::fs::write(&path, "JONATHAN123\nALICE   456\nBOB     789\n").unwrap();
+
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 9, &echo_ok),
+            DataFieldDef::new("Code", 9, 11, &echo_ok),
+            DataFieldDef::new("Spare", 11, 15, &echo_ok),
+        ];
+        let found: Vec<String> = check(&path, &defs, 10).unwrap().iter().map(|d| d.to_string()).collect();
+        assert_eq!(found, ["Name: last column appears to belong to the next field", "Spare: always blank"]);
+
+        let defs = vec![DataFieldDef::new("Name", 0, 6, &echo_ok), DataFieldDef::new("Code", 6, 11, &echo_ok)];
+        let found = check(&path, &defs, 2).unwrap();
+        assert_eq!(found, [
+            FieldDiagnostic { name: "Name".to_string(), issue: FieldIssue::Truncated(1) },
+            FieldDiagnostic { name: "Code".to_string(), issue: FieldIssue::StartsEarly(1) },
+        ]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn column_coverage_charted() {
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok), DataFieldDef::new("Name", 4, 8, &echo_ok)];
+        let lines = ["001 ANN", "002 BOBX9", "003"].map(String::from);
+        let coverage = ColumnCoverage::of(&lines, &defs);
+        assert_eq!(coverage.rows, 3);
+        assert_eq!(coverage.columns.len(), 9);
+        assert_eq!(coverage.columns[0], ColumnUse { filled: 3, field: Some("Id".to_string()) });
+        assert_eq!(coverage.columns[3], ColumnUse { filled: 0, field: None });
+        assert_eq!(coverage.columns[7].filled, 1);
+        assert_eq!(coverage.uncovered(), [8]);
+
+        let chart = coverage.to_string();
+        assert_eq!(chart.lines().count(), 10);
+        assert_eq!(chart.lines().nth(1).unwrap(), format!("     0    100%  {}  Id", "#".repeat(20)));
+        assert_eq!(chart.lines().nth(5).unwrap(), format!("     4     67%  {:<20}  Name", "#".repeat(13)));
+        assert!(chart.ends_with("(none)\n"));
+    }
+
+    #[test]
+    fn overlaps_rejected_unless_allowed() {
+        let key = || DataFieldDef::new("Key", 0, 10, &echo_ok);
+        let branch = || DataFieldDef::new("Branch", 0, 4, &echo_ok);
+        match validate(&[key(), branch()]) {
+            Err(DataFileError::OverlappingFields(a, b)) => assert_eq!((a.as_str(), b.as_str()), ("Key", "Branch")),
+            _ => panic!()
+        }
+        assert!(validate(&[key().allow_overlaps(), branch(), DataFieldDef::new("Account", 4, 10, &echo_ok)]).is_ok());
+        assert!(validate(&[branch(), DataFieldDef::new("Account", 4, 10, &echo_ok)]).is_ok());
+    }
+
+    #[test]
+    fn layouts_registered_and_found() {
+        let path = std::env::temp_dir().join(format!("ffreader_registry_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"fields": [{"name": "Meter", "start": 0, "length": 8}]}"#).unwrap();
+
+        let mut registry = SchemaRegistry::new();
+        registry.register_for_files("accounts", "v1", "ACCT_2023*.TXT", vec![DataFieldDef::new("Account", 0, 10, &echo_ok)]);
+        registry.register_for_files("accounts", "v2", "ACCT_2024*.TXT", vec![DataFieldDef::new("Account", 0, 11, &echo_ok)]);
+        registry.register_json("meters", "v1", &path).unwrap();
+        registry.register("broken", "v1", vec![DataFieldDef::new("A", 0, 4, &echo_ok), DataFieldDef::new("A", 4, 8, &echo_ok)]);
+        assert!(registry.register_json("meters", "v2", Path::new("/no/such/layout.json")).is_err());
+
+        assert_eq!(registry.get("accounts", "v1").unwrap().defs[0].end_idx, 10);
+        assert_eq!(registry.latest("accounts").unwrap().version, "v2");
+        assert_eq!(registry.latest("meters").unwrap().defs[0].end_idx, 8);
+        assert_eq!(registry.for_file(Path::new("/feeds/ACCT_20240105.TXT")).unwrap().version, "v2");
+        assert!(registry.for_file(Path::new("METERS.TXT")).is_none());
+
+        let invalid = registry.validate();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0.name, "broken");
+        assert!(matches!(invalid[0].1, DataFileError::DuplicateFieldName(_)));
+
+        registry.register("broken", "v1", vec![]);
+        assert_eq!(registry.layouts().len(), 4);
+        assert!(registry.validate().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
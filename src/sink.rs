@@ -0,0 +1,260 @@
+use std::io;
+#[cfg(any(feature = "csv", feature = "json"))]
+use std::io::Write;
+#[cfg(feature = "csv")]
+use crate::FormulaGuard;
+#[cfg(feature = "json")]
+use crate::export::json_record;
+
+/// A destination for the exported rows of a Pipeline; see `Pipeline::write_to()`. CsvSink and
+/// JsonLinesSink write to any `Write`, and other destinations, such as a message queue or a
+/// database, can be targeted by implementing the trait.
+///
+/// Rows are given as the exported name and value of each field, after the pipeline's
+/// ExportOptions are applied; a None value is a null. Errors are returned as `io::Error`, which
+/// can wrap any error with `io::Error::other()`, and stop the pipeline.
+pub trait OutputSink {
+    /// Start the output with the exported names of the fields. Called once, before any rows,
+    /// even if none are written.
+    fn write_header(&mut self, names: &[String]) -> io::Result<()>;
+
+    /// Write the exported name and value of each field of a row; a None value is a null.
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()>;
+
+    /// End the output once every row is written, flushing anything buffered.
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for &mut S {
+    fn write_header(&mut self, names: &[String]) -> io::Result<()> {
+        (**self).write_header(names)
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        (**self).write_row(values)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for Box<S> {
+    fn write_header(&mut self, names: &[String]) -> io::Result<()> {
+        (**self).write_header(names)
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        (**self).write_row(values)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// Writes rows as CSV, with a header row of field names. Nulls are written as empty values.
+#[cfg(feature = "csv")]
+pub struct CsvSink<W: Write> {
+    writer: W,
+    formula_guard: FormulaGuard
+}
+
+#[cfg(feature = "csv")]
+impl<W: Write> CsvSink<W> {
+    /// Write CSV to a writer, which is best buffered.
+    pub fn new(writer: W) -> Self {
+        CsvSink { writer, formula_guard: FormulaGuard::default() }
+    }
+
+    /// Set how values that a spreadsheet would run as a formula are neutralized.
+    pub fn with_formula_guard(mut self, formula_guard: FormulaGuard) -> Self {
+        self.formula_guard = formula_guard;
+        self
+    }
+
+    /// Get the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a line of values.
+    fn write_line<'v>(&mut self, values: impl Iterator<Item = &'v str>) -> io::Result<()> {
+        let line = values.map(|v| self.formula_guard.csv_field(v)).collect::<Vec<String>>().join(",");
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<W: Write> OutputSink for CsvSink<W> {
+    fn write_header(&mut self, names: &[String]) -> io::Result<()> {
+        self.write_line(names.iter().map(String::as_str))
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        self.write_line(values.iter().map(|(_, v)| v.as_deref().unwrap_or_default()))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes rows as JSON Lines, an object per line.
+#[cfg(feature = "json")]
+pub struct JsonLinesSink<W: Write> {
+    writer: W
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> JsonLinesSink<W> {
+    /// Write JSON Lines to a writer, which is best buffered.
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer }
+    }
+
+    /// Get the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> OutputSink for JsonLinesSink<W> {
+    fn write_header(&mut self, _names: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        writeln!(self.writer, "{}", json_record(values))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Streams rows as one JSON record per line, flushing after each, to stdout or any `Write`, for
+/// piping into a message-bus producer at the head of a streaming ETL. Records never span lines,
+/// as line breaks in values are escaped, and with `with_key()` each line starts with a key and
+/// a separator, the framing of console producers such as
+/// `kafka-console-producer --property parse.key=true --property key.separator=<TAB>`.
+/// ```
+/// use std::io::Cursor;
+/// use ffreader::{DataFieldDef, DataFieldResult, DataFileReader, LoadOptions, Pipeline, StreamSink};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Id", 0, 4, &post_function), DataFieldDef::new("Name", 4, 10, &post_function)];
+/// let data = format!("{:<200}\n{:<200}\n", "0001ANN", "0002BOB");
+/// let mut sink = StreamSink::new(vec![]).with_key("Id", '\t');
+/// Pipeline::new(DataFileReader::new(Cursor::new(data), &defs, LoadOptions::default()))
+///     .write_to(&mut sink).unwrap();
+/// assert_eq!(String::from_utf8(sink.into_inner()).unwrap(),
+///            "0001\t{\"Id\": \"0001\",\"Name\": \"ANN\"}\n0002\t{\"Id\": \"0002\",\"Name\": \"BOB\"}\n");
+/// ```
+#[cfg(feature = "json")]
+pub struct StreamSink<W: Write> {
+    writer: W,
+    key: Option<(String, char)>
+}
+
+#[cfg(feature = "json")]
+impl StreamSink<io::Stdout> {
+    /// Stream records to stdout.
+    pub fn stdout() -> Self {
+        StreamSink::new(io::stdout())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> StreamSink<W> {
+    /// Stream records to a writer.
+    pub fn new(writer: W) -> Self {
+        StreamSink { writer, key: None }
+    }
+
+    /// Start each line with the value of an exported field as the record's key, followed by the
+    /// separator; a null key is written as an empty one. Writing a row fails if the field isn't
+    /// exported, or its value holds the separator or a line break.
+    pub fn with_key(mut self, field_name: &str, separator: char) -> Self {
+        self.key = Some((field_name.to_string(), separator));
+        self
+    }
+
+    /// Get the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Get the key of a row, checking that it can be framed.
+    fn key<'v>(field_name: &str, separator: char, values: &'v [(String, Option<String>)]) -> io::Result<&'v str> {
+        let Some((_, value)) = values.iter().find(|(name, _)| name == field_name) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Key field {} not exported", field_name)));
+        };
+        let key = value.as_deref().unwrap_or_default();
+        match key.contains([separator, '\n', '\r']) {
+            true => Err(io::Error::new(io::ErrorKind::InvalidData,
+                                       format!("Key {:?} of field {} can't be framed", key, field_name))),
+            false => Ok(key)
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> OutputSink for StreamSink<W> {
+    fn write_header(&mut self, _names: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, values: &[(String, Option<String>)]) -> io::Result<()> {
+        let mut line = String::new();
+        if let Some((field_name, separator)) = &self.key {
+            line.push_str(Self::key(field_name, *separator, values)?);
+            line.push(*separator);
+        }
+        line.push_str(&json_record(values));
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(all(test, feature = "csv", feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sinks_write_header_and_rows() {
+        let names = vec!["Name".to_string(), "Note".to_string()];
+        let row = vec![("Name".to_string(), Some("=SUM(A1)".to_string())), ("Note".to_string(), None)];
+
+        let mut csv = CsvSink::new(vec![]).with_formula_guard(FormulaGuard::Prefix);
+        csv.write_header(&names).unwrap();
+        csv.write_row(&row).unwrap();
+        csv.finish().unwrap();
+        assert_eq!(String::from_utf8(csv.into_inner()).unwrap(), "Name,Note\n'=SUM(A1),\n");
+
+        let mut jsonl = JsonLinesSink::new(vec![]);
+        {
+            let mut sink: Box<dyn OutputSink + '_> = Box::new(&mut jsonl);
+            sink.write_header(&names).unwrap();
+            sink.write_row(&row).unwrap();
+            sink.finish().unwrap();
+        }
+        assert_eq!(String::from_utf8(jsonl.into_inner()).unwrap(), "{\"Name\": \"=SUM(A1)\",\"Note\": null}\n");
+
+        let mut stream = StreamSink::new(vec![]).with_key("Note", '|');
+        stream.write_row(&[("Note".to_string(), Some("two\nlines".to_string()))]).unwrap_err();
+        stream.write_row(&row).unwrap();
+        stream.write_row(&[("Name".to_string(), Some("a\nb".to_string()))]).unwrap_err();
+        let mut keyless = StreamSink::new(vec![]);
+        keyless.write_row(&[("Name".to_string(), Some("a\nb".to_string()))]).unwrap();
+        assert_eq!(String::from_utf8(stream.into_inner()).unwrap(), "|{\"Name\": \"=SUM(A1)\",\"Note\": null}\n");
+        assert_eq!(String::from_utf8(keyless.into_inner()).unwrap(), "{\"Name\": \"a\\nb\"}\n");
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::{AtomicFile, AuditEntry, BlankValue, DataField, DataFile, DataFileError, DataFileResult, DataRow,
+            LoadOptions, LoadSummary, LoadWarning, NameMatching, Redaction, SourceHash, WarningKind};
+
+/// Bytes identifying a saved DataFile.
+const MAGIC: &[u8; 4] = b"FFRS";
+
+/// The version of the format written by `DataFile::save()`; files of other versions are refused.
+const VERSION: u8 = 2;
+
+/// Writes the parts of a saved DataFile: integers as LEB128 varints, strings prefixed by their
+/// length, and options by a 0 or 1 tag.
+struct Encoder {
+    out: Vec<u8>
+}
+
+impl Encoder {
+    fn uint(&mut self, mut n: u64) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                self.out.push(byte);
+                return;
+            }
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    fn usize(&mut self, n: usize) {
+        self.uint(n as u64)
+    }
+
+    fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        self.out.extend_from_slice(s.as_bytes());
+    }
+
+    fn opt<T>(&mut self, value: Option<T>, mut write: impl FnMut(&mut Self, T)) {
+        match value {
+            Some(v) => {
+                self.out.push(1);
+                write(self, v);
+            }
+            None => self.out.push(0)
+        }
+    }
+}
+
+/// Reads the parts written by an Encoder, failing with a description if the data runs out or
+/// doesn't make sense.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self.bytes.get(self.pos).ok_or("unexpected end of file")?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn uint(&mut self) -> Result<u64, String> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(format!("integer too long at byte {}", self.pos))
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        usize::try_from(self.uint()?).map_err(|e| e.to_string())
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(format!("invalid tag {} at byte {}", b, self.pos - 1))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.usize()?;
+        let bytes = self.pos.checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or("unexpected end of file")?;
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).map_err(|_| format!("invalid text before byte {}", self.pos))
+    }
+
+    fn opt<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T, String>) -> Result<Option<T>, String> {
+        match self.bool()? {
+            true => read(self).map(Some),
+            false => Ok(None)
+        }
+    }
+
+    /// Read a count of items, which each take at least a byte, so that a damaged count fails
+    /// rather than allocating for it.
+    fn count(&mut self) -> Result<usize, String> {
+        let n = self.usize()?;
+        match n <= self.bytes.len() - self.pos {
+            true => Ok(n),
+            false => Err(format!("invalid count {} at byte {}", n, self.pos))
+        }
+    }
+}
+
+impl DataFile {
+    /// Save the loaded rows, warnings and summary to a file in a compact binary format, so that
+    /// an expensive parse can be cached and reopened with `DataFile::open()` by later stages
+    /// without reading the source text again. The file is written under a temporary name and
+    /// moved into place once complete.
+    ///
+    /// Saved are the rows (including their line indexes, ids, raw lines and sources), the warnings,
+    /// the summary, the source hash, the audit log, and the redactions, blanks and implied
+    /// decimals used by exports. Typed values, the provenance of fields, indexes made with
+    /// `index_by()` and the LoadOptions are not; the file is opened with default options.
+    /// ```
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let df = DataFile::try_from_str(&format!("{:<200}\n", "54123456"), &defs, &LoadOptions::default()).unwrap();
+    /// let path = std::env::temp_dir().join("ffreader_save_doc.ffrs");
+    /// df.save(&path).unwrap();
+    /// let opened = DataFile::open(&path).unwrap();
+    /// assert_eq!(opened.rows(), df.rows());
+    /// assert_eq!(opened.summary().rows_loaded, 1);
+    /// ```
+    pub fn save(&self, path: &Path) -> DataFileResult<()> {
+        let file_error = |e| DataFileError::FileError(path.into(), e);
+        let mut file = AtomicFile::create(path).map_err(file_error)?;
+        file.write_all(&self.encode()).map_err(file_error)?;
+        file.commit().map_err(file_error)
+    }
+
+    /// Open a DataFile saved with `DataFile::save()`. Fails with
+    /// `DataFileError::InvalidSnapshot` if the file wasn't written by `save()`, was written by an
+    /// incompatible version, or is damaged.
+    pub fn open(path: &Path) -> DataFileResult<DataFile> {
+        let bytes = std::fs::read(path).map_err(|e| DataFileError::FileError(path.into(), e))?;
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(DataFileError::InvalidSnapshot(path.into(), "not a saved DataFile".to_string()));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(DataFileError::InvalidSnapshot(path.into(), format!("unsupported version {}", bytes[MAGIC.len()])));
+        }
+        let mut decoder = Decoder { bytes: &bytes, pos: MAGIC.len() + 1 };
+        let data_file = decode(&mut decoder).map_err(|m| DataFileError::InvalidSnapshot(path.into(), m))?;
+        match decoder.pos == bytes.len() {
+            true => Ok(data_file),
+            false => Err(DataFileError::InvalidSnapshot(path.into(), format!("unexpected data at byte {}", decoder.pos)))
+        }
+    }
+
+    /// Encode the DataFile in the format read by `decode()`, with the header.
+    fn encode(&self) -> Vec<u8> {
+        let mut e = Encoder { out: MAGIC.to_vec() };
+        e.out.push(VERSION);
+
+        e.str(&self.path().to_string_lossy());
+        e.opt(self.source_hash(), |e, h| {
+            e.str(&h.path.to_string_lossy());
+            e.usize(h.byte_count);
+            e.str(&h.sha256);
+        });
+
+        let summary = self.summary();
+        for n in [summary.rows_read, summary.rows_loaded, summary.rows_skipped, summary.rows_rejected] {
+            e.usize(n);
+        }
+        e.uint(u64::try_from(summary.elapsed.as_nanos()).unwrap_or(u64::MAX));
+        e.usize(summary.warnings_by_kind.len());
+        for (kind, count) in &summary.warnings_by_kind {
+            e.usize(kind_index(*kind));
+            e.usize(*count);
+        }
+
+        let redactions = sorted(self.redactions());
+        e.usize(redactions.len());
+        for (name, redaction) in redactions {
+            e.str(name);
+            match redaction {
+                Redaction::MaskAllButLast(n) => { e.out.push(0); e.usize(*n) }
+                Redaction::Hash(salt) => { e.out.push(1); e.str(salt) }
+                Redaction::Token(t) => { e.out.push(2); e.str(t) }
+            }
+        }
+        let blanks = sorted(self.blanks());
+        e.usize(blanks.len());
+        for (name, blank) in blanks {
+            e.str(name);
+            e.out.push(match blank {
+                BlankValue::AsIs => 0,
+                BlankValue::Empty => 1,
+                BlankValue::Null => 2
+            });
+        }
+        let implied_decimals = sorted(self.implied_decimal_fields());
+        e.usize(implied_decimals.len());
+        for (name, places) in implied_decimals {
+            e.str(name);
+            e.usize(*places);
+        }
+
+        e.usize(self.warnings().len());
+        for w in self.warnings() {
+            e.usize(w.line_index());
+            e.usize(kind_index(w.kind()));
+            e.opt(w.field_name(), Encoder::str);
+            e.opt(w.raw(), Encoder::str);
+            e.usize(w.raw_column());
+            e.out.push(w.is_error() as u8);
+            e.str(&w.message());
+        }
+
+        e.usize(self.audit_log().len());
+        for entry in self.audit_log() {
+            e.usize(entry.line_index);
+            e.str(&entry.field);
+            e.str(&entry.before);
+            e.str(&entry.after);
+        }
+
+        // field names and aliases are written once, and referred to by position
+        let mut names: Vec<(&str, &[String])> = vec![];
+        let mut positions: HashMap<(&str, &[String]), usize> = HashMap::new();
+        for field in self.rows().iter().flat_map(|r| r.fields()) {
+            positions.entry((field.name(), field.aliases())).or_insert_with(|| {
+                names.push((field.name(), field.aliases()));
+                names.len() - 1
+            });
+        }
+        e.usize(names.len());
+        for (name, aliases) in &names {
+            e.str(name);
+            e.usize(aliases.len());
+            aliases.iter().for_each(|a| e.str(a));
+        }
+
+        e.usize(self.rows().len());
+        for row in self.rows() {
+            e.opt(row.line_index(), Encoder::usize);
+            e.opt(row.row_id(), Encoder::usize);
+            e.out.push(match row.name_matching() {
+                NameMatching::Exact => 0,
+                NameMatching::Normalized => 1
+            });
+            e.opt(row.raw(), Encoder::str);
+            e.opt(row.source(), Encoder::str);
+            e.usize(row.fields().len());
+            for field in row.fields() {
+                e.usize(positions[&(field.name(), field.aliases())]);
+                e.str(field.raw());
+                e.opt(Some(field.data()).filter(|d| !d.is_empty()), |e, d| e.str(&d));
+            }
+        }
+        e.out
+    }
+}
+
+/// Decode a DataFile encoded by `DataFile::encode()`, after the header.
+fn decode(d: &mut Decoder) -> Result<DataFile, String> {
+    let path = d.string()?;
+    let source_hash = d.opt(|d| Ok(SourceHash { path: d.string()?.into(), byte_count: d.usize()?, sha256: d.string()? }))?;
+
+    let mut summary = LoadSummary {
+        rows_read: d.usize()?,
+        rows_loaded: d.usize()?,
+        rows_skipped: d.usize()?,
+        rows_rejected: d.usize()?,
+        elapsed: Duration::from_nanos(d.uint()?),
+        ..Default::default()
+    };
+    for _ in 0..d.count()? {
+        summary.warnings_by_kind.insert(kind(d)?, d.usize()?);
+    }
+
+    let mut redactions = vec![];
+    for _ in 0..d.count()? {
+        let name = d.string()?;
+        let redaction = match d.byte()? {
+            0 => Redaction::MaskAllButLast(d.usize()?),
+            1 => Redaction::Hash(d.string()?),
+            2 => Redaction::Token(d.string()?),
+            t => return Err(format!("invalid redaction {}", t))
+        };
+        redactions.push((name, redaction));
+    }
+    let mut blanks = vec![];
+    for _ in 0..d.count()? {
+        let name = d.string()?;
+        let blank = match d.byte()? {
+            0 => BlankValue::AsIs,
+            1 => BlankValue::Empty,
+            2 => BlankValue::Null,
+            t => return Err(format!("invalid blank value {}", t))
+        };
+        blanks.push((name, blank));
+    }
+    let mut implied_decimals = vec![];
+    for _ in 0..d.count()? {
+        implied_decimals.push((d.string()?, d.usize()?));
+    }
+
+    let mut warnings = vec![];
+    for _ in 0..d.count()? {
+        warnings.push(LoadWarning::from_parts(d.usize()?, kind(d)?, d.opt(Decoder::string)?, d.opt(Decoder::string)?,
+                                              d.usize()?, d.bool()?, d.string()?));
+    }
+
+    let mut audit_log = vec![];
+    for _ in 0..d.count()? {
+        audit_log.push(AuditEntry { line_index: d.usize()?, field: d.string()?, before: d.string()?, after: d.string()? });
+    }
+
+    let mut names: Vec<(Arc<str>, Arc<[String]>)> = vec![];
+    for _ in 0..d.count()? {
+        let name = Arc::from(d.string()?);
+        let aliases = (0..d.count()?).map(|_| d.string()).collect::<Result<Vec<String>, String>>()?;
+        names.push((name, Arc::from(aliases)));
+    }
+
+    let mut rows = vec![];
+    for _ in 0..d.count()? {
+        let line_index = d.opt(Decoder::usize)?;
+        let row_id = d.opt(Decoder::usize)?;
+        let name_matching = match d.byte()? {
+            0 => NameMatching::Exact,
+            1 => NameMatching::Normalized,
+            t => return Err(format!("invalid name matching {}", t))
+        };
+        let raw = d.opt(Decoder::string)?;
+        let source = d.opt(Decoder::string)?;
+        let mut fields = vec![];
+        for _ in 0..d.count()? {
+            let (name, aliases) = names.get(d.usize()?).cloned().ok_or("invalid field name")?;
+            fields.push(DataField::from_parts(name, aliases, d.string()?, d.opt(Decoder::string)?));
+        }
+        let mut row = DataRow::from_fields(fields, line_index).with_name_matching(name_matching).with_raw(raw)
+            .with_row_id(row_id);
+        if let Some(source) = source {
+            row.set_source(Arc::from(source));
+        }
+        rows.push(row);
+    }
+
+    let mut data_file = DataFile::from_parts(Path::new(&path), rows, warnings, summary, source_hash, &[],
+                                             &LoadOptions::default());
+    redactions.into_iter().for_each(|(n, r)| data_file.set_redaction(n, r));
+    blanks.into_iter().for_each(|(n, b)| data_file.set_blank(n, b));
+    implied_decimals.into_iter().for_each(|(n, p)| data_file.set_implied_decimals(n, p));
+    data_file.set_audit_log(audit_log);
+    Ok(data_file)
+}
+
+/// Get the position of a warning kind in `WarningKind::ALL`, as it is saved.
+fn kind_index(kind: WarningKind) -> usize {
+    WarningKind::ALL.iter().position(|k| *k == kind).unwrap_or_default()
+}
+
+/// Read a warning kind saved by its position.
+fn kind(d: &mut Decoder) -> Result<WarningKind, String> {
+    let index = d.usize()?;
+    WarningKind::ALL.get(index).copied().ok_or(format!("invalid warning kind {}", index))
+}
+
+/// Sort the entries of a map by key, so that saving the same DataFile gives the same bytes.
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<(&String, &V)> = map.iter().collect();
+    entries.sort_by_key(|(k, _)| *k);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldDef, DataFieldError, DataFieldResult};
+
+    fn upper(s: String) -> DataFieldResult<String> { Ok(s.to_uppercase()) }
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn saved_file_reopened() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 4, &numeric).with_alias("Key"),
+            DataFieldDef::new("Name", 4, 10, &upper).with_redaction(Redaction::Token("X".to_string())),
+            DataFieldDef::new("Amount", 10, 16, &numeric).with_implied_decimals(2).with_blank(BlankValue::Null),
+        ];
+        let data = format!("{:<200}\n\n{:<200}\n{:<200}\n", "0001ann   001250", "00X2BOB", "0003cy");
+        let options = LoadOptions { keep_raw_lines: true, audit: true, name_matching: NameMatching::Normalized,
+                                    ..Default::default() };
+        let df = DataFile::try_from_str(&data, &defs, &options).unwrap();
+
+        let path = std::env::temp_dir().join(format!("ffreader_snapshot_{}.ffrs", std::process::id()));
+        df.save(&path).unwrap();
+        let opened = DataFile::open(&path).unwrap();
+        assert_eq!(opened.rows(), df.rows());
+        assert_eq!(opened.rows()[1].line_index(), Some(3));
+        assert_eq!(opened.rows()[1].raw(), df.rows()[1].raw());
+        assert_eq!(opened.rows()[0].get("key").unwrap().data(), "0001");
+        assert_eq!(opened.summary(), df.summary());
+        assert_eq!(opened.redactions(), df.redactions());
+        assert_eq!(opened.blanks(), df.blanks());
+        assert_eq!(opened.implied_decimals("Amount"), 2);
+        assert_eq!(opened.audit_log(), df.audit_log());
+        let warnings = |f: &DataFile| f.warnings().iter()
+            .map(|w| (w.line_index(), w.kind(), w.field_name().map(str::to_string), w.is_error(), w.message()))
+            .collect::<Vec<_>>();
+        assert_eq!(warnings(&opened), warnings(&df));
+        assert_eq!(std::fs::read(&path).unwrap(), opened.encode());
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        assert!(matches!(DataFile::open(&path), Err(DataFileError::InvalidSnapshot(_, _))));
+        std::fs::write(&path, &data).unwrap();
+        assert!(matches!(DataFile::open(&path), Err(DataFileError::InvalidSnapshot(_, m)) if m == "not a saved DataFile"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+use unicode_width::UnicodeWidthStr;
+use crate::{DataFile, DataRowResult, ExportOptions};
+
+impl DataFile {
+    /// Render the rows as a table of aligned columns with a header, for inspecting parsed
+    /// output in a terminal.
+    ///
+    /// At most `max_rows` rows are shown (all if None), followed by a count of those left out.
+    /// Only the named fields are shown, in the order given; an empty list shows all fields.
+    /// Fails if a named field is not found. Columns are aligned by the width each value takes on
+    /// screen, so that accented and wide characters line up.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+    ///     DataFieldDef::new("Status", 16, 23, &post_function),
+    /// ];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// println!("{}", df.to_table(Some(20), &[]).unwrap());
+    /// ```
+    pub fn to_table(&self, max_rows: Option<usize>, fields: &[&str]) -> DataRowResult<String> {
+        let options = ExportOptions {
+            fields: (!fields.is_empty()).then(|| fields.iter().map(|f| f.to_string()).collect()),
+            ..Default::default()
+        };
+        let (header, rows) = options.cells(self, max_rows)?;
+
+        let mut widths: Vec<usize> = header.iter().map(|h| h.width()).collect();
+        for row in &rows {
+            for (w, value) in widths.iter_mut().zip(row) {
+                *w = (*w).max(value.width());
+            }
+        }
+        let line = |values: &[String]| values.iter().zip(&widths)
+            .map(|(v, w)| format!("{}{}", v, " ".repeat(w - v.width())))
+            .collect::<Vec<String>>()
+            .join(" | ")
+            .trim_end()
+            .to_string();
+
+        let mut table = line(&header);
+        table.push('\n');
+        table.push_str(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<String>>().join("-+-"));
+        table.push('\n');
+        for row in &rows {
+            table.push_str(&line(row));
+            table.push('\n');
+        }
+        if rows.len() < self.rows().len() {
+            table.push_str(&format!("({} more rows)\n", self.rows().len() - rows.len()));
+        }
+
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnUnit, DataFieldDef, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn table_aligned() {
+        let path = std::env::temp_dir().join(format!("ffreader_table_{}.txt", std::process::id()));
+        let contents: String = ["ANN  A", "BOBBYI", "CAT  A"].iter().map(|v| format!("{:<200}\n", v)).collect();
+        std::fs::write(&path, contents).unwrap();
+        let defs = vec![
+            DataFieldDef::new("Name", 0, 5, &echo_ok),
+            DataFieldDef::new("Status", 5, 6, &echo_ok),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        assert_eq!(df.to_table(Some(2), &[]).unwrap(),
+                   "Name  | Status\n------+-------\nANN   | A\nBOBBY | I\n(1 more rows)\n");
+        assert_eq!(df.to_table(None, &["Status"]).unwrap(), "Status\n------\nA\nI\nA\n");
+        assert!(df.to_table(None, &["Missing"]).is_err());
+        assert_eq!(df.rows()[1].to_string(), "Name=BOBBY, Status=I");
+
+        std::fs::remove_file(path).unwrap();
+
+        let data = format!("{:<200}\n{:<200}\n", "ANN  A", "JOSÉ A");
+        let df = DataFile::loader(&defs).columns(ColumnUnit::Chars).load_str(&data).unwrap();
+        assert_eq!(df.to_table(None, &[]).unwrap(), "Name | Status\n-----+-------\nANN  | A\nJOSÉ | A\n");
+    }
+}
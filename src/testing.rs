@@ -0,0 +1,105 @@
+//! Property-based testing of layouts with `proptest`, enabled with the `proptest` feature.
+//!
+//! `rows()` (or `TestGen::strategy()`, to set the kind of value of some fields) gives a
+//! strategy generating rows that load with a layout, as TestGen generates them, so that
+//! proptest can shrink a failing row to a minimal one. `assert_roundtrip()` checks that a row
+//! survives being parsed, written with the fixed-width writer and parsed again unchanged,
+//! which catches post-processing that isn't idempotent and alignment settings that don't match
+//! the layout.
+//! ```
+//! use ffreader::{testing, DataFieldDef, DataFieldResult, LoadOptions};
+//! use proptest::prelude::*;
+//! fn upper(value: String) -> DataFieldResult<String> {
+//!     Ok(value.to_uppercase())
+//! }
+//! let defs = vec![
+//!     DataFieldDef::new("Account", 0, 10, &upper),
+//!     DataFieldDef::new("Status", 10, 12, &upper),
+//! ];
+//! proptest!(|(row in testing::rows(&defs))| {
+//!     testing::assert_roundtrip(&row, &defs, &LoadOptions::default())?;
+//! });
+//! ```
+
+use proptest::prelude::*;
+use proptest::sample::select;
+use crate::{DataFieldDef, DataRow, LoadOptions, TestGen, TestValue};
+use crate::testgen::{render_generated, ALPHANUMERIC, LETTERS};
+use crate::writer::render_loadable_row;
+
+/// A strategy generating the text of a field of the given width.
+fn value_strategy(value: TestValue, width: usize) -> BoxedStrategy<String> {
+    let text = |chars: &[u8], len: std::ops::RangeInclusive<usize>| {
+        proptest::collection::vec(select(chars.iter().map(|c| *c as char).collect::<Vec<char>>()), len)
+            .prop_map(|c| c.into_iter().collect())
+            .boxed()
+    };
+    match value {
+        TestValue::Digits => text(b"0123456789", width..=width),
+        TestValue::Letters => text(LETTERS, width.min(1)..=width),
+        TestValue::Alphanumeric => text(ALPHANUMERIC, width.min(1)..=width),
+        TestValue::OneOf(values) if !values.is_empty() => select(values).boxed(),
+        TestValue::OneOf(_) | TestValue::Blank => Just(String::new()).boxed()
+    }
+}
+
+impl<'a> TestGen<'a> {
+    /// Get a proptest strategy generating rows as `row()` does, with the kinds of values set
+    /// with `with_value()`. The seed is not used; proptest chooses the values.
+    pub fn strategy(&self) -> impl Strategy<Value = String> + 'a {
+        let row_defs = self.row_defs;
+        let values: Vec<BoxedStrategy<String>> = row_defs.iter()
+            .map(|d| value_strategy(self.value_of(d), d.end_idx.saturating_sub(d.start_idx)))
+            .collect();
+        values.prop_map(move |mut values| render_generated(row_defs, |i| std::mem::take(&mut values[i])))
+    }
+}
+
+/// Get a proptest strategy generating rows that load with the layout; see `TestGen`.
+pub fn rows<'a>(row_defs: &'a Vec<DataFieldDef<'a>>) -> impl Strategy<Value = String> + 'a {
+    TestGen::new(row_defs, 0).strategy()
+}
+
+/// Check that a row parses, and that writing it with the fixed-width writer and parsing it again gives
+/// the same values, and then the same line. Fails the test case otherwise, so that it can be
+/// used with `?` in `proptest!`.
+pub fn assert_roundtrip(row: &str, row_defs: &Vec<DataFieldDef>, options: &LoadOptions) -> Result<(), TestCaseError> {
+    let parse = |line: &str| DataRow::try_create_with_options(line, row_defs, options)
+        .map_err(|e| TestCaseError::fail(format!("{} in row {:?}", e, line)));
+    let values = |row: &DataRow| row.fields().iter().map(|f| (f.name().to_string(), f.data())).collect::<Vec<_>>();
+
+    let first = parse(row)?;
+    let written = render_loadable_row(&first, row_defs);
+    let second = parse(&written)?;
+    prop_assert_eq!(values(&first), values(&second), "values changed by writing {:?} as {:?}", row, written);
+    prop_assert_eq!(&written, &render_loadable_row(&second, row_defs), "rewriting {:?} changed it", written);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Alignment, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    fn tag(s: String) -> DataFieldResult<String> { Ok(s + "X") }
+
+    #[test]
+    fn generated_rows_round_trip() {
+        let defs = vec![
+            DataFieldDef::new("Id", 0, 6, &echo_ok),
+            DataFieldDef::new("Flag", 6, 7, &echo_ok),
+            DataFieldDef::new("Unit", 7, 10, &echo_ok).only_when("Flag", &["U"]),
+            DataFieldDef::new("Amount", 10, 18, &echo_ok).with_alignment(Alignment::Right).with_pad_char('0'),
+        ];
+        proptest!(|(row in rows(&defs))| {
+            assert_roundtrip(&row, &defs, &LoadOptions::default())?;
+        });
+
+        let tagged = vec![DataFieldDef::new("Name", 0, 8, &tag)];
+        let mut runner = proptest::test_runner::TestRunner::deterministic();
+        let strategy = TestGen::new(&tagged, 0).with_value("Name", TestValue::Letters).strategy();
+        assert!(runner.run(&strategy, |row| assert_roundtrip(&row, &tagged, &LoadOptions::default())).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+use crate::{LoadSummary, LoadWarning};
+
+/// The span of a file load, when the `tracing` feature is enabled; otherwise nothing.
+#[derive(Clone)]
+pub(crate) struct LoadSpan(#[cfg(feature = "tracing")] tracing::Span);
+
+impl LoadSpan {
+    /// Create the span for loading the file at the path (empty when loading from memory).
+    pub(crate) fn new(path: &Path) -> LoadSpan {
+        #[cfg(feature = "tracing")]
+        return LoadSpan(tracing::info_span!("ffreader::load", path = %path.display()));
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = path;
+            LoadSpan()
+        }
+    }
+
+    /// Run the function within the span.
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.0.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+}
+
+/// Record that a line was skipped as blank or a comment.
+pub(crate) fn skipped_line(line_index: usize, reason: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(line = line_index + 1, reason, "skipped line");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (line_index, reason);
+}
+
+/// Record a warning as it is added to the load.
+pub(crate) fn warning(warning: &LoadWarning) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(line = warning.line_number(), kind = %warning.kind(), field = warning.field_name(),
+                    detail = %warning.message(), "load warning");
+    #[cfg(not(feature = "tracing"))]
+    let _ = warning;
+}
+
+/// Record the summary of a finished load.
+pub(crate) fn finished(summary: &LoadSummary) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(rows_read = summary.rows_read, rows_loaded = summary.rows_loaded,
+                   rows_skipped = summary.rows_skipped, rows_rejected = summary.rows_rejected,
+                   warnings = summary.warning_count(), elapsed_ms = summary.elapsed.as_millis() as u64,
+                   "load finished");
+    #[cfg(not(feature = "tracing"))]
+    let _ = summary;
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::{Event, Metadata, Subscriber};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use crate::{DataFile, DataFieldDef, DataFieldResult, LoadOptions};
+
+    /// Collects the names of the spans created and the messages of the events recorded.
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<String>>>);
+
+    /// Finds the message of an event.
+    struct Message(String);
+
+    impl Visit for Message {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.0.lock().unwrap().push(span.metadata().name().to_string());
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            self.0.lock().unwrap().push(message.0);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn load_traced() {
+        let collector = Collector::default();
+        let defs = vec![DataFieldDef::new("Id", 0, 3, &echo_ok)];
+        let data = format!("{:<200}\n\nshort\n", "001");
+        let options = LoadOptions { skip_blank_lines: true, ..Default::default() };
+        tracing::subscriber::with_default(collector.clone(), || {
+            DataFile::try_from_str(&data, &defs, &options).unwrap();
+        });
+        let names = collector.0.lock().unwrap().clone();
+        assert_eq!(names, ["ffreader::load", "skipped line", "load warning", "load finished"]);
+    }
+}
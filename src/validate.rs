@@ -0,0 +1,91 @@
+use std::path::Path;
+use crate::{DataFieldDef, DataFile, DataFileReader, DataFileResult, LoadOptions, LoadSummary, LoadWarning, Loader};
+
+/// The outcome of validating a file with `DataFile::validate()`: everything a load would report,
+/// without the rows.
+#[derive(Clone)]
+pub struct Validation {
+    /// Counts of the rows read, loaded, skipped and rejected.
+    pub summary: LoadSummary,
+    /// Warnings generated, as a load would report them.
+    pub warnings: Vec<LoadWarning>
+}
+
+impl Validation {
+    /// Determine whether the file loaded without any warnings.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+impl DataFile {
+    /// Check a file without keeping its data: every row is parsed and every post-processing
+    /// function is run, but the rows are discarded, so even a very large file is checked in
+    /// constant memory (apart from the warnings). Fails as `try_load()` would, on I/O errors or
+    /// a non-ASCII file.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+    /// let validation = DataFile::validate(Path::new("extract.txt"), &defs).unwrap();
+    /// if !validation.is_clean() {
+    ///     println!("{}", validation.summary);
+    /// }
+    /// ```
+    pub fn validate<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>) -> DataFileResult<Validation> {
+        Self::validate_with_options(path, row_defs, LoadOptions::default())
+    }
+
+    /// Check a file as with `validate()`, using the provided LoadOptions.
+    pub fn validate_with_options<'a>(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>, options: LoadOptions)
+        -> DataFileResult<Validation> {
+        let mut reader = DataFileReader::open(path, row_defs, options)?;
+        for row in &mut reader {
+            row?;
+        }
+        let (summary, warnings) = reader.finish();
+        Ok(Validation { summary, warnings })
+    }
+}
+
+impl Loader<'_> {
+    /// Check a file with these options, as with `DataFile::validate()`.
+    pub fn validate(self, path: &Path) -> DataFileResult<Validation> {
+        let (row_defs, options) = self.into_parts();
+        DataFile::validate_with_options(path, row_defs, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldError, DataFieldResult};
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn validation_matches_load() {
+        let path = std::env::temp_dir().join(format!("ffreader_validate_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\n{:<200}\nshort\n", "0001", "00X2")).unwrap();
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric)];
+
+        let validation = DataFile::validate(&path, &defs).unwrap();
+        let df = DataFile::try_load(&path, &defs).unwrap();
+        assert!(!validation.is_clean());
+        assert_eq!(validation.summary.rows_loaded, 1);
+        assert_eq!(validation.summary.rows_rejected, 2);
+        assert_eq!(validation.summary.warnings_by_kind, df.summary().warnings_by_kind);
+        let lines: Vec<usize> = validation.warnings.iter().map(|w| w.line_number()).collect();
+        assert_eq!(lines, [2, 3]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
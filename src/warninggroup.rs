@@ -0,0 +1,188 @@
+use std::fmt::{Display, Formatter};
+use crate::{LoadWarning, WarningKind};
+
+/// The warnings of one kind for one field (or for whole lines), summarized as a count with the
+/// first few in full, so that a problem repeated on thousands of rows is reported once.
+#[derive(Debug, Clone)]
+pub struct WarningGroup {
+    /// The kind of the warnings.
+    pub kind: WarningKind,
+    /// The field the warnings pertain to, or None for warnings about whole lines.
+    pub field_name: Option<String>,
+    /// The number of warnings in the group.
+    pub count: usize,
+    /// How many of them record a rejected row; see `LoadWarning::is_error()`.
+    pub rejected: usize,
+    /// The first warnings of the group, in the order added, up to the number of examples the
+    /// aggregator keeps.
+    pub examples: Vec<LoadWarning>
+}
+
+impl WarningGroup {
+    /// Determine whether a warning belongs to the group.
+    pub fn matches(&self, warning: &LoadWarning) -> bool {
+        warning.kind() == self.kind && warning.field_name() == self.field_name.as_deref()
+    }
+
+    /// Get the 1-based line number of the first warning of the group, if any was kept.
+    pub fn first_line_number(&self) -> Option<usize> {
+        self.examples.first().map(|w| w.line_number())
+    }
+}
+
+impl Display for WarningGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(field_name) = &self.field_name {
+            write!(f, "{}: ", field_name)?;
+        }
+        write!(f, "{} on {} line{}", self.kind, thousands(self.count), if self.count == 1 { "" } else { "s" })?;
+        match self.examples.first() {
+            Some(first) => write!(f, " (first at line {}: {})", first.line_number(), first.message()),
+            None => Ok(())
+        }
+    }
+}
+
+/// Write a count with commas between groups of three digits, e.g. 14,203.
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut s = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(',');
+        }
+        s.push(c);
+    }
+    s
+}
+
+/// Summarizes warnings into a WarningGroup per kind and field, keeping only the first few of
+/// each in full. Feeding it the warnings taken from a DataFileReader in batches keeps memory
+/// bounded however many rows repeat a problem, and `DataFile::warning_groups()` summarizes a
+/// loaded file's warnings for a report, with `DataFile::warnings_in()` giving the detail.
+/// ```
+/// use ffreader::{DataFieldDef, DataFieldError, DataFieldResult, DataFile, LoadOptions, WarningAggregator};
+/// fn numeric(value: String) -> DataFieldResult<String> {
+///     match value.chars().all(|c| c.is_ascii_digit()) {
+///         true => Ok(value),
+///         false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+///     }
+/// }
+/// let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric)];
+/// let data = (0..1500).map(|i| format!("{:<200}\n", if i % 3 == 0 { "00X1" } else { "0001" })).collect::<String>();
+/// let df = DataFile::try_from_str(&data, &defs, &LoadOptions::default()).unwrap();
+///
+/// let mut aggregator = WarningAggregator::new(2);
+/// aggregator.extend(df.warnings().iter().cloned());
+/// let groups = aggregator.groups();
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!((groups[0].count, groups[0].examples.len()), (500, 2));
+/// assert!(groups[0].to_string().starts_with("Id: FieldError on 500 lines (first at line 1: "));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WarningAggregator {
+    examples: usize,
+    groups: Vec<WarningGroup>
+}
+
+impl WarningAggregator {
+    /// Number of warnings of each group kept in full by default.
+    pub const DEFAULT_EXAMPLES: usize = 5;
+
+    /// Create an aggregator keeping up to `examples` warnings of each group in full.
+    pub fn new(examples: usize) -> WarningAggregator {
+        WarningAggregator { examples, groups: vec![] }
+    }
+
+    /// Add a warning to its group, starting a new group if it's the first of its kind and field.
+    pub fn add(&mut self, warning: LoadWarning) {
+        let group = match self.groups.iter().position(|g| g.matches(&warning)) {
+            Some(i) => &mut self.groups[i],
+            None => {
+                self.groups.push(WarningGroup {
+                    kind: warning.kind(),
+                    field_name: warning.field_name().map(String::from),
+                    count: 0,
+                    rejected: 0,
+                    examples: vec![]
+                });
+                self.groups.last_mut().expect("group just added")
+            }
+        };
+        group.count += 1;
+        group.rejected += warning.is_error() as usize;
+        if group.examples.len() < self.examples {
+            group.examples.push(warning);
+        }
+    }
+
+    /// Get the groups, in the order their first warnings were added.
+    pub fn groups(&self) -> &[WarningGroup] {
+        &self.groups
+    }
+
+    /// Get the number of warnings added.
+    pub fn count(&self) -> usize {
+        self.groups.iter().map(|g| g.count).sum()
+    }
+
+    /// Take the groups, leaving the aggregator empty.
+    pub fn take_groups(&mut self) -> Vec<WarningGroup> {
+        std::mem::take(&mut self.groups)
+    }
+}
+
+impl Default for WarningAggregator {
+    fn default() -> Self {
+        WarningAggregator::new(WarningAggregator::DEFAULT_EXAMPLES)
+    }
+}
+
+impl Extend<LoadWarning> for WarningAggregator {
+    fn extend<T: IntoIterator<Item = LoadWarning>>(&mut self, warnings: T) {
+        warnings.into_iter().for_each(|w| self.add(w));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::{DataFieldDef, DataFieldError, DataFieldResult, DataFileReader, LoadOptions};
+
+    fn numeric(s: String) -> DataFieldResult<String> {
+        match s.chars().all(|c| c.is_ascii_digit()) {
+            true => Ok(s),
+            false => Err(DataFieldError::Problem(Box::new("Not numeric")))
+        }
+    }
+
+    #[test]
+    fn repeated_warnings_grouped() {
+        let defs = vec![DataFieldDef::new("Id", 0, 4, &numeric), DataFieldDef::new("Code", 4, 6, &numeric)];
+        let data = (0..14203).map(|i| match i % 7 {
+            0 => format!("{:<200}\n", "00X112"),
+            3 => "short\n".to_string(),
+            _ => format!("{:<200}\n", "000112"),
+        }).collect::<String>();
+
+        let mut reader = DataFileReader::new(Cursor::new(data), &defs, LoadOptions::default());
+        let mut aggregator = WarningAggregator::default();
+        while let Some(row) = reader.next() {
+            row.unwrap();
+            aggregator.extend(reader.take_warnings());
+        }
+        aggregator.extend(reader.take_warnings());
+
+        let groups = aggregator.groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].kind, groups[0].field_name.as_deref()), (WarningKind::FieldError, Some("Id")));
+        assert_eq!((groups[0].count, groups[0].rejected, groups[0].examples.len()), (2029, 2029, 5));
+        assert_eq!(groups[1].kind, WarningKind::BadRowLength);
+        assert_eq!(groups[1].first_line_number(), Some(4));
+        assert_eq!(aggregator.count(), reader.summary().warning_count());
+        assert!(groups[0].to_string().starts_with("Id: FieldError on 2,029 lines (first at line 1: "));
+        assert_eq!(thousands(14203), "14,203");
+        assert_eq!(thousands(999), "999");
+    }
+}
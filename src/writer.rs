@@ -0,0 +1,267 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::{Anchor, DataFieldDef, DataRow};
+
+/// How a value is placed within its field when written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    /// Value at the start of the field, padded on the right (the default).
+    #[default]
+    Left,
+    /// Value at the end of the field, padded on the left.
+    Right
+}
+
+/// Render a row as a fixed-width line using the provided field definitions.
+///
+/// Each field's data is aligned and padded according to its definition; values longer than
+/// the field are truncated. Columns not covered by any field are filled with spaces, and the
+/// line extends to the end of the last field. Fields anchored to the end of the row follow
+/// directly after those anchored to the start.
+pub fn render_row(row: &DataRow, row_defs: &Vec<DataFieldDef>) -> String {
+    let extent = |anchor| row_defs.iter().filter(|d| d.anchor == anchor).map(|d| d.end_idx).max().unwrap_or(0);
+    let length = extent(Anchor::Start) + extent(Anchor::End);
+    let mut line = vec![' '; length];
+
+    for def in row_defs {
+        let width = def.end_idx.saturating_sub(def.start_idx);
+        let start_idx = def.span(length).0.min(length - width);
+        let data = row.fields().iter()
+            .find(|f| f.name() == def.name)
+            .map(|f| f.data())
+            .unwrap_or_default();
+        let value: Vec<char> = data.chars().take(width).collect();
+        let padding = vec![def.pad_char; width - value.len()];
+        let cell = match def.alignment {
+            Alignment::Left => [value, padding].concat(),
+            Alignment::Right => [padding, value].concat()
+        };
+        line.splice(start_idx..start_idx + width, cell);
+    }
+
+    line.into_iter().collect()
+}
+
+/// Render a row as with `render_row()`, padded with spaces to the minimum length of a row that
+/// can be loaded. The padding goes before any fields anchored to the end of the row.
+pub(crate) fn render_loadable_row(row: &DataRow, row_defs: &Vec<DataFieldDef>) -> String {
+    let mut line = render_row(row, row_defs);
+    let padding = DataRow::MINIMUM_LENGTH.saturating_sub(line.len());
+    let end_anchored = row_defs.iter().filter(|d| d.anchor == Anchor::End).map(|d| d.end_idx).max();
+    line.insert_str(line.len() - end_anchored.unwrap_or(0), &" ".repeat(padding));
+    line
+}
+
+/// Writes rows as fixed-width lines to any `io::Write`, rendered as with `render_row()`, e.g.
+/// to produce a file for a downstream system. Writes are buffered; call `finish()` to flush
+/// them and get the underlying writer back.
+///
+/// To never leave a half-written file behind, write with `create_atomic()`: the rows go to a
+/// temporary file in the same directory, which only replaces the target on `commit()`, and is
+/// removed if the writer is dropped without committing (e.g. when the job fails part way).
+/// ```no_run
+/// use std::path::Path;
+/// use ffreader::{DataFile, DataFieldDef, DataFieldResult, FixedWidthWriter};
+/// fn post_function(value: String) -> DataFieldResult<String> {
+///     Ok(value)
+/// }
+/// let defs = vec![DataFieldDef::new("Field_1", 0, 11, &post_function)];
+/// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+/// let mut writer = FixedWidthWriter::create_atomic(Path::new("outbound.txt"), &defs).unwrap();
+/// writer.write_rows(df.iter()).unwrap();
+/// writer.commit().unwrap();
+/// ```
+pub struct FixedWidthWriter<'a, W: Write> {
+    writer: BufWriter<W>,
+    row_defs: &'a Vec<DataFieldDef<'a>>,
+    line_ending: String,
+    rows_written: usize
+}
+
+impl<'a, W: Write> FixedWidthWriter<'a, W> {
+    /// Write rows of the given definitions to the writer, each line ended with `\n`.
+    pub fn new(writer: W, row_defs: &'a Vec<DataFieldDef<'a>>) -> Self {
+        FixedWidthWriter {
+            writer: BufWriter::new(writer),
+            row_defs,
+            line_ending: "\n".to_string(),
+            rows_written: 0
+        }
+    }
+
+    /// Set the text ending each line, e.g. `\r\n`, or an empty string for fixed-length records.
+    pub fn with_line_ending(mut self, line_ending: impl ToString) -> Self {
+        self.line_ending = line_ending.to_string();
+        self
+    }
+
+    /// Write a row.
+    pub fn write_row(&mut self, row: &DataRow) -> std::io::Result<()> {
+        self.writer.write_all(render_row(row, self.row_defs).as_bytes())?;
+        self.writer.write_all(self.line_ending.as_bytes())?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Write each of the rows.
+    pub fn write_rows<'r>(&mut self, rows: impl IntoIterator<Item = &'r DataRow>) -> std::io::Result<()> {
+        rows.into_iter().try_for_each(|r| self.write_row(r))
+    }
+
+    /// Get the number of rows written so far.
+    pub fn rows_written(&self) -> usize {
+        self.rows_written
+    }
+
+    /// Flush the rows written, returning the underlying writer.
+    pub fn finish(self) -> std::io::Result<W> {
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+impl<'a> FixedWidthWriter<'a, File> {
+    /// Append rows to a file, creating it if it doesn't exist.
+    pub fn append(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>) -> std::io::Result<Self> {
+        Ok(Self::new(OpenOptions::new().append(true).create(true).open(path)?, row_defs))
+    }
+}
+
+impl<'a> FixedWidthWriter<'a, AtomicFile> {
+    /// Write rows to a temporary file that replaces the file at the path on `commit()`.
+    pub fn create_atomic(path: &Path, row_defs: &'a Vec<DataFieldDef<'a>>) -> std::io::Result<Self> {
+        Ok(Self::new(AtomicFile::create(path)?, row_defs))
+    }
+
+    /// Flush and sync the rows written, then move the file into place.
+    pub fn commit(self) -> std::io::Result<()> {
+        self.finish()?.commit()
+    }
+}
+
+/// A file written under a temporary name and moved into place only once complete; see
+/// `FixedWidthWriter::create_atomic()`. The temporary file is removed if it is dropped without
+/// being committed.
+pub struct AtomicFile {
+    file: File,
+    temp_path: PathBuf,
+    path: PathBuf,
+    committed: bool
+}
+
+impl AtomicFile {
+    /// Create a temporary file alongside the path, so that it can be renamed onto it.
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp_path = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+        Ok(AtomicFile { file: File::create(&temp_path)?, temp_path, path: path.into(), committed: false })
+    }
+
+    /// Sync the file and move it into place, replacing any file already at the path.
+    pub fn commit(mut self) -> std::io::Result<()> {
+        self.file.sync_all()?;
+        std::fs::rename(&self.temp_path, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Describe the first difference between an original line and its re-rendered version,
+/// if any. Trailing spaces after the last defined column are not considered a difference.
+pub(crate) fn roundtrip_difference(original: &str, rendered: &str) -> Option<String> {
+    let (original, rendered) = (original.as_bytes(), rendered.as_bytes());
+
+    if let Some(column) = original.iter().zip(rendered).position(|(o, r)| o != r) {
+        let snippet = |s: &[u8]| String::from_utf8_lossy(&s[column..(column + 20).min(s.len())]).to_string();
+        return Some(format!("Round trip differs at column {}: expected \"{}\", rendered \"{}\"",
+                            column, snippet(original), snippet(rendered)));
+    }
+    if original.len() < rendered.len() {
+        return Some(format!("Round trip differs: line is {} bytes, rendered {}", original.len(), rendered.len()));
+    }
+    if let Some(extra) = original[rendered.len()..].iter().position(|b| *b != b' ') {
+        return Some(format!("Round trip differs: unmapped data at column {}", rendered.len() + extra));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataFieldResult;
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn rows_rendered_and_compared() {
+        let defs = vec![
+            DataFieldDef::new("name", 0, 6, &echo_ok),
+            DataFieldDef::new("amount", 8, 14, &echo_ok)
+                .with_alignment(Alignment::Right)
+                .with_pad_char('0'),
+        ];
+        let line = format!("{:<183}", "ABC     000042");
+        let row = DataRow::try_create(&line, &defs).unwrap();
+
+        let rendered = render_row(&row, &defs);
+        assert_eq!(rendered, "ABC     000042");
+        assert_eq!(roundtrip_difference(&line, &rendered), None);
+        assert!(roundtrip_difference("ABC   X 000042", &rendered).unwrap().contains("column 6"));
+        assert!(roundtrip_difference(&format!("{}  Z", rendered), &rendered).unwrap().contains("column 16"));
+
+        let defs = vec![
+            DataFieldDef::new("name", 0, 6, &echo_ok),
+            DataFieldDef::new("check", 0, 1, &echo_ok).with_anchor(Anchor::End),
+        ];
+        let row = DataRow::try_create(&format!("{:<199}7", "ABC"), &defs).unwrap();
+        assert_eq!(render_row(&row, &defs), "ABC   7");
+    }
+
+    #[test]
+    fn rows_written_atomically_or_appended() {
+        let defs = vec![DataFieldDef::new("name", 0, 6, &echo_ok), DataFieldDef::new("code", 6, 8, &echo_ok)];
+        let row = DataRow::try_create(&format!("{:<183}", "ABC   01"), &defs).unwrap();
+
+        let mut writer = FixedWidthWriter::new(vec![], &defs).with_line_ending("\r\n");
+        writer.write_rows([&row, &row]).unwrap();
+        assert_eq!(writer.rows_written(), 2);
+        assert_eq!(writer.finish().unwrap(), b"ABC   01\r\nABC   01\r\n");
+
+        let dir = std::env::temp_dir().join(format!("ffreader_writer_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.txt");
+        std::fs::write(&path, "previous\n").unwrap();
+        let mut writer = FixedWidthWriter::create_atomic(&path, &defs).unwrap();
+        writer.write_row(&row).unwrap();
+        drop(writer);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let mut writer = FixedWidthWriter::create_atomic(&path, &defs).unwrap();
+        writer.write_row(&row).unwrap();
+        writer.commit().unwrap();
+        let mut writer = FixedWidthWriter::append(&path, &defs).unwrap();
+        writer.write_row(&row).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ABC   01\nABC   01\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
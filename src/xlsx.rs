@@ -0,0 +1,114 @@
+use std::path::Path;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use crate::{DataFile, DataFileError, DataFileResult, ExportOptions};
+use crate::aggregate::parse_number;
+
+impl From<XlsxError> for DataFileError {
+    fn from(value: XlsxError) -> Self {
+        DataFileError::XlsxError(value)
+    }
+}
+
+impl DataFile {
+    /// Write the data to an Excel spreadsheet with a header row of field names.
+    /// Only available with the `xlsx` feature.
+    pub fn write_xlsx(&self, path: &Path) -> DataFileResult<()> {
+        self.write_xlsx_with_options(path, &ExportOptions::default(), false)
+    }
+
+    /// Write the data to an Excel spreadsheet using the provided ExportOptions, optionally
+    /// adding a second sheet listing the load warnings.
+    ///
+    /// Fields with implied decimal places are written as numbers (with that many decimal
+    /// places shown) when they parse; other values are written as text, which preserves
+    /// leading zeros in account numbers and the like. Only available with the `xlsx` feature.
+    /// ```no_run
+    /// use std::path::Path;
+    /// use ffreader::{DataFile, DataFieldDef, DataFieldResult, ExportOptions};
+    /// fn post_function(value: String) -> DataFieldResult<String> {
+    ///     Ok(value)
+    /// }
+    /// let defs = vec![
+    ///     DataFieldDef::new("AccountNo1", 0, 11, &post_function),
+    ///     DataFieldDef::new("Amount", 11, 20, &post_function).with_implied_decimals(2),
+    /// ];
+    /// let df = DataFile::try_load(Path::new("extract.txt"), &defs).unwrap();
+    /// df.write_xlsx_with_options(Path::new("extract.xlsx"), &ExportOptions::default(), true).unwrap();
+    /// ```
+    pub fn write_xlsx_with_options(&self, path: &Path, options: &ExportOptions, warnings_sheet: bool)
+        -> DataFileResult<()> {
+        let (header, rows) = options.cells(self, options.max_rows).map_err(DataFileError::ExportError)?;
+        let bold = Format::new().set_bold();
+        let mut workbook = Workbook::new();
+
+        let sheet = workbook.add_worksheet().set_name("Data")?;
+        write_header(sheet, &header, &bold)?;
+        let numeric_formats: Vec<Option<Format>> = header.iter()
+            .map(|h| match self.implied_decimals(h) {
+                0 => None,
+                d => Some(Format::new().set_num_format(format!("0.{}", "0".repeat(d))))
+            })
+            .collect();
+        for (r, row) in rows.iter().enumerate() {
+            let r = r as u32 + 1;
+            for (c, value) in row.iter().enumerate() {
+                let number = numeric_formats[c].as_ref()
+                    .and_then(|format| parse_number(value, self.implied_decimals(&header[c])).map(|n| (n, format)));
+                match number {
+                    Some((n, format)) => sheet.write_number_with_format(r, c as u16, n, format)?,
+                    None => sheet.write_string(r, c as u16, value)?
+                };
+            }
+        }
+
+        if warnings_sheet {
+            let sheet = workbook.add_worksheet().set_name("Warnings")?;
+            write_header(sheet, &["Line", "Kind", "Field", "Raw", "Message"].map(String::from), &bold)?;
+            for (r, w) in self.warnings().iter().enumerate() {
+                let r = r as u32 + 1;
+                sheet.write_number(r, 0, w.line_number() as f64)?;
+                sheet.write_string(r, 1, w.kind().to_string())?;
+                sheet.write_string(r, 2, w.field_name().unwrap_or_default())?;
+                sheet.write_string(r, 3, w.raw().unwrap_or_default())?;
+                sheet.write_string(r, 4, w.message())?;
+            }
+        }
+
+        workbook.save(path)?;
+        Ok(())
+    }
+}
+
+fn write_header(sheet: &mut Worksheet, header: &[String], bold: &Format) -> Result<(), XlsxError> {
+    for (c, name) in header.iter().enumerate() {
+        sheet.write_string_with_format(0, c as u16, name, bold)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFieldDef, DataFieldResult};
+
+    fn echo_ok(s: String) -> DataFieldResult<String> { Ok(s) }
+
+    #[test]
+    fn workbook_written() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("ffreader_xlsx_{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{:<200}\n{:<200}\nshort\n", "00110050", "002N/A")).unwrap();
+        let defs = vec![
+            DataFieldDef::new("AccountNo1", 0, 3, &echo_ok),
+            DataFieldDef::new("Amount", 3, 8, &echo_ok).with_implied_decimals(2),
+        ];
+        let df = DataFile::try_load(&path, &defs).unwrap();
+
+        let output = dir.join(format!("ffreader_xlsx_{}.xlsx", std::process::id()));
+        df.write_xlsx_with_options(&output, &ExportOptions::default(), true).unwrap();
+        assert!(std::fs::read(&output).unwrap().starts_with(b"PK"));
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}